@@ -0,0 +1,57 @@
+//! Compare `decode_flat_dict` with reading the same dictionary through a `Decoder`.
+//!
+//! Run with `cargo bench --bench flat_dict`.
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use bendy::decoding::{decode_flat_dict, Decoder, Error, FlatDict};
+
+const ITERATIONS: u32 = 1_000_000;
+
+/// The arguments of a KRPC `get_peers` response without nodes
+const KRPC_ARGUMENTS: &[u8] = b"d2:id20:abcdefghij01234567895:token8:aoeusnth\
+    6:values6:\x01\x02\x03\x04\x1a\xe1e";
+
+/// A dictionary with a few more, longer entries
+const WIDE: &[u8] = b"d4:key06:value04:key16:value14:key26:value24:key36:value3\
+    4:key46:value44:key56:value54:key66:value64:key76:value7e";
+
+fn decode_general(input: &[u8]) -> Result<FlatDict<'_>, Error> {
+    let mut decoder = Decoder::new(input);
+    let mut dict = decoder.next_object()?.unwrap().try_into_dictionary()?;
+    let mut pairs = Vec::new();
+    while let Some((key, value)) = dict.next_pair()? {
+        pairs.push((key, value.try_into_bytes()?));
+    }
+    Ok(pairs)
+}
+
+fn measure(decode: impl Fn(&[u8]) -> Result<FlatDict<'_>, Error>, input: &[u8]) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(decode(black_box(input)).unwrap());
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn main() {
+    for (name, input) in [("krpc arguments", KRPC_ARGUMENTS), ("wide", WIDE)] {
+        assert_eq!(
+            decode_flat_dict(input).unwrap(),
+            decode_general(input).unwrap()
+        );
+
+        let general = measure(decode_general, input);
+        let flat = measure(decode_flat_dict, input);
+        println!(
+            "{:<16} general: {:>8?}  flat: {:>8?}  ({:.1}x)",
+            name,
+            general,
+            flat,
+            general.as_secs_f64() / flat.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,456 @@
+//! Parsing of the `#[bendy(...)]` attributes.
+
+use syn::{
+    Attribute, Data, DataEnum, DeriveInput, Fields, FieldsNamed, FieldsUnnamed, GenericArgument,
+    Ident, LitByteStr, LitStr, PathArguments, Type,
+};
+
+/// Attributes on the struct or enum itself.
+#[derive(Default)]
+pub struct Container {
+    /// Renames the fields of a struct or the variants of an enum
+    pub rename_all: Option<RenameRule>,
+    /// Store the variant name of an enum under this key, next to the variant's fields
+    pub tag: Option<LitStr>,
+    /// Represent a fieldless enum by its discriminant
+    pub integer: bool,
+    /// Fail to decode dictionaries with keys that don't belong to any field
+    pub deny_unknown_fields: bool,
+}
+
+impl Container {
+    pub fn from_ast(input: &DeriveInput) -> syn::Result<Self> {
+        let mut container = Container::default();
+        for attr in bendy_attrs(&input.attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename_all") {
+                    let rule: LitStr = meta.value()?.parse()?;
+                    container.rename_all = Some(RenameRule::parse(&rule)?);
+                    Ok(())
+                } else if meta.path.is_ident("tag") {
+                    container.tag = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("integer") {
+                    container.integer = true;
+                    Ok(())
+                } else if meta.path.is_ident("deny_unknown_fields") {
+                    container.deny_unknown_fields = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown bendy container attribute"))
+                }
+            })?;
+        }
+
+        let is_enum = match input.data {
+            Data::Enum(_) => true,
+            Data::Struct(_) | Data::Union(_) => false,
+        };
+        if let Some(tag) = &container.tag {
+            if !is_enum {
+                return Err(syn::Error::new(
+                    tag.span(),
+                    "`tag` is only supported on enums",
+                ));
+            }
+            if container.integer {
+                return Err(syn::Error::new(
+                    tag.span(),
+                    "`tag` and `integer` can't be combined",
+                ));
+            }
+        }
+        if container.integer && !is_enum {
+            return Err(syn::Error::new_spanned(
+                input,
+                "`integer` is only supported on enums",
+            ));
+        }
+
+        Ok(container)
+    }
+
+    /// The key of the tag of an internally tagged enum
+    pub fn tag_key(&self) -> Option<LitByteStr> {
+        self.tag
+            .as_ref()
+            .map(|tag| LitByteStr::new(tag.value().as_bytes(), tag.span()))
+    }
+}
+
+/// An enum variant together with the name it is stored under.
+pub struct Variant<'a> {
+    pub ident: &'a Ident,
+    pub fields: &'a Fields,
+    pub name: String,
+    /// Renames the fields of a struct variant
+    pub rename_all: Option<RenameRule>,
+}
+
+impl Variant<'_> {
+    pub fn tag(&self) -> LitByteStr {
+        LitByteStr::new(self.name.as_bytes(), self.ident.span())
+    }
+}
+
+/// Resolve the names of the given variants and check that they fit the enum's
+/// representation.
+pub fn variants<'a>(container: &Container, data: &'a DataEnum) -> syn::Result<Vec<Variant<'a>>> {
+    let mut variants: Vec<Variant> = Vec::new();
+    for variant in &data.variants {
+        let mut rename = None;
+        let mut rename_all = None;
+        for attr in bendy_attrs(&variant.attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                    Ok(())
+                } else if meta.path.is_ident("rename_all") {
+                    let rule: LitStr = meta.value()?.parse()?;
+                    rename_all = Some(RenameRule::parse(&rule)?);
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown bendy variant attribute"))
+                }
+            })?;
+        }
+
+        let supported = match (&variant.fields, container) {
+            (Fields::Unit, _) => true,
+            (_, Container { integer: true, .. }) => false,
+            (Fields::Named(_), _) => true,
+            (Fields::Unnamed(_), Container { tag: None, .. }) => true,
+            (Fields::Unnamed(_), Container { tag: Some(_), .. }) => false,
+        };
+        if !supported {
+            let message = if container.integer {
+                "enums with `integer` can only have unit variants"
+            } else {
+                "internally tagged enums can only have unit and struct variants"
+            };
+            return Err(syn::Error::new_spanned(variant, message));
+        }
+
+        let name = match (rename, &container.rename_all) {
+            (Some(rename), _) => rename,
+            (None, Some(rule)) => rule.apply_to_variant(&unraw(&variant.ident)),
+            (None, None) => unraw(&variant.ident),
+        };
+        if variants.iter().any(|other| other.name == name) {
+            return Err(syn::Error::new_spanned(
+                variant,
+                format!("duplicate variant name `{}`", name),
+            ));
+        }
+        if let (Some(tag), Fields::Named(fields)) = (&container.tag, &variant.fields) {
+            let fields = dict_fields(rename_all, fields)?;
+            let conflict = fields
+                .iter()
+                .find(|field| !field.flatten && field.name == tag.value());
+            if let Some(field) = conflict {
+                return Err(syn::Error::new(
+                    field.ident.span(),
+                    format!("field `{}` conflicts with the tag of the enum", field.name),
+                ));
+            }
+        }
+        variants.push(Variant {
+            ident: &variant.ident,
+            fields: &variant.fields,
+            name,
+            rename_all,
+        });
+    }
+    Ok(variants)
+}
+
+/// Attributes on a field.
+#[derive(Default)]
+struct Field {
+    rename: Option<LitStr>,
+    default: bool,
+    flatten: bool,
+}
+
+impl Field {
+    fn from_ast(field: &syn::Field) -> syn::Result<Self> {
+        let mut attrs = Field::default();
+        for attr in bendy_attrs(&field.attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    attrs.rename = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("default") {
+                    attrs.default = true;
+                    Ok(())
+                } else if meta.path.is_ident("flatten") {
+                    attrs.flatten = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown bendy field attribute"))
+                }
+            })?;
+        }
+        Ok(attrs)
+    }
+}
+
+/// A named field together with the dictionary key it is stored under.
+pub struct DictField<'a> {
+    pub ident: &'a Ident,
+    pub ty: &'a Type,
+    pub name: String,
+    /// The `T` of a field of type `Option<T>`. Such fields may be missing from the
+    /// dictionary and aren't emitted if they are `None`.
+    pub option: Option<&'a Type>,
+    /// Whether a missing key decodes to `Default::default()`
+    pub default: bool,
+    /// Whether the entries of the field are merged into the surrounding dictionary. Such
+    /// fields don't have a key of their own.
+    pub flatten: bool,
+}
+
+impl DictField<'_> {
+    pub fn key(&self) -> LitByteStr {
+        LitByteStr::new(self.name.as_bytes(), self.ident.span())
+    }
+
+    /// Whether the key may be missing from the dictionary
+    pub fn is_optional(&self) -> bool {
+        self.default || self.option.is_some()
+    }
+}
+
+/// Resolve the dictionary keys of the given fields, rejecting duplicates.
+pub fn dict_fields(
+    rename_all: Option<RenameRule>,
+    fields: &FieldsNamed,
+) -> syn::Result<Vec<DictField<'_>>> {
+    let mut dict_fields: Vec<DictField> = Vec::new();
+    for field in &fields.named {
+        let attrs = Field::from_ast(field)?;
+        let ident = field.ident.as_ref().unwrap();
+        if attrs.flatten && (attrs.rename.is_some() || attrs.default) {
+            return Err(syn::Error::new_spanned(
+                field,
+                "`flatten` can't be combined with other field attributes",
+            ));
+        }
+        let name = match (&attrs.rename, &rename_all) {
+            (Some(rename), _) => rename.value(),
+            (None, Some(rule)) => rule.apply_to_field(&unraw(ident)),
+            (None, None) => unraw(ident),
+        };
+
+        if !attrs.flatten
+            && dict_fields
+                .iter()
+                .any(|other| !other.flatten && other.name == name)
+        {
+            return Err(syn::Error::new_spanned(
+                field,
+                format!("duplicate dictionary key `{}`", name),
+            ));
+        }
+        dict_fields.push(DictField {
+            ident,
+            ty: &field.ty,
+            name,
+            option: option_argument(&field.ty).filter(|_| !attrs.flatten),
+            default: attrs.default,
+            flatten: attrs.flatten,
+        });
+    }
+    Ok(dict_fields)
+}
+
+/// Check that the fields of a tuple struct carry no attributes that only make sense
+/// for dictionary entries.
+pub fn check_list_fields(fields: &FieldsUnnamed) -> syn::Result<()> {
+    for field in &fields.unnamed {
+        let attrs = Field::from_ast(field)?;
+        if attrs.rename.is_some() || attrs.default || attrs.flatten {
+            return Err(syn::Error::new_spanned(
+                field,
+                "bendy field attributes are only supported on named fields",
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Extract `T` from a field of type `Option<T>`. Like serde, this only looks at the
+/// written type, so aliases of `Option` are treated like any other type.
+fn option_argument(ty: &Type) -> Option<&Type> {
+    let path = match ty {
+        Type::Path(ty) if ty.qself.is_none() => &ty.path,
+        _ => return None,
+    };
+    let segment = path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn bendy_attrs(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attrs.iter().filter(|attr| attr.path().is_ident("bendy"))
+}
+
+fn unraw(ident: &Ident) -> String {
+    let name = ident.to_string();
+    match name.strip_prefix("r#") {
+        Some(name) => name.to_string(),
+        None => name,
+    }
+}
+
+/// The case conventions supported by `rename_all`.
+#[derive(Clone, Copy)]
+pub enum RenameRule {
+    LowerCase,
+    UpperCase,
+    PascalCase,
+    CamelCase,
+    SnakeCase,
+    ScreamingSnakeCase,
+    KebabCase,
+    ScreamingKebabCase,
+    SpaceSeparated,
+}
+
+impl RenameRule {
+    const ALL: &'static [(&'static str, RenameRule)] = &[
+        ("lowercase", RenameRule::LowerCase),
+        ("UPPERCASE", RenameRule::UpperCase),
+        ("PascalCase", RenameRule::PascalCase),
+        ("camelCase", RenameRule::CamelCase),
+        ("snake_case", RenameRule::SnakeCase),
+        ("SCREAMING_SNAKE_CASE", RenameRule::ScreamingSnakeCase),
+        ("kebab-case", RenameRule::KebabCase),
+        ("SCREAMING-KEBAB-CASE", RenameRule::ScreamingKebabCase),
+        ("space separated", RenameRule::SpaceSeparated),
+    ];
+
+    fn parse(lit: &LitStr) -> syn::Result<Self> {
+        let value = lit.value();
+        RenameRule::ALL
+            .iter()
+            .find(|(name, _)| *name == value)
+            .map(|(_, rule)| *rule)
+            .ok_or_else(|| {
+                let names: Vec<_> = RenameRule::ALL
+                    .iter()
+                    .map(|(name, _)| format!("\"{}\"", name))
+                    .collect();
+                syn::Error::new(
+                    lit.span(),
+                    format!("unknown rename rule, expected one of {}", names.join(", ")),
+                )
+            })
+    }
+
+    /// Rename a field, which is expected to be in snake case.
+    fn apply_to_field(self, field: &str) -> String {
+        match self {
+            RenameRule::LowerCase | RenameRule::SnakeCase => field.to_string(),
+            RenameRule::UpperCase | RenameRule::ScreamingSnakeCase => field.to_ascii_uppercase(),
+            RenameRule::PascalCase => field.split('_').map(capitalize).collect(),
+            RenameRule::CamelCase => {
+                let pascal: String = field.split('_').map(capitalize).collect();
+                let mut chars = pascal.chars();
+                match chars.next() {
+                    Some(first) => first.to_lowercase().chain(chars).collect(),
+                    None => pascal,
+                }
+            },
+            RenameRule::KebabCase => field.replace('_', "-"),
+            RenameRule::ScreamingKebabCase => field.replace('_', "-").to_ascii_uppercase(),
+            RenameRule::SpaceSeparated => field.replace('_', " "),
+        }
+    }
+
+    /// Rename a variant, which is expected to be in Pascal case.
+    fn apply_to_variant(self, variant: &str) -> String {
+        match self {
+            RenameRule::PascalCase => variant.to_string(),
+            RenameRule::LowerCase => variant.to_ascii_lowercase(),
+            RenameRule::UpperCase => variant.to_ascii_uppercase(),
+            RenameRule::CamelCase => {
+                let mut chars = variant.chars();
+                match chars.next() {
+                    Some(first) => first.to_lowercase().chain(chars).collect(),
+                    None => String::new(),
+                }
+            },
+            RenameRule::SnakeCase
+            | RenameRule::ScreamingSnakeCase
+            | RenameRule::KebabCase
+            | RenameRule::ScreamingKebabCase
+            | RenameRule::SpaceSeparated => {
+                let mut snake = String::new();
+                for (i, ch) in variant.char_indices() {
+                    if i > 0 && ch.is_uppercase() {
+                        snake.push('_');
+                    }
+                    snake.extend(ch.to_lowercase());
+                }
+                self.apply_to_field(&snake)
+            },
+        }
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rename_rules_split_snake_case_fields() {
+        let rename = |rule: &str| {
+            let rule = RenameRule::parse(&LitStr::new(rule, proc_macro2::Span::call_site()));
+            rule.unwrap().apply_to_field("piece_length")
+        };
+
+        assert_eq!(rename("lowercase"), "piece_length");
+        assert_eq!(rename("UPPERCASE"), "PIECE_LENGTH");
+        assert_eq!(rename("PascalCase"), "PieceLength");
+        assert_eq!(rename("camelCase"), "pieceLength");
+        assert_eq!(rename("SCREAMING_SNAKE_CASE"), "PIECE_LENGTH");
+        assert_eq!(rename("kebab-case"), "piece-length");
+        assert_eq!(rename("SCREAMING-KEBAB-CASE"), "PIECE-LENGTH");
+        assert_eq!(rename("space separated"), "piece length");
+    }
+
+    #[test]
+    fn rename_rules_split_pascal_case_variants() {
+        let rename = |rule: &str| {
+            let rule = RenameRule::parse(&LitStr::new(rule, proc_macro2::Span::call_site()));
+            rule.unwrap().apply_to_variant("GetPeers")
+        };
+
+        assert_eq!(rename("lowercase"), "getpeers");
+        assert_eq!(rename("UPPERCASE"), "GETPEERS");
+        assert_eq!(rename("PascalCase"), "GetPeers");
+        assert_eq!(rename("camelCase"), "getPeers");
+        assert_eq!(rename("snake_case"), "get_peers");
+        assert_eq!(rename("SCREAMING_SNAKE_CASE"), "GET_PEERS");
+        assert_eq!(rename("kebab-case"), "get-peers");
+        assert_eq!(rename("SCREAMING-KEBAB-CASE"), "GET-PEERS");
+        assert_eq!(rename("space separated"), "get peers");
+    }
+}
@@ -0,0 +1,495 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse_quote, Data, DeriveInput, Fields, FieldsUnnamed, LitByteStr};
+
+use crate::{
+    attr::{self, Container, DictField, RenameRule, Variant},
+    container_depth, max_depth, with_bound,
+};
+
+pub fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let container = Container::from_ast(input)?;
+    let generics = with_bound(
+        &input.generics,
+        parse_quote!(::bendy::decoding::FromBencode),
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let (depth, body) = match &input.data {
+        Data::Struct(data) => {
+            if let Fields::Unit = data.fields {
+                return Err(syn::Error::new_spanned(
+                    input,
+                    "FromBencode can't be derived for unit structs",
+                ));
+            }
+            let constructor = quote!(Self);
+            let depth = fields_depth(container.rename_all, &data.fields)?;
+            let body = decode_fields(
+                &constructor,
+                container.rename_all,
+                &data.fields,
+                None,
+                container.deny_unknown_fields,
+            )?;
+            (depth, body)
+        },
+        Data::Enum(data) => {
+            let variants = attr::variants(&container, data)?;
+            if container.integer {
+                (quote!(0), decode_integer_enum(&variants))
+            } else if let Some(tag) = container.tag_key() {
+                decode_internally_tagged(&container, &tag, &variants)?
+            } else {
+                decode_externally_tagged(&container, &variants)?
+            }
+        },
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "FromBencode can't be derived for unions",
+            ));
+        },
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::bendy::decoding::FromBencode for #name #ty_generics #where_clause {
+            const EXPECTED_RECURSION_DEPTH: usize = #depth;
+
+            fn decode_bencode_object(
+                object: ::bendy::decoding::Object,
+            ) -> ::core::result::Result<Self, ::bendy::decoding::Error>
+            where
+                Self: Sized,
+            {
+                #body
+            }
+        }
+    })
+}
+
+fn item_depth(ty: &syn::Type) -> TokenStream {
+    quote!(<#ty as ::bendy::decoding::FromBencode>::EXPECTED_RECURSION_DEPTH)
+}
+
+/// The depth of the representation of the given fields.
+fn fields_depth(rename_all: Option<RenameRule>, fields: &Fields) -> syn::Result<TokenStream> {
+    Ok(match fields {
+        Fields::Named(fields) => {
+            let fields = attr::dict_fields(rename_all, fields)?;
+            container_depth(fields.iter().map(|field| {
+                let depth = item_depth(field.ty);
+                if field.flatten {
+                    // The entries of the flattened dictionary are on the level of this one
+                    quote!(#depth.saturating_sub(1))
+                } else {
+                    depth
+                }
+            }))
+        },
+        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => item_depth(&fields.unnamed[0].ty),
+        Fields::Unnamed(fields) => {
+            container_depth(fields.unnamed.iter().map(|field| item_depth(&field.ty)))
+        },
+        Fields::Unit => quote!(0),
+    })
+}
+
+/// An expression decoding `object` into `constructor` with the given fields.
+///
+/// A dictionary key equal to `tag` is skipped, as it's already been interpreted by an
+/// internally tagged enum. Any other unknown key is an error if `deny_unknown_fields` is
+/// set.
+fn decode_fields(
+    constructor: &TokenStream,
+    rename_all: Option<RenameRule>,
+    fields: &Fields,
+    tag: Option<&LitByteStr>,
+    deny_unknown_fields: bool,
+) -> syn::Result<TokenStream> {
+    Ok(match fields {
+        Fields::Named(named) => {
+            let fields = attr::dict_fields(rename_all, named)?;
+            if deny_unknown_fields && fields.iter().any(|field| field.flatten) {
+                return Err(syn::Error::new_spanned(
+                    named,
+                    "`deny_unknown_fields` can't be combined with `flatten`",
+                ));
+            }
+            decode_dict(constructor, &fields, tag, deny_unknown_fields)
+        },
+        Fields::Unnamed(fields) => {
+            attr::check_list_fields(fields)?;
+            if fields.unnamed.len() == 1 {
+                let ty = &fields.unnamed[0].ty;
+                quote! {
+                    <#ty as ::bendy::decoding::FromBencode>::decode_bencode_object(object)
+                        .map(#constructor)
+                }
+            } else {
+                decode_list(constructor, fields)
+            }
+        },
+        Fields::Unit => quote!(::core::result::Result::Ok(#constructor)),
+    })
+}
+
+fn decode_dict(
+    constructor: &TokenStream,
+    fields: &[DictField],
+    tag: Option<&LitByteStr>,
+    deny_unknown_fields: bool,
+) -> TokenStream {
+    let keyed: Vec<_> = fields.iter().filter(|field| !field.flatten).collect();
+    let types = keyed.iter().map(|field| field.ty);
+    let keys = keyed.iter().map(|field| field.key());
+    let names = keyed.iter().map(|field| &field.name);
+    let slots: Vec<_> = keyed
+        .iter()
+        .map(|field| format_ident!("__field_{}", field.ident))
+        .collect();
+
+    let idents = fields.iter().map(|field| field.ident);
+    let values = fields.iter().map(|field| {
+        let slot = format_ident!("__field_{}", field.ident);
+        let ty = field.ty;
+        if field.flatten {
+            quote! {{
+                let mut decoder = ::bendy::decoding::Decoder::new_with_options(raw, options.clone())
+                    .with_offset_base(start)
+                    .with_max_depth(<#ty as ::bendy::decoding::FromBencode>::EXPECTED_RECURSION_DEPTH);
+                let value = match decoder.next_object()? {
+                    ::core::option::Option::Some(object) => {
+                        <#ty as ::bendy::decoding::FromBencode>::decode_bencode_object(object)?
+                    },
+                    ::core::option::Option::None => ::core::unreachable!(),
+                };
+                value
+            }}
+        } else if field.is_optional() {
+            quote!(#slot.unwrap_or_default())
+        } else {
+            let name = &field.name;
+            quote! {
+                #slot.ok_or_else(|| ::bendy::decoding::Error::missing_field(#name))?
+            }
+        }
+    });
+    let tag = tag.map(|tag| quote!(#tag => (),));
+    let (offset, unknown) = if deny_unknown_fields {
+        let offset = quote!(let offset = dict.offset(););
+        let unknown = quote! {
+            _ => {
+                return ::core::result::Result::Err(
+                    ::bendy::decoding::Error::unknown_field(key, offset),
+                );
+            },
+        };
+        (Some(offset), unknown)
+    } else {
+        (None, quote!(_ => (),))
+    };
+
+    // Flattened fields pick their entries out of the same dictionary, so it's decoded
+    // once more for each of them.
+    let preamble = if fields.iter().any(|field| field.flatten) {
+        Some(quote! {
+            let dict = object.try_into_dictionary()?;
+            let start = dict.start_offset();
+            let options = dict.options().clone();
+            let raw = dict.into_raw()?;
+            let mut decoder = ::bendy::decoding::Decoder::new_with_options(raw, options.clone())
+                .with_offset_base(start)
+                .with_max_depth(<Self as ::bendy::decoding::FromBencode>::EXPECTED_RECURSION_DEPTH);
+            let object = match decoder.next_object()? {
+                ::core::option::Option::Some(object) => object,
+                // `raw` holds the complete dictionary which was just decoded
+                ::core::option::Option::None => ::core::unreachable!(),
+            };
+        })
+    } else {
+        None
+    };
+
+    quote! {{
+        #preamble
+        #(let mut #slots = ::core::option::Option::None;)*
+
+        let mut dict = object.try_into_dictionary()?;
+        loop {
+            #offset
+            let (key, value) = match dict.next_pair()? {
+                ::core::option::Option::Some(pair) => pair,
+                ::core::option::Option::None => break,
+            };
+            match key {
+                #(
+                    #keys => {
+                        #slots = ::core::option::Option::Some(
+                            <#types as ::bendy::decoding::FromBencode>::decode_bencode_object(value)
+                                .map_err(|error| error.context_field(#names))?,
+                        );
+                    },
+                )*
+                #tag
+                #unknown
+            }
+        }
+
+        ::core::result::Result::Ok(#constructor {
+            #(#idents: #values,)*
+        })
+    }}
+}
+
+fn decode_list(constructor: &TokenStream, fields: &FieldsUnnamed) -> TokenStream {
+    let types = fields.unnamed.iter().map(|field| &field.ty);
+    let indices: Vec<_> = (0..fields.unnamed.len()).map(|i| i.to_string()).collect();
+    let positions = 0..fields.unnamed.len();
+    let slots: Vec<_> = (0..fields.unnamed.len())
+        .map(|i| format_ident!("__field_{}", i))
+        .collect();
+    let len = fields.unnamed.len();
+
+    quote! {{
+        let mut list = object.try_into_list()?;
+        #(
+            let #slots = match list.next_object()? {
+                ::core::option::Option::Some(item) => {
+                    <#types as ::bendy::decoding::FromBencode>::decode_bencode_object(item)
+                        .map_err(|error| error.context_index(#positions))?
+                },
+                ::core::option::Option::None => {
+                    return ::core::result::Result::Err(
+                        ::bendy::decoding::Error::missing_field(#indices),
+                    );
+                },
+            };
+        )*
+        if list.next_object()?.is_some() {
+            return ::core::result::Result::Err(::bendy::decoding::Error::unexpected_field(#len));
+        }
+
+        ::core::result::Result::Ok(#constructor(#(#slots),*))
+    }}
+}
+
+fn constructor(variant: &Variant) -> TokenStream {
+    let ident = variant.ident;
+    quote!(Self::#ident)
+}
+
+/// Unit variants are byte strings holding their name, all other variants are
+/// dictionaries with the name as their only key.
+fn decode_externally_tagged(
+    container: &Container,
+    variants: &[Variant],
+) -> syn::Result<(TokenStream, TokenStream)> {
+    let depth = max_depth(
+        variants
+            .iter()
+            .map(|variant| match variant.fields {
+                Fields::Unit => Ok(quote!(0)),
+                fields => {
+                    let depth = fields_depth(variant.rename_all, fields)?;
+                    Ok(quote!((#depth + 1)))
+                },
+            })
+            .collect::<syn::Result<Vec<_>>>()?
+            .into_iter(),
+    );
+
+    let (units, others): (Vec<_>, Vec<_>) = variants
+        .iter()
+        .partition(|variant| matches!(variant.fields, Fields::Unit));
+
+    let bytes_arm = if units.is_empty() {
+        None
+    } else {
+        let tags = units.iter().map(|variant| variant.tag());
+        let constructors = units.iter().map(|variant| constructor(variant));
+        Some(quote! {
+            ::bendy::decoding::Object::Bytes(tag) => match tag {
+                #(#tags => ::core::result::Result::Ok(#constructors),)*
+                _ => ::core::result::Result::Err(::bendy::decoding::Error::unknown_variant(tag)),
+            },
+        })
+    };
+
+    let dict_arm = if others.is_empty() {
+        None
+    } else {
+        let tags = others.iter().map(|variant| variant.tag());
+        let decoders = others
+            .iter()
+            .map(|variant| {
+                decode_fields(
+                    &constructor(variant),
+                    variant.rename_all,
+                    variant.fields,
+                    None,
+                    container.deny_unknown_fields,
+                )
+            })
+            .collect::<syn::Result<Vec<_>>>()?;
+        Some(quote! {
+            ::bendy::decoding::Object::Dict(mut dict) => {
+                let (tag, object) = dict
+                    .next_pair()?
+                    .ok_or_else(|| ::bendy::decoding::Error::missing_field("variant"))?;
+                let value = match tag {
+                    #(
+                        #tags => {
+                            let value: ::core::result::Result<Self, ::bendy::decoding::Error> =
+                                #decoders;
+                            value?
+                        },
+                    )*
+                    _ => {
+                        return ::core::result::Result::Err(
+                            ::bendy::decoding::Error::unknown_variant(tag),
+                        );
+                    },
+                };
+                if dict.next_pair()?.is_some() {
+                    return ::core::result::Result::Err(
+                        ::bendy::decoding::Error::unexpected_token("End", "String"),
+                    );
+                }
+                ::core::result::Result::Ok(value)
+            },
+        })
+    };
+
+    let expected = match (&bytes_arm, &dict_arm) {
+        (Some(_), Some(_)) => "String or Dict",
+        (Some(_), None) => "String",
+        (None, _) => "Dict",
+    };
+    let body = quote! {
+        match object {
+            #bytes_arm
+            #dict_arm
+            object => ::core::result::Result::Err(::bendy::decoding::Error::unexpected_token(
+                #expected,
+                object.into_token().name(),
+            )),
+        }
+    };
+
+    Ok((depth, body))
+}
+
+/// Variants are dictionaries holding their name under the tag key, next to the fields
+/// of the variant.
+fn decode_internally_tagged(
+    container: &Container,
+    tag: &LitByteStr,
+    variants: &[Variant],
+) -> syn::Result<(TokenStream, TokenStream)> {
+    let depth = max_depth(
+        variants
+            .iter()
+            .map(|variant| fields_depth(variant.rename_all, variant.fields))
+            .collect::<syn::Result<Vec<_>>>()?
+            .into_iter(),
+    );
+    // Unit variants still are a dictionary holding the tag
+    let depth = quote!({
+        let depth = #depth;
+        if depth > 1 { depth } else { 1 }
+    });
+
+    let tag_name = String::from_utf8(tag.value()).unwrap();
+    let tags = variants.iter().map(Variant::tag);
+    let decoders = variants
+        .iter()
+        .map(|variant| {
+            decode_fields(
+                &constructor(variant),
+                variant.rename_all,
+                variant.fields,
+                Some(tag),
+                container.deny_unknown_fields,
+            )
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+    let decode_variant = if variants
+        .iter()
+        .all(|variant| matches!(variant.fields, Fields::Unit))
+    {
+        quote! {
+            match tag {
+                #(#tags => #decoders,)*
+                _ => ::core::result::Result::Err(::bendy::decoding::Error::unknown_variant(tag)),
+            }
+        }
+    } else {
+        quote! {
+            let mut decoder = ::bendy::decoding::Decoder::new_with_options(raw, options.clone())
+                .with_offset_base(start)
+                .with_max_depth(max_depth);
+            let object = match decoder.next_object()? {
+                ::core::option::Option::Some(object) => object,
+                // `raw` holds the complete dictionary which was just decoded
+                ::core::option::Option::None => ::core::unreachable!(),
+            };
+            let value = match tag {
+                #(#tags => #decoders,)*
+                _ => ::core::result::Result::Err(::bendy::decoding::Error::unknown_variant(tag)),
+            };
+            value
+        }
+    };
+
+    // The tag may come after other keys, so the dictionary is decoded twice: once to
+    // find the tag, and once more to decode the fields of the variant it names.
+    let body = quote! {
+        let dict = object.try_into_dictionary()?;
+        let start = dict.start_offset();
+        let options = dict.options().clone();
+        let raw = dict.into_raw()?;
+        let max_depth = <Self as ::bendy::decoding::FromBencode>::EXPECTED_RECURSION_DEPTH;
+        // Of repeated tags, the decoder only skips all but the first one by itself
+        let last_wins = options.duplicate_keys() == ::bendy::decoding::DuplicateKeys::LastWins;
+
+        let mut tag = ::core::option::Option::None;
+        let mut decoder = ::bendy::decoding::Decoder::new_with_options(raw, options.clone())
+            .with_offset_base(start)
+            .with_max_depth(max_depth);
+        if let ::core::option::Option::Some(object) = decoder.next_object()? {
+            let mut dict = object.try_into_dictionary()?;
+            while let ::core::option::Option::Some((key, value)) = dict.next_pair()? {
+                if key == #tag {
+                    tag = ::core::option::Option::Some(
+                        value.try_into_bytes().map_err(|error| error.context_field(#tag_name))?,
+                    );
+                    if !last_wins {
+                        break;
+                    }
+                }
+            }
+        }
+        let tag = tag.ok_or_else(|| ::bendy::decoding::Error::missing_field(#tag_name))?;
+        #decode_variant
+    };
+
+    Ok((depth, body))
+}
+
+/// Variants are integers holding their discriminant.
+fn decode_integer_enum(variants: &[Variant]) -> TokenStream {
+    let constructors: Vec<_> = variants.iter().map(constructor).collect();
+
+    quote! {
+        let text = object.try_into_integer()?;
+        let value: i64 = text.parse()?;
+        #(
+            if value == (#constructors as i64) {
+                return ::core::result::Result::Ok(#constructors);
+            }
+        )*
+        ::core::result::Result::Err(::bendy::decoding::Error::unknown_variant(text))
+    }
+}
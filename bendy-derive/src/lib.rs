@@ -0,0 +1,99 @@
+//! Derive macros for the `ToBencode` and `FromBencode` traits of [bendy].
+//!
+//! Don't depend on this crate directly; enable the `derive` feature of bendy instead,
+//! which re-exports the macros next to the traits they implement.
+//!
+//! Structs with named fields are represented as dictionaries keyed by the field names,
+//! tuple structs as lists and newtype structs as their only field.
+//!
+//! Enums are externally tagged by default: unit variants are byte strings holding the
+//! variant name, and all other variants are dictionaries with the variant name as their
+//! only key, mapping to the variant's fields. Alternatively, enums can be represented
+//! as
+//!
+//! - internally tagged dictionaries, holding the variant name under a given key next to
+//!   the fields of the variant, as used by KRPC: `#[bendy(tag = "y")]`. Only unit and
+//!   struct variants are supported.
+//! - integers holding the discriminant of the variant: `#[bendy(integer)]`. Only unit
+//!   variants are supported.
+//!
+//! # Attributes
+//!
+//! - `#[bendy(rename = "piece length")]` on a field or variant stores it under the given
+//!   name instead of its identifier.
+//! - `#[bendy(rename_all = "kebab-case")]` on a struct or struct variant renames all
+//!   fields without an explicit `rename`; on an enum, it renames the variants. The supported conventions are `"lowercase"`, `"UPPERCASE"`,
+//!   `"PascalCase"`, `"camelCase"`, `"snake_case"`, `"SCREAMING_SNAKE_CASE"`,
+//!   `"kebab-case"`, `"SCREAMING-KEBAB-CASE"` and `"space separated"`.
+//! - `#[bendy(deny_unknown_fields)]` on a struct or enum fails decoding dictionaries with
+//!   keys that don't belong to any field, reporting the key and its offset in the input.
+//!   By default, unknown keys are ignored. This can't be combined with `flatten`.
+//! - `#[bendy(default)]` on a field decodes a missing key to `Default::default()`.
+//! - `#[bendy(flatten)]` on a field merges the entries of the dictionary it encodes to into
+//!   the dictionary of the surrounding struct, and picks them out of it when decoding.
+//!   This allows sharing common keys between messages, like the `t` and `v` keys of KRPC.
+//!   As the keys of the field are only known at runtime, the entries of structs with
+//!   flattened fields are sorted while encoding.
+//!
+//! Fields of type `Option<T>` are optional: a missing key decodes to `None`, and `None`
+//! isn't emitted at all.
+//!
+//! [bendy]: https://docs.rs/bendy
+
+extern crate proc_macro;
+
+mod attr;
+mod from_bencode;
+mod to_bencode;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, DeriveInput, Generics, TypeParamBound};
+
+/// Derive `bendy::decoding::FromBencode`.
+#[proc_macro_derive(FromBencode, attributes(bendy))]
+pub fn derive_from_bencode(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    from_bencode::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derive `bendy::encoding::ToBencode`.
+#[proc_macro_derive(ToBencode, attributes(bendy))]
+pub fn derive_to_bencode(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    to_bencode::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Require every type parameter to implement the derived trait.
+fn with_bound(generics: &Generics, bound: TypeParamBound) -> Generics {
+    let mut generics = generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(bound.clone());
+    }
+    generics
+}
+
+/// A constant expression for the largest of the given depths.
+fn max_depth(depths: impl Iterator<Item = TokenStream2>) -> TokenStream2 {
+    quote! {{
+        let mut depth = 0;
+        #(
+            if #depths > depth {
+                depth = #depths;
+            }
+        )*
+        depth
+    }}
+}
+
+/// A constant expression for the depth of a container holding items of the given
+/// depths.
+fn container_depth(item_depths: impl Iterator<Item = TokenStream2>) -> TokenStream2 {
+    let depth = max_depth(item_depths);
+    quote!((#depth + 1))
+}
@@ -0,0 +1,320 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse_quote, Data, DeriveInput, Fields, Index, LitByteStr};
+
+use crate::{
+    attr::{self, Container, DictField, RenameRule, Variant},
+    container_depth, max_depth, with_bound,
+};
+
+pub fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let container = Container::from_ast(input)?;
+    let generics = with_bound(&input.generics, parse_quote!(::bendy::encoding::ToBencode));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let (depth, body) = match &input.data {
+        Data::Struct(data) => {
+            if let Fields::Unit = data.fields {
+                return Err(syn::Error::new_spanned(
+                    input,
+                    "ToBencode can't be derived for unit structs",
+                ));
+            }
+            let depth = fields_depth(container.rename_all, &data.fields)?;
+            let body = encode_struct(container.rename_all, &data.fields)?;
+            (depth, body)
+        },
+        Data::Enum(data) => {
+            let variants = attr::variants(&container, data)?;
+            if container.integer {
+                (quote!(0), encode_integer_enum(&variants))
+            } else if let Some(tag) = container.tag_key() {
+                encode_internally_tagged(&tag, &variants)?
+            } else {
+                encode_externally_tagged(&variants)?
+            }
+        },
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "ToBencode can't be derived for unions",
+            ));
+        },
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::bendy::encoding::ToBencode for #name #ty_generics #where_clause {
+            const MAX_DEPTH: usize = #depth;
+
+            fn encode(
+                &self,
+                encoder: ::bendy::encoding::SingleItemEncoder,
+            ) -> ::core::result::Result<(), ::bendy::encoding::Error> {
+                #body
+            }
+        }
+    })
+}
+
+fn item_depth(ty: &syn::Type) -> TokenStream {
+    quote!(<#ty as ::bendy::encoding::ToBencode>::MAX_DEPTH)
+}
+
+/// The depth of the representation of the given fields.
+fn fields_depth(rename_all: Option<RenameRule>, fields: &Fields) -> syn::Result<TokenStream> {
+    Ok(match fields {
+        Fields::Named(fields) => {
+            let fields = attr::dict_fields(rename_all, fields)?;
+            container_depth(fields.iter().map(|field| {
+                let depth = item_depth(field.option.unwrap_or(field.ty));
+                if field.flatten {
+                    // The entries of the flattened dictionary are on the level of this one
+                    quote!(#depth.saturating_sub(1))
+                } else {
+                    depth
+                }
+            }))
+        },
+        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => item_depth(&fields.unnamed[0].ty),
+        Fields::Unnamed(fields) => {
+            container_depth(fields.unnamed.iter().map(|field| item_depth(&field.ty)))
+        },
+        Fields::Unit => quote!(0),
+    })
+}
+
+/// A binding for a field of an enum variant, which can't clash with the names used in
+/// the generated code.
+fn binding(member: impl quote::IdentFragment) -> syn::Ident {
+    format_ident!("__field_{}", member)
+}
+
+fn encode_struct(rename_all: Option<RenameRule>, fields: &Fields) -> syn::Result<TokenStream> {
+    match fields {
+        Fields::Named(fields) => {
+            let fields = attr::dict_fields(rename_all, fields)?;
+            let pairs = fields
+                .iter()
+                .map(|field| {
+                    let ident = field.ident;
+                    (field, quote!(&self.#ident))
+                })
+                .collect();
+            Ok(encode_dict(pairs, None))
+        },
+        Fields::Unnamed(fields) => {
+            attr::check_list_fields(fields)?;
+            let values: Vec<_> = (0..fields.unnamed.len())
+                .map(|i| {
+                    let index = Index::from(i);
+                    quote!(&self.#index)
+                })
+                .collect();
+            Ok(encode_items(&values))
+        },
+        Fields::Unit => unreachable!("unit structs are rejected"),
+    }
+}
+
+/// Emit a dictionary holding the given fields, and the given tag. Each field comes with
+/// an expression returning a reference to its value.
+fn encode_dict(
+    pairs: Vec<(&DictField, TokenStream)>,
+    tag: Option<(&LitByteStr, &str)>,
+) -> TokenStream {
+    // The entries of flattened fields are only known at runtime, so they need to be
+    // sorted by the encoder
+    let (flattened, pairs): (Vec<_>, Vec<_>) =
+        pairs.into_iter().partition(|(field, _)| field.flatten);
+    if !flattened.is_empty() {
+        let values = flattened.iter().map(|(_, value)| value);
+        let statements = pairs.iter().map(|(field, value)| emit_pair(field, value));
+        let tag = tag.map(|(key, name)| emit_tag(key, name));
+        return quote! {
+            encoder.emit_unsorted_dict(|dict| {
+                #(#statements)*
+                #tag
+                #(dict.emit_flattened(#values)?;)*
+                ::core::result::Result::Ok(())
+            })
+        };
+    }
+
+    // Otherwise, emit the pairs in canonical order right away instead of sorting at
+    // runtime
+    let mut statements: Vec<(Vec<u8>, TokenStream)> = pairs
+        .into_iter()
+        .map(|(field, value)| (field.key().value(), emit_pair(field, &value)))
+        .collect();
+    if let Some((key, name)) = tag {
+        statements.push((key.value(), emit_tag(key, name)));
+    }
+    statements.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    if statements.is_empty() {
+        quote!(encoder.emit_dict(|_| ::core::result::Result::Ok(())))
+    } else {
+        let statements = statements.iter().map(|(_, statement)| statement);
+        quote! {
+            encoder.emit_dict(|mut dict| {
+                #(#statements)*
+                ::core::result::Result::Ok(())
+            })
+        }
+    }
+}
+
+/// Emit a field into `dict`, given an expression returning a reference to its value.
+fn emit_pair(field: &DictField, value: &TokenStream) -> TokenStream {
+    let key = field.key();
+    if field.option.is_some() {
+        quote! {
+            if let ::core::option::Option::Some(value) = #value {
+                dict.emit_pair(#key, value)?;
+            }
+        }
+    } else {
+        quote!(dict.emit_pair(#key, #value)?;)
+    }
+}
+
+/// Emit the tag of an internally tagged variant into `dict`.
+fn emit_tag(key: &LitByteStr, name: &str) -> TokenStream {
+    let name = LitByteStr::new(name.as_bytes(), key.span());
+    quote!(dict.emit_pair_with(#key, |e| e.emit_bytes(#name))?;)
+}
+
+/// Emit the given values as a list, or as the value itself if there is only one.
+fn encode_items(values: &[TokenStream]) -> TokenStream {
+    if let [value] = values {
+        quote!(::bendy::encoding::ToBencode::encode(#value, encoder))
+    } else {
+        quote! {
+            encoder.emit_list(|list| {
+                #(list.emit(#values)?;)*
+                ::core::result::Result::Ok(())
+            })
+        }
+    }
+}
+
+/// A pattern binding the fields of the given variant, and the encoding of its fields.
+fn encode_variant(
+    variant: &Variant,
+    tag: Option<&LitByteStr>,
+) -> syn::Result<(TokenStream, TokenStream)> {
+    let ident = variant.ident;
+    Ok(match variant.fields {
+        Fields::Named(fields) => {
+            let fields = attr::dict_fields(variant.rename_all, fields)?;
+            let idents = fields.iter().map(|field| field.ident);
+            let bindings: Vec<_> = fields.iter().map(|field| binding(field.ident)).collect();
+            let pattern = quote!(Self::#ident { #(#idents: #bindings),* });
+            let pairs = fields
+                .iter()
+                .zip(&bindings)
+                .map(|(field, binding)| (field, quote!(#binding)))
+                .collect();
+            let tag = tag.map(|tag| (tag, variant.name.as_str()));
+            (pattern, encode_dict(pairs, tag))
+        },
+        Fields::Unnamed(fields) => {
+            attr::check_list_fields(fields)?;
+            let bindings: Vec<_> = (0..fields.unnamed.len()).map(binding).collect();
+            let values: Vec<_> = bindings.iter().map(|binding| quote!(#binding)).collect();
+            (quote!(Self::#ident(#(#bindings),*)), encode_items(&values))
+        },
+        Fields::Unit => {
+            let name = variant.tag();
+            let body = match tag {
+                Some(tag) => quote! {
+                    encoder.emit_dict(|mut dict| {
+                        dict.emit_pair_with(#tag, |e| e.emit_bytes(#name))
+                    })
+                },
+                None => quote!(encoder.emit_bytes(#name)),
+            };
+            (quote!(Self::#ident), body)
+        },
+    })
+}
+
+/// Unit variants are byte strings holding their name, all other variants are
+/// dictionaries with the name as their only key.
+fn encode_externally_tagged(variants: &[Variant]) -> syn::Result<(TokenStream, TokenStream)> {
+    let depth = max_depth(
+        variants
+            .iter()
+            .map(|variant| match variant.fields {
+                Fields::Unit => Ok(quote!(0)),
+                fields => {
+                    let depth = fields_depth(variant.rename_all, fields)?;
+                    Ok(quote!((#depth + 1)))
+                },
+            })
+            .collect::<syn::Result<Vec<_>>>()?
+            .into_iter(),
+    );
+
+    let arms = variants
+        .iter()
+        .map(|variant| {
+            let (pattern, body) = encode_variant(variant, None)?;
+            Ok(match variant.fields {
+                Fields::Unit => quote!(#pattern => #body,),
+                _ => {
+                    let name = variant.tag();
+                    quote! {
+                        #pattern => encoder.emit_dict(|mut dict| {
+                            dict.emit_pair_with(#name, |encoder| #body)
+                        }),
+                    }
+                },
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    Ok((depth, quote!(match self { #(#arms)* })))
+}
+
+/// Variants are dictionaries holding their name under the tag key, next to the fields
+/// of the variant.
+fn encode_internally_tagged(
+    tag: &LitByteStr,
+    variants: &[Variant],
+) -> syn::Result<(TokenStream, TokenStream)> {
+    let depth = max_depth(
+        variants
+            .iter()
+            .map(|variant| fields_depth(variant.rename_all, variant.fields))
+            .collect::<syn::Result<Vec<_>>>()?
+            .into_iter(),
+    );
+    // Unit variants still are a dictionary holding the tag
+    let depth = quote!({
+        let depth = #depth;
+        if depth > 1 { depth } else { 1 }
+    });
+
+    let arms = variants
+        .iter()
+        .map(|variant| {
+            let (pattern, body) = encode_variant(variant, Some(tag))?;
+            Ok(quote!(#pattern => #body,))
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    Ok((depth, quote!(match self { #(#arms)* })))
+}
+
+/// Variants are integers holding their discriminant.
+fn encode_integer_enum(variants: &[Variant]) -> TokenStream {
+    let idents = variants.iter().map(|variant| variant.ident);
+
+    quote! {
+        match self {
+            #(Self::#idents => encoder.emit_int(Self::#idents as i64),)*
+        }
+    }
+}
@@ -0,0 +1,247 @@
+//! A [`tokio_util::codec`] implementation for streams of back to back bencoded values.
+//!
+//! ```
+//! use bendy::{codec::BencodeCodec, value::Value};
+//! use bytes::BytesMut;
+//! use tokio_util::codec::{Decoder, Encoder};
+//!
+//! let mut codec = BencodeCodec::new();
+//! let mut buffer = BytesMut::new();
+//! let value = Value::list().with_item(1).with_item(2);
+//! codec.encode(&value, &mut buffer).unwrap();
+//! buffer.extend_from_slice(b"d1:y");
+//!
+//! assert_eq!(codec.decode(&mut buffer).unwrap(), Some(value));
+//! assert_eq!(codec.decode(&mut buffer).unwrap(), None);
+//! assert_eq!(&buffer[..], b"d1:y");
+//! ```
+//!
+//! Wrapped in a `Framed`, e.g. `Framed::new(socket, BencodeCodec::new())`, the codec turns
+//! a socket into a stream of [`Value`]s and a sink of anything implementing
+//! [`ToBencode`].
+
+use bytes::BytesMut;
+use tokio_util::codec;
+
+use crate::{
+    decoding::{self, DecodeOptions, Decoder, ErrorKind, FromBencode, Limit},
+    encoding::{self, ToBencode},
+    state_tracker::{StructureError, Token},
+    value::Value,
+};
+
+/// The maximum length of a frame accepted by default, 16 MiB
+pub const DEFAULT_MAX_FRAME_LENGTH: usize = 16 << 20;
+
+/// A codec splitting a byte stream into bencoded values, and encoding values into it
+///
+/// Each decoded frame is exactly one value, which is decoded into an owned [`Value`]
+/// once all of it arrived. The tokens of an incomplete frame are only scanned once, no
+/// matter how many reads it arrives in. The [`DecodeOptions`] apply to every frame.
+///
+/// Frames are limited to [`DEFAULT_MAX_FRAME_LENGTH`] bytes, or the maximum document size
+/// of the options if that is smaller, so a peer can't make the codec buffer an endless
+/// value.
+#[derive(Clone, Debug)]
+pub struct BencodeCodec {
+    options: DecodeOptions,
+    max_frame_length: usize,
+    /// The length of the tokens of the next frame scanned so far
+    scanned: usize,
+    /// The number of lists and dictionaries open after the scanned tokens
+    depth: usize,
+}
+
+impl Default for BencodeCodec {
+    fn default() -> Self {
+        Self::new_with_options(DecodeOptions::default())
+    }
+}
+
+impl BencodeCodec {
+    /// Create a codec decoding canonical bencode
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a codec validating each frame as configured by the given options
+    pub fn new_with_options(options: DecodeOptions) -> Self {
+        BencodeCodec {
+            options,
+            max_frame_length: DEFAULT_MAX_FRAME_LENGTH,
+            scanned: 0,
+            depth: 0,
+        }
+    }
+
+    /// Set the maximum length of a frame, which defaults to [`DEFAULT_MAX_FRAME_LENGTH`].
+    /// Longer frames fail with a [`Limit::DocumentSize`] error.
+    #[must_use]
+    pub fn with_max_frame_length(mut self, max_frame_length: usize) -> Self {
+        self.max_frame_length = max_frame_length;
+        self
+    }
+
+    /// Scan the tokens of the next frame which arrived since the last call. Returns the
+    /// length of the frame once it is complete.
+    fn scan_frame(&mut self, src: &[u8]) -> Result<Option<usize>, decoding::Error> {
+        // Frames following the first one don't count towards its size
+        let max_length = self.max_frame_length.min(self.options.max_document_size);
+        let window = &src[..src.len().min(max_length)];
+
+        while self.scanned == 0 || self.depth > 0 {
+            let source = &window[self.scanned..];
+            let (token, length) = match Decoder::read_raw_token(source, self.scanned, &self.options)
+            {
+                Ok(token) => token,
+                Err(error) => match error.kind() {
+                    ErrorKind::StructureError(StructureError::UnexpectedEof) => {
+                        if src.len() > max_length {
+                            let limit = Limit::DocumentSize(max_length);
+                            return Err(decoding::Error::limit_exceeded(limit));
+                        }
+                        return Ok(None);
+                    },
+                    _ => return Err(error),
+                },
+            };
+
+            self.scanned += length;
+            match token {
+                Token::List | Token::Dict => self.depth += 1,
+                // A stray end at the top level makes a frame of its own, which fails to
+                // decode
+                Token::End => self.depth = self.depth.saturating_sub(1),
+                Token::String(_) | Token::Num(_) => (),
+            }
+        }
+
+        Ok(Some(self.scanned))
+    }
+}
+
+impl codec::Decoder for BencodeCodec {
+    type Error = decoding::Error;
+    type Item = Value<'static>;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let length = match self.scan_frame(src) {
+            Ok(Some(length)) => length,
+            Ok(None) => return Ok(None),
+            Err(error) => {
+                // Start over in case the codec is used with another buffer
+                self.scanned = 0;
+                self.depth = 0;
+                return Err(error);
+            },
+        };
+
+        self.scanned = 0;
+        let frame = src.split_to(length);
+        let value = Value::from_bencode_with_options(&frame, self.options.clone())?;
+        Ok(Some(value.into_owned()))
+    }
+}
+
+impl<T: ToBencode> codec::Encoder<T> for BencodeCodec {
+    type Error = encoding::Error;
+
+    fn encode(&mut self, item: T, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        dst.extend_from_slice(&item.to_bencode()?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tokio_util::codec::{Decoder, Encoder};
+
+    use super::*;
+    use crate::decoding::ErrorKind;
+
+    #[test]
+    fn frames_should_be_split_at_value_boundaries() {
+        let mut codec = BencodeCodec::new();
+        let mut buffer = BytesMut::new();
+        let input = b"d1:ai1eeli2ei3ee4:spam";
+
+        let mut values = Vec::new();
+        for &byte in input.iter() {
+            buffer.extend_from_slice(&[byte]);
+            while let Some(value) = codec.decode(&mut buffer).unwrap() {
+                values.push(value);
+            }
+        }
+
+        let expected: Vec<Value> = vec![
+            Value::dict().with_entry("a", 1),
+            Value::list().with_item(2).with_item(3),
+            Value::from("spam"),
+        ];
+        assert_eq!(values, expected);
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn oversized_and_malformed_frames_should_fail() {
+        let options = DecodeOptions::new().with_max_document_size(8);
+        let mut codec = BencodeCodec::new_with_options(options);
+
+        let mut buffer = BytesMut::from(&b"li1ei2eei3e"[..]);
+        assert!(codec.decode(&mut buffer).unwrap().is_some());
+        assert!(codec.decode(&mut buffer).unwrap().is_some());
+
+        let mut buffer = BytesMut::from(&b"li1ei2ei3"[..]);
+        let error = codec.decode(&mut buffer).unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::LimitExceeded(_)));
+
+        let mut buffer = BytesMut::from(&b"lx"[..]);
+        assert!(codec.decode(&mut buffer).is_err());
+    }
+
+    #[test]
+    fn frames_should_be_scanned_incrementally() {
+        let mut codec = BencodeCodec::new();
+        let mut buffer = BytesMut::from(&b"ld1:ai1"[..]);
+        assert_eq!(codec.decode(&mut buffer).unwrap(), None);
+        assert_eq!((codec.scanned, codec.depth), (5, 2));
+
+        buffer.extend_from_slice(b"ee5:hel");
+        assert_eq!(codec.decode(&mut buffer).unwrap(), None);
+        assert_eq!((codec.scanned, codec.depth), (9, 1));
+
+        buffer.extend_from_slice(b"loei1e");
+        let expected = Value::list()
+            .with_item(Value::dict().with_entry("a", 1))
+            .with_item("hello");
+        assert_eq!(codec.decode(&mut buffer).unwrap(), Some(expected));
+        assert_eq!((codec.scanned, codec.depth), (0, 0));
+        assert_eq!(codec.decode(&mut buffer).unwrap(), Some(Value::from(1)));
+    }
+
+    #[test]
+    fn frames_should_be_limited_by_default() {
+        let mut codec = BencodeCodec::new();
+        let mut buffer = BytesMut::from(&b"99999999:"[..]);
+        buffer.resize(DEFAULT_MAX_FRAME_LENGTH + 1, b'x');
+        let error = codec.decode(&mut buffer).unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ErrorKind::LimitExceeded(Limit::DocumentSize(DEFAULT_MAX_FRAME_LENGTH))
+        ));
+
+        let mut codec = BencodeCodec::new().with_max_frame_length(4);
+        let mut buffer = BytesMut::from(&b"i12e5:hello"[..]);
+        assert_eq!(codec.decode(&mut buffer).unwrap(), Some(Value::from(12)));
+        assert!(codec.decode(&mut buffer).is_err());
+    }
+
+    #[test]
+    fn values_should_be_encoded_back_to_back() {
+        let mut codec = BencodeCodec::new();
+        let mut buffer = BytesMut::new();
+        codec.encode(vec![1], &mut buffer).unwrap();
+        codec.encode(7_u8, &mut buffer).unwrap();
+        assert_eq!(&buffer[..], b"li1eei7e");
+    }
+}
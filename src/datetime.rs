@@ -0,0 +1,113 @@
+//! Timestamps encoded as integers of seconds since the Unix epoch, like the
+//! `creation date` of torrent files. Fractions of a second are dropped when encoding.
+
+use crate::{
+    decoding::{Error, FromBencode, Object},
+    encoding::{self, SingleItemEncoder, ToBencode},
+};
+
+/// The error of a timestamp which can't be represented by the target type
+fn timestamp_out_of_range(seconds: i64) -> Error {
+    Error::unexpected_token("a timestamp in the supported range", seconds)
+}
+
+/// ```
+/// use bendy::{decoding::FromBencode, encoding::ToBencode};
+/// use chrono::{DateTime, TimeZone, Utc};
+///
+/// let date = DateTime::<Utc>::from_bencode(b"i1577836800e").unwrap();
+/// assert_eq!(date, Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap());
+/// assert_eq!(date.to_bencode().unwrap(), b"i1577836800e");
+/// ```
+#[cfg(feature = "chrono")]
+impl FromBencode for chrono::DateTime<chrono::Utc> {
+    const EXPECTED_RECURSION_DEPTH: usize = 0;
+
+    fn decode_bencode_object(object: Object) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        let seconds = i64::decode_bencode_object(object)?;
+        chrono::DateTime::from_timestamp(seconds, 0).ok_or_else(|| timestamp_out_of_range(seconds))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl ToBencode for chrono::DateTime<chrono::Utc> {
+    const MAX_DEPTH: usize = 0;
+
+    fn encode(&self, encoder: SingleItemEncoder) -> Result<(), encoding::Error> {
+        encoder.emit_int(self.timestamp())
+    }
+}
+
+/// ```
+/// use bendy::{decoding::FromBencode, encoding::ToBencode};
+/// use time::OffsetDateTime;
+///
+/// let date = OffsetDateTime::from_bencode(b"i1577836800e").unwrap();
+/// assert_eq!(date.year(), 2020);
+/// assert_eq!(date.to_bencode().unwrap(), b"i1577836800e");
+/// ```
+#[cfg(feature = "time")]
+impl FromBencode for time::OffsetDateTime {
+    const EXPECTED_RECURSION_DEPTH: usize = 0;
+
+    fn decode_bencode_object(object: Object) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        let seconds = i64::decode_bencode_object(object)?;
+        time::OffsetDateTime::from_unix_timestamp(seconds)
+            .map_err(|_| timestamp_out_of_range(seconds))
+    }
+}
+
+/// Dates with an offset other than UTC encode the same instant as their UTC equivalent
+#[cfg(feature = "time")]
+impl ToBencode for time::OffsetDateTime {
+    const MAX_DEPTH: usize = 0;
+
+    fn encode(&self, encoder: SingleItemEncoder) -> Result<(), encoding::Error> {
+        encoder.emit_int(self.unix_timestamp())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn chrono_dates_should_round_trip() {
+        use chrono::{DateTime, TimeZone, Utc};
+
+        let date = Utc.with_ymd_and_hms(1969, 7, 20, 20, 17, 40).unwrap();
+        let encoded = date.to_bencode().unwrap();
+        assert_eq!(encoded, b"i-14182940e");
+        assert_eq!(DateTime::<Utc>::from_bencode(&encoded).unwrap(), date);
+
+        assert!(DateTime::<Utc>::from_bencode(b"i9223372036854775807e").is_err());
+        assert!(DateTime::<Utc>::from_bencode(b"4:2020").is_err());
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn time_dates_should_round_trip() {
+        use time::{Duration, OffsetDateTime, UtcOffset};
+
+        let date = OffsetDateTime::UNIX_EPOCH + Duration::seconds(1_700_000_000);
+        let encoded = date.to_bencode().unwrap();
+        assert_eq!(encoded, b"i1700000000e");
+        assert_eq!(OffsetDateTime::from_bencode(&encoded).unwrap(), date);
+
+        let offset = date.to_offset(UtcOffset::from_hms(2, 0, 0).unwrap());
+        assert_eq!(offset.to_bencode().unwrap(), encoded);
+
+        // Fractions of a second are dropped
+        let fraction = date + Duration::milliseconds(500);
+        assert_eq!(fraction.to_bencode().unwrap(), encoded);
+
+        assert!(OffsetDateTime::from_bencode(b"i9223372036854775807e").is_err());
+    }
+}
@@ -62,15 +62,101 @@
 //! #
 //! # assert!(syntax_check(b"i18e"));
 //! ```
+//!
+//! # Deriving `FromBencode`
+//!
+//! With the `derive` feature, `FromBencode` and `ToBencode` can be derived for structs and
+//! enums. Structs with named fields map to dictionaries keyed by the field names, tuple
+//! structs to lists and newtype structs to their only field. Enums can be represented by a
+//! tag, an integer or a tagged dictionary; see the [`bendy-derive`] documentation for all
+//! options.
+//!
+//! Unknown dictionary keys are ignored, and errors name the field that failed to decode.
+//! Keys which aren't valid identifiers can be set with `#[bendy(rename = "piece length")]`
+//! on a field, or for all fields with `#[bendy(rename_all = "kebab-case")]` on the struct.
+//! Fields of type `Option<T>` may be missing and are omitted when they are `None`, and
+//! missing fields marked with `#[bendy(default)]` take their default value:
+//!
+//! ```
+//! # #[cfg(feature = "derive")]
+//! # {
+//! use bendy::{decoding::FromBencode, encoding::ToBencode};
+//!
+//! #[derive(FromBencode, ToBencode, Debug, PartialEq)]
+//! struct Peer {
+//!     ip: String,
+//!     port: u16,
+//!     #[bendy(rename = "peer id")]
+//!     peer_id: Option<String>,
+//! }
+//!
+//! let encoded = b"d2:ip9:127.0.0.17:peer id3:abc4:porti6881ee";
+//! let peer = Peer::from_bencode(encoded).unwrap();
+//! assert_eq!(peer.port, 6881);
+//! assert_eq!(peer.to_bencode().unwrap(), encoded);
+//!
+//! let peer = Peer::from_bencode(b"d2:ip9:127.0.0.14:porti6881ee").unwrap();
+//! assert_eq!(peer.peer_id, None);
+//!
+//! let error = Peer::from_bencode(b"d2:ip0:7:peer id0:4:port4:6881e").unwrap_err();
+//! assert!(error.to_string().ends_with("in port"));
+//! # }
+//! ```
+//!
+//! [`bendy-derive`]: https://docs.rs/bendy-derive
 
+#[cfg(feature = "futures-io")]
+mod async_decoder;
+#[cfg(feature = "bytes")]
+mod buf;
+#[cfg(feature = "std")]
+mod chunks;
+mod compare;
 mod decoder;
+#[cfg(feature = "diagnostics")]
+mod diagnostic;
+mod digits;
 mod error;
+mod flat;
 mod from_bencode;
+#[cfg(feature = "mmap")]
+mod mmap;
 mod object;
+mod options;
+#[cfg(feature = "std")]
+mod reader;
+mod session;
+mod stream;
+mod validate;
+mod visitor;
 
+#[cfg(feature = "futures-io")]
+pub use self::async_decoder::AsyncDecoder;
+#[cfg(feature = "bytes")]
+pub use self::buf::BufDecoder;
+#[cfg(feature = "std")]
+pub use self::chunks::ChunksDecoder;
+#[cfg(feature = "diagnostics")]
+pub use self::diagnostic::Diagnostic;
+#[cfg(feature = "mmap")]
+pub use self::mmap::MappedFile;
+#[cfg(feature = "std")]
+pub use self::reader::ReaderDecoder;
 pub use self::{
-    decoder::{Decoder, DictDecoder, ListDecoder, Tokens},
-    error::{Error, ErrorKind, ResultExt},
+    compare::{canonical_eq, canonical_eq_with_options, Equality},
+    decoder::{Checkpoint, Decoder, DictDecoder, ListDecoder, Tokens, TypedTokens, Utf8Policy},
+    error::{Error, ErrorKind, Limit, PathSegment, ResultExt},
+    flat::{decode_flat_dict, FlatDict},
     from_bencode::FromBencode,
     object::Object,
+    options::{DecodeOptions, DuplicateKeys, Preset, DEFAULT_MAX_DEPTH},
+    session::Session,
+    stream::{Event, StreamDecoder},
+    validate::{
+        complete_len, complete_len_with_options, is_canonical, validate, validate_with_options,
+        Violation,
+    },
+    visitor::{decode_with_visitor, decode_with_visitor_with_options, Visitor},
 };
+#[cfg(feature = "derive")]
+pub use bendy_derive::FromBencode;
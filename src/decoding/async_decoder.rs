@@ -0,0 +1,291 @@
+use futures_util::{
+    io::{AsyncRead, AsyncReadExt},
+    stream::{self, Stream},
+};
+#[cfg(feature = "tokio")]
+use tokio_util::compat::{Compat, TokioAsyncReadCompatExt};
+
+use crate::{
+    decoding::{DecodeOptions, Error, Event, FromBencode, StreamDecoder},
+    value::Value,
+};
+
+/// The number of bytes read from the reader at once
+const BUFFER_SIZE: usize = 8 * 1024;
+
+/// A decoder reading from an [`AsyncRead`] of `futures-io`, as used by async-std and smol.
+/// With the `tokio` feature, [`AsyncDecoder::from_tokio`] reads from a tokio `AsyncRead`.
+///
+/// Reading suspends whenever more input is needed, so messages can be decoded as they
+/// arrive over the network. [`AsyncDecoder::next_token`] returns the input as owned
+/// [`Event`]s, while [`AsyncDecoder::next_object`] collects a complete value and decodes
+/// it with [`FromBencode`].
+///
+/// The reader is read in chunks, which may reach past the current value; the rest of a
+/// chunk is kept for the following calls.
+///
+/// ```
+/// # use bendy::decoding::{AsyncDecoder, Error, Event};
+/// # use tokio_ as tokio;
+/// #
+/// # async fn run() -> Result<(), Error> {
+/// let socket: &[u8] = b"d1:md11:ut_metadatai3ee1:pi6881eei1e";
+/// let mut decoder = AsyncDecoder::new(socket);
+///
+/// assert_eq!(decoder.next_token().await?, Some(Event::Dict));
+/// assert_eq!(decoder.next_token().await?, Some(Event::String(b"m".to_vec())));
+/// assert_eq!(decoder.next_token().await?, Some(Event::Dict));
+///
+/// let mut decoder = AsyncDecoder::new(socket);
+/// let handshake: bendy::value::Value = decoder.next_object().await?.unwrap();
+/// assert_eq!(handshake["p"], 6881.into());
+/// assert_eq!(decoder.next_object::<u8>().await?, Some(1));
+/// assert_eq!(decoder.next_object::<u8>().await?, None);
+/// # Ok(())
+/// # }
+/// #
+/// # tokio::runtime::Builder::new_current_thread()
+/// #     .build()
+/// #     .unwrap()
+/// #     .block_on(run())
+/// #     .unwrap();
+/// ```
+#[derive(Debug)]
+pub struct AsyncDecoder<R> {
+    reader: R,
+    stream: StreamDecoder,
+    chunk: Vec<u8>,
+    /// Whether the reader reached its end
+    eof: bool,
+}
+
+impl<R: AsyncRead + Unpin> AsyncDecoder<R> {
+    /// Create a new decoder reading from the given reader
+    pub fn new(reader: R) -> Self {
+        Self::new_with_options(reader, DecodeOptions::default())
+    }
+
+    /// Create a new decoder reading from the given reader, validating the input as
+    /// configured by the given options
+    pub fn new_with_options(reader: R, options: DecodeOptions) -> Self {
+        AsyncDecoder {
+            reader,
+            stream: StreamDecoder::new_with_options(options),
+            chunk: Vec::new(),
+            eof: false,
+        }
+    }
+
+    /// Read the next token, waiting for more input as needed. Returns `Ok(None)` at the
+    /// end of the input.
+    ///
+    /// This is cancellation safe: if the future is dropped before it completes, no input
+    /// is lost.
+    pub async fn next_token(&mut self) -> Result<Option<Event>, Error> {
+        self.pull(StreamDecoder::next_event).await
+    }
+
+    /// Read the next complete value and decode it. Returns `Ok(None)` if the input ends
+    /// before the value starts.
+    ///
+    /// This is cancellation safe as well: the part of the value read so far is kept for
+    /// the next call.
+    pub async fn next_object<T: FromBencode>(&mut self) -> Result<Option<T>, Error> {
+        match self.pull(StreamDecoder::next_value).await? {
+            Some(value) => {
+                T::from_bencode_with_options(&value, self.stream.options().clone()).map(Some)
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Read from the stream decoder, feeding it from the reader until it has enough input
+    async fn pull<T>(
+        &mut self,
+        mut read: impl FnMut(&mut StreamDecoder) -> Result<Option<T>, Error>,
+    ) -> Result<Option<T>, Error> {
+        loop {
+            if let Some(item) = read(&mut self.stream)? {
+                return Ok(Some(item));
+            }
+            if self.eof {
+                return Ok(None);
+            }
+
+            self.chunk.resize(BUFFER_SIZE, 0);
+            let length = self.reader.read(&mut self.chunk).await?;
+            if length == 0 {
+                self.eof = true;
+                self.stream.finish();
+            } else {
+                self.stream.feed(&self.chunk[..length]);
+            }
+        }
+    }
+
+    /// Turn the decoder into a stream of the values in the input, e.g. to apply timeouts
+    /// with the usual stream combinators. The stream ends after the first error.
+    ///
+    /// ```
+    /// # use bendy::{decoding::{AsyncDecoder, Error}, value::Value};
+    /// # use futures_util::StreamExt;
+    /// # use tokio_ as tokio;
+    /// #
+    /// # async fn run() -> Result<(), Error> {
+    /// let socket: &[u8] = b"d1:y1:qed1:y1:re";
+    /// let messages = AsyncDecoder::new(socket).into_stream();
+    ///
+    /// let kinds: Vec<Value> = messages
+    ///     .map(|message| message.unwrap()["y"].clone())
+    ///     .collect()
+    ///     .await;
+    /// assert_eq!(kinds, vec![Value::from("q"), Value::from("r")]);
+    /// # Ok(())
+    /// # }
+    /// #
+    /// # tokio::runtime::Builder::new_current_thread()
+    /// #     .build()
+    /// #     .unwrap()
+    /// #     .block_on(run())
+    /// #     .unwrap();
+    /// ```
+    pub fn into_stream(self) -> impl Stream<Item = Result<Value<'static>, Error>> {
+        stream::unfold(Some(self), |decoder| async move {
+            let mut decoder = decoder?;
+            match decoder.next_object().await {
+                Ok(Some(value)) => Some((Ok(value), Some(decoder))),
+                Ok(None) => None,
+                Err(error) => Some((Err(error), None)),
+            }
+        })
+    }
+
+    /// The offset of the next token in the input
+    pub fn position(&self) -> usize {
+        self.stream.position()
+    }
+
+    /// Get a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Consume the decoder, returning the underlying reader. Input which was already
+    /// taken from the reader but not read as a token yet is lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<R: tokio_::io::AsyncRead + Unpin> AsyncDecoder<Compat<R>> {
+    /// Create a new decoder reading from the given tokio reader. Use
+    /// [`AsyncDecoder::new_with_options`] with `reader.compat()` for other options.
+    ///
+    /// ```
+    /// # use bendy::decoding::AsyncDecoder;
+    /// # use tokio_ as tokio;
+    /// use tokio::io::AsyncWriteExt;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let (socket, mut peer) = tokio::io::duplex(64);
+    /// peer.write_all(b"i42e").await.unwrap();
+    /// drop(peer);
+    ///
+    /// let mut decoder = AsyncDecoder::from_tokio(socket);
+    /// assert_eq!(decoder.next_object::<u8>().await.unwrap(), Some(42));
+    /// assert_eq!(decoder.next_object::<u8>().await.unwrap(), None);
+    /// # });
+    /// ```
+    pub fn from_tokio(reader: R) -> Self {
+        Self::new(reader.compat())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use futures_util::StreamExt;
+    use std::{
+        future::Future,
+        io,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    use crate::decoding::ErrorKind;
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        tokio_::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    /// A reader returning one byte at a time, waking the task in between
+    struct Trickle<'a> {
+        input: &'a [u8],
+        pending: bool,
+    }
+
+    impl<'a> AsyncRead for Trickle<'a> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            self.pending = !self.pending;
+            if self.pending {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            match self.input.split_first() {
+                Some((&byte, rest)) if !buf.is_empty() => {
+                    buf[0] = byte;
+                    self.input = rest;
+                    Poll::Ready(Ok(1))
+                },
+                _ => Poll::Ready(Ok(0)),
+            }
+        }
+    }
+
+    #[test]
+    fn values_should_be_decoded_across_reads() {
+        let trickle = Trickle {
+            input: b"li1ei2eeli3ee3:foo",
+            pending: false,
+        };
+        let mut decoder = AsyncDecoder::new(trickle);
+
+        block_on(async {
+            assert_eq!(decoder.next_object().await.unwrap(), Some(vec![1, 2]));
+            assert_eq!(decoder.next_object().await.unwrap(), Some(vec![3]));
+            assert_eq!(decoder.position(), 13);
+            assert_eq!(
+                decoder.next_token().await.unwrap(),
+                Some(Event::String(b"foo".to_vec()))
+            );
+            assert_eq!(decoder.next_token().await.unwrap(), None);
+        });
+    }
+
+    #[test]
+    fn streams_should_end_after_errors() {
+        let decoder = AsyncDecoder::new(&b"i1ei2eix"[..]);
+        let values: Vec<_> = block_on(decoder.into_stream().collect());
+
+        assert_eq!(values.len(), 3);
+        assert_eq!(values[0].as_ref().unwrap(), &Value::from(1));
+        assert_eq!(values[1].as_ref().unwrap(), &Value::from(2));
+        assert!(values[2].is_err());
+    }
+
+    #[test]
+    fn truncated_input_should_fail() {
+        let mut decoder = AsyncDecoder::new(&b"li1ei2"[..]);
+        let error = block_on(decoder.next_object::<Vec<u8>>()).unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::StructureError(_)));
+    }
+}
@@ -0,0 +1,153 @@
+use bytes::Buf;
+
+use crate::decoding::{DecodeOptions, Decoder, Error, Event, FromBencode, StreamDecoder};
+
+/// The maximum number of bytes taken from the buffer at once
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// A decoder reading from a [`bytes::Buf`], created by [`Decoder::from_buf`]
+///
+/// The input doesn't have to be contiguous, like a `Chain` of buffers: it is taken from
+/// the buffer in chunks as needed, so only the part of the input which wasn't read as an
+/// [`Event`] yet is copied.
+///
+/// ```
+/// use bendy::decoding::{Decoder, Event};
+/// use bytes::Buf;
+///
+/// let input = (&b"li1e3:f"[..]).chain(&b"ooe"[..]);
+/// let mut decoder = Decoder::from_buf(input);
+///
+/// assert_eq!(decoder.next_event().unwrap(), Some(Event::List));
+/// assert_eq!(decoder.next_event().unwrap(), Some(Event::Num("1".into())));
+/// assert_eq!(decoder.next_event().unwrap(), Some(Event::String(b"foo".to_vec())));
+/// assert_eq!(decoder.next_event().unwrap(), Some(Event::End));
+/// assert_eq!(decoder.next_event().unwrap(), None);
+/// ```
+#[derive(Debug)]
+pub struct BufDecoder<B> {
+    buf: B,
+    stream: StreamDecoder,
+}
+
+impl<'ser> Decoder<'ser> {
+    /// Create a decoder reading from the given buffer, which need not be contiguous
+    pub fn from_buf<B: Buf>(buf: B) -> BufDecoder<B> {
+        BufDecoder::new(buf)
+    }
+}
+
+impl<B: Buf> BufDecoder<B> {
+    /// Create a decoder reading from the given buffer
+    pub fn new(buf: B) -> Self {
+        Self::new_with_options(buf, DecodeOptions::default())
+    }
+
+    /// Create a decoder reading from the given buffer, validating the input as
+    /// configured by the given options
+    pub fn new_with_options(buf: B, options: DecodeOptions) -> Self {
+        BufDecoder {
+            buf,
+            stream: StreamDecoder::new_with_options(options),
+        }
+    }
+
+    /// Read byte strings of at least `threshold` bytes in chunks, see
+    /// [`StreamDecoder::with_string_chunks`]
+    #[must_use]
+    pub fn with_string_chunks(mut self, threshold: usize) -> Self {
+        self.stream = self.stream.with_string_chunks(threshold);
+        self
+    }
+
+    /// Read the next event, taking input from the buffer as needed. Returns `Ok(None)` at
+    /// the end of the input.
+    pub fn next_event(&mut self) -> Result<Option<Event>, Error> {
+        self.pull(StreamDecoder::next_event)
+    }
+
+    /// Read the next complete value and decode it. Returns `Ok(None)` at the end of the
+    /// input.
+    pub fn next_object<T: FromBencode>(&mut self) -> Result<Option<T>, Error> {
+        match self.pull(StreamDecoder::next_value)? {
+            Some(value) => {
+                T::from_bencode_with_options(&value, self.stream.options().clone()).map(Some)
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Read from the stream decoder, feeding it from the buffer until it has enough input
+    fn pull<T>(
+        &mut self,
+        mut read: impl FnMut(&mut StreamDecoder) -> Result<Option<T>, Error>,
+    ) -> Result<Option<T>, Error> {
+        loop {
+            if let Some(item) = read(&mut self.stream)? {
+                return Ok(Some(item));
+            }
+            if !self.buf.has_remaining() {
+                self.stream.finish();
+                return read(&mut self.stream);
+            }
+
+            let chunk = self.buf.chunk();
+            let length = chunk.len().min(CHUNK_SIZE);
+            self.stream.feed(&chunk[..length]);
+            self.buf.advance(length);
+        }
+    }
+
+    /// The offset of the next event in the input
+    pub fn position(&self) -> usize {
+        self.stream.position()
+    }
+
+    /// The number of lists and dictionaries enclosing the next event
+    pub fn depth(&self) -> usize {
+        self.stream.depth()
+    }
+
+    /// Consume the decoder, returning the buffer. Input which was already taken from the
+    /// buffer but not read as an event yet is lost.
+    pub fn into_inner(self) -> B {
+        self.buf
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::state_tracker::Token;
+
+    #[test]
+    fn chained_buffers_should_decode_like_a_slice() {
+        let input = b"d3:bard1:xi-12ee3:fooli1e7:bencodeee";
+        let tokens: Vec<Token> = Decoder::new(input)
+            .tokens()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        for split in 0..=input.len() {
+            let (head, tail) = input.split_at(split);
+            let mut decoder = Decoder::from_buf(head.chain(tail));
+
+            let mut events = Vec::new();
+            while let Some(event) = decoder.next_event().unwrap() {
+                events.push(event);
+            }
+            let events: Vec<Token> = events.iter().filter_map(Event::as_token).collect();
+            assert_eq!(events, tokens);
+        }
+    }
+
+    #[test]
+    fn truncated_buffers_should_fail() {
+        let mut decoder = Decoder::from_buf(&b"l3:fo"[..]);
+        assert_eq!(decoder.next_event().unwrap(), Some(Event::List));
+        assert!(decoder.next_event().is_err());
+    }
+}
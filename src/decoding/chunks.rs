@@ -0,0 +1,173 @@
+use std::io;
+
+use crate::decoding::{DecodeOptions, Decoder, Error, Event, FromBencode, StreamDecoder};
+
+/// A decoder pulling its input from an iterator of chunks, created by
+/// [`Decoder::from_chunks`]
+///
+/// This suits input which is segmented already, like the chunks of a file or the segments
+/// of a rope: chunks are fed to a [`StreamDecoder`] as more input is needed, so tokens may
+/// span any number of them. Errors of the iterator are returned as decoding errors.
+///
+/// ```
+/// use bendy::decoding::{Decoder, Event};
+///
+/// let segments = vec![Ok(&b"l4:sp"[..]), Ok(b""), Ok(b"am"), Ok(b"e")];
+/// let mut decoder = Decoder::from_chunks(segments);
+///
+/// assert_eq!(decoder.next_event().unwrap(), Some(Event::List));
+/// assert_eq!(decoder.next_event().unwrap(), Some(Event::String(b"spam".to_vec())));
+/// assert_eq!(decoder.next_event().unwrap(), Some(Event::End));
+/// assert_eq!(decoder.next_event().unwrap(), None);
+/// ```
+#[derive(Debug)]
+pub struct ChunksDecoder<I> {
+    chunks: I,
+    stream: StreamDecoder,
+    /// Whether the iterator ended; it isn't asked for more chunks after that
+    eof: bool,
+}
+
+impl<'ser> Decoder<'ser> {
+    /// Create a decoder reading from the given chunks of input
+    pub fn from_chunks<I, C>(chunks: I) -> ChunksDecoder<I::IntoIter>
+    where
+        I: IntoIterator<Item = io::Result<C>>,
+        C: AsRef<[u8]>,
+    {
+        ChunksDecoder::new(chunks.into_iter())
+    }
+}
+
+impl<I, C> ChunksDecoder<I>
+where
+    I: Iterator<Item = io::Result<C>>,
+    C: AsRef<[u8]>,
+{
+    /// Create a decoder reading from the given iterator of chunks
+    pub fn new(chunks: I) -> Self {
+        Self::new_with_options(chunks, DecodeOptions::default())
+    }
+
+    /// Create a decoder reading from the given iterator of chunks, validating the input
+    /// as configured by the given options
+    pub fn new_with_options(chunks: I, options: DecodeOptions) -> Self {
+        ChunksDecoder {
+            chunks,
+            stream: StreamDecoder::new_with_options(options),
+            eof: false,
+        }
+    }
+
+    /// Read byte strings of at least `threshold` bytes in chunks, see
+    /// [`StreamDecoder::with_string_chunks`]
+    #[must_use]
+    pub fn with_string_chunks(mut self, threshold: usize) -> Self {
+        self.stream = self.stream.with_string_chunks(threshold);
+        self
+    }
+
+    /// Read the next event, taking chunks from the iterator as needed. Returns `Ok(None)`
+    /// at the end of the input.
+    pub fn next_event(&mut self) -> Result<Option<Event>, Error> {
+        self.pull(StreamDecoder::next_event)
+    }
+
+    /// Read the next complete value and decode it. Returns `Ok(None)` at the end of the
+    /// input.
+    pub fn next_object<T: FromBencode>(&mut self) -> Result<Option<T>, Error> {
+        match self.pull(StreamDecoder::next_value)? {
+            Some(value) => {
+                T::from_bencode_with_options(&value, self.stream.options().clone()).map(Some)
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Read from the stream decoder, feeding it from the iterator until it has enough
+    /// input
+    fn pull<T>(
+        &mut self,
+        mut read: impl FnMut(&mut StreamDecoder) -> Result<Option<T>, Error>,
+    ) -> Result<Option<T>, Error> {
+        loop {
+            if let Some(item) = read(&mut self.stream)? {
+                return Ok(Some(item));
+            }
+            if self.eof {
+                return Ok(None);
+            }
+
+            match self.chunks.next() {
+                Some(chunk) => self.stream.feed(chunk?.as_ref()),
+                None => {
+                    self.eof = true;
+                    self.stream.finish();
+                },
+            }
+        }
+    }
+
+    /// The offset of the next event in the input
+    pub fn position(&self) -> usize {
+        self.stream.position()
+    }
+
+    /// The number of lists and dictionaries enclosing the next event
+    pub fn depth(&self) -> usize {
+        self.stream.depth()
+    }
+
+    /// Consume the decoder, returning the remaining chunks. Input which was already taken
+    /// from the iterator but not read as an event yet is lost.
+    pub fn into_inner(self) -> I {
+        self.chunks
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::value::Value;
+
+    #[test]
+    fn chunks_should_decode_like_a_slice() {
+        let input = b"d3:bard1:xi-12ee3:fooli1e7:bencodeee";
+        let expected = Value::from_bencode(input).unwrap();
+
+        for size in 1..=input.len() {
+            let chunks = input.chunks(size).map(|chunk| Ok(chunk.to_vec()));
+            let mut decoder = Decoder::from_chunks(chunks);
+            assert_eq!(
+                decoder.next_object::<Value>().unwrap(),
+                Some(expected.clone())
+            );
+            assert_eq!(decoder.next_object::<Value>().unwrap(), None);
+        }
+    }
+
+    #[test]
+    fn chunk_errors_and_truncated_input_should_fail() {
+        let chunks = vec![Ok(&b"li1e"[..]), Err(io::ErrorKind::BrokenPipe.into())];
+        let mut decoder = Decoder::from_chunks(chunks);
+        assert_eq!(decoder.next_event().unwrap(), Some(Event::List));
+        assert_eq!(decoder.next_event().unwrap(), Some(Event::Num("1".into())));
+        assert!(decoder.next_event().is_err());
+
+        let mut decoder = Decoder::from_chunks(vec![Ok(b"l3:fo")]);
+        assert_eq!(decoder.next_event().unwrap(), Some(Event::List));
+        assert!(decoder.next_event().is_err());
+    }
+
+    #[test]
+    fn chunks_after_the_end_of_the_iterator_should_be_ignored() {
+        // An iterator which isn't fused, yielding more chunks after its first `None`
+        let mut chunks = vec![Some(&b"i1e"[..]), None, Some(b"i2e")].into_iter();
+        let chunks = std::iter::from_fn(move || chunks.next()?.map(Ok));
+        let mut decoder = Decoder::from_chunks(chunks);
+
+        assert_eq!(decoder.next_event().unwrap(), Some(Event::Num("1".into())));
+        assert_eq!(decoder.next_event().unwrap(), None);
+        assert_eq!(decoder.next_event().unwrap(), None);
+    }
+}
@@ -0,0 +1,391 @@
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+
+use crate::{
+    decoding::{
+        validate_with_options, DecodeOptions, Decoder, DictDecoder, DuplicateKeys, Error,
+        ListDecoder, Object, PathSegment,
+    },
+    state_tracker::StructureError,
+};
+
+/// The outcome of [`canonical_eq`]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Equality {
+    /// Both buffers hold the same value
+    Equal,
+    /// The values differ at the given path. At an empty path, the values differ as a
+    /// whole, e.g. because they are of different types.
+    Different(Vec<PathSegment>),
+}
+
+impl Equality {
+    /// Whether both buffers hold the same value
+    pub fn is_equal(&self) -> bool {
+        *self == Equality::Equal
+    }
+}
+
+/// Compare the values held by two encoded buffers, decoding them in lockstep without
+/// building either of them in memory. Apart from the path to a difference, nothing is
+/// allocated beyond the decoders themselves.
+///
+/// Both buffers need to hold exactly one canonical value; see
+/// [`canonical_eq_with_options`] to compare values with cosmetic differences.
+///
+/// ```
+/// use bendy::decoding::{canonical_eq, Equality, PathSegment};
+///
+/// let a = b"d5:filesld6:lengthi1eed6:lengthi2eee4:name1:ae";
+/// assert!(canonical_eq(a, a).unwrap().is_equal());
+///
+/// let b = b"d5:filesld6:lengthi1eed6:lengthi3eee4:name1:ae";
+/// assert_eq!(
+///     canonical_eq(a, b).unwrap(),
+///     Equality::Different(vec![
+///         PathSegment::Field("files".into()),
+///         PathSegment::Index(1),
+///         PathSegment::Field("length".into()),
+///     ])
+/// );
+/// ```
+pub fn canonical_eq(a: &[u8], b: &[u8]) -> Result<Equality, Error> {
+    canonical_eq_with_options(a, b, DecodeOptions::default())
+}
+
+/// Compare the values held by two encoded buffers like [`canonical_eq`], accepting
+/// whatever the given options accept. Differences the options allow for don't count:
+/// integers are compared by value, dictionaries with unsorted keys by their entries, and
+/// of duplicate keys only the value that the duplicate key policy keeps is compared.
+///
+/// Dictionaries that may have unsorted or repeated keys can't be compared in lockstep:
+/// each of them is scanned up front, and if its keys aren't sorted and unique its
+/// entries are collected and sorted in a `Vec`. Their values are then decoded again, so
+/// the cost grows with how deeply such dictionaries are nested.
+///
+/// ```
+/// use bendy::decoding::{canonical_eq_with_options, DecodeOptions};
+///
+/// let options = DecodeOptions::new().with_unsorted_keys().with_leading_zeros();
+/// let equality =
+///     canonical_eq_with_options(b"d1:bi02e1:ai1ee", b"d1:ai1e1:bi2ee", options).unwrap();
+/// assert!(equality.is_equal());
+/// ```
+pub fn canonical_eq_with_options(
+    a: &[u8],
+    b: &[u8],
+    options: DecodeOptions,
+) -> Result<Equality, Error> {
+    validate_with_options(a, options.clone())?;
+    validate_with_options(b, options.clone())?;
+
+    Ok(match compare_raw(a, b, &options)? {
+        None => Equality::Equal,
+        Some(mut path) => {
+            path.reverse();
+            Equality::Different(path)
+        },
+    })
+}
+
+/// A path to the difference of two values, innermost segment first, or `None` if the
+/// values are equal
+type Difference = Option<Vec<PathSegment>>;
+
+/// The keys and raw values of an encoded dictionary
+type Entries<'a> = Vec<(&'a [u8], &'a [u8])>;
+
+fn compare_raw(a: &[u8], b: &[u8], options: &DecodeOptions) -> Result<Difference, Error> {
+    let mut a = Decoder::new_with_options(a, options.clone());
+    let mut b = Decoder::new_with_options(b, options.clone());
+    let difference = match (a.next_object()?, b.next_object()?) {
+        (Some(a), Some(b)) => compare(a, b, options)?,
+        _ => return Err(Error::from(StructureError::UnexpectedEof)),
+    };
+    Ok(difference)
+}
+
+fn compare(a: Object, b: Object, options: &DecodeOptions) -> Result<Difference, Error> {
+    let different = match (a, b) {
+        (Object::Integer(a), Object::Integer(b)) => normalize(a) != normalize(b),
+        (Object::Bytes(a), Object::Bytes(b)) => a != b,
+        (Object::List(a), Object::List(b)) => return compare_lists(a, b, options),
+        // Keys may repeat or come in any order, so entries are looked up by key
+        (Object::Dict(a), Object::Dict(b))
+            if options.unsorted_keys || options.duplicate_keys == DuplicateKeys::LastWins =>
+        {
+            return compare_unsorted_dicts(a.into_raw()?, b.into_raw()?, options);
+        },
+        (Object::Dict(a), Object::Dict(b)) => return compare_dicts(a, b, options),
+        _ => true,
+    };
+
+    Ok(if different { Some(Vec::new()) } else { None })
+}
+
+/// Split an integer into its sign and digits, without leading zeros
+fn normalize(integer: &str) -> (bool, &str) {
+    let (negative, digits) = match integer.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, integer),
+    };
+    let digits = digits.trim_start_matches('0');
+    (negative && !digits.is_empty(), digits)
+}
+
+fn within(difference: Difference, segment: PathSegment) -> Difference {
+    difference.map(|mut path| {
+        path.push(segment);
+        path
+    })
+}
+
+fn compare_lists(
+    mut a: ListDecoder,
+    mut b: ListDecoder,
+    options: &DecodeOptions,
+) -> Result<Difference, Error> {
+    let mut index = 0;
+    loop {
+        let difference = match (a.next_object()?, b.next_object()?) {
+            (None, None) => return Ok(None),
+            (Some(a), Some(b)) => compare(a, b, options)?,
+            _ => Some(Vec::new()),
+        };
+
+        if difference.is_some() {
+            return Ok(within(difference, PathSegment::Index(index)));
+        }
+        index += 1;
+    }
+}
+
+fn compare_dicts(
+    mut a: DictDecoder,
+    mut b: DictDecoder,
+    options: &DecodeOptions,
+) -> Result<Difference, Error> {
+    loop {
+        let (key, difference) = match (a.next_pair()?, b.next_pair()?) {
+            (None, None) => return Ok(None),
+            (Some((key_a, a)), Some((key_b, b))) if key_a == key_b => {
+                (key_a, compare(a, b, options)?)
+            },
+            (Some((key_a, _)), Some((key_b, _))) => (key_a.min(key_b), Some(Vec::new())),
+            (Some((key, _)), None) | (None, Some((key, _))) => (key, Some(Vec::new())),
+        };
+
+        if difference.is_some() {
+            return Ok(within(difference, field(key)));
+        }
+    }
+}
+
+fn compare_unsorted_dicts(
+    a: &[u8],
+    b: &[u8],
+    options: &DecodeOptions,
+) -> Result<Difference, Error> {
+    // Sorted dictionaries without repeated keys can be compared in lockstep right away
+    if is_sorted_dict(a, options)? && is_sorted_dict(b, options)? {
+        let mut a = Decoder::new_with_options(a, options.clone());
+        let mut b = Decoder::new_with_options(b, options.clone());
+        return compare_dicts(dict_of(&mut a)?, dict_of(&mut b)?, options);
+    }
+
+    let a = sorted_entries(a, options)?;
+    let b = sorted_entries(b, options)?;
+    let (mut a, mut b) = (a.iter().peekable(), b.iter().peekable());
+    loop {
+        let (key, difference) = match (a.peek(), b.peek()) {
+            (None, None) => return Ok(None),
+            (Some(&&(key_a, value_a)), Some(&&(key_b, value_b))) if key_a == key_b => {
+                a.next();
+                b.next();
+                (key_a, compare_raw(value_a, value_b, options)?)
+            },
+            (Some(&&(key_a, _)), Some(&&(key_b, _))) => (key_a.min(key_b), Some(Vec::new())),
+            (Some(&&(key, _)), None) | (None, Some(&&(key, _))) => (key, Some(Vec::new())),
+        };
+
+        if difference.is_some() {
+            return Ok(within(difference, field(key)));
+        }
+    }
+}
+
+fn dict_of<'obj, 'ser>(decoder: &'obj mut Decoder<'ser>) -> Result<DictDecoder<'obj, 'ser>, Error> {
+    match decoder.next_object()? {
+        Some(Object::Dict(dict)) => Ok(dict),
+        _ => Err(Error::from(StructureError::UnexpectedEof)),
+    }
+}
+
+fn field(key: &[u8]) -> PathSegment {
+    PathSegment::Field(String::from_utf8_lossy(key).into_owned())
+}
+
+/// Whether the keys of an encoded dictionary are sorted and unique
+fn is_sorted_dict(dict: &[u8], options: &DecodeOptions) -> Result<bool, Error> {
+    let mut decoder = Decoder::new_with_options(dict, options.clone());
+    let mut pairs = dict_of(&mut decoder)?;
+
+    let mut last = None;
+    while let Some((key, _)) = pairs.next_pair_raw()? {
+        if last.is_some_and(|last| last >= key) {
+            return Ok(false);
+        }
+        last = Some(key);
+    }
+
+    Ok(true)
+}
+
+/// The entries of an encoded dictionary sorted by key, with the raw value that the
+/// duplicate key policy keeps for repeated keys
+fn sorted_entries<'a>(dict: &'a [u8], options: &DecodeOptions) -> Result<Entries<'a>, Error> {
+    let mut decoder = Decoder::new_with_options(dict, options.clone());
+    let mut pairs = dict_of(&mut decoder)?;
+
+    let mut entries = Vec::new();
+    while let Some(pair) = pairs.next_pair_raw()? {
+        entries.push(pair);
+    }
+
+    // The sort is stable, so repeated keys stay in the order they were read
+    entries.sort_by_key(|&(key, _)| key);
+    let last_wins = options.duplicate_keys == DuplicateKeys::LastWins;
+    entries.dedup_by(|next, kept| {
+        let repeated = next.0 == kept.0;
+        if repeated && last_wins {
+            kept.1 = next.1;
+        }
+        repeated
+    });
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::{format, string::ToString};
+
+    use super::*;
+
+    fn different(path: &[&str]) -> Equality {
+        let path = path
+            .iter()
+            .map(|segment| match segment.parse() {
+                Ok(index) => PathSegment::Index(index),
+                Err(_) => PathSegment::Field(segment.to_string()),
+            })
+            .collect();
+        Equality::Different(path)
+    }
+
+    #[test]
+    fn differences_should_be_located() {
+        let a = b"d1:ali1ei2ee1:bd1:c1:xee";
+        assert_eq!(canonical_eq(a, a).unwrap(), Equality::Equal);
+
+        let cases: &[(&[u8], &[&str])] = &[
+            (b"i1e", &[]),
+            (b"d1:ali1ei3ee1:bd1:c1:xee", &["a", "1"]),
+            (b"d1:ali1ee1:bd1:c1:xee", &["a", "1"]),
+            (b"d1:ali1ei2ei3ee1:bd1:c1:xee", &["a", "2"]),
+            (b"d1:ali1ei2ee1:bd1:c1:yee", &["b", "c"]),
+            (b"d1:ali1ei2ee1:bd1:d1:xee", &["b", "c"]),
+            (b"d1:ali1ei2ee1:bd1:c1:xe1:cdee", &["c"]),
+            (b"d1:ali1ei2ee1:bl1:xee", &["b"]),
+        ];
+        for (b, path) in cases {
+            assert_eq!(canonical_eq(a, b).unwrap(), different(path));
+            assert_eq!(canonical_eq(b, a).unwrap(), different(path));
+        }
+    }
+
+    #[test]
+    fn cosmetic_differences_should_be_ignored_if_allowed() {
+        let options = DecodeOptions::new()
+            .with_unsorted_keys()
+            .with_leading_zeros()
+            .with_negative_zero();
+        let a = b"d1:ai1e1:bli0ei-5eee";
+        let b = b"d1:bli-0ei-05ee1:ai001ee";
+
+        assert!(canonical_eq(a, b).is_err());
+        assert!(canonical_eq_with_options(a, b, options.clone())
+            .unwrap()
+            .is_equal());
+        assert_eq!(
+            canonical_eq_with_options(a, b"d1:bli0ei-5ee1:ai2ee", options.clone()).unwrap(),
+            different(&["a"])
+        );
+        assert_eq!(
+            canonical_eq_with_options(a, b"d1:bli0ei-5eee", options).unwrap(),
+            different(&["a"])
+        );
+    }
+
+    #[test]
+    fn duplicate_keys_should_follow_the_policy() {
+        let a = b"d1:ai1e1:ai2ee";
+        let first = DecodeOptions::new().with_duplicate_keys(DuplicateKeys::FirstWins);
+        let last = DecodeOptions::new().with_duplicate_keys(DuplicateKeys::LastWins);
+
+        assert!(canonical_eq_with_options(a, b"d1:ai1ee", first.clone())
+            .unwrap()
+            .is_equal());
+        assert!(!canonical_eq_with_options(a, b"d1:ai2ee", first)
+            .unwrap()
+            .is_equal());
+        assert!(canonical_eq_with_options(a, b"d1:ai2ee", last.clone())
+            .unwrap()
+            .is_equal());
+        assert!(!canonical_eq_with_options(a, b"d1:ai1ee", last)
+            .unwrap()
+            .is_equal());
+    }
+
+    #[test]
+    fn unsorted_duplicate_keys_should_follow_the_policy() {
+        let a = b"d1:bi1e1:ai1e1:ai2ee";
+        let b = b"d1:ai2e1:bi1ee";
+        let first = DecodeOptions::new()
+            .with_unsorted_keys()
+            .with_duplicate_keys(DuplicateKeys::FirstWins);
+        let last = DecodeOptions::new()
+            .with_unsorted_keys()
+            .with_duplicate_keys(DuplicateKeys::LastWins);
+
+        assert!(canonical_eq_with_options(a, b, last).unwrap().is_equal());
+        assert_eq!(
+            canonical_eq_with_options(a, b, first).unwrap(),
+            different(&["a"])
+        );
+    }
+
+    #[test]
+    fn large_unsorted_dicts_should_be_compared_by_sorting() {
+        let options = DecodeOptions::new().with_unsorted_keys();
+        let dict = |changed: u32| {
+            let mut dict = b"d".to_vec();
+            for key in (0..20_000u32).rev() {
+                let value = if key == changed { key + 1 } else { key };
+                dict.extend_from_slice(format!("6:k{:05}i{}e", key, value).as_bytes());
+            }
+            dict.push(b'e');
+            dict
+        };
+
+        let a = dict(u32::MAX);
+        assert!(canonical_eq_with_options(&a, &a, options.clone())
+            .unwrap()
+            .is_equal());
+        assert_eq!(
+            canonical_eq_with_options(&a, &dict(42), options).unwrap(),
+            different(&["k00042"])
+        );
+    }
+}
@@ -1,9 +1,22 @@
-use alloc::format;
-use core::str;
+use alloc::{borrow::Cow, format, string::String};
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, vec::Vec};
+use core::{
+    convert::TryFrom,
+    fmt,
+    iter::{self, FromIterator},
+    ptr, str,
+};
+
+use memchr::memchr;
 
 use crate::{
-    decoding::{Error, Object},
-    state_tracker::{StateTracker, StructureError, Token},
+    decoding::{
+        digits::digit_run_len, DecodeOptions, DuplicateKeys, Error, FlatDict, FromBencode, Limit,
+        Object, Visitor,
+    },
+    state_tracker::{Number, StateTracker, StructureError, Token, TypedToken},
+    value::Value,
 };
 
 /// A bencode decoder
@@ -14,27 +27,273 @@ use crate::{
 pub struct Decoder<'a> {
     source: &'a [u8],
     offset: usize,
+    /// Offset of `source` in a larger input, added to the offsets reported to callers
+    offset_base: usize,
+    options: DecodeOptions,
+    /// The number of tokens read so far
+    token_count: usize,
+    /// Whether the next token is the end of a container skipped by error recovery
+    pending_end: bool,
+    /// The value of the last integer token, so [`TypedTokens`] needn't parse it again
+    number: Option<Number<'a>>,
+    progress: Option<Progress<'a>>,
+    state: StateTracker<&'a [u8], Error>,
+}
+
+/// A callback reporting the position of a [`Decoder`], set with
+/// [`Decoder::with_progress`]
+struct Progress<'a> {
+    interval: usize,
+    /// The offset at which the callback is called next
+    next: usize,
+    callback: Callback<'a>,
+}
+
+/// The callback of a [`Progress`]. It's only ever called through a mutable reference,
+/// so unlike the callback itself, it can be shared between threads.
+struct Callback<'a>(Box<dyn FnMut(usize) -> bool + Send + 'a>);
+
+// SAFETY: A shared reference to a `Callback` gives no access to the callback inside,
+// which can only be called through `&mut Callback`.
+unsafe impl<'a> Sync for Callback<'a> {}
+
+impl<'a> Callback<'a> {
+    fn call(&mut self, position: usize) -> bool {
+        (self.0)(position)
+    }
+}
+
+impl<'a> fmt::Debug for Progress<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Progress")
+            .field("interval", &self.interval)
+            .field("next", &self.next)
+            .finish()
+    }
+}
+
+/// A saved position of a [`Decoder`], created by [`Decoder::checkpoint`]
+#[derive(Clone, Debug)]
+pub struct Checkpoint<'a> {
+    source: &'a [u8],
+    offset: usize,
+    token_count: usize,
+    pending_end: bool,
     state: StateTracker<&'a [u8], Error>,
 }
 
 impl<'ser> Decoder<'ser> {
     /// Create a new decoder from the given byte array
     pub fn new(buffer: &'ser [u8]) -> Self {
+        Self::new_with_options(buffer, DecodeOptions::default())
+    }
+
+    /// Create a new decoder from the given byte array, validating the input as configured
+    /// by the given options
+    pub fn new_with_options(buffer: &'ser [u8], options: DecodeOptions) -> Self {
+        let state = Self::state_for(&options);
+        Self::new_with_state(buffer, options, state)
+    }
+
+    /// Create a state tracker validating the input as configured by the given options
+    pub(crate) fn state_for<S>(options: &DecodeOptions) -> StateTracker<S, Error>
+    where
+        S: AsRef<[u8]> + Ord,
+    {
+        let mut state = StateTracker::new();
+        state.set_max_depth(options.max_depth);
+        state.set_allow_unsorted_keys(options.unsorted_keys);
+        state.set_allow_duplicate_keys(options.duplicate_keys != DuplicateKeys::Error);
+        state
+    }
+
+    /// Create a decoder with the given state tracker, which must be configured for the
+    /// given options and empty
+    pub(crate) fn new_with_state(
+        buffer: &'ser [u8],
+        options: DecodeOptions,
+        state: StateTracker<&'ser [u8], Error>,
+    ) -> Self {
         Decoder {
             source: buffer,
             offset: 0,
-            state: StateTracker::new(),
+            offset_base: 0,
+            options,
+            token_count: 0,
+            pending_end: false,
+            number: None,
+            progress: None,
+            state,
         }
     }
 
-    /// Set the maximum nesting depth of the decoder. An unlimited-depth decoder may be
-    /// created using `with_max_depth(<usize>::max_value())`, but be warned that this will likely
-    /// exhaust memory if the nesting depth is too deep (even when reading raw tokens)
+    /// The options this decoder validates its input with
+    pub fn options(&self) -> &DecodeOptions {
+        &self.options
+    }
+
+    /// Treat the buffer as starting at the given offset of a larger input, so offsets
+    /// reported by the decoder point into that input. This is useful when decoding a part
+    /// of the input, like a dictionary returned by [`DictDecoder::into_raw`], once more.
+    pub fn with_offset_base(mut self, offset_base: usize) -> Self {
+        self.offset_base = offset_base;
+        self
+    }
+
+    /// Require the input to consist of exactly one value. By default, the decoder stops
+    /// after the first value and ignores whatever follows, see
+    /// [`Decoder::into_remainder`]; in strict mode, trailing data is an error.
+    ///
+    /// ```
+    /// use bendy::decoding::Decoder;
+    ///
+    /// let mut decoder = Decoder::new(b"li1ee\n").with_strict_eof();
+    /// let mut list = decoder.next_object().unwrap().unwrap().try_into_list().unwrap();
+    /// assert!(list.next_object().unwrap().is_some());
+    /// assert!(list.next_object().is_err());
+    /// ```
+    pub fn with_strict_eof(mut self) -> Self {
+        self.options.strict_eof = true;
+        self
+    }
+
+    /// Call `callback` with the current position whenever the decoder advanced by
+    /// `interval` bytes or more since the last call, e.g. to show the progress of
+    /// decoding a large input. Returning `false` cancels decoding, failing it with
+    /// [`ErrorKind::Cancelled`](crate::decoding::ErrorKind::Cancelled).
+    ///
+    /// The callback is called between tokens, so long byte strings may advance the
+    /// decoder by more than `interval` at once. It has to be `Send` so that the
+    /// decoder stays `Send`; the decoder stays `Sync` either way, since the callback is
+    /// only called through a mutable reference.
+    ///
+    /// ```
+    /// use bendy::decoding::{Decoder, ErrorKind};
+    ///
+    /// let input = b"li1ei2ei3ei4ee";
+    /// let mut decoder = Decoder::new(input).with_progress(6, |position| {
+    ///     println!("{}%", 100 * position / input.len());
+    ///     position < 10
+    /// });
+    ///
+    /// let error = decoder.tokens().last().unwrap().unwrap_err();
+    /// assert!(matches!(error.kind(), ErrorKind::Cancelled(13)));
+    /// ```
+    pub fn with_progress(
+        mut self,
+        interval: usize,
+        callback: impl FnMut(usize) -> bool + Send + 'ser,
+    ) -> Self {
+        self.progress = Some(Progress {
+            interval,
+            next: self.offset.saturating_add(interval),
+            callback: Callback(Box::new(callback)),
+        });
+        self
+    }
+
+    /// Set the maximum nesting depth of the decoder, which defaults to
+    /// [`DEFAULT_MAX_DEPTH`](crate::decoding::DEFAULT_MAX_DEPTH). Deeper input fails with
+    /// an error. An unlimited-depth decoder may be created using
+    /// `with_max_depth(<usize>::max_value())`, but be warned that this will likely exhaust
+    /// memory if the nesting depth is too deep (even when reading raw tokens)
     pub fn with_max_depth(mut self, new_max_depth: usize) -> Self {
+        self.options.max_depth = new_max_depth;
         self.state.set_max_depth(new_max_depth);
         self
     }
 
+    /// The offset of the next token in the input
+    ///
+    /// ```
+    /// use bendy::decoding::Decoder;
+    ///
+    /// let mut decoder = Decoder::new(b"li1ei2ee3:foo");
+    /// decoder.next_object().unwrap();
+    /// assert_eq!(decoder.position(), 8);
+    /// assert_eq!(decoder.remaining(), b"3:foo");
+    /// assert_eq!(decoder.depth(), 0);
+    /// ```
+    pub fn position(&self) -> usize {
+        self.offset_base + self.offset
+    }
+
+    /// The part of the input which hasn't been read yet
+    pub fn remaining(&self) -> &'ser [u8] {
+        &self.source[self.offset..]
+    }
+
+    /// The number of lists and dictionaries enclosing the next token. Inside of a
+    /// [`ListDecoder`] or [`DictDecoder`], use their `depth()` instead.
+    pub fn depth(&self) -> usize {
+        self.state.depth()
+    }
+
+    /// Consume the decoder, returning the input which hasn't been read yet. This is
+    /// meant for framed streams which carry several messages back to back.
+    ///
+    /// ```
+    /// use bendy::decoding::{Decoder, FromBencode};
+    ///
+    /// let mut decoder = Decoder::new(b"i1ei2e");
+    /// let first = u32::decode_bencode_object(decoder.next_object().unwrap().unwrap());
+    /// assert_eq!(first.unwrap(), 1);
+    /// assert_eq!(decoder.into_remainder(), b"i2e");
+    /// ```
+    pub fn into_remainder(self) -> &'ser [u8] {
+        &self.source[self.offset..]
+    }
+
+    /// Consume the decoder, returning its state tracker
+    pub(crate) fn into_state(self) -> StateTracker<&'ser [u8], Error> {
+        self.state
+    }
+
+    /// Save the current position of the decoder, so it can be rewound to it with
+    /// [`Decoder::restore`]. This allows speculative parsing, e.g. trying one schema and
+    /// falling back to another, without starting over from the beginning of the input.
+    ///
+    /// ```
+    /// use bendy::decoding::{Decoder, FromBencode};
+    ///
+    /// let mut decoder = Decoder::new(b"l3:fooi1ee");
+    /// let checkpoint = decoder.checkpoint();
+    ///
+    /// let object = decoder.next_object().unwrap().unwrap();
+    /// assert!(Vec::<String>::decode_bencode_object(object).is_err());
+    ///
+    /// decoder.restore(checkpoint);
+    /// let object = decoder.next_object().unwrap().unwrap();
+    /// let list = bendy::value::Value::decode_bencode_object(object).unwrap();
+    /// assert_eq!(list.to_string(), r#"["foo", 1]"#);
+    /// ```
+    pub fn checkpoint(&self) -> Checkpoint<'ser> {
+        Checkpoint {
+            source: self.source,
+            offset: self.offset,
+            token_count: self.token_count,
+            pending_end: self.pending_end,
+            state: self.state.clone(),
+        }
+    }
+
+    /// Rewind the decoder to a position saved with [`Decoder::checkpoint`]. Errors
+    /// encountered since then are discarded as well.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the checkpoint was created by a decoder for a different buffer.
+    pub fn restore(&mut self, checkpoint: Checkpoint<'ser>) {
+        assert!(
+            ptr::eq(self.source, checkpoint.source),
+            "checkpoint belongs to a different decoder"
+        );
+        self.offset = checkpoint.offset;
+        self.token_count = checkpoint.token_count;
+        self.pending_end = checkpoint.pending_end;
+        self.state = checkpoint.state;
+    }
+
     fn take_byte(&mut self) -> Option<u8> {
         if self.offset < self.source.len() {
             let ret = Some(self.source[self.offset]);
@@ -56,83 +315,204 @@ impl<'ser> Decoder<'ser> {
         }
     }
 
-    fn take_int(&mut self, expected_terminator: char) -> Result<&'ser str, StructureError> {
-        enum State {
-            Start,
-            Sign,
-            Zero,
-            Digits,
+    /// Read an integer up to the given terminator. Unless `lenient`, only the canonical
+    /// form is accepted; otherwise leading zeros and negative zero are accepted as
+    /// configured in the options.
+    fn take_int(
+        &mut self,
+        expected_terminator: char,
+        lenient: bool,
+    ) -> Result<ScannedInt<'ser>, StructureError> {
+        /// What the parser expected where it found an unexpected character
+        enum Expected {
+            SignOrDigit,
+            NonZeroDigit,
+            Terminator,
+            TerminatorOrDigit,
         }
 
-        let mut curpos = self.offset;
-        let mut state = State::Start;
+        let leading_zeros = lenient && self.options.leading_zeros;
+        let negative_zero = lenient && self.options.negative_zero;
+
+        let rest = &self.source[self.offset..];
+        let (length, terminated) = scan_int(rest, expected_terminator as u8);
+        let digits = &rest[..length];
+
+        let unexpected = |index: usize, expected: Expected| {
+            let expected: String = match expected {
+                Expected::SignOrDigit => "'-' or '0'..'9'".into(),
+                Expected::NonZeroDigit => "'1'..'9'".into(),
+                Expected::Terminator => format!("{:?}", expected_terminator),
+                Expected::TerminatorOrDigit => format!("{:?} or '0'..'9'", expected_terminator),
+            };
+            let position = self.offset_base + self.offset + index;
+            StructureError::unexpected(expected, rest[index] as char, position)
+        };
 
-        let mut success = false;
-        while curpos < self.source.len() {
-            let c = self.source[curpos] as char;
-            match state {
-                State::Start => {
-                    if c == '-' {
-                        state = State::Sign;
-                    } else if c == '0' {
-                        state = State::Zero;
-                    } else if c >= '1' && c <= '9' {
-                        state = State::Digits;
-                    } else {
-                        return Err(StructureError::unexpected("'-' or '0'..'9'", c, curpos));
-                    }
-                },
-                State::Zero => {
-                    if c == expected_terminator {
-                        success = true;
-                        break;
-                    } else {
-                        return Err(StructureError::unexpected(
-                            &format!("{:?}", expected_terminator),
-                            c,
-                            curpos,
-                        ));
-                    }
-                },
-                State::Sign => {
-                    if c >= '1' && c <= '9' {
-                        state = State::Digits;
-                    } else {
-                        return Err(StructureError::unexpected("'1'..'9'", c, curpos));
-                    }
-                },
-                State::Digits => {
-                    if c >= '0' && c <= '9' {
-                        // do nothing, this is ok
-                    } else if c == expected_terminator {
-                        success = true;
-                        break;
-                    } else {
-                        return Err(StructureError::unexpected(
-                            &format!("{:?} or '0'..'9'", expected_terminator),
-                            c,
-                            curpos,
-                        ));
-                    }
-                },
+        // Check the run of digits after the sign in bulk, then look at where it ends
+        let negative = digits.first() == Some(&b'-');
+        let start = usize::from(negative);
+        let run_end = start + digit_run_len(&digits[start..]);
+        let run = &digits[start..run_end];
+
+        if run.first() == Some(&b'0') {
+            if negative && !(leading_zeros || negative_zero) {
+                return Err(unexpected(start, Expected::NonZeroDigit));
+            }
+            if run.len() > 1 && !leading_zeros {
+                return Err(match negative {
+                    true => unexpected(start + 1, Expected::NonZeroDigit),
+                    false => unexpected(start + 1, Expected::Terminator),
+                });
             }
-            curpos += 1;
         }
 
-        if !success {
+        let zero_run = negative && !run.is_empty() && run.iter().all(|&byte| byte == b'0');
+        let expected = || {
+            if run.is_empty() && !negative {
+                Expected::SignOrDigit
+            } else if run.is_empty() || zero_run {
+                Expected::NonZeroDigit
+            } else if run == b"0" {
+                Expected::Terminator
+            } else {
+                Expected::TerminatorOrDigit
+            }
+        };
+
+        if run_end < length {
+            return Err(unexpected(run_end, expected()));
+        }
+        if !terminated {
             return Err(StructureError::UnexpectedEof);
         }
+        if run.is_empty() || (zero_run && !negative_zero) {
+            return Err(unexpected(length, expected()));
+        }
+
+        // Accumulate the value from the checked digits, so callers needn't parse them
+        let magnitude = run.iter().try_fold(0u64, |value, &byte| {
+            value.checked_mul(10)?.checked_add(u64::from(byte - b'0'))
+        });
 
-        let slice = &self.source[self.offset..curpos];
-        self.offset = curpos + 1;
-        let ival = if cfg!(debug) {
-            str::from_utf8(slice).expect("We've already examined every byte in the string")
+        self.offset += length + 1;
+        let digits = if cfg!(debug_assertions) {
+            str::from_utf8(digits).expect("We've already examined every byte in the string")
         } else {
             // Avoid a second UTF-8 check here
-            unsafe { str::from_utf8_unchecked(slice) }
+            unsafe { str::from_utf8_unchecked(digits) }
         };
 
-        Ok(ival)
+        Ok(ScannedInt {
+            digits,
+            magnitude,
+            negative,
+        })
+    }
+
+    /// Read a single token from the start of `source`, which begins at `offset_base` of
+    /// the input, returning it along with its length. Fails with `UnexpectedEof` if the
+    /// token is incomplete. Implements
+    /// [`StreamDecoder`](crate::decoding::StreamDecoder).
+    pub(crate) fn read_raw_token(
+        source: &'ser [u8],
+        offset_base: usize,
+        options: &DecodeOptions,
+    ) -> Result<(Token<'ser>, usize), Error> {
+        let mut decoder =
+            Decoder::new_with_options(source, options.clone()).with_offset_base(offset_base);
+        let token = decoder.raw_next_token()?;
+        Ok((token, decoder.offset))
+    }
+
+    /// Read the length prefix of a byte string from the start of `source` like
+    /// [`Decoder::read_raw_token`], returning the length of the string and of the prefix
+    pub(crate) fn read_string_length(
+        source: &'ser [u8],
+        offset_base: usize,
+        options: &DecodeOptions,
+    ) -> Result<(usize, usize), Error> {
+        let mut decoder =
+            Decoder::new_with_options(source, options.clone()).with_offset_base(offset_base);
+        let len = decoder.take_string_length()?;
+        Ok((len, decoder.offset))
+    }
+
+    /// Read a dictionary of byte strings from the start of `source` straight from its
+    /// tokens, without a state tracker. Implements
+    /// [`decode_flat_dict`](crate::decoding::decode_flat_dict).
+    pub(crate) fn read_flat_dict(source: &'ser [u8]) -> Result<FlatDict<'ser>, Error> {
+        let mut decoder = Decoder::new(source);
+        match decoder.raw_next_token()? {
+            Token::Dict => (),
+            token => return Err(Error::unexpected_token("Dict", token.name())),
+        }
+
+        let mut pairs = FlatDict::new();
+        loop {
+            let position = decoder.position();
+            let key = match decoder.raw_next_token()? {
+                Token::End => return Ok(pairs),
+                Token::String(key) => key,
+                token => {
+                    return Err(Error::from(StructureError::NonStringKey {
+                        found: token.name(),
+                        position,
+                    }));
+                },
+            };
+            if pairs.last().is_some_and(|&(last, _)| last >= key) {
+                return Err(Error::from(StructureError::UnsortedKeys { position }));
+            }
+
+            let position = decoder.position();
+            let value = match decoder.raw_next_token()? {
+                Token::String(value) => value,
+                Token::End => return Err(Error::from(StructureError::MissingValue { position })),
+                token => return Err(Error::unexpected_token("String", token.name())),
+            };
+            pairs.push((key, value));
+        }
+    }
+
+    /// Count a token about to be read against the token limit
+    fn count_token(&mut self) -> Result<(), Error> {
+        if self.token_count == self.options.max_tokens {
+            let limit = Limit::TokenCount(self.options.max_tokens);
+            return Err(Error::limit_exceeded(limit));
+        }
+        self.token_count += 1;
+        Ok(())
+    }
+
+    /// Call the progress callback if the decoder advanced far enough since the last call
+    fn report_progress(&mut self) -> Result<(), Error> {
+        let position = self.position();
+        if let Some(progress) = &mut self.progress {
+            if self.offset >= progress.next {
+                progress.next = self.offset.saturating_add(progress.interval);
+                if !progress.callback.call(position) {
+                    return Err(Error::cancelled(position));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Read the length prefix of a byte string, including the colon
+    fn take_string_length(&mut self) -> Result<usize, Error> {
+        let position = self.position();
+        let int = self.take_int(':', false)?;
+        let len = int
+            .magnitude
+            .filter(|_| !int.negative)
+            .and_then(|magnitude| usize::try_from(magnitude).ok())
+            .ok_or(StructureError::InvalidLength { position })?;
+        if len > self.options.max_string_length {
+            let limit = Limit::StringLength(self.options.max_string_length);
+            return Err(Error::limit_exceeded(limit));
+        }
+        Ok(len)
     }
 
     fn raw_next_token(&mut self) -> Result<Token<'ser>, Error> {
@@ -140,23 +520,22 @@ impl<'ser> Decoder<'ser> {
             'e' => Token::End,
             'l' => Token::List,
             'd' => Token::Dict,
-            'i' => Token::Num(self.take_int('e')?),
-            c if c >= '0' && c <= '9' => {
+            'i' => {
+                let int = self.take_int('e', true)?;
+                self.number = Some(int.number());
+                Token::Num(int.digits)
+            },
+            c if c.is_ascii_digit() => {
                 self.offset -= 1;
 
-                let curpos = self.offset;
-                let ival = self.take_int(':')?;
-                let len = usize::from_str_radix(ival, 10).map_err(|_| {
-                    StructureError::SyntaxError(format!("Invalid integer at offset {}", curpos))
-                })?;
+                let len = self.take_string_length()?;
                 Token::String(self.take_chunk(len).ok_or(StructureError::UnexpectedEof)?)
             },
             tok => {
-                return Err(Error::from(StructureError::SyntaxError(format!(
-                    "Invalid token starting with {:?} at offset {}",
-                    tok,
-                    self.offset - 1
-                ))));
+                return Err(Error::from(StructureError::InvalidToken {
+                    found: tok,
+                    position: self.position() - 1,
+                }));
             },
         };
 
@@ -165,25 +544,234 @@ impl<'ser> Decoder<'ser> {
 
     /// Read the next token. Returns Ok(Some(token)) if a token was successfully read,
     fn next_token(&mut self) -> Result<Option<Token<'ser>>, Error> {
+        #[cfg(feature = "tracing")]
+        let failed = self.state.is_failed();
+
+        let result = self.read_token();
+
+        #[cfg(feature = "tracing")]
+        self.trace_token(&result, failed);
+
+        result
+    }
+
+    /// Emit events for containers being entered and left, complete values and new errors
+    #[cfg(feature = "tracing")]
+    fn trace_token(&self, result: &Result<Option<Token<'ser>>, Error>, failed: bool) {
+        let depth = self.state.depth();
+        match result {
+            Ok(Some(Token::List)) => {
+                tracing::trace!(offset = self.position() - 1, depth, "entered list");
+            },
+            Ok(Some(Token::Dict)) => {
+                tracing::trace!(offset = self.position() - 1, depth, "entered dictionary");
+            },
+            Ok(Some(Token::End)) => {
+                tracing::trace!(offset = self.position(), depth, "left container");
+            },
+            _ => (),
+        }
+
+        match result {
+            Ok(Some(_)) if depth == 0 => {
+                tracing::debug!(
+                    offset = self.position(),
+                    tokens = self.token_count,
+                    "decoded value"
+                );
+            },
+            Err(error) if !failed => {
+                tracing::debug!(
+                    offset = ?error.position(),
+                    tokens = self.token_count,
+                    %error,
+                    "decoding failed"
+                );
+            },
+            _ => (),
+        }
+    }
+
+    fn read_token(&mut self) -> Result<Option<Token<'ser>>, Error> {
         self.state.check_error()?;
 
-        if self.offset == self.source.len() {
-            self.state.observe_eof()?;
-            return Ok(None);
+        if self.source.len() > self.options.max_document_size {
+            let limit = Limit::DocumentSize(self.options.max_document_size);
+            return self.state.latch_err(Err(Error::limit_exceeded(limit)));
         }
 
-        let tok_result = self.raw_next_token();
-        let tok = self.state.latch_err(tok_result)?;
+        let tok = if self.pending_end {
+            // The end of a list or dictionary skipped by `recover`, which already closed
+            // it in the state tracker
+            self.pending_end = false;
+            Token::End
+        } else {
+            if self.offset == self.source.len() {
+                self.state.observe_eof()?;
+                return Ok(None);
+            }
+
+            let counted = self.count_token();
+            self.state.latch_err(counted)?;
+
+            let position = self.position();
+            let depth = self.state.depth();
+            let start = self.offset;
+            let tok = match self.raw_next_token() {
+                Ok(tok) => tok,
+                Err(error) => {
+                    let resume = self.malformed_token_end(start, &error);
+                    return self.recover(error, depth, 1, resume);
+                },
+            };
+
+            if let Err(error) = self.state.observe_token(&tok, position) {
+                // The rejected token may have opened a container, or already closed the
+                // enclosing one
+                let open = match tok {
+                    Token::List | Token::Dict => 2,
+                    Token::End => 0,
+                    _ => 1,
+                };
+                return self.recover(error, depth, open, Some(self.offset));
+            }
+
+            let progress = self.report_progress();
+            self.state.latch_err(progress)?;
+
+            tok
+        };
+
+        if self.options.strict_eof && self.state.depth() == 0 && self.offset != self.source.len() {
+            let position = self.position();
+            return self
+                .state
+                .latch_err(Err(Error::from(StructureError::TrailingData { position })));
+        }
 
-        self.state.observe_token(&tok)?;
         Ok(Some(tok))
     }
 
+    /// Handle an error found at the given depth. With error recovery enabled, the input is
+    /// resynchronized at the end of the innermost open list or dictionary, which is
+    /// reported as ended by the next token, and the error is returned without latching
+    /// it. `open` is the number of containers to skip to the end of, and `resume` the
+    /// offset to start skipping at, if any.
+    fn recover<T>(
+        &mut self,
+        error: Error,
+        depth: usize,
+        open: usize,
+        resume: Option<usize>,
+    ) -> Result<T, Error> {
+        let resume = match resume {
+            Some(resume) if self.options.error_recovery && depth > 0 => resume,
+            _ => return self.state.latch_err(Err(error)),
+        };
+
+        let offset = self.offset;
+        self.offset = resume;
+        if !self.resync(open) {
+            self.offset = offset;
+            return self.state.latch_err(Err(error));
+        }
+
+        self.state.recover(depth - 1);
+        self.pending_end = true;
+        Err(error)
+    }
+
+    /// Skip tokens until the given number of lists and dictionaries were closed, skipping
+    /// single bytes where the input is malformed. Returns `false` if the input ended first.
+    fn resync(&mut self, mut open: usize) -> bool {
+        while open > 0 {
+            let start = self.offset;
+            match self.raw_next_token() {
+                Ok(Token::List) | Ok(Token::Dict) => open += 1,
+                Ok(Token::End) => open -= 1,
+                Ok(_) => (),
+                Err(error) => match self.malformed_token_end(start, &error) {
+                    Some(resume) => self.offset = resume,
+                    None => return false,
+                },
+            }
+        }
+        true
+    }
+
+    /// The offset after the malformed token starting at `start`, where skipping can
+    /// resume. Integers are skipped up to their terminating `e`, which would otherwise be
+    /// taken for the end of the enclosing container; anything else only up to the byte
+    /// the error was found at. `None` if the input ends first.
+    fn malformed_token_end(&self, start: usize, error: &Error) -> Option<usize> {
+        let resume = error.position()? - self.offset_base + 1;
+        if self.source[start] != b'i' {
+            return Some(resume);
+        }
+        self.source[start..]
+            .iter()
+            .position(|&byte| byte == b'e')
+            .map(|end| start + end + 1)
+            .filter(|&end| end >= resume)
+            .or(Some(resume))
+    }
+
+    /// Look at the next token without consuming it. Returns `Ok(None)` at the end of the
+    /// input.
+    ///
+    /// Only the syntax of the token is checked; whether it is allowed at this point of
+    /// the structure is validated once it is actually read.
+    ///
+    /// ```
+    /// use bendy::{decoding::Decoder, state_tracker::Token};
+    ///
+    /// let mut decoder = Decoder::new(b"d3:fooi1ee");
+    /// assert_eq!(decoder.peek_token().unwrap(), Some(Token::Dict));
+    /// assert!(decoder.next_object().unwrap().unwrap().try_into_dictionary().is_ok());
+    /// ```
+    pub fn peek_token(&mut self) -> Result<Option<Token<'ser>>, Error> {
+        self.state.check_error()?;
+
+        if self.pending_end {
+            return Ok(Some(Token::End));
+        }
+
+        if self.offset == self.source.len() {
+            return Ok(None);
+        }
+
+        let offset = self.offset;
+        let token = self.raw_next_token();
+        self.offset = offset;
+        token.map(Some)
+    }
+
     /// Iterate over the tokens in the input stream. This guarantees that the resulting stream
     /// of tokens constitutes a valid bencoded structure.
     pub fn tokens(self) -> Tokens<'ser> {
         Tokens(self)
     }
+
+    /// Iterate over the tokens in the input stream like [`Decoder::tokens()`], but with
+    /// integers already classified into `i64`, `u64` or an unparsed big number.
+    ///
+    /// ```
+    /// use bendy::{
+    ///     decoding::Decoder,
+    ///     state_tracker::{Number, TypedToken},
+    /// };
+    ///
+    /// let mut tokens = Decoder::new(b"li-1ei18446744073709551615ee").typed_tokens();
+    /// assert_eq!(tokens.next().unwrap().unwrap(), TypedToken::List);
+    /// assert_eq!(tokens.next().unwrap().unwrap(), TypedToken::Num(Number::I64(-1)));
+    /// assert_eq!(
+    ///     tokens.next().unwrap().unwrap(),
+    ///     TypedToken::Num(Number::U64(u64::MAX))
+    /// );
+    /// ```
+    pub fn typed_tokens(self) -> TypedTokens<'ser> {
+        TypedTokens(Tokens(self))
+    }
 }
 
 /// Iterator over the tokens in the input stream. This guarantees that the resulting stream
@@ -206,6 +794,25 @@ impl<'a> Iterator for Tokens<'a> {
     }
 }
 
+/// Iterator over the tokens in the input stream with classified integers. This
+/// guarantees that the resulting stream of tokens constitutes a valid bencoded structure.
+pub struct TypedTokens<'a>(Tokens<'a>);
+
+impl<'a> Iterator for TypedTokens<'a> {
+    type Item = Result<TypedToken<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let token = self.0.next()?;
+        Some(token.map(|token| match token {
+            Token::Num(digits) => {
+                let number = self.0 .0.number.take();
+                TypedToken::Num(number.unwrap_or_else(|| Number::classify(digits)))
+            },
+            token => TypedToken::from(token),
+        }))
+    }
+}
+
 // High level interface
 
 impl<'ser> Decoder<'ser> {
@@ -228,6 +835,438 @@ impl<'ser> Decoder<'ser> {
             Some(Num(s)) => Some(Object::Integer(s)),
         })
     }
+
+    /// Read the next object and return the raw bytes spanning its encoding. The object is
+    /// fully validated, but not decoded.
+    ///
+    /// This is useful to hash parts of a document, e.g. to compute the info-hash of a
+    /// torrent, or to forward them verbatim without encoding them again.
+    ///
+    /// ```
+    /// use bendy::decoding::Decoder;
+    ///
+    /// let mut decoder = Decoder::new(b"d3:bari1e3:fooli2ei3eee");
+    /// let mut dict = decoder.next_object().unwrap().unwrap().try_into_dictionary().unwrap();
+    ///
+    /// let (key, raw) = dict.next_pair_raw().unwrap().unwrap();
+    /// assert_eq!((key, raw), (&b"bar"[..], &b"i1e"[..]));
+    ///
+    /// let (key, raw) = dict.next_pair_raw().unwrap().unwrap();
+    /// assert_eq!((key, raw), (&b"foo"[..], &b"li2ei3ee"[..]));
+    /// ```
+    pub fn next_raw(&mut self) -> Result<Option<&'ser [u8]>, Error> {
+        let start = self.offset;
+        match self.next_object()? {
+            None => return Ok(None),
+            Some(Object::List(mut list)) => {
+                list.consume_all()?;
+            },
+            Some(Object::Dict(mut dict)) => {
+                dict.consume_all()?;
+            },
+            Some(Object::Bytes(_)) | Some(Object::Integer(_)) => (),
+        }
+
+        Ok(Some(&self.source[start..self.offset]))
+    }
+
+    /// Iterate over a sequence of concatenated documents, such as a log of bencoded
+    /// messages, reading each of them into a [`Value`] borrowing from the input. Stops at
+    /// the end of the input, or after the first error.
+    ///
+    /// ```
+    /// use bendy::{decoding::Decoder, value::Value};
+    ///
+    /// let documents = Decoder::new(b"d3:fooi1eei2e3:bar")
+    ///     .documents()
+    ///     .collect::<Result<Vec<_>, _>>()
+    ///     .unwrap();
+    /// assert_eq!(documents.len(), 3);
+    /// assert_eq!(documents[1], Value::Integer(2));
+    /// ```
+    pub fn documents(self) -> Documents<'ser> {
+        Documents(self)
+    }
+
+    /// Skip ahead to the object at the given path and return it, without decoding
+    /// anything but the containers along the way. Each segment of the path is either a
+    /// dictionary key or, for lists, a decimal index.
+    ///
+    /// Returns `Ok(None)` if there is no object at the path. Values along the way are
+    /// skipped using [`Decoder::skip_value()`], and the remaining input after the object
+    /// is neither read nor validated, so the decoder shouldn't be used for anything else
+    /// afterwards. If a key occurs more than once, the first occurrence is returned, or
+    /// the last one with [`DuplicateKeys::LastWins`], like decoding the whole dictionary
+    /// would.
+    ///
+    /// ```
+    /// use bendy::decoding::Decoder;
+    ///
+    /// let torrent = b"d8:announce3:foo4:infod6:lengthi3e6:pieces3:abcee";
+    ///
+    /// let mut decoder = Decoder::new(torrent);
+    /// let pieces = decoder.seek_path(&[b"info", b"pieces"]).unwrap().unwrap();
+    /// assert_eq!(pieces.try_into_bytes().unwrap(), b"abc");
+    ///
+    /// let mut decoder = Decoder::new(torrent);
+    /// assert!(decoder.seek_path(&[b"info", b"name"]).unwrap().is_none());
+    /// ```
+    pub fn seek_path<'obj>(
+        &'obj mut self,
+        path: &[&[u8]],
+    ) -> Result<Option<Object<'obj, 'ser>>, Error> {
+        let last_wins = self.options.duplicate_keys == DuplicateKeys::LastWins;
+        for &segment in path {
+            match self.next_token()? {
+                Some(Token::Dict) => {
+                    let mut last_key_end = None;
+                    loop {
+                        match self.next_token()? {
+                            Some(Token::String(key)) if key == segment => {
+                                if !last_wins {
+                                    break;
+                                }
+                                let end =
+                                    *last_key_end.get_or_insert_with(|| self.last_key_end(segment));
+                                if self.offset >= end {
+                                    break;
+                                }
+                                self.skip_value()?;
+                            },
+                            // Keys are sorted, so the segment can't come up anymore
+                            Some(Token::String(key))
+                                if key > segment && !self.options.unsorted_keys =>
+                            {
+                                return Ok(None)
+                            },
+                            Some(Token::String(_)) => {
+                                self.skip_value()?;
+                            },
+                            _ => return Ok(None),
+                        }
+                    }
+                },
+                Some(Token::List) => {
+                    let index = match str::from_utf8(segment).ok().and_then(|s| s.parse().ok()) {
+                        Some(index) => index,
+                        None => return Ok(None),
+                    };
+                    for _ in 0..index {
+                        if !self.skip_value()? {
+                            return Ok(None);
+                        }
+                    }
+                },
+                _ => return Ok(None),
+            }
+        }
+
+        self.next_object()
+    }
+
+    /// The offset right after the last occurrence of `key` among the rest of the
+    /// dictionary being read, whose next token is the value of `key`. Only the raw tokens
+    /// are scanned, on a copy of the decoder's position; malformed input ends the search,
+    /// and fails once the decoder itself gets to it.
+    fn last_key_end(&self, key: &[u8]) -> usize {
+        let mut lookahead = Decoder::new_with_options(self.source, self.options.clone());
+        lookahead.offset = self.offset;
+
+        let mut end = self.offset;
+        loop {
+            // Skip the value of the previous key
+            let mut open = 0_usize;
+            loop {
+                match lookahead.raw_next_token() {
+                    Ok(Token::List) | Ok(Token::Dict) => open += 1,
+                    Ok(Token::End) if open > 0 => open -= 1,
+                    Ok(Token::String(_)) | Ok(Token::Num(_)) => (),
+                    Ok(Token::End) | Err(_) => return end,
+                }
+                if open == 0 {
+                    break;
+                }
+            }
+
+            match lookahead.raw_next_token() {
+                Ok(Token::String(next)) if next == key => end = lookahead.offset,
+                Ok(Token::String(_)) => (),
+                _ => return end,
+            }
+        }
+    }
+
+    /// Skip the next value without decoding it. Returns `Ok(false)` if the enclosing
+    /// container or the input ended instead.
+    ///
+    /// This is considerably cheaper than reading and dropping an [`Object`], as the
+    /// contents of a skipped list or dictionary are only checked to be well-formed tokens
+    /// with balanced nesting. In particular, the order of keys in skipped dictionaries is
+    /// not validated.
+    ///
+    /// ```
+    /// use bendy::decoding::Decoder;
+    ///
+    /// let mut decoder = Decoder::new(b"d3:fooli1ei2eee3:bar");
+    ///
+    /// assert!(decoder.skip_value().unwrap());
+    /// let bar = decoder.next_object().unwrap().unwrap();
+    /// assert_eq!(bar.try_into_bytes().unwrap(), b"bar");
+    /// assert!(!decoder.skip_value().unwrap());
+    /// ```
+    pub fn skip_value(&mut self) -> Result<bool, Error> {
+        match self.next_token()? {
+            Some(Token::List) | Some(Token::Dict) => (),
+            Some(Token::End) | None => return Ok(false),
+            Some(_) => return Ok(true),
+        }
+
+        let result = self.skip_contents();
+        self.state.latch_err(result)?;
+        self.state.observe_token(&Token::End, self.position() - 1)?;
+        Ok(true)
+    }
+
+    /// Check that the input is exactly one value, like reading all tokens with strict EOF
+    /// handling would. Implements
+    /// [`validate_with_options`](crate::decoding::validate_with_options).
+    pub(crate) fn validate(mut self) -> Result<(), Error> {
+        self.scan_value()?;
+
+        if self.offset != self.source.len() {
+            let position = self.position();
+            return Err(Error::from(StructureError::TrailingData { position }));
+        }
+
+        Ok(())
+    }
+
+    /// Read the next value, reporting its tokens to the visitor. Implements
+    /// [`decode_with_visitor_with_options`](crate::decoding::decode_with_visitor_with_options).
+    pub(crate) fn visit<V: Visitor + ?Sized>(mut self, visitor: &mut V) -> Result<(), Error> {
+        loop {
+            let key = self.state.expects_key();
+            let token = match self.next_token()? {
+                Some(token) => token,
+                None => return Err(Error::from(StructureError::UnexpectedEof)),
+            };
+
+            match token {
+                Token::List => visitor.on_list_start()?,
+                Token::Dict => visitor.on_dict_start()?,
+                Token::String(bytes) if key => visitor.on_key(bytes)?,
+                Token::String(bytes) => visitor.on_bytes(bytes)?,
+                Token::Num(number) => visitor.on_int(number)?,
+                Token::End => visitor.on_end()?,
+            }
+
+            if self.state.depth() == 0 {
+                break;
+            }
+        }
+
+        if self.options.strict_eof {
+            // Fails if there is anything after the value
+            self.next_token()?;
+        }
+        Ok(())
+    }
+
+    /// Read past the next value, checking it like reading its tokens would, but without
+    /// the overhead of the state tracker where possible. Fails with `UnexpectedEof` if
+    /// the input ends before the value does.
+    pub(crate) fn scan_value(&mut self) -> Result<(), Error> {
+        if self.options.unsorted_keys {
+            // Detecting duplicates needs the keys of every open dictionary, so leave that
+            // to the state tracker
+            if self.next_token()?.is_none() {
+                return Err(Error::from(StructureError::UnexpectedEof));
+            }
+            while self.state.depth() > 0 {
+                self.next_token()?;
+            }
+            return Ok(());
+        }
+
+        if self.source.len() > self.options.max_document_size {
+            let limit = Limit::DocumentSize(self.options.max_document_size);
+            return Err(Error::limit_exceeded(limit));
+        }
+
+        enum Frame<'a> {
+            List,
+            Dict {
+                last_key: Option<&'a [u8]>,
+                expect_value: bool,
+            },
+        }
+
+        let allow_duplicate_keys = self.options.duplicate_keys != DuplicateKeys::Error;
+        let mut stack: Vec<Frame> = Vec::new();
+
+        loop {
+            self.count_token()?;
+
+            let position = self.position();
+            let token = self.raw_next_token()?;
+            self.report_progress()?;
+
+            let may_nest = match stack.last_mut() {
+                None => match token {
+                    Token::End => {
+                        return Err(Error::from(StructureError::UnexpectedEnd { position }));
+                    },
+                    _ => true,
+                },
+                Some(Frame::List) => true,
+                Some(Frame::Dict {
+                    last_key,
+                    expect_value,
+                }) if !*expect_value => match token {
+                    Token::String(key) => {
+                        if let Some(last_key) = *last_key {
+                            if last_key > key || (last_key == key && !allow_duplicate_keys) {
+                                return Err(Error::from(StructureError::UnsortedKeys { position }));
+                            }
+                        }
+                        *last_key = Some(key);
+                        *expect_value = true;
+                        false
+                    },
+                    Token::End => true,
+                    _ => {
+                        let found = token.name();
+                        let error = StructureError::NonStringKey { found, position };
+                        return Err(Error::from(error));
+                    },
+                },
+                Some(Frame::Dict { expect_value, .. }) => match token {
+                    Token::End => {
+                        return Err(Error::from(StructureError::MissingValue { position }));
+                    },
+                    _ => {
+                        *expect_value = false;
+                        true
+                    },
+                },
+            };
+
+            if may_nest {
+                match token {
+                    Token::List | Token::Dict if stack.len() >= self.options.max_depth => {
+                        return Err(Error::from(StructureError::NestingTooDeep { position }));
+                    },
+                    Token::List => stack.push(Frame::List),
+                    Token::Dict => stack.push(Frame::Dict {
+                        last_key: None,
+                        expect_value: false,
+                    }),
+                    Token::End => {
+                        stack.pop();
+                    },
+                    _ => (),
+                }
+            }
+
+            if stack.is_empty() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Advance past the contents and end of a just opened list or dictionary, tracking
+    /// nothing but the nesting depth
+    fn skip_contents(&mut self) -> Result<(), Error> {
+        // The opened container is already accounted for in the state tracker
+        let max_depth = self.state.remaining_depth().saturating_add(1);
+        let mut depth = 1_usize;
+
+        while depth > 0 {
+            self.count_token()?;
+            let token = self.raw_next_token()?;
+            self.report_progress()?;
+
+            match token {
+                Token::List | Token::Dict => {
+                    depth += 1;
+                    if depth > max_depth {
+                        let position = self.position() - 1;
+                        return Err(Error::from(StructureError::NestingTooDeep { position }));
+                    }
+                },
+                Token::End => depth -= 1,
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Read (and validate) the rest of the innermost open list or dictionary, including
+    /// its end. Unlike reading and dropping its items one by one, this doesn't recurse
+    /// into nested containers, so it is safe to use on arbitrarily deep input.
+    fn drain_container(&mut self) -> Result<(), Error> {
+        let mut depth = 0_usize;
+        loop {
+            match self.next_token()? {
+                Some(Token::List) | Some(Token::Dict) => depth += 1,
+                Some(Token::End) if depth == 0 => return Ok(()),
+                Some(Token::End) => depth -= 1,
+                Some(_) => (),
+                None => return Ok(()),
+            }
+        }
+    }
+
+    /// Read past the next object. Returns `Ok(false)` if the enclosing container ended
+    /// instead.
+    fn skip_object(&mut self) -> Result<bool, Error> {
+        let mut depth = 0_usize;
+        loop {
+            match self.next_token()? {
+                Some(Token::List) | Some(Token::Dict) => depth += 1,
+                Some(Token::End) if depth == 0 => return Ok(false),
+                Some(Token::End) => depth -= 1,
+                Some(_) => (),
+                None => return Ok(false),
+            }
+
+            if depth == 0 {
+                return Ok(true);
+            }
+        }
+    }
+}
+
+/// Iterator over concatenated documents in the input stream, see
+/// [`Decoder::documents()`]
+pub struct Documents<'a>(Decoder<'a>);
+
+impl<'a> Iterator for Documents<'a> {
+    type Item = Result<Value<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Only report an error once
+        if self.0.state.check_error().is_err() {
+            return None;
+        }
+        match self.0.next_object() {
+            Ok(Some(object)) => Some(object.into_value()),
+            Ok(None) => None,
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+/// How [`DictDecoder::next_pair_str`] handles keys which aren't valid UTF-8
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Utf8Policy {
+    /// Fail with an error
+    Strict,
+    /// Replace invalid sequences with `U+FFFD REPLACEMENT CHARACTER`
+    Lossy,
+    /// Skip the pair entirely
+    Skip,
 }
 
 /// A dictionary read from the input stream
@@ -236,6 +1275,8 @@ pub struct DictDecoder<'obj, 'ser: 'obj> {
     decoder: &'obj mut Decoder<'ser>,
     finished: bool,
     start_point: usize,
+    /// A key read by [`DictDecoder::find`] whose pair hasn't been returned yet
+    pending_key: Option<&'ser [u8]>,
 }
 
 /// A list read from the input stream
@@ -253,6 +1294,7 @@ impl<'obj, 'ser: 'obj> DictDecoder<'obj, 'ser> {
             decoder,
             finished: false,
             start_point: offset,
+            pending_key: None,
         }
     }
 
@@ -261,33 +1303,221 @@ impl<'obj, 'ser: 'obj> DictDecoder<'obj, 'ser> {
     pub fn next_pair<'item>(
         &'item mut self,
     ) -> Result<Option<(&'ser [u8], Object<'item, 'ser>)>, Error> {
+        match self.next_key()? {
+            Some(k) => {
+                let position = self.decoder.position();
+                let v = self
+                    .decoder
+                    .next_object()?
+                    .ok_or_else(|| missing_value(position))?;
+                Ok(Some((k, v)))
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Parse the next key/value pair from the dictionary, returning the raw bytes of the
+    /// value like [`Decoder::next_raw`]. Returns `Ok(None)` at the end of the dictionary.
+    #[allow(clippy::type_complexity)]
+    pub fn next_pair_raw(&mut self) -> Result<Option<(&'ser [u8], &'ser [u8])>, Error> {
+        match self.next_key()? {
+            Some(k) => {
+                let position = self.decoder.position();
+                let v = self
+                    .decoder
+                    .next_raw()?
+                    .ok_or_else(|| missing_value(position))?;
+                Ok(Some((k, v)))
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Parse the next key/value pair from the dictionary, with the key decoded as UTF-8.
+    /// Returns `Ok(None)` at the end of the dictionary.
+    ///
+    /// Keys which aren't valid UTF-8 are handled according to the given policy.
+    ///
+    /// ```
+    /// use bendy::decoding::{Decoder, Utf8Policy};
+    ///
+    /// let mut decoder = Decoder::new(b"d3:fooi1e2:\xff\xfei2ee");
+    /// let mut dict = decoder.next_object().unwrap().unwrap().try_into_dictionary().unwrap();
+    ///
+    /// let (key, value) = dict.next_pair_str(Utf8Policy::Skip).unwrap().unwrap();
+    /// assert_eq!(key, "foo");
+    /// assert_eq!(value.try_into_integer().unwrap(), "1");
+    /// assert!(dict.next_pair_str(Utf8Policy::Skip).unwrap().is_none());
+    /// ```
+    pub fn next_pair_str<'item>(
+        &'item mut self,
+        policy: Utf8Policy,
+    ) -> Result<Option<(Cow<'ser, str>, Object<'item, 'ser>)>, Error> {
+        loop {
+            let k = match self.next_key()? {
+                Some(k) => k,
+                None => return Ok(None),
+            };
+
+            let key = match (str::from_utf8(k), policy) {
+                (Ok(key), _) => Cow::Borrowed(key),
+                (Err(error), Utf8Policy::Strict) => {
+                    return Err(Error::from(error).context_field(String::from_utf8_lossy(k)));
+                },
+                (Err(_), Utf8Policy::Lossy) => String::from_utf8_lossy(k),
+                (Err(_), Utf8Policy::Skip) => {
+                    self.decoder.skip_object()?;
+                    continue;
+                },
+            };
+
+            let position = self.decoder.position();
+            let v = self
+                .decoder
+                .next_object()?
+                .ok_or_else(|| missing_value(position))?;
+            return Ok(Some((key, v)));
+        }
+    }
+
+    /// Skip ahead to the value of the given key. Returns `Ok(None)` if the dictionary
+    /// doesn't contain the key.
+    ///
+    /// As keys are sorted, the search stops at the first key following the requested
+    /// one. That pair isn't skipped, so the dictionary can be searched for several keys
+    /// in ascending order, or read further using [`DictDecoder::next_pair`]. If the
+    /// decoder accepts unsorted keys, a search for a missing key consumes the rest of the
+    /// dictionary instead.
+    ///
+    /// ```
+    /// use bendy::decoding::Decoder;
+    ///
+    /// let mut decoder = Decoder::new(b"d3:bari1e3:bazi2e3:fooi3ee");
+    /// let mut dict = decoder.next_object().unwrap().unwrap().try_into_dictionary().unwrap();
+    ///
+    /// let baz = dict.find(b"baz").unwrap().unwrap();
+    /// assert_eq!(baz.try_into_integer().unwrap(), "2");
+    ///
+    /// assert!(dict.find(b"cat").unwrap().is_none());
+    /// let (key, _) = dict.next_pair().unwrap().unwrap();
+    /// assert_eq!(key, b"foo");
+    /// ```
+    pub fn find<'item>(&'item mut self, key: &[u8]) -> Result<Option<Object<'item, 'ser>>, Error> {
+        while let Some(k) = self.next_key()? {
+            if k == key {
+                return self.decoder.next_object();
+            }
+
+            if k > key && !self.decoder.options.unsorted_keys {
+                self.pending_key = Some(k);
+                return Ok(None);
+            }
+
+            self.decoder.skip_object()?;
+        }
+
+        Ok(None)
+    }
+
+    /// Parse the next key from the dictionary, leaving its value to be read next.
+    /// Returns `Ok(None)` at the end of the dictionary
+    fn next_key(&mut self) -> Result<Option<&'ser [u8]>, Error> {
+        if let Some(key) = self.pending_key.take() {
+            return Ok(Some(key));
+        }
+
         if self.finished {
             return Ok(None);
         }
 
-        // We convert to a token to release the mut ref to decoder
-        let key = self.decoder.next_object()?.map(Object::into_token);
-
-        if let Some(Token::String(k)) = key {
-            // This unwrap should be safe because None would produce an error here
-            let v = self.decoder.next_object()?.unwrap();
-            Ok(Some((k, v)))
-        } else {
-            // We can't have gotten anything but a string, as anything else would be
-            // a state error
-            self.finished = true;
-            Ok(None)
+        loop {
+            // We convert to a token to release the mut ref to decoder
+            let key = self.decoder.next_object()?.map(Object::into_token);
+
+            if let Some(Token::String(k)) = key {
+                if self.decoder.options.duplicate_keys == DuplicateKeys::FirstWins
+                    && self.decoder.state.last_key_was_duplicate()
+                {
+                    self.decoder.skip_object()?;
+                    continue;
+                }
+                return Ok(Some(k));
+            } else {
+                // We can't have gotten anything but a string, as anything else would be
+                // a state error
+                self.finished = true;
+                return Ok(None);
+            }
         }
     }
 
     /// Consume (and validate the structure of) the rest of the items from the
-    /// dictionary. This method should be used to check for encoding errors if
-    /// [`DictDecoder::next_pair`] is not called until it returns `Ok(None)`.
-    pub fn consume_all(&mut self) -> Result<(), Error> {
+    /// dictionary, returning how many pairs were skipped. This method should be used to
+    /// check for encoding errors if [`DictDecoder::next_pair`] is not called until it
+    /// returns `Ok(None)`.
+    pub fn consume_all(&mut self) -> Result<usize, Error> {
+        let mut count = 0;
         while self.next_pair()?.is_some() {
-            // just drop the items
+            count += 1;
         }
-        Ok(())
+        Ok(count)
+    }
+
+    /// Check that all pairs of the dictionary have been read, failing with an
+    /// [`ErrorKind::UnknownField`](crate::decoding::ErrorKind::UnknownField) for the
+    /// first unread key otherwise. Useful for strict schemas.
+    ///
+    /// ```
+    /// use bendy::decoding::Decoder;
+    ///
+    /// let mut decoder = Decoder::new(b"d3:bari1e3:fooi2ee");
+    /// let mut dict = decoder.next_object().unwrap().unwrap().try_into_dictionary().unwrap();
+    ///
+    /// dict.next_pair().unwrap();
+    /// let error = dict.finish().unwrap_err();
+    /// assert_eq!(error.to_string(), "Error: unknown field foo at offset 9");
+    /// ```
+    pub fn finish(mut self) -> Result<(), Error> {
+        let offset = self.offset();
+        match self.next_key()? {
+            Some(key) => Err(Error::unknown_field(key, offset)),
+            None => Ok(()),
+        }
+    }
+
+    /// Decode the remaining pairs of the dictionary into a collection. Keys are converted
+    /// from their raw bytes, values are decoded using [`FromBencode`].
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    ///
+    /// use bendy::{decoding::Decoder, value::Value};
+    ///
+    /// let mut decoder = Decoder::new(b"d3:bari1e3:foo3:baze");
+    /// let mut dict = decoder.next_object().unwrap().unwrap().try_into_dictionary().unwrap();
+    ///
+    /// let map: BTreeMap<Vec<u8>, Value> = dict.collect().unwrap();
+    /// assert_eq!(map[&b"bar"[..]], Value::Integer(1));
+    /// assert_eq!(map[&b"foo"[..]], Value::Bytes(b"baz"[..].into()));
+    /// ```
+    pub fn collect<K, V, C>(&mut self) -> Result<C, Error>
+    where
+        K: From<&'ser [u8]>,
+        V: FromBencode,
+        C: FromIterator<(K, V)>,
+    {
+        let mut next = || -> Result<Option<(K, V)>, Error> {
+            match self.next_pair()? {
+                Some((key, value)) => {
+                    let value = V::decode_bencode_object(value)
+                        .map_err(|error| error.context_field(String::from_utf8_lossy(key)))?;
+                    Ok(Some((K::from(key), value)))
+                },
+                None => Ok(None),
+            }
+        };
+
+        iter::from_fn(|| next().transpose()).collect()
     }
 
     /// Get the raw bytes that made up this dictionary
@@ -295,12 +1525,55 @@ impl<'obj, 'ser: 'obj> DictDecoder<'obj, 'ser> {
         self.consume_all()?;
         Ok(&self.decoder.source[self.start_point..self.decoder.offset])
     }
+
+    /// The options of the decoder reading this dictionary, to decode the result of
+    /// [`DictDecoder::into_raw`] once more just as leniently
+    pub fn options(&self) -> &DecodeOptions {
+        self.decoder.options()
+    }
+
+    /// The offset of this dictionary in the input
+    pub fn start_offset(&self) -> usize {
+        self.decoder.offset_base + self.start_point
+    }
+
+    /// The number of lists and dictionaries enclosing the entries of this dictionary,
+    /// including the dictionary itself
+    pub fn depth(&self) -> usize {
+        self.decoder.depth()
+    }
+
+    /// The offset in the input of the next key, or of the end of the dictionary if all
+    /// pairs have been read. Used to point to keys in error messages:
+    ///
+    /// ```
+    /// use bendy::decoding::Decoder;
+    ///
+    /// let mut decoder = Decoder::new(b"d3:bari1e3:fooi2ee");
+    /// let mut dict = decoder.next_object().unwrap().unwrap().try_into_dictionary().unwrap();
+    /// assert_eq!(dict.offset(), 1);
+    ///
+    /// dict.next_pair().unwrap();
+    /// assert_eq!(dict.offset(), 9);
+    /// ```
+    pub fn offset(&self) -> usize {
+        self.decoder.offset_base + self.decoder.offset
+    }
+}
+
+/// The state tracker rejects a key without a value before the dictionary decoder could
+/// observe it, so this error is only a fallback to avoid panicking
+fn missing_value(position: usize) -> Error {
+    Error::from(StructureError::MissingValue { position })
 }
 
 impl<'obj, 'ser: 'obj> Drop for DictDecoder<'obj, 'ser> {
     fn drop(&mut self) {
-        // we don't care about errors in drop; they'll be reported again in the parent
-        self.consume_all().ok();
+        if !self.finished {
+            self.finished = true;
+            // we don't care about errors in drop; they'll be reported again in the parent
+            self.decoder.drain_container().ok();
+        }
     }
 }
 
@@ -325,19 +1598,179 @@ impl<'obj, 'ser: 'obj> ListDecoder<'obj, 'ser> {
             self.finished = true;
         }
 
-        Ok(item)
+        Ok(item)
+    }
+
+    /// The number of lists and dictionaries enclosing the items of this list, including
+    /// the list itself
+    ///
+    /// ```
+    /// use bendy::decoding::Decoder;
+    ///
+    /// let mut decoder = Decoder::new(b"lli1eee");
+    /// let mut outer = decoder.next_object().unwrap().unwrap().try_into_list().unwrap();
+    /// assert_eq!(outer.depth(), 1);
+    ///
+    /// let inner = outer.next_object().unwrap().unwrap().try_into_list().unwrap();
+    /// assert_eq!(inner.depth(), 2);
+    /// ```
+    pub fn depth(&self) -> usize {
+        self.decoder.depth()
+    }
+
+    /// Look at the next item's token without consuming it, e.g. to decide how to decode
+    /// the item. Returns `Ok(None)` at the end of the list.
+    ///
+    /// ```
+    /// use bendy::{decoding::Decoder, state_tracker::Token};
+    ///
+    /// let mut decoder = Decoder::new(b"li1e3:fooe");
+    /// let mut list = decoder.next_object().unwrap().unwrap().try_into_list().unwrap();
+    ///
+    /// while let Some(token) = list.peek_token().unwrap() {
+    ///     let item = list.next_object().unwrap().unwrap();
+    ///     match token {
+    ///         Token::Num(_) => assert_eq!(item.try_into_int::<u32>().unwrap(), 1),
+    ///         _ => assert_eq!(item.try_into_str().unwrap(), "foo"),
+    ///     }
+    /// }
+    /// ```
+    pub fn peek_token(&mut self) -> Result<Option<Token<'ser>>, Error> {
+        if self.finished {
+            return Ok(None);
+        }
+
+        match self.decoder.peek_token()? {
+            Some(Token::End) => Ok(None),
+            token => Ok(token),
+        }
+    }
+
+    /// Consume (and validate the structure of) the rest of the items from the
+    /// list. This method should be used to check for encoding errors if
+    /// [`ListDecoder::next_object`] is not called until it returns [`Ok(())`].
+    ///
+    /// [`Ok(())`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Ok
+    ///
+    /// Returns how many items were skipped.
+    pub fn consume_all(&mut self) -> Result<usize, Error> {
+        let mut count = 0;
+        while self.next_object()?.is_some() {
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Check that all items of the list have been read, failing otherwise. Useful for
+    /// strict schemas.
+    ///
+    /// ```
+    /// use bendy::decoding::Decoder;
+    ///
+    /// let mut decoder = Decoder::new(b"li1ei2ee");
+    /// let mut list = decoder.next_object().unwrap().unwrap().try_into_list().unwrap();
+    ///
+    /// list.next_object().unwrap();
+    /// let error = list.finish().unwrap_err();
+    /// assert_eq!(error.to_string(), "Error: discovered Num but expected End");
+    /// ```
+    pub fn finish(mut self) -> Result<(), Error> {
+        match self.next_object()? {
+            Some(object) => Err(Error::unexpected_token("End", object.into_token().name())),
+            None => Ok(()),
+        }
+    }
+
+    /// Call the given closure on each of the remaining items, stopping at the first
+    /// error.
+    ///
+    /// ```
+    /// use bendy::decoding::{Decoder, Error};
+    ///
+    /// let mut decoder = Decoder::new(b"li1ei2ei3ee");
+    /// let mut list = decoder.next_object().unwrap().unwrap().try_into_list().unwrap();
+    ///
+    /// let mut sum = 0;
+    /// list.try_for_each(|item| {
+    ///     sum += item.try_into_int::<u32>()?;
+    ///     Ok::<_, Error>(())
+    /// })
+    /// .unwrap();
+    /// assert_eq!(sum, 6);
+    /// ```
+    pub fn try_for_each<F, E>(&mut self, mut f: F) -> Result<(), E>
+    where
+        F: FnMut(Object<'_, 'ser>) -> Result<(), E>,
+        E: From<Error>,
+    {
+        while let Some(object) = self.next_object()? {
+            f(object)?;
+        }
+        Ok(())
+    }
+
+    /// Consume (and validate the structure of) the rest of the items from the list,
+    /// returning how many there were. This is an alias of [`ListDecoder::consume_all`].
+    pub fn count_remaining(&mut self) -> Result<usize, Error> {
+        self.consume_all()
     }
 
-    /// Consume (and validate the structure of) the rest of the items from the
-    /// list. This method should be used to check for encoding errors if
-    /// [`ListDecoder::next_object`] is not called until it returns [`Ok(())`].
+    /// Skip `n` items and return the one after them. Returns `Ok(None)` if the list
+    /// ends before that. Like [`Iterator::nth`], indices count from the current position.
     ///
-    /// [`Ok(())`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Ok
-    pub fn consume_all(&mut self) -> Result<(), Error> {
-        while self.next_object()?.is_some() {
-            // just drop the items
+    /// ```
+    /// use bendy::decoding::Decoder;
+    ///
+    /// let mut decoder = Decoder::new(b"l3:foo3:bar3:baze");
+    /// let mut list = decoder.next_object().unwrap().unwrap().try_into_list().unwrap();
+    ///
+    /// let item = list.nth(1).unwrap().unwrap();
+    /// assert_eq!(item.try_into_bytes().unwrap(), b"bar");
+    /// assert!(list.nth(1).unwrap().is_none());
+    /// ```
+    pub fn nth<'item>(&'item mut self, n: usize) -> Result<Option<Object<'item, 'ser>>, Error> {
+        for _ in 0..n {
+            if self.finished {
+                break;
+            }
+            if !self.decoder.skip_object()? {
+                self.finished = true;
+            }
         }
-        Ok(())
+
+        self.next_object()
+    }
+
+    /// Decode the remaining items of the list into a collection using [`FromBencode`].
+    ///
+    /// ```
+    /// use bendy::decoding::Decoder;
+    ///
+    /// let mut decoder = Decoder::new(b"li1ei2ei3ee");
+    /// let mut list = decoder.next_object().unwrap().unwrap().try_into_list().unwrap();
+    ///
+    /// let items: Vec<u32> = list.collect().unwrap();
+    /// assert_eq!(items, [1, 2, 3]);
+    /// ```
+    pub fn collect<T, C>(&mut self) -> Result<C, Error>
+    where
+        T: FromBencode,
+        C: FromIterator<T>,
+    {
+        let mut index = 0;
+        let mut next = || -> Result<Option<T>, Error> {
+            match self.next_object()? {
+                Some(object) => {
+                    let item = T::decode_bencode_object(object);
+                    let item = item.map_err(|error| error.context_index(index))?;
+                    index += 1;
+                    Ok(Some(item))
+                },
+                None => Ok(None),
+            }
+        };
+
+        iter::from_fn(|| next().transpose()).collect()
     }
 
     /// Get the raw bytes that made up this list
@@ -349,21 +1782,84 @@ impl<'obj, 'ser: 'obj> ListDecoder<'obj, 'ser> {
 
 impl<'obj, 'ser: 'obj> Drop for ListDecoder<'obj, 'ser> {
     fn drop(&mut self) {
-        // we don't care about errors in drop; they'll be reported again in the parent
-        self.consume_all().ok();
+        if !self.finished {
+            self.finished = true;
+            // we don't care about errors in drop; they'll be reported again in the parent
+            self.decoder.drain_container().ok();
+        }
+    }
+}
+
+/// An integer or string length read by [`Decoder::take_int`]
+#[derive(Clone, Copy, Debug)]
+struct ScannedInt<'a> {
+    /// The integer as written, including the sign
+    digits: &'a str,
+    /// The absolute value, accumulated from the checked digits; `None` if it
+    /// doesn't fit into a `u64`
+    magnitude: Option<u64>,
+    negative: bool,
+}
+
+impl<'a> ScannedInt<'a> {
+    /// Classify the integer like [`Number::classify`], without parsing it again
+    fn number(self) -> Number<'a> {
+        match (self.negative, self.magnitude) {
+            (false, Some(value)) => match i64::try_from(value) {
+                Ok(value) => Number::I64(value),
+                Err(_) => Number::U64(value),
+            },
+            (true, Some(value)) if value <= i64::MIN.unsigned_abs() => {
+                Number::I64(0i64.wrapping_sub_unsigned(value))
+            },
+            _ => Number::Big(self.digits),
+        }
+    }
+}
+
+/// Integers and string lengths are searched for their terminator within this many bytes
+/// first, which covers all but huge integers. Beyond it, the search only continues if
+/// all bytes so far can be part of the integer, so malformed input isn't scanned to its
+/// end on every attempt to read it, as when recovering from errors.
+const INT_SCAN_WINDOW: usize = 64;
+
+/// Find the terminator of an integer at the start of `source`, returning the length of
+/// the integer and whether the terminator was found. Without a terminator, the length
+/// covers all bytes which were scanned.
+fn scan_int(source: &[u8], terminator: u8) -> (usize, bool) {
+    let window = &source[..source.len().min(INT_SCAN_WINDOW)];
+    if let Some(length) = memchr(terminator, window) {
+        return (length, true);
+    }
+
+    let plausible = window.len() <= 1 || digit_run_len(&window[1..]) == window.len() - 1;
+    if !plausible || window.len() == source.len() {
+        return (window.len(), false);
+    }
+    match memchr(terminator, &source[window.len()..]) {
+        Some(length) => (window.len() + length, true),
+        None => (source.len(), false),
     }
 }
 
 #[cfg(test)]
+#[allow(
+    mismatched_lifetime_syntaxes,
+    clippy::needless_borrow,
+    clippy::redundant_slicing,
+    clippy::redundant_static_lifetimes
+)]
 mod test {
 
+    use alloc::string::ToString;
     #[cfg(not(feature = "std"))]
     use alloc::{vec, vec::Vec};
-    use core::iter;
+    use core::{cell::Cell, iter};
 
     use regex;
 
     use super::*;
+    use crate::decoding::ErrorKind;
 
     static SIMPLE_MSG: &'static [u8] = b"d3:bari1e3:fooli2ei3eee";
 
@@ -392,6 +1888,73 @@ mod test {
         }
     }
 
+    #[test]
+    fn seek_path_finds_nested_objects() {
+        let msg = b"d1:ad1:bli1ei2eee1:cd1:bi3eee";
+
+        let mut decoder = Decoder::new(msg);
+        let object = decoder.seek_path(&[b"a", b"b", b"1"]).unwrap().unwrap();
+        assert_eq!(object.try_into_integer().unwrap(), "2");
+
+        let mut decoder = Decoder::new(msg);
+        let object = decoder.seek_path(&[b"c", b"b"]).unwrap().unwrap();
+        assert_eq!(object.try_into_integer().unwrap(), "3");
+
+        let mut decoder = Decoder::new(msg);
+        let object = decoder.seek_path(&[]).unwrap().unwrap();
+        assert_eq!(
+            object.try_into_dictionary().unwrap().into_raw().unwrap(),
+            msg
+        );
+    }
+
+    #[test]
+    fn seek_path_reports_missing_objects() {
+        let msg = b"d1:ad1:bli1ei2eee1:cd1:bi3eee";
+
+        for path in &[
+            &[&b"b"[..]][..],
+            &[b"d"],
+            &[b"a", b"a"],
+            &[b"a", b"b", b"2"],
+            &[b"a", b"b", b"x"],
+            &[b"a", b"b", b"0", b"0"],
+        ] {
+            let mut decoder = Decoder::new(msg);
+            assert!(decoder.seek_path(path).unwrap().is_none(), "{:?}", path);
+        }
+    }
+
+    #[test]
+    fn seek_path_reports_malformed_input() {
+        let mut decoder = Decoder::new(b"d1:ai1e1:bi2e");
+        assert!(decoder.seek_path(&[b"c"]).is_err());
+
+        let mut decoder = Decoder::new(b"d1:ali1e");
+        assert!(decoder.seek_path(&[b"a", b"1"]).is_err());
+    }
+
+    #[test]
+    fn seek_path_should_follow_the_duplicate_key_policy() {
+        fn seek(input: &[u8], path: &[&[u8]], duplicate_keys: DuplicateKeys) -> String {
+            let options = DecodeOptions::new()
+                .with_duplicate_keys(duplicate_keys)
+                .with_unsorted_keys();
+            let mut decoder = Decoder::new_with_options(input, options);
+            let object = decoder.seek_path(path).unwrap().unwrap();
+            object.try_into_integer().unwrap().to_string()
+        }
+
+        let input = b"d1:ai1e1:ai2ee";
+        assert_eq!(seek(input, &[b"a"], DuplicateKeys::FirstWins), "1");
+        assert_eq!(seek(input, &[b"a"], DuplicateKeys::LastWins), "2");
+
+        let input = b"d1:ad1:bi1ee1:cli4ee1:ad1:bi2e1:bi3eee";
+        assert_eq!(seek(input, &[b"a", b"b"], DuplicateKeys::FirstWins), "1");
+        assert_eq!(seek(input, &[b"a", b"b"], DuplicateKeys::LastWins), "3");
+        assert_eq!(seek(input, &[b"c", b"0"], DuplicateKeys::LastWins), "4");
+    }
+
     #[test]
     fn simple_bdecode_tokenization() {
         use self::Token::*;
@@ -465,6 +2028,129 @@ mod test {
         decode_err(b"d3:fooe", r"Missing map value");
     }
 
+    fn recovering_tokens(msg: &[u8]) -> Vec<Result<Token<'_>, Error>> {
+        let options = DecodeOptions::new().with_error_recovery();
+        let mut decoder = Decoder::new_with_options(msg, options);
+        let mut tokens = Vec::new();
+        loop {
+            match decoder.next_token() {
+                Ok(Some(token)) => tokens.push(Ok(token)),
+                Ok(None) => return tokens,
+                Err(error) => {
+                    let fatal = decoder.state.check_error().is_err();
+                    tokens.push(Err(error));
+                    if fatal {
+                        return tokens;
+                    }
+                },
+            }
+        }
+    }
+
+    #[test]
+    fn recovery_should_skip_to_end_of_enclosing_container() {
+        use self::Token::*;
+
+        let tokens = recovering_tokens(b"lli1exi2eei3ee");
+        assert!(tokens[3].is_err());
+        let tokens: Vec<_> = tokens.into_iter().filter_map(Result::ok).collect();
+        assert_eq!(tokens, vec![List, List, Num("1"), End, Num("3"), End]);
+    }
+
+    #[test]
+    fn recovery_should_skip_malformed_integers_entirely() {
+        use self::Token::*;
+
+        let tokens = recovering_tokens(b"li1eixei3ee");
+        assert!(tokens[2].is_err());
+        let tokens: Vec<_> = tokens.into_iter().filter_map(Result::ok).collect();
+        assert_eq!(tokens, vec![List, Num("1"), End]);
+
+        let tokens = recovering_tokens(b"ld1:ai01ee1:bi2ee");
+        assert!(tokens[3].is_err());
+        let tokens: Vec<_> = tokens.into_iter().filter_map(Result::ok).collect();
+        assert_eq!(
+            tokens,
+            vec![List, Dict, String(b"a"), End, String(b"b"), Num("2"), End]
+        );
+    }
+
+    #[test]
+    fn recovery_should_restore_map_state() {
+        use self::Token::*;
+
+        let tokens = recovering_tokens(b"d1:ad1:bi1e1:ai2ee1:ci3ee");
+        assert!(tokens[5].is_err());
+        let tokens: Vec<_> = tokens.into_iter().filter_map(Result::ok).collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Dict,
+                String(b"a"),
+                Dict,
+                String(b"b"),
+                Num("1"),
+                End,
+                String(b"c"),
+                Num("3"),
+                End
+            ]
+        );
+    }
+
+    #[test]
+    fn recovery_should_give_up_at_eof_and_top_level() {
+        let tokens = recovering_tokens(b"li1ex");
+        assert!(tokens.last().unwrap().is_err());
+        assert_eq!(tokens.len(), 3);
+
+        let tokens = recovering_tokens(b"x");
+        assert_eq!(tokens.len(), 1);
+    }
+
+    #[test]
+    fn structure_errors_should_report_positions() {
+        let structure_error = |msg: &[u8]| {
+            let error = Decoder::new(msg)
+                .with_offset_base(100)
+                .tokens()
+                .last()
+                .unwrap();
+            match error.unwrap_err().kind() {
+                ErrorKind::StructureError(error) => error.clone(),
+                other => panic!("Unexpected error: {}", other),
+            }
+        };
+
+        assert_eq!(
+            structure_error(b"d3:fooi1eli1eee"),
+            StructureError::NonStringKey {
+                found: "List",
+                position: 109,
+            }
+        );
+        assert_eq!(
+            structure_error(b"l3:fooi1x"),
+            StructureError::UnexpectedCharacter {
+                expected: "'e' or '0'..'9'".into(),
+                found: 'x',
+                position: 108,
+            }
+        );
+        assert_eq!(
+            structure_error(b"lxe"),
+            StructureError::InvalidToken {
+                found: 'x',
+                position: 101,
+            }
+        );
+        assert_eq!(
+            structure_error(b"d3:bari1e3:fooi2e3:bari3ee"),
+            StructureError::UnsortedKeys { position: 117 }
+        );
+        assert_eq!(structure_error(b"d3:fooe").position(), Some(106));
+    }
+
     #[test]
     fn strings_must_have_bodies() {
         decode_err(b"3:", r"EOF");
@@ -475,6 +2161,59 @@ mod test {
         decode_err(b"ie", r"Expected.*got 'e'");
     }
 
+    #[test]
+    fn ints_longer_than_the_scan_window_should_parse() {
+        use self::Token::*;
+
+        let digits = "1".repeat(INT_SCAN_WINDOW * 2);
+        let msg = format!("i-{}e{}:{}", digits, INT_SCAN_WINDOW * 3, "x".repeat(192));
+        let tokens: Vec<_> = decode_tokens(msg.as_bytes());
+        assert_eq!(tokens[0], Num(&msg[1..digits.len() + 2]));
+        assert_eq!(tokens.len(), 2);
+
+        let mut msg = format!("i{}", digits).into_bytes();
+        msg[INT_SCAN_WINDOW + 3] = b'x';
+        decode_err(&msg, r"got 'x'");
+        decode_err(format!("i{}", digits).as_bytes(), r"EOF");
+    }
+
+    #[test]
+    fn typed_ints_should_be_classified_like_their_digits() {
+        let options = DecodeOptions::new()
+            .with_leading_zeros()
+            .with_negative_zero();
+        for digits in [
+            "0",
+            "-1",
+            "007",
+            "-0",
+            "9223372036854775807",
+            "9223372036854775808",
+            "-9223372036854775808",
+            "-9223372036854775809",
+            "18446744073709551615",
+            "18446744073709551616",
+            "-18446744073709551615",
+        ] {
+            let msg = format!("i{}e", digits);
+            let decoder = Decoder::new_with_options(msg.as_bytes(), options.clone());
+            assert_eq!(
+                decoder.typed_tokens().next().unwrap().unwrap(),
+                TypedToken::Num(Number::classify(digits)),
+                "{}",
+                digits
+            );
+        }
+    }
+
+    #[test]
+    fn string_lengths_beyond_usize_should_fail() {
+        decode_err(
+            b"18446744073709551616:x",
+            r"Invalid string length at offset 0",
+        );
+    }
+
     #[test]
     fn recursion_should_be_limited() {
         let mut msg = Vec::new();
@@ -500,6 +2239,369 @@ mod test {
             .is_err());
     }
 
+    #[test]
+    fn next_pair_str_should_apply_policy_to_invalid_keys() {
+        let msg = b"d3:fooi1e2:\xff\xfei2ee";
+        let keys = |policy| {
+            let mut decoder = Decoder::new(msg);
+            let mut dict = decoder.next_object()?.unwrap().try_into_dictionary()?;
+            let mut keys = Vec::new();
+            while let Some((key, _)) = dict.next_pair_str(policy)? {
+                keys.push(key.into_owned());
+            }
+            Ok::<_, Error>(keys)
+        };
+
+        assert!(keys(Utf8Policy::Strict).is_err());
+        assert_eq!(
+            keys(Utf8Policy::Lossy).unwrap(),
+            ["foo", "\u{fffd}\u{fffd}"]
+        );
+        assert_eq!(keys(Utf8Policy::Skip).unwrap(), ["foo"]);
+    }
+
+    #[test]
+    fn find_should_keep_the_pair_it_stopped_at() {
+        let mut decoder = Decoder::new(b"d3:bari1e3:fooli2eee");
+        let mut dict = decoder
+            .next_object()
+            .unwrap()
+            .unwrap()
+            .try_into_dictionary()
+            .unwrap();
+
+        assert!(dict.find(b"baz").unwrap().is_none());
+        assert!(dict.find(b"baz").unwrap().is_none());
+        assert!(dict.find(b"foo").unwrap().unwrap().try_into_list().is_ok());
+        assert!(dict.find(b"qux").unwrap().is_none());
+        assert!(dict.next_pair().unwrap().is_none());
+    }
+
+    #[test]
+    fn collect_should_add_key_to_errors() {
+        let mut decoder = Decoder::new(b"d3:bari1e3:foo3:baze");
+        let mut dict = decoder
+            .next_object()
+            .unwrap()
+            .unwrap()
+            .try_into_dictionary()
+            .unwrap();
+
+        let error = dict.collect::<Vec<u8>, u32, Vec<_>>().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Error: discovered String but expected Num in foo"
+        );
+    }
+
+    #[test]
+    fn list_helpers_should_stop_at_end_of_list() {
+        let mut decoder = Decoder::new(b"lli1eei2ei3eei4e");
+        let mut list = decoder
+            .next_object()
+            .unwrap()
+            .unwrap()
+            .try_into_list()
+            .unwrap();
+
+        assert_eq!(
+            list.nth(1).unwrap().unwrap().try_into_integer().unwrap(),
+            "2"
+        );
+        assert_eq!(list.count_remaining().unwrap(), 1);
+        assert!(list.nth(0).unwrap().is_none());
+        drop(list);
+
+        let token = decoder.tokens().next().unwrap().unwrap();
+        assert_eq!(token, Token::Num("4"));
+    }
+
+    #[test]
+    fn skip_value_should_respect_max_depth() {
+        let test_msg = b"lllleeeei1e";
+
+        let mut decoder = Decoder::new(test_msg).with_max_depth(4);
+        assert!(decoder.skip_value().unwrap());
+        let token = decoder.tokens().next().unwrap().unwrap();
+        assert_eq!(token, Token::Num("1"));
+
+        let mut decoder = Decoder::new(test_msg).with_max_depth(3);
+        assert!(decoder.skip_value().is_err());
+    }
+
+    #[test]
+    fn skip_value_should_close_enclosing_container() {
+        let mut decoder = Decoder::new(b"d3:bard1:bi1e1:ai2ee3:fooi3eei4e");
+        let mut dict = decoder
+            .next_object()
+            .unwrap()
+            .unwrap()
+            .try_into_dictionary()
+            .unwrap();
+        assert_eq!(dict.next_key().unwrap(), Some(&b"bar"[..]));
+        assert!(dict.decoder.skip_value().unwrap());
+        let (key, value) = dict.next_pair().unwrap().unwrap();
+        assert_eq!(key, b"foo");
+        assert_eq!(value.try_into_integer().unwrap(), "3");
+        assert!(dict.next_pair().unwrap().is_none());
+        drop(dict);
+
+        let token = decoder.tokens().next().unwrap().unwrap();
+        assert_eq!(token, Token::Num("4"));
+    }
+
+    #[test]
+    fn skip_value_should_respect_max_tokens() {
+        let test_msg = b"li1ei2ei3ei4ei5ei6ee";
+        let options = DecodeOptions::new().with_max_tokens(3);
+
+        let mut decoder = Decoder::new_with_options(test_msg, options.clone());
+        let error = decoder.skip_value().unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ErrorKind::LimitExceeded(Limit::TokenCount(3))
+        ));
+
+        let mut decoder = Decoder::new_with_options(b"d1:ali1ei2ee1:bi3ee", options);
+        let error = decoder.seek_path(&[b"b"]).err().unwrap();
+        assert!(matches!(
+            error.kind(),
+            ErrorKind::LimitExceeded(Limit::TokenCount(3))
+        ));
+
+        let options = DecodeOptions::new().with_max_tokens(8);
+        let mut decoder = Decoder::new_with_options(test_msg, options);
+        assert!(decoder.skip_value().unwrap());
+    }
+
+    #[test]
+    fn skip_value_should_reject_truncated_input() {
+        let mut decoder = Decoder::new(b"ll3:fooe");
+        assert!(decoder.skip_value().is_err());
+        assert!(decoder.tokens().next().is_none());
+    }
+
+    #[test]
+    fn next_raw_should_reject_invalid_contents() {
+        let mut decoder = Decoder::new(b"ld3:fooi1e3:bari2eee");
+        assert!(decoder.next_raw().is_err());
+
+        let mut decoder = Decoder::new(b"3:fooi-0e");
+        assert_eq!(decoder.next_raw().unwrap(), Some(&b"3:foo"[..]));
+        assert!(decoder.next_raw().is_err());
+    }
+
+    #[test]
+    fn peek_token_should_not_consume() {
+        let mut decoder = Decoder::new(b"l3:fooe");
+        assert_eq!(decoder.peek_token().unwrap(), Some(Token::List));
+        assert_eq!(decoder.peek_token().unwrap(), Some(Token::List));
+        assert_eq!(
+            decode_tokens(b"l3:fooe"),
+            decoder.tokens().collect::<Result<Vec<_>, _>>().unwrap()
+        );
+
+        let mut decoder = Decoder::new(b"i-0e");
+        assert!(decoder.peek_token().is_err());
+        assert!(decoder.tokens().next().unwrap().is_err());
+    }
+
+    #[test]
+    fn restore_should_discard_later_errors() {
+        // Unsorted keys
+        let mut decoder = Decoder::new(b"d3:fooi1e3:bari2ee");
+        decoder.next_token().unwrap();
+        let checkpoint = decoder.checkpoint();
+
+        decoder.next_token().unwrap();
+        decoder.next_token().unwrap();
+        assert!(decoder.next_token().is_err());
+        assert!(decoder.next_token().is_err());
+
+        decoder.restore(checkpoint);
+        assert_eq!(decoder.next_token().unwrap(), Some(Token::String(b"foo")));
+    }
+
+    #[test]
+    #[should_panic(expected = "different decoder")]
+    fn restore_should_reject_foreign_checkpoints() {
+        let checkpoint = Decoder::new(b"i1e").checkpoint();
+        Decoder::new(b"i2e").restore(checkpoint);
+    }
+
+    #[test]
+    fn strict_eof_should_reject_trailing_data() {
+        let mut decoder = Decoder::new(b"i1ei2e").with_strict_eof();
+        assert!(decoder.next_object().is_err());
+
+        let mut decoder = Decoder::new(b"d3:fooi1ee").with_strict_eof();
+        assert_eq!(decoder.next_raw().unwrap(), Some(&b"d3:fooi1ee"[..]));
+        assert!(decoder.next_object().unwrap().is_none());
+    }
+
+    #[test]
+    fn documents_should_stop_after_error() {
+        let mut documents = Decoder::new(b"i1ei-0ei2e").documents();
+        assert_eq!(documents.next().unwrap().unwrap(), Value::Integer(1));
+        assert!(documents.next().unwrap().is_err());
+        assert!(documents.next().is_none());
+    }
+
+    #[test]
+    fn find_should_search_whole_dict_if_keys_are_unsorted() {
+        let options = DecodeOptions::new().with_unsorted_keys();
+        let mut decoder = Decoder::new_with_options(b"d3:fooi1e3:bari2ee", options);
+        let mut dict = decoder
+            .next_object()
+            .unwrap()
+            .unwrap()
+            .try_into_dictionary()
+            .unwrap();
+
+        let bar = dict.find(b"bar").unwrap().unwrap();
+        assert_eq!(bar.try_into_integer().unwrap(), "2");
+    }
+
+    #[test]
+    fn first_wins_should_skip_repeated_unsorted_keys() {
+        let options = DecodeOptions::new()
+            .with_unsorted_keys()
+            .with_duplicate_keys(DuplicateKeys::FirstWins);
+        let mut decoder = Decoder::new_with_options(b"d1:bi1e1:ai2e1:bi3e1:ai4ee", options);
+        let mut dict = decoder
+            .next_object()
+            .unwrap()
+            .unwrap()
+            .try_into_dictionary()
+            .unwrap();
+        let pairs = dict.collect::<Vec<u8>, u32, Vec<_>>().unwrap();
+        assert_eq!(pairs, [(b"b".to_vec(), 1), (b"a".to_vec(), 2)]);
+    }
+
+    #[test]
+    fn lenient_integers_should_be_opt_in() {
+        let decode = |msg: &'static [u8], options: DecodeOptions| {
+            Decoder::new_with_options(msg, options)
+                .tokens()
+                .next()
+                .unwrap()
+        };
+
+        let strict = DecodeOptions::new();
+        let leading_zeros = DecodeOptions::new().with_leading_zeros();
+        let negative_zero = DecodeOptions::new().with_negative_zero();
+
+        for msg in [&b"i03e"[..], b"i00e", b"i-03e", b"i-0e"] {
+            assert!(decode(msg, strict.clone()).is_err());
+        }
+
+        assert_eq!(
+            decode(b"i03e", leading_zeros.clone()).unwrap(),
+            Token::Num("03")
+        );
+        assert_eq!(
+            decode(b"i-003e", leading_zeros.clone()).unwrap(),
+            Token::Num("-003")
+        );
+        assert!(decode(b"i-0e", leading_zeros.clone()).is_err());
+        assert!(decode(b"i-00e", leading_zeros).is_err());
+
+        assert_eq!(
+            decode(b"i-0e", negative_zero.clone()).unwrap(),
+            Token::Num("-0")
+        );
+        assert!(decode(b"i-03e", negative_zero.clone()).is_err());
+
+        // String lengths stay strict
+        assert!(decode(b"03:foo", negative_zero.with_leading_zeros()).is_err());
+    }
+
+    #[test]
+    fn limits_should_be_enforced() {
+        let limit = |options: DecodeOptions| {
+            let mut decoder = Decoder::new_with_options(b"l3:fooi1ee", options);
+            match decoder.next_raw() {
+                Err(error) => match error.kind() {
+                    ErrorKind::LimitExceeded(limit) => Some(*limit),
+                    _ => panic!("unexpected error {}", error),
+                },
+                Ok(_) => None,
+            }
+        };
+
+        assert_eq!(limit(DecodeOptions::new()), None);
+
+        let options = DecodeOptions::new().with_max_string_length(3);
+        assert_eq!(limit(options), None);
+        let options = DecodeOptions::new().with_max_string_length(2);
+        assert_eq!(limit(options), Some(Limit::StringLength(2)));
+
+        let options = DecodeOptions::new().with_max_tokens(4);
+        assert_eq!(limit(options), None);
+        let options = DecodeOptions::new().with_max_tokens(3);
+        assert_eq!(limit(options), Some(Limit::TokenCount(3)));
+
+        let options = DecodeOptions::new().with_max_document_size(10);
+        assert_eq!(limit(options), None);
+        let options = DecodeOptions::new().with_max_document_size(9);
+        assert_eq!(limit(options), Some(Limit::DocumentSize(9)));
+    }
+
+    #[test]
+    fn progress_should_be_reported_and_cancellable() {
+        let input = b"l3:fooi1e3:bari2eli3eee";
+
+        let mut positions = Vec::new();
+        let decoder = Decoder::new(input).with_progress(5, |position| {
+            positions.push(position);
+            true
+        });
+        assert_eq!(decoder.tokens().count(), 9);
+        assert_eq!(positions, vec![6, 14, 21]);
+
+        let mut positions = Vec::new();
+        let mut decoder = Decoder::new(input).with_progress(8, |position| {
+            positions.push(position);
+            false
+        });
+        {
+            let object = decoder.next_object().unwrap().unwrap();
+            let mut list = object.try_into_list().unwrap();
+            list.next_object().unwrap();
+            let error = list.next_object().err().unwrap();
+            assert_eq!(error.position(), Some(9));
+            assert!(list.next_object().is_err());
+        }
+        drop(decoder);
+        assert_eq!(positions, vec![9]);
+
+        let mut calls = 0;
+        let mut decoder = Decoder::new(input).with_progress(0, |_| {
+            calls += 1;
+            true
+        });
+        assert!(decoder.skip_value().unwrap());
+        drop(decoder);
+        assert_eq!(calls, 9);
+    }
+
+    #[test]
+    fn decoders_should_be_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+        assert_send_sync(&Decoder::new(b"i1e"));
+        assert_send_sync(&Decoder::new(b"i1e").with_progress(1, |_| true));
+
+        // The callback doesn't have to be `Sync` itself
+        let calls = Cell::new(0);
+        let mut decoder = Decoder::new(b"li1ei2ee").with_progress(0, move |_| {
+            calls.set(calls.get() + 1);
+            calls.get() < 3
+        });
+        assert!(decoder.skip_value().is_err());
+        assert_send_sync(&decoder);
+    }
+
     #[test]
     fn dict_drop_should_consume_struct() {
         let mut decoder = Decoder::new(b"d3:fooi1e3:quxi2eei1000e");
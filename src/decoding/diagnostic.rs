@@ -0,0 +1,188 @@
+use core::fmt::{self, Display, Formatter};
+
+use crate::decoding::Error;
+
+/// The number of bytes shown per row of an excerpt
+const ROW_LENGTH: usize = 16;
+
+/// A decoding error rendered together with a hex and ASCII excerpt of the input around
+/// the position it was detected at, created by [`Error::diagnostic`]
+///
+/// ```
+/// use bendy::decoding::{Decoder, Error};
+///
+/// let input = b"d3:fooi12x3e";
+/// let error = Decoder::new(input).tokens().last().unwrap().unwrap_err();
+///
+/// assert_eq!(
+///     error.diagnostic(input).to_string(),
+///     "\
+/// Error: bencode encoding corrupted (Expected 'e' or '0'..'9', got 'x' at offset 9)
+///  --> offset 9
+/// 00000000  64 33 3a 66 6f 6f 69 31 32 78 33 65             |d3:fooi12x3e|
+///                                      ^^                             ^
+/// "
+/// );
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Diagnostic<'a> {
+    error: &'a Error,
+    source: &'a [u8],
+    offset_base: usize,
+    context_rows: usize,
+}
+
+impl Error {
+    /// Render this error with an excerpt of the input it was raised for. Errors without
+    /// a position are rendered as the error message alone.
+    pub fn diagnostic<'a>(&'a self, source: &'a [u8]) -> Diagnostic<'a> {
+        Diagnostic {
+            error: self,
+            source,
+            offset_base: 0,
+            context_rows: 1,
+        }
+    }
+}
+
+impl<'a> Diagnostic<'a> {
+    /// Set the offset of `source` in the complete input, if the error was raised by a
+    /// decoder created with
+    /// [`Decoder::with_offset_base`](crate::decoding::Decoder::with_offset_base)
+    #[must_use]
+    pub fn with_offset_base(mut self, offset_base: usize) -> Self {
+        self.offset_base = offset_base;
+        self
+    }
+
+    /// Set the number of rows shown before and after the row of the error. Defaults to 1.
+    #[must_use]
+    pub fn with_context_rows(mut self, context_rows: usize) -> Self {
+        self.context_rows = context_rows;
+        self
+    }
+
+    fn write_row(&self, f: &mut Formatter, start: usize) -> fmt::Result {
+        let end = (start + ROW_LENGTH).min(self.source.len());
+        let bytes = &self.source[start..end];
+
+        write!(f, "{:08x}  ", self.offset_base + start)?;
+        for i in 0..ROW_LENGTH {
+            match bytes.get(i) {
+                Some(byte) => write!(f, "{:02x} ", byte)?,
+                None => f.write_str("   ")?,
+            }
+        }
+
+        f.write_str("|")?;
+        for &byte in bytes {
+            let c = if byte.is_ascii_graphic() || byte == b' ' {
+                byte as char
+            } else {
+                '.'
+            };
+            write!(f, "{}", c)?;
+        }
+        f.write_str("|\n")
+    }
+}
+
+impl<'a> Display for Diagnostic<'a> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        writeln!(f, "{}", self.error)?;
+
+        let position = match self.error.position() {
+            Some(position) => position,
+            None => return Ok(()),
+        };
+        writeln!(f, " --> offset {}", position)?;
+
+        // Positions at the very end of the input are shown after its last byte
+        let relative = match position.checked_sub(self.offset_base) {
+            Some(relative) if relative <= self.source.len() => relative,
+            _ => return Ok(()),
+        };
+
+        let row = relative / ROW_LENGTH;
+        let first_row = row.saturating_sub(self.context_rows);
+        let last_row = row.saturating_add(self.context_rows);
+
+        for current in first_row..=last_row {
+            let start = current * ROW_LENGTH;
+            if start >= self.source.len() && current != row {
+                break;
+            }
+            self.write_row(f, start.min(self.source.len()))?;
+
+            if current == row {
+                let column = relative % ROW_LENGTH;
+                writeln!(
+                    f,
+                    "{:hex$}^^{:ascii$}^",
+                    "",
+                    "",
+                    hex = 10 + 3 * column,
+                    ascii = 47 - 2 * column,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::{format, string::ToString, vec::Vec};
+
+    use super::*;
+    use crate::{decoding::Decoder, state_tracker::Token};
+
+    fn decode_error(input: &[u8]) -> Error {
+        Decoder::new(input)
+            .tokens()
+            .collect::<Result<Vec<Token>, Error>>()
+            .unwrap_err()
+    }
+
+    #[test]
+    fn rows_around_the_error_are_shown() {
+        let mut input = b"l".to_vec();
+        for _ in 0..20 {
+            input.extend_from_slice(b"i1e");
+        }
+        input.extend_from_slice(b"?e");
+
+        let rendered = decode_error(&input).diagnostic(&input).to_string();
+        let lines: Vec<&str> = rendered.lines().collect();
+
+        assert_eq!(lines[1], " --> offset 61");
+        assert_eq!(lines.len(), 5);
+        assert!(lines[2].starts_with("00000020  "));
+        assert!(lines[3].starts_with("00000030  65 69 31"));
+        assert!(lines[3].ends_with("|ei1ei1ei1ei1e?e|"));
+        assert_eq!(lines[4].find('^'), Some(10 + 3 * 13));
+        assert_eq!(lines[4].rfind('^'), Some(59 + 13));
+    }
+
+    #[test]
+    fn errors_without_position_render_the_message_only() {
+        let error = decode_error(b"l");
+        assert_eq!(error.diagnostic(b"l").to_string(), format!("{}\n", error));
+    }
+
+    #[test]
+    fn offset_base_is_applied() {
+        let error = Decoder::new(b"x")
+            .with_offset_base(0x100)
+            .tokens()
+            .next()
+            .unwrap()
+            .unwrap_err();
+        let rendered = error.diagnostic(b"x").with_offset_base(0x100).to_string();
+
+        assert!(rendered.contains("00000100  78"));
+        assert!(rendered.ends_with("^^                                               ^\n"));
+    }
+}
@@ -0,0 +1,105 @@
+//! Checking runs of ASCII digits, which is most of the work in validating integers and
+//! string lengths. With the `simd` feature, x86-64 processors check 16 bytes at a time,
+//! or 32 if they support AVX2.
+
+/// Runs shorter than this are checked byte by byte, as most integers are
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+const MIN_SIMD_LENGTH: usize = 16;
+
+/// The length of the run of ASCII digits at the start of `bytes`
+pub(crate) fn digit_run_len(bytes: &[u8]) -> usize {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    if bytes.len() >= MIN_SIMD_LENGTH {
+        return x86::digit_run_len(bytes);
+    }
+    scalar_digit_run_len(bytes)
+}
+
+fn scalar_digit_run_len(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .position(|byte| !byte.is_ascii_digit())
+        .unwrap_or(bytes.len())
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod x86 {
+    use core::arch::x86_64::*;
+
+    use super::scalar_digit_run_len;
+
+    pub(super) fn digit_run_len(bytes: &[u8]) -> usize {
+        // The result of the detection is cached, so this is cheap after the first call
+        if std::is_x86_feature_detected!("avx2") {
+            unsafe { digit_run_len_avx2(bytes) }
+        } else {
+            // SSE2 is part of x86-64 itself
+            unsafe { digit_run_len_sse2(bytes) }
+        }
+    }
+
+    #[target_feature(enable = "sse2")]
+    unsafe fn digit_run_len_sse2(bytes: &[u8]) -> usize {
+        // The comparisons are signed, so bytes from 0x80 on count as below '0'
+        let below = _mm_set1_epi8(b'0' as i8);
+        let above = _mm_set1_epi8(b'9' as i8);
+
+        let mut offset = 0;
+        while offset + 16 <= bytes.len() {
+            let chunk = _mm_loadu_si128(bytes.as_ptr().add(offset) as *const __m128i);
+            let outside = _mm_or_si128(_mm_cmplt_epi8(chunk, below), _mm_cmpgt_epi8(chunk, above));
+            let mask = _mm_movemask_epi8(outside);
+            if mask != 0 {
+                return offset + mask.trailing_zeros() as usize;
+            }
+            offset += 16;
+        }
+        offset + scalar_digit_run_len(&bytes[offset..])
+    }
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn digit_run_len_avx2(bytes: &[u8]) -> usize {
+        let below = _mm256_set1_epi8(b'0' as i8);
+        let above = _mm256_set1_epi8(b'9' as i8);
+
+        let mut offset = 0;
+        while offset + 32 <= bytes.len() {
+            let chunk = _mm256_loadu_si256(bytes.as_ptr().add(offset) as *const __m256i);
+            let outside = _mm256_or_si256(
+                _mm256_cmpgt_epi8(below, chunk),
+                _mm256_cmpgt_epi8(chunk, above),
+            );
+            let mask = _mm256_movemask_epi8(outside);
+            if mask != 0 {
+                return offset + mask.trailing_zeros() as usize;
+            }
+            offset += 32;
+        }
+        offset + digit_run_len_sse2(&bytes[offset..])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::vec;
+
+    use super::*;
+
+    #[test]
+    fn runs_should_end_at_the_first_non_digit() {
+        for length in 0..100 {
+            let mut bytes = vec![b'7'; length];
+            assert_eq!(digit_run_len(&bytes), length);
+
+            for end in 0..length {
+                for byte in [b'/', b':', b'e', 0x80, 0xb0, 0xff] {
+                    bytes[end] = byte;
+                    assert_eq!(digit_run_len(&bytes), end, "{} in {}", byte, length);
+                    assert_eq!(digit_run_len(&bytes), scalar_digit_run_len(&bytes));
+                }
+                bytes[end] = b'0' + (end % 10) as u8;
+            }
+        }
+    }
+}
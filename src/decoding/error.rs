@@ -4,8 +4,8 @@ use alloc::{str::Utf8Error, string::FromUtf8Error};
 use core::num::ParseIntError;
 
 use alloc::{
-    format,
     string::{String, ToString},
+    vec::Vec,
 };
 use core::fmt::{self, Display, Formatter};
 
@@ -18,14 +18,15 @@ use crate::state_tracker::StructureError;
 
 #[derive(Debug, Clone, Fail)]
 pub struct Error {
-    #[fail(context)]
-    context: Option<String>,
+    /// The path to the element which failed to decode, outermost first
+    path: Vec<PathSegment>,
     #[fail(cause)]
     error: ErrorKind,
 }
 
 /// An enumeration of potential errors that appear during bencode deserialization.
 #[derive(Debug, Clone, Fail)]
+#[non_exhaustive]
 pub enum ErrorKind {
     /// Error that occurs if the serialized structure contains invalid semantics.
     #[cfg(feature = "std")]
@@ -45,22 +46,117 @@ pub enum ErrorKind {
     #[fail(display = "unexpected field: {}", _0)]
     UnexpectedField(String),
     /// Error through an unexpected bencode token during deserialization.
-    #[fail(display = "discovered {} but expected {}", _0, _1)]
+    #[fail(display = "discovered {} but expected {}", _1, _0)]
     UnexpectedToken(String, String),
+    /// Error that occurs if a dictionary contains a key which isn't expected, at the given
+    /// offset of the input.
+    #[fail(display = "unknown field {} at offset {}", _0, _1)]
+    UnknownField(String, usize),
+    /// Error that occurs if an enum tag doesn't name any of the enum's variants.
+    #[fail(display = "unknown variant: {}", _0)]
+    UnknownVariant(String),
+    /// Error that occurs if the input exceeds one of the limits set in the
+    /// [`DecodeOptions`](crate::decoding::DecodeOptions).
+    #[fail(display = "{} exceeded", _0)]
+    LimitExceeded(Limit),
+    /// Error that occurs if a progress callback, set with
+    /// [`Decoder::with_progress`](crate::decoding::Decoder::with_progress), cancelled
+    /// decoding at the given offset of the input.
+    #[fail(display = "decoding cancelled at offset {}", _0)]
+    Cancelled(usize),
+}
+
+/// A resource limit of the decoder, holding the configured maximum
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Limit {
+    /// The maximum length of a single byte string
+    StringLength(usize),
+    /// The maximum number of tokens
+    TokenCount(usize),
+    /// The maximum size of the input
+    DocumentSize(usize),
+}
+
+impl Display for Limit {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Limit::StringLength(max) => write!(f, "string length limit of {} bytes", max),
+            Limit::TokenCount(max) => write!(f, "token count limit of {}", max),
+            Limit::DocumentSize(max) => write!(f, "document size limit of {} bytes", max),
+        }
+    }
+}
+
+/// A step on the path from the decoded document to the element an [`Error`] occurred in
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum PathSegment {
+    /// A dictionary key or struct field
+    Field(String),
+    /// A position in a list
+    Index(usize),
 }
 
 pub trait ResultExt {
     fn context(self, context: impl Display) -> Self;
+
+    /// See [`Error::context_field`]
+    fn context_field(self, field: impl Display) -> Self;
+
+    /// See [`Error::context_index`]
+    fn context_index(self, index: usize) -> Self;
 }
 
 impl Error {
-    pub fn context(mut self, context: impl Display) -> Self {
-        if let Some(current) = self.context.as_mut() {
-            *current = format!("{}.{}", context, current);
-        } else {
-            self.context = Some(context.to_string());
+    /// The kind of the error, without its context
+    pub fn kind(&self) -> &ErrorKind {
+        &self.error
+    }
+
+    /// The byte offset in the input the error was detected at, if known
+    pub fn position(&self) -> Option<usize> {
+        match &self.error {
+            ErrorKind::StructureError(error) => error.position(),
+            ErrorKind::UnknownField(_, offset) | ErrorKind::Cancelled(offset) => Some(*offset),
+            _ => None,
         }
+    }
+
+    /// The path to the element which failed to decode, outermost first. Empty if the
+    /// error wasn't given any context.
+    pub fn path(&self) -> &[PathSegment] {
+        &self.path
+    }
+
+    /// Same as [`Error::context_field`]
+    pub fn context(self, context: impl Display) -> Self {
+        self.context_field(context)
+    }
 
+    /// Record that the error occurred in the given dictionary key or struct field. As
+    /// the error is passed up, each enclosing element adds itself in front of the path.
+    ///
+    /// ```
+    /// use bendy::decoding::{Error, PathSegment};
+    ///
+    /// let error = Error::missing_field("length")
+    ///     .context_index(1423)
+    ///     .context_field("files")
+    ///     .context_field("info");
+    ///
+    /// assert_eq!(error.path()[2], PathSegment::Index(1423));
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     "Error: missing field: length in info.files[1423]"
+    /// );
+    /// ```
+    pub fn context_field(mut self, field: impl Display) -> Self {
+        self.path.insert(0, PathSegment::Field(field.to_string()));
+        self
+    }
+
+    /// Record that the error occurred in the given position of a list
+    pub fn context_index(mut self, index: usize) -> Self {
+        self.path.insert(0, PathSegment::Index(index));
         self
     }
 
@@ -87,6 +183,29 @@ impl Error {
         Self::from(ErrorKind::UnexpectedField(field_name.to_string()))
     }
 
+    /// Returns a `Error::UnknownField` which contains the (lossily decoded) key and its
+    /// offset in the input.
+    pub fn unknown_field(key: impl AsRef<[u8]>, offset: usize) -> Error {
+        let key = String::from_utf8_lossy(key.as_ref()).into_owned();
+        Self::from(ErrorKind::UnknownField(key, offset))
+    }
+
+    /// Returns a `Error::UnknownVariant` which contains the (lossily decoded) tag.
+    pub fn unknown_variant(tag: impl AsRef<[u8]>) -> Error {
+        let tag = String::from_utf8_lossy(tag.as_ref()).into_owned();
+        Self::from(ErrorKind::UnknownVariant(tag))
+    }
+
+    /// Returns a `Error::LimitExceeded` for the given limit.
+    pub fn limit_exceeded(limit: Limit) -> Error {
+        Self::from(ErrorKind::LimitExceeded(limit))
+    }
+
+    /// Returns a `Error::Cancelled` for decoding cancelled at the given offset.
+    pub fn cancelled(offset: usize) -> Error {
+        Self::from(ErrorKind::Cancelled(offset))
+    }
+
     /// Returns a `Error::UnexpectedElement` which contains a custom error message.
     pub fn unexpected_token(expected: impl Display, discovered: impl Display) -> Error {
         Self::from(ErrorKind::UnexpectedToken(
@@ -98,10 +217,18 @@ impl Error {
 
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        match &self.context {
-            Some(context) => write!(f, "Error: {} in {}", self.error, context),
-            None => write!(f, "Error: {}", self.error),
+        write!(f, "Error: {}", self.error)?;
+
+        for (i, segment) in self.path.iter().enumerate() {
+            match segment {
+                PathSegment::Field(field) if i == 0 => write!(f, " in {}", field)?,
+                PathSegment::Field(field) => write!(f, ".{}", field)?,
+                PathSegment::Index(index) if i == 0 => write!(f, " in [{}]", index)?,
+                PathSegment::Index(index) => write!(f, "[{}]", index)?,
+            }
         }
+
+        Ok(())
     }
 }
 
@@ -114,7 +241,7 @@ impl From<StructureError> for Error {
 impl From<ErrorKind> for Error {
     fn from(kind: ErrorKind) -> Self {
         Self {
-            context: None,
+            path: Vec::new(),
             error: kind,
         }
     }
@@ -152,4 +279,12 @@ impl<T> ResultExt for Result<T, Error> {
     fn context(self, context: impl Display) -> Result<T, Error> {
         self.map_err(|err| err.context(context))
     }
+
+    fn context_field(self, field: impl Display) -> Result<T, Error> {
+        self.map_err(|err| err.context_field(field))
+    }
+
+    fn context_index(self, index: usize) -> Result<T, Error> {
+        self.map_err(|err| err.context_index(index))
+    }
 }
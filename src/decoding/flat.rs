@@ -0,0 +1,106 @@
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use crate::decoding::{Decoder, Error};
+
+/// The key-value pairs of a dictionary of byte strings, borrowed from the input
+pub type FlatDict<'ser> = Vec<(&'ser [u8], &'ser [u8])>;
+
+/// Decode a dictionary whose values are all byte strings, such as the arguments of a
+/// KRPC query or a tracker's scrape entry, into its key-value pairs.
+///
+/// This skips the state tracking of a [`Decoder`] and reads the tokens directly, which
+/// makes it more than twice as fast for these small, flat dictionaries. In exchange, it
+/// only accepts canonical input with sorted, unique keys and fails on any nested value
+/// or integer, so callers should fall back to the general decoder on error. Like the
+/// decoder, anything after the dictionary is ignored.
+///
+/// ```
+/// use bendy::decoding::decode_flat_dict;
+///
+/// let pairs = decode_flat_dict(b"d2:id3:abc6:target3:xyze").unwrap();
+/// assert_eq!(pairs, [(&b"id"[..], &b"abc"[..]), (b"target", b"xyz")]);
+///
+/// assert!(decode_flat_dict(b"d2:idi1ee").is_err());
+/// assert!(decode_flat_dict(b"d6:target3:xyz2:id3:abce").is_err());
+/// ```
+pub fn decode_flat_dict(input: &[u8]) -> Result<FlatDict<'_>, Error> {
+    Decoder::read_flat_dict(input)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{decoding::ErrorKind, state_tracker::StructureError};
+
+    /// Decode the dictionary with the general decoder
+    fn decode_general(input: &[u8]) -> FlatDict<'_> {
+        let mut decoder = Decoder::new(input);
+        let mut dict = decoder
+            .next_object()
+            .unwrap()
+            .unwrap()
+            .try_into_dictionary()
+            .unwrap();
+        let mut pairs = Vec::new();
+        while let Some((key, value)) = dict.next_pair().unwrap() {
+            pairs.push((key, value.try_into_bytes().unwrap()));
+        }
+        pairs
+    }
+
+    fn structure_error(input: &[u8]) -> StructureError {
+        match decode_flat_dict(input).unwrap_err().kind() {
+            ErrorKind::StructureError(error) => error.clone(),
+            other => panic!("Unexpected error: {}", other),
+        }
+    }
+
+    #[test]
+    fn flat_dicts_should_match_the_general_decoder() {
+        for input in [
+            &b"de"[..],
+            b"d0:0:e",
+            b"d1:a1:b1:c0:e",
+            b"d2:id20:abcdefghij01234567895:token8:aoeusnth1:v4:LT01e",
+            b"d1:a1:bei1e",
+        ] {
+            assert_eq!(decode_flat_dict(input).unwrap(), decode_general(input));
+        }
+    }
+
+    #[test]
+    fn non_canonical_dicts_should_fail() {
+        assert_eq!(
+            structure_error(b"d1:b0:1:a0:e"),
+            StructureError::UnsortedKeys { position: 6 }
+        );
+        assert_eq!(
+            structure_error(b"d1:a0:1:a0:e"),
+            StructureError::UnsortedKeys { position: 6 }
+        );
+        assert_eq!(
+            structure_error(b"d1:a0:lee"),
+            StructureError::NonStringKey {
+                found: "List",
+                position: 6
+            }
+        );
+        assert_eq!(
+            structure_error(b"d1:ae"),
+            StructureError::MissingValue { position: 4 }
+        );
+        assert_eq!(structure_error(b"d1:a1:b"), StructureError::UnexpectedEof);
+        assert_eq!(structure_error(b"d1:a3:b"), StructureError::UnexpectedEof);
+    }
+
+    #[test]
+    fn values_other_than_byte_strings_should_fail() {
+        for input in [&b"le"[..], b"3:foo", b"d1:ai1ee", b"d1:alee", b"d1:adee"] {
+            match decode_flat_dict(input).unwrap_err().kind() {
+                ErrorKind::UnexpectedToken(..) => (),
+                other => panic!("Unexpected error for {:?}: {}", input, other),
+            }
+        }
+    }
+}
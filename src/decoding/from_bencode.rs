@@ -1,15 +1,19 @@
 #[cfg(not(feature = "std"))]
-use alloc::{collections::BTreeMap, rc::Rc, string::String, vec::Vec};
+use alloc::{boxed::Box, collections::BTreeMap, rc::Rc, string::String, vec::Vec};
 
 #[cfg(feature = "std")]
 use std::{
     collections::{BTreeMap, HashMap},
     hash::{BuildHasher, Hash},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
     rc::Rc,
+    str,
 };
 
+#[cfg(feature = "std")]
+use crate::encoding::AsCompact;
 use crate::{
-    decoding::{Decoder, Error, Object},
+    decoding::{DecodeOptions, Decoder, Error, Object, ResultExt, DEFAULT_MAX_DEPTH},
     encoding::AsString,
     state_tracker::StructureError,
 };
@@ -17,14 +21,27 @@ use crate::{
 ///Basic trait for bencode based value deserialization.
 pub trait FromBencode {
     /// Maximum allowed depth of nested structures before the decoding should be aborted.
-    const EXPECTED_RECURSION_DEPTH: usize = 2048;
+    const EXPECTED_RECURSION_DEPTH: usize = DEFAULT_MAX_DEPTH;
 
     /// Deserialize an object from its byte representation.
     fn from_bencode(bytes: &[u8]) -> Result<Self, Error>
     where
         Self: Sized,
     {
-        let mut decoder = Decoder::new(bytes).with_max_depth(Self::EXPECTED_RECURSION_DEPTH);
+        let options = DecodeOptions::new().with_max_depth(Self::EXPECTED_RECURSION_DEPTH);
+        Self::from_bencode_with_options(bytes, options)
+    }
+
+    /// Deserialize an object from its byte representation, validating it as configured
+    /// by the given options.
+    fn from_bencode_with_options(bytes: &[u8], options: DecodeOptions) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("from_bencode", len = bytes.len()).entered();
+
+        let mut decoder = Decoder::new_with_options(bytes, options);
         let object = decoder.next_object()?;
 
         object.map_or(
@@ -70,7 +87,7 @@ impl<ContentT: FromBencode> FromBencode for Vec<ContentT> {
         let mut results = Vec::new();
 
         while let Some(object) = list.next_object()? {
-            let item = ContentT::decode_bencode_object(object)?;
+            let item = ContentT::decode_bencode_object(object).context_index(results.len())?;
             results.push(item);
         }
 
@@ -107,8 +124,9 @@ where
         let mut result = BTreeMap::default();
 
         while let Some((key, value)) = dict.next_pair()? {
+            let value = V::decode_bencode_object(value)
+                .map_err(|error| error.context_field(String::from_utf8_lossy(key)))?;
             let key = K::decode_bencode_object(Object::Bytes(key))?;
-            let value = V::decode_bencode_object(value)?;
 
             result.insert(key, value);
         }
@@ -134,8 +152,9 @@ where
         let mut result = HashMap::default();
 
         while let Some((key, value)) = dict.next_pair()? {
+            let value = V::decode_bencode_object(value)
+                .map_err(|error| error.context_field(String::from_utf8_lossy(key)))?;
             let key = K::decode_bencode_object(Object::Bytes(key))?;
-            let value = V::decode_bencode_object(value)?;
 
             result.insert(key, value);
         }
@@ -155,6 +174,30 @@ impl<T: FromBencode> FromBencode for Rc<T> {
     }
 }
 
+impl<T: FromBencode> FromBencode for Box<T> {
+    const EXPECTED_RECURSION_DEPTH: usize = T::EXPECTED_RECURSION_DEPTH;
+
+    fn decode_bencode_object(object: Object) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        T::decode_bencode_object(object).map(Box::new)
+    }
+}
+
+/// Bencode has no null value, so a decoded object is always `Some`. This is meant for
+/// optional dictionary entries, where the caller decodes missing keys as `None`.
+impl<T: FromBencode> FromBencode for Option<T> {
+    const EXPECTED_RECURSION_DEPTH: usize = T::EXPECTED_RECURSION_DEPTH;
+
+    fn decode_bencode_object(object: Object) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        T::decode_bencode_object(object).map(Some)
+    }
+}
+
 impl FromBencode for AsString<Vec<u8>> {
     const EXPECTED_RECURSION_DEPTH: usize = 0;
 
@@ -166,6 +209,120 @@ impl FromBencode for AsString<Vec<u8>> {
     }
 }
 
+#[cfg(feature = "std")]
+macro_rules! impl_from_bencode_for_address {
+    ($($type:ty)*) => {$(
+        impl FromBencode for $type {
+            const EXPECTED_RECURSION_DEPTH: usize = 0;
+
+            fn decode_bencode_object(object: Object) -> Result<Self, Error>
+            where
+                Self: Sized,
+            {
+                let content = str::from_utf8(object.try_into_bytes()?)?;
+                let address = content.parse::<$type>()?;
+
+                Ok(address)
+            }
+        }
+    )*}
+}
+
+#[cfg(feature = "std")]
+impl_from_bencode_for_address!(IpAddr Ipv4Addr Ipv6Addr SocketAddr);
+
+/// Split the bytes of a compact address into its IP address and the rest
+#[cfg(feature = "std")]
+fn decode_compact_ip<'a>(
+    bytes: &'a [u8],
+    suffix: usize,
+    expected: &str,
+) -> Result<(IpAddr, &'a [u8]), Error> {
+    match bytes.len().checked_sub(suffix) {
+        Some(4) => {
+            let mut octets = [0; 4];
+            octets.copy_from_slice(&bytes[..4]);
+            Ok((IpAddr::from(octets), &bytes[4..]))
+        },
+        Some(16) => {
+            let mut octets = [0; 16];
+            octets.copy_from_slice(&bytes[..16]);
+            Ok((IpAddr::from(octets), &bytes[16..]))
+        },
+        _ => Err(Error::unexpected_token(
+            expected,
+            format!("{} bytes", bytes.len()),
+        )),
+    }
+}
+
+#[cfg(feature = "std")]
+impl FromBencode for AsCompact<Ipv4Addr> {
+    const EXPECTED_RECURSION_DEPTH: usize = 0;
+
+    fn decode_bencode_object(object: Object) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        match AsCompact::<IpAddr>::decode_bencode_object(object)? {
+            AsCompact(IpAddr::V4(ip)) => Ok(AsCompact(ip)),
+            AsCompact(IpAddr::V6(_)) => Err(Error::unexpected_token(
+                "an IPv4 address of 4 bytes",
+                "16 bytes",
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl FromBencode for AsCompact<Ipv6Addr> {
+    const EXPECTED_RECURSION_DEPTH: usize = 0;
+
+    fn decode_bencode_object(object: Object) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        match AsCompact::<IpAddr>::decode_bencode_object(object)? {
+            AsCompact(IpAddr::V6(ip)) => Ok(AsCompact(ip)),
+            AsCompact(IpAddr::V4(_)) => Err(Error::unexpected_token(
+                "an IPv6 address of 16 bytes",
+                "4 bytes",
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl FromBencode for AsCompact<IpAddr> {
+    const EXPECTED_RECURSION_DEPTH: usize = 0;
+
+    fn decode_bencode_object(object: Object) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        let bytes = object.try_into_bytes()?;
+        let (ip, _) = decode_compact_ip(bytes, 0, "an IP address of 4 or 16 bytes")?;
+
+        Ok(AsCompact(ip))
+    }
+}
+
+#[cfg(feature = "std")]
+impl FromBencode for AsCompact<SocketAddr> {
+    const EXPECTED_RECURSION_DEPTH: usize = 0;
+
+    fn decode_bencode_object(object: Object) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        let bytes = object.try_into_bytes()?;
+        let (ip, port) = decode_compact_ip(bytes, 2, "a compact peer of 6 or 18 bytes")?;
+        let port = u16::from_be_bytes([port[0], port[1]]);
+
+        Ok(AsCompact(SocketAddr::new(ip, port)))
+    }
+}
+
 #[cfg(test)]
 mod test {
 
@@ -196,6 +353,68 @@ mod test {
         assert_eq!(expected_message.as_bytes(), &decoded_vector.0[..]);
     }
 
+    #[test]
+    fn from_bencode_to_option_should_wrap_value() {
+        assert_eq!(Option::<u8>::from_bencode(b"i3e").unwrap(), Some(3));
+        assert_eq!(
+            Option::<Box<String>>::from_bencode(b"3:foo").unwrap(),
+            Some(Box::new(String::from("foo")))
+        );
+        assert!(Option::<u8>::from_bencode(b"3:foo").is_err());
+    }
+
+    #[test]
+    fn nested_collection_errors_should_carry_path() {
+        use crate::decoding::PathSegment;
+
+        let error = BTreeMap::<String, Vec<u8>>::from_bencode(b"d3:fooli1ei999eee").unwrap_err();
+        assert_eq!(
+            error.path(),
+            [PathSegment::Field("foo".into()), PathSegment::Index(1)]
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn addresses_should_round_trip_as_text_and_compact() {
+        use crate::encoding::{AsCompact, ToBencode};
+
+        let peers: [SocketAddr; 2] = ["1.2.3.4:80".parse().unwrap(), "[::1]:6881".parse().unwrap()];
+        for &peer in &peers {
+            let text = peer.to_bencode().unwrap();
+            assert_eq!(SocketAddr::from_bencode(&text).unwrap(), peer);
+
+            let compact = AsCompact(peer).to_bencode().unwrap();
+            assert_eq!(AsCompact::from_bencode(&compact).unwrap(), AsCompact(peer));
+
+            let ip = AsCompact(peer.ip()).to_bencode().unwrap();
+            assert_eq!(AsCompact::from_bencode(&ip).unwrap(), AsCompact(peer.ip()));
+        }
+
+        assert_eq!(
+            Ipv4Addr::from_bencode(b"7:1.2.3.4").unwrap(),
+            Ipv4Addr::new(1, 2, 3, 4)
+        );
+        assert_eq!(
+            AsCompact::<Ipv6Addr>::from_bencode(&[b"16:", &[0; 16][..]].concat()).unwrap(),
+            AsCompact(Ipv6Addr::UNSPECIFIED)
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn malformed_addresses_should_fail() {
+        use crate::encoding::AsCompact;
+
+        assert!(IpAddr::from_bencode(b"7:1.2.3.x").is_err());
+        assert!(Ipv4Addr::from_bencode(b"3:::1").is_err());
+        assert!(SocketAddr::from_bencode(b"7:1.2.3.4").is_err());
+        assert!(AsCompact::<IpAddr>::from_bencode(b"5:abcde").is_err());
+        assert!(AsCompact::<Ipv6Addr>::from_bencode(b"4:abcd").is_err());
+        assert!(AsCompact::<SocketAddr>::from_bencode(b"4:abcd").is_err());
+        assert!(AsCompact::<SocketAddr>::from_bencode(b"1:a").is_err());
+    }
+
     #[test]
     #[should_panic(expected = "Num")]
     fn from_bencode_to_as_string_should_fail_for_integer() {
@@ -0,0 +1,89 @@
+use std::{fs::File, io, ops::Deref, path::Path};
+
+use memmap2::Mmap;
+
+use crate::decoding::{DecodeOptions, Decoder};
+
+/// A memory-mapped file, created by [`Decoder::open`]
+///
+/// The file is paged in by the operating system as it is read. Decoders created with
+/// [`MappedFile::decoder`] borrow from the map, so they can't outlive it. The contents are
+/// also available as a byte slice, e.g. for
+/// [`Value::decode`](crate::value::Value::decode).
+#[derive(Debug)]
+pub struct MappedFile {
+    map: Mmap,
+}
+
+impl<'ser> Decoder<'ser> {
+    /// Memory-map the file at the given path for decoding
+    ///
+    /// ```no_run
+    /// use bendy::decoding::Decoder;
+    ///
+    /// // Safety: the file isn't modified while it is mapped
+    /// let file = unsafe { Decoder::open("ubuntu.torrent") }?;
+    /// let mut decoder = file.decoder();
+    /// let torrent = decoder.next_object().unwrap();
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while it is mapped, by this or any
+    /// other process, as the decoder would observe the changes in the middle of reading.
+    pub unsafe fn open(path: impl AsRef<Path>) -> io::Result<MappedFile> {
+        let file = File::open(path)?;
+        let map = Mmap::map(&file)?;
+        Ok(MappedFile { map })
+    }
+}
+
+impl MappedFile {
+    /// Create a decoder reading the file
+    pub fn decoder(&self) -> Decoder<'_> {
+        Decoder::new(&self.map)
+    }
+
+    /// Create a decoder reading the file, validating it as configured by the given
+    /// options
+    pub fn decoder_with_options(&self, options: DecodeOptions) -> Decoder<'_> {
+        Decoder::new_with_options(&self.map, options)
+    }
+}
+
+impl Deref for MappedFile {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.map
+    }
+}
+
+impl AsRef<[u8]> for MappedFile {
+    fn as_ref(&self) -> &[u8] {
+        &self.map
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs, process};
+
+    use super::*;
+    use crate::value::Value;
+
+    #[test]
+    fn mapped_files_should_decode() {
+        let path = env::temp_dir().join(format!("bendy-mmap-{}.torrent", process::id()));
+        fs::write(&path, b"d4:name3:fooe").unwrap();
+
+        let file = unsafe { Decoder::open(&path) }.unwrap();
+        assert!(file.decoder().skip_value().unwrap());
+        assert_eq!(Value::decode(&file).unwrap()["name"], Value::from("foo"));
+
+        drop(file);
+        fs::remove_file(&path).unwrap();
+        assert!(unsafe { Decoder::open(&path) }.is_err());
+    }
+}
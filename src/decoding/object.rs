@@ -1,6 +1,14 @@
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+use core::{
+    num::ParseIntError,
+    str::{self, FromStr},
+};
+
 use crate::{
     decoding::{DictDecoder, Error, ListDecoder},
     state_tracker::Token,
+    value::Value,
 };
 
 /// An object read from a decoder
@@ -112,6 +120,32 @@ impl<'obj, 'ser: 'obj> Object<'obj, 'ser> {
         self.bytes_or_else(|obj| Err(Error::unexpected_token("String", obj.into_token().name())))
     }
 
+    /// Try to treat the object as a UTF-8 encoded string. Any other variant, or a byte
+    /// string which isn't valid UTF-8, results in an error describing what was found
+    /// instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bendy::decoding::Object;
+    ///
+    /// let x = Object::Bytes(b"foo");
+    /// assert_eq!("foo", x.try_into_str().unwrap());
+    ///
+    /// let x = Object::Bytes(b"\xff");
+    /// assert!(x.try_into_str().is_err());
+    ///
+    /// let x = Object::Integer("123");
+    /// assert_eq!(
+    ///     x.try_into_str().unwrap_err().to_string(),
+    ///     "Error: discovered Num but expected String"
+    /// );
+    /// ```
+    pub fn try_into_str(self) -> Result<&'ser str, Error> {
+        let bytes = self.try_into_bytes()?;
+        str::from_utf8(bytes).map_err(Error::from)
+    }
+
     /// Try to treat the object as an integer and return the internal string representation,
     /// mapping [`Object::Integer(v)`] into [`Ok(v)`]. Any other variant returns the given
     /// default value.
@@ -202,6 +236,123 @@ impl<'obj, 'ser: 'obj> Object<'obj, 'ser> {
         self.integer_or_else(|obj| Err(Error::unexpected_token("Num", obj.into_token().name())))
     }
 
+    /// Try to treat the object as an integer and parse it into the given integer type.
+    /// Any other variant, or an integer which doesn't fit into the type, results in an
+    /// error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bendy::decoding::Object;
+    ///
+    /// let x = Object::Integer("-123");
+    /// assert_eq!(-123, x.try_into_int::<i64>().unwrap());
+    ///
+    /// let x = Object::Integer("256");
+    /// assert!(x.try_into_int::<u8>().is_err());
+    ///
+    /// let x = Object::Bytes(b"123");
+    /// assert!(x.try_into_int::<u64>().is_err());
+    /// ```
+    pub fn try_into_int<T>(self) -> Result<T, Error>
+    where
+        T: FromStr<Err = ParseIntError>,
+    {
+        let digits = self.try_into_integer()?;
+        digits.parse().map_err(Error::from)
+    }
+
+    /// Parse the object as an `i64`.
+    ///
+    /// Unlike the `try_into_*` methods this doesn't consume the object. Any other
+    /// variant, as well as an integer which doesn't fit into an `i64`, results in an
+    /// error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bendy::decoding::Object;
+    ///
+    /// assert_eq!(-42, Object::Integer("-42").as_i64().unwrap());
+    /// assert!(Object::Integer("9223372036854775808").as_i64().is_err());
+    /// assert!(Object::Bytes(b"42").as_i64().is_err());
+    /// ```
+    pub fn as_i64(&self) -> Result<i64, Error> {
+        self.parse_integer()
+    }
+
+    /// Parse the object as an `u64`.
+    ///
+    /// Any other variant, as well as a negative integer or one which doesn't fit into
+    /// an `u64`, results in an error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bendy::decoding::Object;
+    ///
+    /// assert_eq!(u64::MAX, Object::Integer("18446744073709551615").as_u64().unwrap());
+    /// assert!(Object::Integer("18446744073709551616").as_u64().is_err());
+    /// assert!(Object::Integer("-1").as_u64().is_err());
+    /// ```
+    pub fn as_u64(&self) -> Result<u64, Error> {
+        self.parse_integer()
+    }
+
+    /// Parse the object as an `u128`.
+    ///
+    /// Any other variant, as well as a negative integer or one which doesn't fit into
+    /// an `u128`, results in an error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bendy::decoding::Object;
+    ///
+    /// let x = Object::Integer("18446744073709551616");
+    /// assert_eq!(u128::from(u64::MAX) + 1, x.as_u128().unwrap());
+    /// ```
+    pub fn as_u128(&self) -> Result<u128, Error> {
+        self.parse_integer()
+    }
+
+    /// Parse the object as an arbitrary precision integer.
+    ///
+    /// Any variant but [`Object::Integer`] results in an error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bendy::decoding::Object;
+    /// use num_bigint::BigInt;
+    ///
+    /// let x = Object::Integer("-340282366920938463463374607431768211456");
+    /// let expected = -(BigInt::from(u128::MAX) + BigInt::from(1));
+    /// assert_eq!(expected, x.as_bigint().unwrap());
+    /// ```
+    #[cfg(feature = "num-bigint")]
+    pub fn as_bigint(&self) -> Result<num_bigint::BigInt, Error> {
+        self.integer_str()?
+            .parse()
+            .map_err(Error::malformed_content)
+    }
+
+    fn parse_integer<T>(&self) -> Result<T, Error>
+    where
+        T: FromStr<Err = ParseIntError>,
+    {
+        self.integer_str()?.parse().map_err(Error::from)
+    }
+
+    fn integer_str(&self) -> Result<&'ser str, Error> {
+        match self {
+            Object::Integer(digits) => Ok(digits),
+            Object::Bytes(_) => Err(Error::unexpected_token("Num", "String")),
+            Object::List(_) => Err(Error::unexpected_token("Num", "List")),
+            Object::Dict(_) => Err(Error::unexpected_token("Num", "Dict")),
+        }
+    }
+
     /// Try to treat the object as a list and return the internal list content decoder,
     /// mapping [`Object::List(v)`] into [`Ok(v)`]. Any other variant returns the given
     /// default value.
@@ -391,4 +542,37 @@ impl<'obj, 'ser: 'obj> Object<'obj, 'ser> {
     pub fn try_into_dictionary(self) -> Result<DictDecoder<'obj, 'ser>, Error> {
         self.dictionary_or_else(|obj| Err(Error::unexpected_token("Dict", obj.into_token().name())))
     }
+
+    /// Read the whole object, including any nested lists and dictionaries, into a
+    /// [`Value`]. Byte strings borrow from the decoded buffer rather than the decoder,
+    /// so the value can be kept after the decoder is done; use [`Value::into_owned`] to
+    /// detach it from the buffer as well.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bendy::{decoding::Decoder, value::Value};
+    ///
+    /// let mut decoder = Decoder::new(b"d4:infod6:lengthi3ee7:unknownl1:aee");
+    /// let mut dict = decoder.next_object().unwrap().unwrap().try_into_dictionary().unwrap();
+    ///
+    /// let mut unknown = None;
+    /// while let Some((key, value)) = dict.next_pair().unwrap() {
+    ///     if key == b"unknown" {
+    ///         unknown = Some(value.into_value().unwrap());
+    ///     }
+    /// }
+    /// drop(dict);
+    ///
+    /// assert_eq!(unknown, Some(Value::List(vec![Value::Bytes(b"a"[..].into())])));
+    /// ```
+    pub fn into_value(self) -> Result<Value<'ser>, Error> {
+        Value::from_object(self)
+    }
+
+    /// Read the whole object and render it for humans, as described for
+    /// [`Value::to_pretty_string`]
+    pub fn to_pretty_string(self) -> Result<String, Error> {
+        Ok(self.into_value()?.to_pretty_string())
+    }
 }
@@ -0,0 +1,341 @@
+/// The maximum nesting depth of lists and dictionaries accepted by default.
+///
+/// Deep enough for any real-world document, yet shallow enough that recursive consumers
+/// like [`Value`](crate::value::Value) or serde stay well within the stack of a thread.
+pub const DEFAULT_MAX_DEPTH: usize = 256;
+
+/// Options controlling how strictly a [`Decoder`](crate::decoding::Decoder) validates
+/// its input, passed to [`Decoder::new_with_options`].
+///
+/// The defaults only accept canonical bencode. Validators can tighten them further,
+/// while clients which need to read documents produced by sloppy encoders can relax
+/// individual checks.
+///
+/// ```
+/// use bendy::decoding::{DecodeOptions, Decoder};
+///
+/// let options = DecodeOptions::new().with_max_depth(2).with_strict_eof();
+///
+/// let mut decoder = Decoder::new_with_options(b"llee", options.clone());
+/// assert!(decoder.next_raw().is_ok());
+///
+/// let mut decoder = Decoder::new_with_options(b"llleee", options);
+/// assert!(decoder.next_raw().is_err());
+/// ```
+///
+/// [`Decoder::new_with_options`]: crate::decoding::Decoder::new_with_options
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DecodeOptions {
+    pub(crate) max_depth: usize,
+    pub(crate) strict_eof: bool,
+    pub(crate) unsorted_keys: bool,
+    pub(crate) duplicate_keys: DuplicateKeys,
+    pub(crate) leading_zeros: bool,
+    pub(crate) negative_zero: bool,
+    pub(crate) max_string_length: usize,
+    pub(crate) max_tokens: usize,
+    pub(crate) max_document_size: usize,
+    pub(crate) error_recovery: bool,
+}
+
+/// How a [`Decoder`](crate::decoding::Decoder) handles keys which occur more than once in
+/// a dictionary
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DuplicateKeys {
+    /// Fail with an error. This is the default, as canonical bencode has no duplicates.
+    Error,
+    /// Skip the pairs of repeated keys, so only the first value of each key is read
+    FirstWins,
+    /// Read all pairs. Consumers collecting the pairs into a map, like the `FromBencode`
+    /// implementations of this crate, keep the last value of each key.
+    LastWins,
+}
+
+/// Named bundles of [`DecodeOptions`], for the kinds of input commonly met in the wild
+///
+/// ```
+/// use bendy::decoding::{DecodeOptions, FromBencode, Preset};
+/// use std::collections::BTreeMap;
+///
+/// // A uTorrent `resume.dat` with unsorted keys and a non-minimal integer
+/// let resume = b"d5:b.txti07e5:a.txti1ee";
+/// assert!(BTreeMap::<String, u32>::from_bencode(resume).is_err());
+///
+/// let options = DecodeOptions::preset(Preset::ClientQuirks);
+/// let dict = BTreeMap::<String, u32>::from_bencode_with_options(resume, options).unwrap();
+/// assert_eq!(dict["b.txt"], 7);
+///
+/// let options = DecodeOptions::preset(Preset::Strict);
+/// assert!(u32::from_bencode_with_options(b"i1e\n", options).is_err());
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Preset {
+    /// Only accept a single value of canonical bencode, with nothing after it
+    Strict,
+    /// Accept `.torrent` files as written by common torrent creators: dictionary keys
+    /// may be unsorted and trailing data, like a final newline, is ignored. Everything
+    /// else must be canonical, as the info dictionary is hashed as it is.
+    Torrent,
+    /// Accept the quirks of files written by BitTorrent clients, like uTorrent's
+    /// `resume.dat`: unsorted keys, duplicate keys where the last value wins, integers
+    /// with leading zeros or negative zero, and trailing data
+    ClientQuirks,
+}
+
+impl From<Preset> for DecodeOptions {
+    fn from(preset: Preset) -> Self {
+        match preset {
+            Preset::Strict => DecodeOptions::new().with_strict_eof(),
+            Preset::Torrent => DecodeOptions::new().with_unsorted_keys(),
+            Preset::ClientQuirks => DecodeOptions::new()
+                .with_unsorted_keys()
+                .with_duplicate_keys(DuplicateKeys::LastWins)
+                .with_leading_zeros()
+                .with_negative_zero(),
+        }
+    }
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        DecodeOptions {
+            max_depth: DEFAULT_MAX_DEPTH,
+            strict_eof: false,
+            unsorted_keys: false,
+            duplicate_keys: DuplicateKeys::Error,
+            leading_zeros: false,
+            negative_zero: false,
+            max_string_length: usize::MAX,
+            max_tokens: usize::MAX,
+            max_document_size: usize::MAX,
+            error_recovery: false,
+        }
+    }
+}
+
+impl DecodeOptions {
+    /// Create the default options
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create the options of a [`Preset`], which can be adjusted further like any other
+    /// options
+    pub fn preset(preset: Preset) -> Self {
+        Self::from(preset)
+    }
+
+    /// Set the maximum nesting depth, see
+    /// [`Decoder::with_max_depth`](crate::decoding::Decoder::with_max_depth). Defaults
+    /// to [`DEFAULT_MAX_DEPTH`].
+    #[must_use]
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Reject trailing data after the first value, see
+    /// [`Decoder::with_strict_eof`](crate::decoding::Decoder::with_strict_eof)
+    #[must_use]
+    pub fn with_strict_eof(mut self) -> Self {
+        self.strict_eof = true;
+        self
+    }
+
+    /// Accept dictionaries whose keys aren't sorted, as produced by plenty of real-world
+    /// clients. Duplicate keys are still rejected.
+    ///
+    /// ```
+    /// use bendy::decoding::{DecodeOptions, FromBencode};
+    /// use std::collections::BTreeMap;
+    ///
+    /// let unsorted = b"d3:fooi1e3:bari2ee";
+    /// assert!(BTreeMap::<String, u32>::from_bencode(unsorted).is_err());
+    ///
+    /// let options = DecodeOptions::new().with_unsorted_keys();
+    /// let dict = BTreeMap::<String, u32>::from_bencode_with_options(unsorted, options).unwrap();
+    /// assert_eq!(dict["bar"], 2);
+    ///
+    /// let duplicate = b"d3:fooi1e3:bari2e3:fooi3ee";
+    /// let options = DecodeOptions::new().with_unsorted_keys();
+    /// assert!(BTreeMap::<String, u32>::from_bencode_with_options(duplicate, options).is_err());
+    /// ```
+    #[must_use]
+    pub fn with_unsorted_keys(mut self) -> Self {
+        self.unsorted_keys = true;
+        self
+    }
+
+    /// Set how keys which occur more than once in a dictionary are handled
+    ///
+    /// ```
+    /// use bendy::decoding::{DecodeOptions, DuplicateKeys, FromBencode};
+    /// use std::collections::BTreeMap;
+    ///
+    /// let duplicate = b"d3:fooi1e3:fooi2ee";
+    /// assert!(BTreeMap::<String, u32>::from_bencode(duplicate).is_err());
+    ///
+    /// let options = DecodeOptions::new().with_duplicate_keys(DuplicateKeys::FirstWins);
+    /// let dict = BTreeMap::<String, u32>::from_bencode_with_options(duplicate, options).unwrap();
+    /// assert_eq!(dict["foo"], 1);
+    ///
+    /// let options = DecodeOptions::new().with_duplicate_keys(DuplicateKeys::LastWins);
+    /// let dict = BTreeMap::<String, u32>::from_bencode_with_options(duplicate, options).unwrap();
+    /// assert_eq!(dict["foo"], 2);
+    /// ```
+    #[must_use]
+    pub fn with_duplicate_keys(mut self, policy: DuplicateKeys) -> Self {
+        self.duplicate_keys = policy;
+        self
+    }
+
+    /// Accept integers with leading zeros, like `i03e`. String lengths must still be
+    /// canonical.
+    ///
+    /// The digits are handed out as they appear in the input. Parsing them, e.g. with
+    /// [`Object::as_i64`](crate::decoding::Object::as_i64) or into a
+    /// [`Value`](crate::value::Value), normalizes them, so re-encoding such a value
+    /// produces canonical bencode.
+    ///
+    /// ```
+    /// use bendy::decoding::{DecodeOptions, FromBencode};
+    ///
+    /// assert!(u32::from_bencode(b"i03e").is_err());
+    ///
+    /// let options = DecodeOptions::new().with_leading_zeros();
+    /// assert_eq!(u32::from_bencode_with_options(b"i03e", options).unwrap(), 3);
+    /// ```
+    #[must_use]
+    pub fn with_leading_zeros(mut self) -> Self {
+        self.leading_zeros = true;
+        self
+    }
+
+    /// Accept negative zero, `i-0e`. Like with
+    /// [`with_leading_zeros`](DecodeOptions::with_leading_zeros), the digits are handed out
+    /// as they appear in the input, and normalized by parsing them.
+    #[must_use]
+    pub fn with_negative_zero(mut self) -> Self {
+        self.negative_zero = true;
+        self
+    }
+
+    /// Limit the length of individual byte strings. Longer strings fail with an
+    /// [`ErrorKind::LimitExceeded`](crate::decoding::ErrorKind::LimitExceeded) error as
+    /// soon as their length prefix is read. Unlimited by default.
+    ///
+    /// ```
+    /// use bendy::decoding::{DecodeOptions, FromBencode};
+    ///
+    /// let options = DecodeOptions::new().with_max_string_length(4);
+    /// let error = String::from_bencode_with_options(b"5:hello", options).unwrap_err();
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     "Error: string length limit of 4 bytes exceeded"
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_max_string_length(mut self, max_string_length: usize) -> Self {
+        self.max_string_length = max_string_length;
+        self
+    }
+
+    /// Limit the total number of tokens read, counting the start and end of lists and
+    /// dictionaries as tokens of their own. Unlimited by default.
+    #[must_use]
+    pub fn with_max_tokens(mut self, max_tokens: usize) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// Limit the size of the input. Larger inputs are rejected before anything is read.
+    /// Unlimited by default.
+    #[must_use]
+    pub fn with_max_document_size(mut self, max_document_size: usize) -> Self {
+        self.max_document_size = max_document_size;
+        self
+    }
+
+    /// Recover from errors inside of lists and dictionaries instead of failing for good.
+    ///
+    /// After a malformed or misplaced element, the error is returned once and the decoder
+    /// skips ahead to the end of the innermost open list or dictionary, which then reads
+    /// as ended, so decoding continues with its siblings. Malformed integers are skipped
+    /// up to their terminating `e`, other malformed bytes one at a time while looking for
+    /// that end, so this is a best effort: if the input ends
+    /// first, the error is final after all. Errors at the top level, unexpected EOFs and
+    /// exceeded limits are always final.
+    ///
+    /// ```
+    /// use bendy::decoding::{DecodeOptions, Decoder};
+    ///
+    /// let options = DecodeOptions::new().with_error_recovery();
+    /// let mut decoder = Decoder::new_with_options(b"ld1:bi1e1:ai2eei3ee", options);
+    /// let mut list = decoder.next_object().unwrap().unwrap().try_into_list().unwrap();
+    ///
+    /// let mut dict = list.next_object().unwrap().unwrap().try_into_dictionary().unwrap();
+    /// assert!(dict.next_pair().unwrap().is_some());
+    /// assert!(dict.next_pair().is_err());
+    /// assert!(dict.next_pair().unwrap().is_none());
+    /// drop(dict);
+    ///
+    /// let sibling = list.next_object().unwrap().unwrap();
+    /// assert_eq!(sibling.try_into_integer().unwrap(), "3");
+    /// assert!(list.next_object().unwrap().is_none());
+    /// ```
+    #[must_use]
+    pub fn with_error_recovery(mut self) -> Self {
+        self.error_recovery = true;
+        self
+    }
+
+    /// The maximum nesting depth
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Whether trailing data after the first value is rejected
+    pub fn strict_eof(&self) -> bool {
+        self.strict_eof
+    }
+
+    /// Whether dictionaries with unsorted keys are accepted
+    pub fn unsorted_keys(&self) -> bool {
+        self.unsorted_keys
+    }
+
+    /// How duplicate dictionary keys are handled
+    pub fn duplicate_keys(&self) -> DuplicateKeys {
+        self.duplicate_keys
+    }
+
+    /// Whether integers with leading zeros are accepted
+    pub fn leading_zeros(&self) -> bool {
+        self.leading_zeros
+    }
+
+    /// Whether negative zero is accepted
+    pub fn negative_zero(&self) -> bool {
+        self.negative_zero
+    }
+
+    /// The maximum length of a byte string
+    pub fn max_string_length(&self) -> usize {
+        self.max_string_length
+    }
+
+    /// The maximum number of tokens
+    pub fn max_tokens(&self) -> usize {
+        self.max_tokens
+    }
+
+    /// The maximum size of the input
+    pub fn max_document_size(&self) -> usize {
+        self.max_document_size
+    }
+
+    /// Whether errors inside of lists and dictionaries are recovered from
+    pub fn error_recovery(&self) -> bool {
+        self.error_recovery
+    }
+}
@@ -0,0 +1,230 @@
+use std::io::{self, BufRead, BufReader, Read};
+
+use crate::decoding::{DecodeOptions, Decoder, Error, Event, FromBencode, StreamDecoder};
+
+/// The capacity of the buffer [`Decoder::from_reader`] reads with
+const BUFFER_SIZE: usize = 8 * 1024;
+
+/// A decoder pulling its input from a reader on demand, created by
+/// [`Decoder::from_reader`] or [`ReaderDecoder::new`]
+///
+/// Only the input which hasn't been read as an [`Event`] yet is buffered, so its size is
+/// bounded by the reader's buffer plus the longest byte string, which can be limited with
+/// [`DecodeOptions::with_max_string_length`]. Errors of the reader are returned as
+/// decoding errors.
+///
+/// ```
+/// use bendy::decoding::{Decoder, Event};
+///
+/// let file: &[u8] = b"d4:name6:ubuntue";
+/// let mut decoder = Decoder::from_reader(file);
+///
+/// assert_eq!(decoder.next_event().unwrap(), Some(Event::Dict));
+/// assert_eq!(decoder.next_event().unwrap(), Some(Event::String(b"name".to_vec())));
+/// assert_eq!(decoder.next_event().unwrap(), Some(Event::String(b"ubuntu".to_vec())));
+/// assert_eq!(decoder.next_event().unwrap(), Some(Event::End));
+/// assert_eq!(decoder.next_event().unwrap(), None);
+/// ```
+#[derive(Debug)]
+pub struct ReaderDecoder<R> {
+    reader: R,
+    stream: StreamDecoder,
+    /// Whether the reader reached its end
+    eof: bool,
+}
+
+impl<'ser> Decoder<'ser> {
+    /// Create a decoder reading from the given reader as the input is needed, instead of
+    /// reading all of it into memory first
+    pub fn from_reader<R: Read>(reader: R) -> ReaderDecoder<BufReader<R>> {
+        ReaderDecoder::new(BufReader::with_capacity(BUFFER_SIZE, reader))
+    }
+}
+
+impl<R: BufRead> ReaderDecoder<R> {
+    /// Create a decoder reading from the given buffered reader
+    pub fn new(reader: R) -> Self {
+        Self::new_with_options(reader, DecodeOptions::default())
+    }
+
+    /// Create a decoder reading from the given buffered reader, validating the input as
+    /// configured by the given options
+    pub fn new_with_options(reader: R, options: DecodeOptions) -> Self {
+        ReaderDecoder {
+            reader,
+            stream: StreamDecoder::new_with_options(options),
+            eof: false,
+        }
+    }
+
+    /// Read byte strings of at least `threshold` bytes in chunks, so they are never held
+    /// in memory as a whole, see [`StreamDecoder::with_string_chunks`]
+    #[must_use]
+    pub fn with_string_chunks(mut self, threshold: usize) -> Self {
+        self.stream = self.stream.with_string_chunks(threshold);
+        self
+    }
+
+    /// Pass the raw bytes of the value at the given path to the callback of
+    /// [`ReaderDecoder::next_event_with_raw`], see [`StreamDecoder::with_raw_path`]
+    #[must_use]
+    pub fn with_raw_path(mut self, path: &[&[u8]]) -> Self {
+        self.stream = self.stream.with_raw_path(path);
+        self
+    }
+
+    /// Read the next event, reading from the reader as needed. Returns `Ok(None)` at the
+    /// end of the input.
+    pub fn next_event(&mut self) -> Result<Option<Event>, Error> {
+        self.next_event_with_raw(|_| ())
+    }
+
+    /// Read the next event, passing the raw bytes of the value at the path set with
+    /// [`ReaderDecoder::with_raw_path`] to the callback
+    pub fn next_event_with_raw(
+        &mut self,
+        mut callback: impl FnMut(&[u8]),
+    ) -> Result<Option<Event>, Error> {
+        self.pull(|stream| stream.next_event_with_raw(&mut callback))
+    }
+
+    /// Read the next complete value and decode it. Returns `Ok(None)` at the end of the
+    /// input.
+    pub fn next_object<T: FromBencode>(&mut self) -> Result<Option<T>, Error> {
+        match self.pull(StreamDecoder::next_value)? {
+            Some(value) => {
+                T::from_bencode_with_options(&value, self.stream.options().clone()).map(Some)
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Read from the stream decoder, feeding it from the reader until it has enough input
+    fn pull<T>(
+        &mut self,
+        mut read: impl FnMut(&mut StreamDecoder) -> Result<Option<T>, Error>,
+    ) -> Result<Option<T>, Error> {
+        loop {
+            if let Some(item) = read(&mut self.stream)? {
+                return Ok(Some(item));
+            }
+            if self.eof {
+                return Ok(None);
+            }
+
+            let chunk = match self.reader.fill_buf() {
+                Ok(chunk) => chunk,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(Error::from(error)),
+            };
+
+            if chunk.is_empty() {
+                self.eof = true;
+                self.stream.finish();
+            } else {
+                let length = chunk.len();
+                self.stream.feed(chunk);
+                self.reader.consume(length);
+            }
+        }
+    }
+
+    /// The offset of the next event in the input
+    pub fn position(&self) -> usize {
+        self.stream.position()
+    }
+
+    /// The number of lists and dictionaries enclosing the next event
+    pub fn depth(&self) -> usize {
+        self.stream.depth()
+    }
+
+    /// Get a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Consume the decoder, returning the underlying reader. Input which was already
+    /// taken from the reader but not read as an event yet is lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{state_tracker::Token, value::Value};
+
+    /// A reader returning one byte at a time, failing with `Interrupted` in between
+    struct Trickle<'a> {
+        input: &'a [u8],
+        interrupt: bool,
+    }
+
+    impl<'a> Read for Trickle<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.interrupt = !self.interrupt;
+            if self.interrupt {
+                return Err(io::ErrorKind::Interrupted.into());
+            }
+            let length = buf.len().min(1);
+            self.input.read(&mut buf[..length])
+        }
+    }
+
+    #[test]
+    fn input_should_be_read_on_demand() {
+        let input = b"d3:bard1:xi-12ee3:fooli1e7:bencodeee";
+        let trickle = Trickle {
+            input,
+            interrupt: false,
+        };
+        let mut decoder = ReaderDecoder::new(BufReader::with_capacity(4, trickle));
+
+        let mut events = Vec::new();
+        while let Some(event) = decoder.next_event().unwrap() {
+            events.push(event);
+            // Nothing is read past the end of the event
+            let consumed = input.len() - decoder.get_ref().get_ref().input.len();
+            assert_eq!(consumed, decoder.position());
+        }
+
+        let tokens: Vec<Token> = Decoder::new(input)
+            .tokens()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let events: Vec<Token> = events.iter().filter_map(Event::as_token).collect();
+        assert_eq!(events, tokens);
+    }
+
+    #[test]
+    fn objects_should_be_decoded_across_reads() {
+        let trickle = Trickle {
+            input: b"li1ei2eed1:ai3ee",
+            interrupt: false,
+        };
+        let mut decoder = ReaderDecoder::new(BufReader::with_capacity(3, trickle));
+
+        assert_eq!(decoder.next_object().unwrap(), Some(vec![1, 2]));
+        let value: Value = decoder.next_object().unwrap().unwrap();
+        assert_eq!(value, Value::dict().with_entry("a", 3));
+        assert_eq!(decoder.next_object::<Value>().unwrap(), None);
+    }
+
+    #[test]
+    fn truncated_input_and_reader_errors_should_fail() {
+        let mut decoder = Decoder::from_reader(&b"li1e"[..]);
+        decoder.next_event().unwrap();
+        decoder.next_event().unwrap();
+        assert!(decoder.next_event().is_err());
+
+        struct Broken;
+        impl Read for Broken {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::ErrorKind::ConnectionReset.into())
+            }
+        }
+        assert!(Decoder::from_reader(Broken).next_event().is_err());
+    }
+}
@@ -0,0 +1,123 @@
+use crate::{
+    decoding::{DecodeOptions, Decoder, Error, FromBencode},
+    state_tracker::{StateTracker, StructureError},
+};
+
+/// A decoder for many small messages, e.g. the packets received by a DHT node
+///
+/// Decoding each message with a fresh [`Decoder`] allocates the stack it validates the
+/// structure with anew. A session keeps this stack between messages instead, so decoding
+/// a message of a familiar shape doesn't allocate beyond what its decoded value needs.
+///
+/// ```
+/// use bendy::decoding::Session;
+///
+/// let mut session = Session::new();
+/// for packet in &[&b"li1ei2ee"[..], b"li3ee"] {
+///     let numbers: Vec<u32> = session.decode(packet).unwrap();
+///     assert!(!numbers.is_empty());
+/// }
+///
+/// let length = session.decode_with(b"d6:lengthi3ee", |decoder| {
+///     decoder.next_object()?.unwrap().try_into_dictionary()?.next_pair().map(|_| 3)
+/// });
+/// assert_eq!(length.unwrap(), 3);
+/// ```
+#[derive(Debug)]
+pub struct Session {
+    options: DecodeOptions,
+    state: Option<StateTracker<&'static [u8], Error>>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Session {
+    /// Create a new session
+    pub fn new() -> Self {
+        Self::new_with_options(DecodeOptions::default())
+    }
+
+    /// Create a new session, validating each message as configured by the given options
+    pub fn new_with_options(options: DecodeOptions) -> Self {
+        Session {
+            state: Some(Decoder::state_for(&options)),
+            options,
+        }
+    }
+
+    /// The options messages are validated with
+    pub fn options(&self) -> &DecodeOptions {
+        &self.options
+    }
+
+    /// Decode a message like [`FromBencode::from_bencode_with_options`], with the options
+    /// of this session
+    pub fn decode<T: FromBencode>(&mut self, message: &[u8]) -> Result<T, Error> {
+        self.decode_with(message, |decoder| {
+            decoder.next_object()?.map_or(
+                Err(Error::from(StructureError::UnexpectedEof)),
+                T::decode_bencode_object,
+            )
+        })
+    }
+
+    /// Decode a message with the given function, which gets a decoder for the message
+    /// that reuses the memory of this session. Errors of a message don't affect the
+    /// following ones.
+    pub fn decode_with<'ser, R>(
+        &mut self,
+        message: &'ser [u8],
+        decode: impl FnOnce(&mut Decoder<'ser>) -> R,
+    ) -> R {
+        let state = match self.state.take() {
+            Some(state) => state.recycle(),
+            // Only if decoding a message panicked
+            None => Decoder::state_for(&self.options),
+        };
+
+        let mut decoder = Decoder::new_with_state(message, self.options.clone(), state);
+        let result = decode(&mut decoder);
+        self.state = Some(decoder.into_state().recycle());
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::{vec, vec::Vec};
+
+    use super::*;
+    use crate::{decoding::ErrorKind, value::Value};
+
+    #[test]
+    fn messages_should_be_decoded_independently() {
+        let mut session = Session::new();
+        assert_eq!(session.decode::<Vec<u8>>(b"li1ei2ee").unwrap(), vec![1, 2]);
+
+        // Failing in the middle of a structure leaves nothing behind
+        assert!(session.decode::<Value>(b"d1:ali1e").is_err());
+        assert!(session.decode::<Value>(b"d1:bi1e1:ai2ee").is_err());
+        assert_eq!(session.decode::<u8>(b"i3e").unwrap(), 3);
+        assert_eq!(
+            session.decode::<Value>(b"d1:bi1ee").unwrap(),
+            Value::dict().with_entry("b", 1)
+        );
+    }
+
+    #[test]
+    fn options_should_apply_to_every_message() {
+        let options = DecodeOptions::new().with_max_depth(2);
+        let mut session = Session::new_with_options(options);
+
+        for _ in 0..2 {
+            assert!(session.decode::<Value>(b"lli1eee").is_ok());
+            let error = session.decode::<Value>(b"llli1eeee").unwrap_err();
+            assert!(matches!(error.kind(), ErrorKind::StructureError(_)));
+        }
+    }
+}
@@ -0,0 +1,707 @@
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+use core::{mem, str};
+
+use crate::{
+    decoding::{DecodeOptions, Decoder, Error, ErrorKind, Limit},
+    state_tracker::{StateTracker, StructureError, Token},
+};
+
+/// A token read by a [`StreamDecoder`], which owns its content
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Event {
+    /// The start of a list
+    List,
+    /// The start of a dictionary
+    Dict,
+    /// A byte string
+    String(Vec<u8>),
+    /// An integer, as its unparsed text
+    Num(String),
+    /// The end of a list or dictionary
+    End,
+    /// The start of a byte string of the given length, which is read in chunks, see
+    /// [`StreamDecoder::with_string_chunks`]
+    StringStart(usize),
+    /// The next part of a byte string started by [`Event::StringStart`]
+    StringChunk(Vec<u8>),
+}
+
+impl Event {
+    /// Borrow the event as a [`Token`]. Parts of chunked byte strings aren't tokens on
+    /// their own, so they are returned as `None`.
+    pub fn as_token(&self) -> Option<Token<'_>> {
+        let token = match self {
+            Event::List => Token::List,
+            Event::Dict => Token::Dict,
+            Event::String(bytes) => Token::String(bytes),
+            Event::Num(number) => Token::Num(number),
+            Event::End => Token::End,
+            Event::StringStart(_) | Event::StringChunk(_) => return None,
+        };
+        Some(token)
+    }
+}
+
+impl<'a> From<Token<'a>> for Event {
+    fn from(token: Token<'a>) -> Self {
+        match token {
+            Token::List => Event::List,
+            Token::Dict => Event::Dict,
+            Token::String(bytes) => Event::String(bytes.to_vec()),
+            Token::Num(number) => Event::Num(number.into()),
+            Token::End => Event::End,
+        }
+    }
+}
+
+/// A decoder for input arriving in chunks, e.g. from a socket
+///
+/// Input is passed to [`StreamDecoder::feed`] as it arrives, and read as [`Event`]s with
+/// [`StreamDecoder::next_event`], which returns `Ok(None)` whenever it needs more input.
+/// Chunks may end anywhere, even in the middle of a byte string; incomplete tokens are
+/// kept until the rest of them arrives. Once the input is complete, call
+/// [`StreamDecoder::finish`], so that `next_event` can tell a truncated document from one
+/// which isn't finished yet.
+///
+/// The events are validated like the tokens of a [`Decoder`], and errors are final.
+///
+/// The stream decoder does no I/O of its own, so it is the core of the decoders for other
+/// transports, like `ReaderDecoder`, `BufDecoder` and `AsyncDecoder`: these only feed it
+/// with input until it returns the next event or value. It reads tokens with the same code
+/// as [`Decoder`], which decodes slices without copying them.
+///
+/// ```
+/// use bendy::decoding::{Event, StreamDecoder};
+///
+/// let mut decoder = StreamDecoder::new();
+/// let mut events = Vec::new();
+///
+/// for chunk in &[&b"d3:foo"[..], b"l5:hel", b"lo", b"i12", b"ee", b"e"] {
+///     decoder.feed(chunk);
+///     while let Some(event) = decoder.next_event().unwrap() {
+///         events.push(event);
+///     }
+/// }
+/// decoder.finish();
+/// assert_eq!(decoder.next_event().unwrap(), None);
+///
+/// assert_eq!(events, vec![
+///     Event::Dict,
+///     Event::String(b"foo".to_vec()),
+///     Event::List,
+///     Event::String(b"hello".to_vec()),
+///     Event::Num("12".into()),
+///     Event::End,
+///     Event::End,
+/// ]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct StreamDecoder {
+    /// The input which hasn't been discarded yet
+    buffer: Vec<u8>,
+    /// Offset of `buffer` in the input
+    offset_base: usize,
+    /// Offset of the next token in `buffer`
+    offset: usize,
+    options: DecodeOptions,
+    /// The number of tokens read so far
+    token_count: usize,
+    /// Whether the first value of the input was read completely
+    value_read: bool,
+    /// Whether the input is complete
+    finished: bool,
+    /// The minimum length of byte strings read in chunks
+    chunk_threshold: usize,
+    /// The number of bytes left of the chunked byte string being read
+    string_remaining: usize,
+    /// The value whose raw bytes are passed to [`StreamDecoder::next_event_with_raw`]
+    raw_path: Option<RawPath>,
+    /// The raw bytes of the value read by [`StreamDecoder::next_value`] so far
+    value: Vec<u8>,
+    /// Whether [`StreamDecoder::next_value`] is collecting a value
+    collecting: bool,
+    state: StateTracker<Vec<u8>, Error>,
+}
+
+/// Where the next value is located in its enclosing container
+#[derive(Clone, Debug)]
+enum Segment {
+    Index(usize),
+    /// The key of the next value, or `None` if the key wasn't read yet
+    Key(Option<Vec<u8>>),
+}
+
+/// Tracks the location of the decoder, to find the value at a path
+#[derive(Clone, Debug)]
+struct RawPath {
+    path: Vec<Vec<u8>>,
+    location: Vec<Segment>,
+    /// The length of the location of the value being passed on, if any
+    capturing: Option<usize>,
+}
+
+impl RawPath {
+    fn at_path(&self) -> bool {
+        self.location.len() == self.path.len()
+            && self
+                .location
+                .iter()
+                .zip(&self.path)
+                .all(|(location, segment)| match location {
+                    Segment::Index(index) => {
+                        let segment = str::from_utf8(segment).ok().and_then(|s| s.parse().ok());
+                        segment == Some(*index)
+                    },
+                    Segment::Key(key) => key.as_ref() == Some(segment),
+                })
+    }
+
+    /// Update the location after a token of `raw` bytes, passing them on if they are
+    /// part of the value at the path. `pending` is true if the token starts a byte
+    /// string which is read in chunks.
+    fn observe(
+        &mut self,
+        token: &Token,
+        key: bool,
+        pending: bool,
+        raw: &[u8],
+        callback: &mut dyn FnMut(&[u8]),
+    ) {
+        if self.capturing.is_none() && !key && *token != Token::End && self.at_path() {
+            self.capturing = Some(self.location.len());
+        }
+        if self.capturing.is_some() {
+            callback(raw);
+        }
+
+        match token {
+            Token::List => self.location.push(Segment::Index(0)),
+            Token::Dict => self.location.push(Segment::Key(None)),
+            Token::End => {
+                self.location.pop();
+                self.advance();
+            },
+            Token::String(bytes) if key => {
+                if let Some(segment) = self.location.last_mut() {
+                    *segment = Segment::Key(Some(bytes.to_vec()));
+                }
+            },
+            _ if pending => (),
+            _ => self.advance(),
+        }
+    }
+
+    /// Move on to the next value after one was read completely
+    fn advance(&mut self) {
+        match self.location.last_mut() {
+            Some(Segment::Index(index)) => *index += 1,
+            Some(Segment::Key(key)) => *key = None,
+            None => (),
+        }
+        if self.capturing == Some(self.location.len()) {
+            self.capturing = None;
+        }
+    }
+}
+
+impl Default for StreamDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StreamDecoder {
+    /// Create a new stream decoder
+    pub fn new() -> Self {
+        Self::new_with_options(DecodeOptions::default())
+    }
+
+    /// Create a new stream decoder, validating the input as configured by the given
+    /// options. Error recovery is not supported.
+    pub fn new_with_options(options: DecodeOptions) -> Self {
+        let state = Decoder::state_for(&options);
+
+        StreamDecoder {
+            buffer: Vec::new(),
+            offset_base: 0,
+            offset: 0,
+            options,
+            token_count: 0,
+            value_read: false,
+            finished: false,
+            chunk_threshold: usize::MAX,
+            string_remaining: 0,
+            raw_path: None,
+            value: Vec::new(),
+            collecting: false,
+            state,
+        }
+    }
+
+    /// Read byte strings of at least `threshold` bytes in chunks instead of buffering them
+    /// completely: such strings are returned as an [`Event::StringStart`] with their
+    /// length, followed by [`Event::StringChunk`]s of whatever part of the string was fed
+    /// so far, until the string is complete. Dictionary keys are always read whole.
+    ///
+    /// ```
+    /// use bendy::decoding::{Event, StreamDecoder};
+    ///
+    /// let mut decoder = StreamDecoder::new().with_string_chunks(4);
+    /// decoder.feed(b"d6:pieces10:0123");
+    /// assert_eq!(decoder.next_event().unwrap(), Some(Event::Dict));
+    /// assert_eq!(decoder.next_event().unwrap(), Some(Event::String(b"pieces".to_vec())));
+    /// assert_eq!(decoder.next_event().unwrap(), Some(Event::StringStart(10)));
+    /// assert_eq!(decoder.next_event().unwrap(), Some(Event::StringChunk(b"0123".to_vec())));
+    /// assert_eq!(decoder.next_event().unwrap(), None);
+    ///
+    /// decoder.feed(b"456789e");
+    /// assert_eq!(decoder.next_event().unwrap(), Some(Event::StringChunk(b"456789".to_vec())));
+    /// assert_eq!(decoder.next_event().unwrap(), Some(Event::End));
+    /// ```
+    #[must_use]
+    pub fn with_string_chunks(mut self, threshold: usize) -> Self {
+        self.chunk_threshold = threshold;
+        self
+    }
+
+    /// Pass the raw bytes of the value at the given path to the callback of
+    /// [`StreamDecoder::next_event_with_raw`] as they are read, e.g. to hash the `info`
+    /// dictionary of a torrent in the same pass as decoding it. Each segment of the path
+    /// is either a dictionary key or, for lists, a decimal index, like for
+    /// [`Decoder::seek_path`]; an empty path designates the whole value.
+    ///
+    /// ```
+    /// use bendy::decoding::StreamDecoder;
+    ///
+    /// let mut decoder = StreamDecoder::new().with_raw_path(&[b"info"]);
+    /// let mut info = Vec::new();
+    /// let mut hash = |raw: &[u8]| info.extend_from_slice(raw);
+    ///
+    /// for chunk in &[&b"d8:announce3:foo4:infod6:len"[..], b"gthi3ee", b"e"] {
+    ///     decoder.feed(chunk);
+    ///     while decoder.next_event_with_raw(&mut hash).unwrap().is_some() {}
+    /// }
+    /// drop(hash);
+    /// assert_eq!(info, b"d6:lengthi3ee");
+    /// ```
+    #[must_use]
+    pub fn with_raw_path(mut self, path: &[&[u8]]) -> Self {
+        self.raw_path = Some(RawPath {
+            path: path.iter().map(|segment| segment.to_vec()).collect(),
+            location: Vec::new(),
+            capturing: None,
+        });
+        self
+    }
+
+    /// Append the next chunk of the input
+    ///
+    /// # Panics
+    ///
+    /// Panics if the input was already marked as complete with [`StreamDecoder::finish`].
+    pub fn feed(&mut self, chunk: &[u8]) {
+        assert!(!self.finished, "input fed after the end of the stream");
+
+        // Discard the tokens read so far
+        self.buffer.drain(..self.offset);
+        self.offset_base += self.offset;
+        self.offset = 0;
+
+        self.buffer.extend_from_slice(chunk);
+    }
+
+    /// Mark the input as complete. Afterwards, [`StreamDecoder::next_event`] fails if the
+    /// input ends in the middle of a value.
+    pub fn finish(&mut self) {
+        self.finished = true;
+    }
+
+    /// The offset of the next event in the input
+    pub fn position(&self) -> usize {
+        self.offset_base + self.offset
+    }
+
+    /// The number of bytes fed but not read yet, including any incomplete token
+    pub fn buffered(&self) -> usize {
+        self.buffer.len() - self.offset
+    }
+
+    /// The number of lists and dictionaries enclosing the next event
+    pub fn depth(&self) -> usize {
+        self.state.depth()
+    }
+
+    /// The options this decoder validates its input with
+    pub fn options(&self) -> &DecodeOptions {
+        &self.options
+    }
+
+    /// Read the next event. Returns `Ok(None)` if more input is needed, or at the end of
+    /// the input once it is complete.
+    pub fn next_event(&mut self) -> Result<Option<Event>, Error> {
+        self.read_event(&mut |_| ())
+    }
+
+    /// Read the next event like [`StreamDecoder::next_event`], passing the raw bytes it
+    /// was read from to the callback if they belong to the value at the path set with
+    /// [`StreamDecoder::with_raw_path`]. Concatenated over all events, these are exactly
+    /// the bytes of that value.
+    pub fn next_event_with_raw(
+        &mut self,
+        mut callback: impl FnMut(&[u8]),
+    ) -> Result<Option<Event>, Error> {
+        self.read_event(&mut callback)
+    }
+
+    /// Read the next complete value and return its raw bytes, which can be decoded with
+    /// [`FromBencode`](crate::decoding::FromBencode). Returns `Ok(None)` if more input is
+    /// needed, or at the end of the input once it is complete; the part of the value read
+    /// so far is kept for the next call.
+    ///
+    /// Values are collected from the next event on, so this should only be called
+    /// between values.
+    ///
+    /// ```
+    /// use bendy::decoding::StreamDecoder;
+    ///
+    /// let mut decoder = StreamDecoder::new();
+    /// decoder.feed(b"li1ei");
+    /// assert_eq!(decoder.next_value().unwrap(), None);
+    /// decoder.feed(b"2eei3");
+    /// assert_eq!(decoder.next_value().unwrap(), Some(b"li1ei2ee".to_vec()));
+    /// assert_eq!(decoder.next_value().unwrap(), None);
+    /// ```
+    pub fn next_value(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        self.collecting = true;
+        let result = loop {
+            match self.next_event() {
+                Ok(Some(_)) if self.state.depth() > 0 || self.string_remaining > 0 => (),
+                Ok(Some(_)) => break Ok(Some(mem::take(&mut self.value))),
+                Ok(None) => break Ok(None),
+                Err(error) => break Err(error),
+            }
+        };
+        self.collecting = false;
+        result
+    }
+
+    fn read_event(&mut self, callback: &mut dyn FnMut(&[u8])) -> Result<Option<Event>, Error> {
+        self.state.check_error()?;
+        if !self.collecting {
+            // Reading events abandons a value collected by `next_value`
+            self.value.clear();
+        }
+
+        let fed = self.offset_base + self.buffer.len();
+        if fed > self.options.max_document_size {
+            let limit = Limit::DocumentSize(self.options.max_document_size);
+            return self.state.latch_err(Err(Error::limit_exceeded(limit)));
+        }
+
+        if self.string_remaining > 0 {
+            return self.next_string_chunk(callback);
+        }
+
+        if self.offset == self.buffer.len() {
+            if self.finished {
+                self.state.observe_eof()?;
+            }
+            return Ok(None);
+        }
+
+        let position = self.position();
+        if self.options.strict_eof && self.value_read && self.state.depth() == 0 {
+            let error = Error::from(StructureError::TrailingData { position });
+            return self.state.latch_err(Err(error));
+        }
+
+        let source = &self.buffer[self.offset..];
+        let chunked = self.chunk_threshold < usize::MAX
+            && source[0].is_ascii_digit()
+            && !self.state.expects_key();
+
+        let (token, length, string_length) = if chunked {
+            let result = Decoder::read_string_length(source, position, &self.options);
+            match result {
+                Ok((string_length, length)) if string_length >= self.chunk_threshold => {
+                    (Token::String(&[]), length, Some(string_length))
+                },
+                Ok(_) => match Decoder::read_raw_token(source, position, &self.options) {
+                    Ok((token, length)) => (token, length, None),
+                    Err(error) => return self.incomplete_or(error),
+                },
+                Err(error) => return self.incomplete_or(error),
+            }
+        } else {
+            match Decoder::read_raw_token(source, position, &self.options) {
+                Ok((token, length)) => (token, length, None),
+                Err(error) => return self.incomplete_or(error),
+            }
+        };
+
+        if self.token_count == self.options.max_tokens {
+            let limit = Limit::TokenCount(self.options.max_tokens);
+            return self.state.latch_err(Err(Error::limit_exceeded(limit)));
+        }
+        self.token_count += 1;
+
+        let key = self.state.expects_key();
+        self.state.observe_token(&token, position)?;
+        let raw = &self.buffer[self.offset..self.offset + length];
+        if let Some(raw_path) = &mut self.raw_path {
+            raw_path.observe(&token, key, string_length.is_some(), raw, callback);
+        }
+        if self.collecting {
+            self.value.extend_from_slice(raw);
+        }
+        let event = match string_length {
+            Some(string_length) => Event::StringStart(string_length),
+            None => Event::from(token),
+        };
+        self.offset += length;
+        self.string_remaining = string_length.unwrap_or(0);
+
+        if self.state.depth() == 0 && self.string_remaining == 0 {
+            self.value_read = true;
+        }
+
+        Ok(Some(event))
+    }
+
+    /// Read the next part of a chunked byte string
+    fn next_string_chunk(
+        &mut self,
+        callback: &mut dyn FnMut(&[u8]),
+    ) -> Result<Option<Event>, Error> {
+        let available = self.buffer.len() - self.offset;
+        if available == 0 {
+            if self.finished {
+                let error = Error::from(StructureError::UnexpectedEof);
+                return self.state.latch_err(Err(error));
+            }
+            return Ok(None);
+        }
+
+        let length = available.min(self.string_remaining);
+        let chunk = self.buffer[self.offset..self.offset + length].to_vec();
+        self.offset += length;
+        self.string_remaining -= length;
+
+        if self.collecting {
+            self.value.extend_from_slice(&chunk);
+        }
+        if let Some(raw_path) = &mut self.raw_path {
+            if raw_path.capturing.is_some() {
+                callback(&chunk);
+            }
+            if self.string_remaining == 0 {
+                raw_path.advance();
+            }
+        }
+
+        if self.state.depth() == 0 && self.string_remaining == 0 {
+            self.value_read = true;
+        }
+
+        Ok(Some(Event::StringChunk(chunk)))
+    }
+
+    /// Wait for more input if the error is about an incomplete token, or fail otherwise
+    fn incomplete_or(&mut self, error: Error) -> Result<Option<Event>, Error> {
+        let incomplete = matches!(
+            error.kind(),
+            ErrorKind::StructureError(StructureError::UnexpectedEof)
+        );
+        if incomplete && !self.finished {
+            return Ok(None);
+        }
+        self.state.latch_err(Err(error))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::{vec, vec::Vec};
+
+    use super::*;
+    use crate::state_tracker::Token;
+
+    const INPUT: &[u8] = b"d3:bard1:xi-12ee3:fooli1e7:bencodeee";
+
+    /// Feed the input in chunks of the given size, collecting all events
+    fn decode_chunked(
+        input: &[u8],
+        size: usize,
+        options: DecodeOptions,
+    ) -> Result<Vec<Event>, Error> {
+        let mut decoder = StreamDecoder::new_with_options(options);
+        let mut events = Vec::new();
+
+        for chunk in input.chunks(size) {
+            decoder.feed(chunk);
+            while let Some(event) = decoder.next_event()? {
+                events.push(event);
+            }
+        }
+
+        decoder.finish();
+        while let Some(event) = decoder.next_event()? {
+            events.push(event);
+        }
+        Ok(events)
+    }
+
+    #[test]
+    fn any_chunking_should_yield_the_tokens() {
+        let tokens: Vec<Token> = Decoder::new(INPUT)
+            .tokens()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        for size in 1..=INPUT.len() {
+            let events = decode_chunked(INPUT, size, DecodeOptions::default()).unwrap();
+            let events: Vec<Token> = events.iter().filter_map(Event::as_token).collect();
+            assert_eq!(events, tokens, "chunks of {} bytes", size);
+        }
+    }
+
+    #[test]
+    fn long_strings_should_be_read_in_chunks() {
+        for size in 1..=INPUT.len() {
+            let mut decoder = StreamDecoder::new().with_string_chunks(3);
+            let mut events = Vec::new();
+            for chunk in INPUT.chunks(size) {
+                decoder.feed(chunk);
+                while let Some(event) = decoder.next_event().unwrap() {
+                    events.push(event);
+                }
+            }
+
+            // Keys are read whole, and values in chunks of at most the fed size
+            assert_eq!(events[1], Event::String(b"bar".to_vec()));
+            assert_eq!(events[3], Event::String(b"x".to_vec()));
+            assert_eq!(events[6], Event::String(b"foo".to_vec()));
+            assert_eq!(events[9], Event::StringStart(7));
+
+            let mut string: Vec<u8> = Vec::new();
+            for event in &events[10..events.len() - 2] {
+                match event {
+                    Event::StringChunk(chunk) if chunk.len() <= size => string.extend(chunk),
+                    _ => panic!("unexpected event {:?}", event),
+                }
+            }
+            assert_eq!(string, b"bencode");
+        }
+
+        let mut decoder = StreamDecoder::new().with_string_chunks(3);
+        decoder.feed(b"4:sp");
+        assert_eq!(decoder.next_event().unwrap(), Some(Event::StringStart(4)));
+        decoder.next_event().unwrap();
+        decoder.finish();
+        assert!(decoder.next_event().is_err());
+    }
+
+    #[test]
+    fn raw_bytes_of_the_path_should_be_passed_on() {
+        let input = b"d8:announce3:foo4:infod5:filesld6:lengthi3eee4:name7:bencodee1:xi1ee";
+        let cases: &[(&[&[u8]], &[u8])] = &[
+            (&[b"info"], b"d5:filesld6:lengthi3eee4:name7:bencodee"),
+            (&[b"info", b"name"], b"7:bencode"),
+            (&[b"info", b"files", b"0", b"length"], b"i3e"),
+            (&[b"x"], b"i1e"),
+            (&[], input),
+            (&[b"info", b"files", b"1"], b""),
+            (&[b"announce", b"0"], b""),
+        ];
+
+        for &(path, expected) in cases {
+            for &threshold in &[usize::MAX, 2] {
+                for size in 1..=input.len() {
+                    let mut decoder = StreamDecoder::new()
+                        .with_string_chunks(threshold)
+                        .with_raw_path(path);
+                    let mut raw = Vec::new();
+                    for chunk in input.chunks(size) {
+                        decoder.feed(chunk);
+                        while decoder
+                            .next_event_with_raw(|bytes| raw.extend_from_slice(bytes))
+                            .unwrap()
+                            .is_some()
+                        {}
+                    }
+                    assert_eq!(raw, expected, "{:?} in chunks of {}", path, size);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn values_should_be_collected_across_chunks() {
+        for size in 1..=INPUT.len() {
+            for &threshold in &[usize::MAX, 2] {
+                let mut decoder = StreamDecoder::new().with_string_chunks(threshold);
+                let mut values = Vec::new();
+                for chunk in INPUT.chunks(size).chain(INPUT.chunks(size)) {
+                    decoder.feed(chunk);
+                    while let Some(value) = decoder.next_value().unwrap() {
+                        values.push(value);
+                    }
+                }
+                assert_eq!(values, vec![INPUT.to_vec(), INPUT.to_vec()]);
+            }
+        }
+
+        let mut decoder = StreamDecoder::new();
+        decoder.feed(b"li1e");
+        assert_eq!(decoder.next_value().unwrap(), None);
+        decoder.finish();
+        assert!(decoder.next_value().is_err());
+    }
+
+    #[test]
+    fn errors_should_report_positions_in_the_stream() {
+        for size in 1..8 {
+            let error = decode_chunked(b"li1ei2ei3x", size, DecodeOptions::default()).unwrap_err();
+            assert_eq!(error.position(), Some(9));
+
+            let error = decode_chunked(b"d1:bi1e1:ai2ee", size, DecodeOptions::default());
+            assert_eq!(error.unwrap_err().position(), Some(7));
+        }
+    }
+
+    #[test]
+    fn truncated_input_should_fail_once_finished() {
+        let mut decoder = StreamDecoder::new();
+        decoder.feed(b"l3:fo");
+        assert_eq!(decoder.next_event().unwrap(), Some(Event::List));
+        assert_eq!(decoder.next_event().unwrap(), None);
+        assert_eq!(decoder.buffered(), 4);
+
+        decoder.finish();
+        assert!(decoder.next_event().is_err());
+
+        let mut decoder = StreamDecoder::new();
+        decoder.feed(b"li1e");
+        decoder.next_event().unwrap();
+        decoder.next_event().unwrap();
+        decoder.finish();
+        assert!(decoder.next_event().is_err());
+    }
+
+    #[test]
+    fn options_should_apply() {
+        let options = DecodeOptions::new().with_max_string_length(4);
+        assert!(decode_chunked(b"l4:spam5:spamse", 3, options).is_err());
+
+        let options = DecodeOptions::new().with_max_document_size(8);
+        assert!(decode_chunked(b"li1ei2ee", 3, options.clone()).is_ok());
+        assert!(decode_chunked(b"li1ei2eei3e", 3, options).is_err());
+
+        let options = DecodeOptions::new();
+        assert_eq!(decode_chunked(b"i1ei2e", 2, options).unwrap().len(), 2);
+        let options = DecodeOptions::new().with_strict_eof();
+        assert!(decode_chunked(b"i1ei2e", 2, options).is_err());
+    }
+}
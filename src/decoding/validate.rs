@@ -0,0 +1,245 @@
+use failure::Fail;
+
+use crate::{
+    decoding::{DecodeOptions, Decoder, Error, ErrorKind},
+    state_tracker::StructureError,
+};
+
+/// Check that the input is exactly one well-formed bencode value with sorted, unique
+/// dictionary keys, without decoding it.
+///
+/// This is cheaper than reading the input with a [`Decoder`], as it tracks nothing but
+/// the open containers and the last key of each dictionary, so it makes a good gate
+/// before storing blobs. Unlike a decoder, trailing data after the value is always
+/// rejected.
+///
+/// ```
+/// use bendy::decoding::validate;
+///
+/// assert!(validate(b"d3:bari1e3:fooli2ei3eee").is_ok());
+/// assert!(validate(b"d3:fooi1e3:bari2ee").is_err());
+/// assert!(validate(b"i1ei2e").is_err());
+/// ```
+pub fn validate(input: &[u8]) -> Result<(), Error> {
+    validate_with_options(input, DecodeOptions::default())
+}
+
+/// Check the input like [`validate`], as strictly as configured by the given options.
+/// The depth and size limits apply as well.
+///
+/// ```
+/// use bendy::decoding::{validate_with_options, DecodeOptions};
+///
+/// let options = DecodeOptions::new().with_unsorted_keys();
+/// assert!(validate_with_options(b"d3:fooi1e3:bari2ee", options).is_ok());
+/// ```
+pub fn validate_with_options(input: &[u8], options: DecodeOptions) -> Result<(), Error> {
+    Decoder::new_with_options(input, options).validate()
+}
+
+/// Find out whether the buffer starts with a complete value, returning its length in
+/// bytes if so, or `Ok(None)` if more input is needed. This is meant for framing streams
+/// of back to back messages, like KRPC over TCP: the value is checked like by
+/// [`validate`], but not decoded, and anything after it is left alone.
+///
+/// Malformed input fails right away, even if the value isn't complete yet.
+///
+/// ```
+/// use bendy::decoding::complete_len;
+///
+/// assert_eq!(complete_len(b"d1:ai1ee").unwrap(), Some(8));
+/// assert_eq!(complete_len(b"d1:ai1eed1:").unwrap(), Some(8));
+/// assert_eq!(complete_len(b"d1:ai1").unwrap(), None);
+/// assert!(complete_len(b"d1:ax").is_err());
+/// ```
+pub fn complete_len(input: &[u8]) -> Result<Option<usize>, Error> {
+    complete_len_with_options(input, DecodeOptions::default())
+}
+
+/// Find the length of the first value like [`complete_len`], checking it as strictly as
+/// configured by the given options. Peers which don't sort their dictionary keys can be
+/// framed with [`DecodeOptions::with_unsorted_keys`].
+pub fn complete_len_with_options(
+    input: &[u8],
+    options: DecodeOptions,
+) -> Result<Option<usize>, Error> {
+    let mut decoder = Decoder::new_with_options(input, options);
+    match decoder.scan_value() {
+        Ok(()) => Ok(Some(decoder.position())),
+        Err(error) => match error.kind() {
+            ErrorKind::StructureError(StructureError::UnexpectedEof) => Ok(None),
+            _ => Err(error),
+        },
+    }
+}
+
+/// The first violation of canonical bencode found by [`is_canonical`]
+#[derive(Debug, Clone, Fail)]
+#[fail(display = "{}", error)]
+pub struct Violation {
+    position: usize,
+    #[fail(cause)]
+    error: Error,
+}
+
+impl Violation {
+    /// The offset of the violation in the input. Input which ends too early is reported
+    /// at its end.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// The error describing the violation
+    pub fn error(&self) -> &Error {
+        &self.error
+    }
+}
+
+/// Check that the buffer is strictly canonical bencode: a single value, with sorted,
+/// unique dictionary keys and integers and string lengths without leading zeros or
+/// negative zero. Returns the first violation otherwise.
+///
+/// Unlike [`validate`], which this is equivalent to otherwise, the nesting depth isn't
+/// limited, as it has nothing to do with being canonical.
+///
+/// ```
+/// use bendy::decoding::is_canonical;
+///
+/// assert!(is_canonical(b"d4:infod6:lengthi1eee").is_ok());
+///
+/// let violation = is_canonical(b"d4:infod6:lengthi01eee").unwrap_err();
+/// assert_eq!(violation.position(), 18);
+/// ```
+pub fn is_canonical(input: &[u8]) -> Result<(), Violation> {
+    let options = DecodeOptions::new().with_max_depth(usize::MAX);
+    validate_with_options(input, options).map_err(|error| Violation {
+        position: error.position().unwrap_or(input.len()),
+        error,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::vec;
+
+    use super::*;
+    use crate::decoding::DuplicateKeys;
+
+    fn structure_error(input: &[u8]) -> StructureError {
+        match validate(input).unwrap_err().kind() {
+            ErrorKind::StructureError(error) => error.clone(),
+            other => panic!("Unexpected error: {}", other),
+        }
+    }
+
+    #[test]
+    fn canonical_values_are_valid() {
+        for input in [
+            &b"i0e"[..],
+            b"0:",
+            b"le",
+            b"de",
+            b"d1:ad1:bli-1eee1:c3:fooe",
+        ] {
+            assert!(validate(input).is_ok(), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn errors_match_the_decoder() {
+        assert_eq!(structure_error(b""), StructureError::UnexpectedEof);
+        assert_eq!(structure_error(b"l"), StructureError::UnexpectedEof);
+        assert_eq!(
+            structure_error(b"li1ee3:foo"),
+            StructureError::TrailingData { position: 5 }
+        );
+        assert_eq!(
+            structure_error(b"d1:bi1e1:bi2ee"),
+            StructureError::UnsortedKeys { position: 7 }
+        );
+        assert_eq!(
+            structure_error(b"d1:ai1eli2eee"),
+            StructureError::NonStringKey {
+                found: "List",
+                position: 7,
+            }
+        );
+        assert_eq!(
+            structure_error(b"d1:ae"),
+            StructureError::MissingValue { position: 4 }
+        );
+        assert_eq!(
+            structure_error(b"i01e"),
+            StructureError::UnexpectedCharacter {
+                expected: "'e'".into(),
+                found: '1',
+                position: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn options_are_respected() {
+        let duplicate = b"d1:ai1e1:ai2ee";
+        let options = DecodeOptions::new().with_duplicate_keys(DuplicateKeys::LastWins);
+        assert!(validate_with_options(duplicate, options).is_ok());
+
+        let unsorted = b"d1:bi1e1:ai2ee";
+        let options = DecodeOptions::new().with_unsorted_keys();
+        assert!(validate_with_options(unsorted, options.clone()).is_ok());
+        assert!(validate_with_options(duplicate, options.clone()).is_err());
+        assert!(validate_with_options(b"", options.clone()).is_err());
+        assert!(validate_with_options(b"lee", options).is_err());
+
+        let options = DecodeOptions::new().with_max_depth(2);
+        assert!(validate_with_options(b"llee", options.clone()).is_ok());
+        assert!(validate_with_options(b"llleee", options).is_err());
+
+        let options = DecodeOptions::new().with_max_tokens(3);
+        assert!(validate_with_options(b"li1ee", options.clone()).is_ok());
+        assert!(validate_with_options(b"li1ei2ee", options).is_err());
+    }
+
+    #[test]
+    fn is_canonical_reports_first_violation() {
+        let mut deep = vec![b'l'; 1000];
+        deep.extend(vec![b'e'; 1000]);
+        assert!(is_canonical(&deep).is_ok());
+
+        assert_eq!(is_canonical(b"").unwrap_err().position(), 0);
+        assert_eq!(is_canonical(b"li1e").unwrap_err().position(), 4);
+        assert_eq!(is_canonical(b"d1:bi1e1:ai-0ee").unwrap_err().position(), 7);
+        assert_eq!(is_canonical(b"02:ab").unwrap_err().position(), 1);
+        assert_eq!(is_canonical(b"i1ei2e").unwrap_err().position(), 3);
+    }
+
+    #[test]
+    fn complete_len_waits_for_every_prefix() {
+        let message = b"d1:ad2:id20:abcdefghij0123456789e1:q4:ping1:t2:aa1:y1:qe";
+        for end in 0..message.len() {
+            assert_eq!(complete_len(&message[..end]).unwrap(), None, "{}", end);
+        }
+        assert_eq!(complete_len(message).unwrap(), Some(message.len()));
+
+        let mut framed = message.to_vec();
+        framed.extend_from_slice(&message[..10]);
+        assert_eq!(complete_len(&framed).unwrap(), Some(message.len()));
+    }
+
+    #[test]
+    fn complete_len_respects_options() {
+        let unsorted = b"d1:bi1e1:ai2eei3e";
+        assert!(complete_len(unsorted).is_err());
+
+        let options = DecodeOptions::new().with_unsorted_keys();
+        assert_eq!(
+            complete_len_with_options(unsorted, options.clone()).unwrap(),
+            Some(14)
+        );
+        assert_eq!(
+            complete_len_with_options(&unsorted[..13], options).unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,165 @@
+use crate::decoding::{DecodeOptions, Decoder, Error};
+
+/// Callbacks for the tokens of a document, see [`decode_with_visitor`]
+///
+/// All callbacks do nothing by default, so only the interesting ones need to be
+/// implemented. Returning an error from a callback stops decoding with that error.
+pub trait Visitor {
+    /// The start of a list. Its items follow until the matching [`Visitor::on_end`].
+    fn on_list_start(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// The start of a dictionary. Its keys and values follow alternately until the
+    /// matching [`Visitor::on_end`].
+    fn on_dict_start(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// A dictionary key
+    fn on_key(&mut self, _key: &[u8]) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// An integer, as its unparsed text
+    fn on_int(&mut self, _value: &str) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// A byte string which isn't a dictionary key
+    fn on_bytes(&mut self, _value: &[u8]) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// The end of the innermost list or dictionary
+    fn on_end(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Decode the first value of the buffer, reporting its tokens to the visitor as they are
+/// read, without building any intermediate structures.
+///
+/// ```
+/// use bendy::decoding::{decode_with_visitor, Error, Visitor};
+///
+/// /// Sums up the lengths of all files of a torrent
+/// #[derive(Default)]
+/// struct TotalLength {
+///     in_length: bool,
+///     total: u64,
+/// }
+///
+/// impl Visitor for TotalLength {
+///     fn on_key(&mut self, key: &[u8]) -> Result<(), Error> {
+///         self.in_length = key == b"length";
+///         Ok(())
+///     }
+///
+///     fn on_int(&mut self, value: &str) -> Result<(), Error> {
+///         if self.in_length {
+///             self.total += value.parse::<u64>()?;
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// let info = b"d5:filesld6:lengthi3eed6:lengthi4eee4:name3:dire";
+/// let mut visitor = TotalLength::default();
+/// decode_with_visitor(info, &mut visitor).unwrap();
+/// assert_eq!(visitor.total, 7);
+/// ```
+pub fn decode_with_visitor<V: Visitor + ?Sized>(buf: &[u8], visitor: &mut V) -> Result<(), Error> {
+    decode_with_visitor_with_options(buf, visitor, DecodeOptions::default())
+}
+
+/// Decode the first value of the buffer like [`decode_with_visitor`], validating it as
+/// configured by the given options
+pub fn decode_with_visitor_with_options<V: Visitor + ?Sized>(
+    buf: &[u8],
+    visitor: &mut V,
+    options: DecodeOptions,
+) -> Result<(), Error> {
+    Decoder::new_with_options(buf, options).visit(visitor)
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::{format, string::String, vec, vec::Vec};
+
+    use super::*;
+
+    /// Records every callback as a line
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl Visitor for Recorder {
+        fn on_list_start(&mut self) -> Result<(), Error> {
+            self.0.push("list".into());
+            Ok(())
+        }
+
+        fn on_dict_start(&mut self) -> Result<(), Error> {
+            self.0.push("dict".into());
+            Ok(())
+        }
+
+        fn on_key(&mut self, key: &[u8]) -> Result<(), Error> {
+            self.0.push(format!("key {}", String::from_utf8_lossy(key)));
+            Ok(())
+        }
+
+        fn on_int(&mut self, value: &str) -> Result<(), Error> {
+            self.0.push(format!("int {}", value));
+            Ok(())
+        }
+
+        fn on_bytes(&mut self, value: &[u8]) -> Result<(), Error> {
+            if value == b"stop" {
+                return Err(Error::unexpected_token("anything else", "stop"));
+            }
+            self.0
+                .push(format!("bytes {}", String::from_utf8_lossy(value)));
+            Ok(())
+        }
+
+        fn on_end(&mut self) -> Result<(), Error> {
+            self.0.push("end".into());
+            Ok(())
+        }
+    }
+
+    fn record(input: &[u8], options: DecodeOptions) -> Result<Vec<String>, Error> {
+        let mut recorder = Recorder::default();
+        decode_with_visitor_with_options(input, &mut recorder, options)?;
+        Ok(recorder.0)
+    }
+
+    #[test]
+    fn tokens_should_be_visited_in_order() {
+        let lines = record(b"d3:bard1:x1:ye3:fooli1e0:eei2e", DecodeOptions::default());
+        assert_eq!(
+            lines.unwrap(),
+            vec![
+                "dict", "key bar", "dict", "key x", "bytes y", "end", "key foo", "list", "int 1",
+                "bytes ", "end", "end",
+            ]
+        );
+        assert_eq!(
+            record(b"i-3e", DecodeOptions::default()).unwrap(),
+            vec!["int -3"]
+        );
+    }
+
+    #[test]
+    fn invalid_input_and_visitor_errors_should_fail() {
+        let options = DecodeOptions::default;
+        assert!(record(b"", options()).is_err());
+        assert!(record(b"li1e", options()).is_err());
+        assert!(record(b"d1:b0:1:a0:e", options()).is_err());
+        assert!(record(b"l4:stope", options()).is_err());
+        assert!(record(b"i1ei2e", options()).is_ok());
+        assert!(record(b"i1ei2e", options().with_strict_eof()).is_err());
+    }
+}
@@ -37,6 +37,9 @@
 //! # }
 //! ```
 //!
+//! With the `derive` feature, `#[derive(ToBencode)]` generates the same implementation; see
+//! the [`decoding`](crate::decoding) module for how structs are represented.
+//!
 //! Then, messages can be serialized using [`ToBencode::to_bencode`]:
 //!
 //! ```
@@ -116,14 +119,34 @@
 //! [`UnsortedKeys`]: self::Error#UnsortedKeys
 //! [`NestingTooDeep`]: self::Error#NestingTooDeep
 
+#[cfg(feature = "futures-io")]
+mod async_encoder;
 mod encoder;
 mod error;
+mod output;
 mod printable_integer;
 mod to_bencode;
+#[cfg(feature = "std")]
+mod writer;
 
+#[cfg(feature = "futures-io")]
+pub use self::async_encoder::AsyncEncoder;
+#[cfg(feature = "digest")]
+pub use self::output::DigestSink;
+#[cfg(feature = "sha-1")]
+pub use self::output::Sha1Sink;
+#[cfg(feature = "sha2")]
+pub use self::output::Sha256Sink;
+#[cfg(feature = "std")]
+pub use self::to_bencode::AsCompact;
+#[cfg(feature = "std")]
+pub use self::writer::WriterEncoder;
 pub use self::{
     encoder::{Encoder, SingleItemEncoder, SortedDictEncoder, UnsortedDictEncoder},
     error::{Error, ErrorKind},
+    output::Output,
     printable_integer::PrintableInteger,
     to_bencode::{AsString, ToBencode},
 };
+#[cfg(feature = "derive")]
+pub use bendy_derive::ToBencode;
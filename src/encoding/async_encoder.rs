@@ -0,0 +1,362 @@
+use futures_util::io::{AsyncWrite, AsyncWriteExt};
+#[cfg(feature = "tokio")]
+use tokio_util::compat::{Compat, TokioAsyncWriteCompatExt};
+
+use crate::{
+    encoding::{Encoder, Error, PrintableInteger, ToBencode},
+    state_tracker::{StateTracker, Token},
+};
+
+/// The default number of buffered bytes after which the output is written out.
+const DEFAULT_FLUSH_THRESHOLD: usize = 8 * 1024;
+
+/// An encoder writing to an [`AsyncWrite`] of `futures-io`, as used by async-std and smol.
+/// With the `tokio` feature, [`AsyncEncoder::from_tokio`] writes to a tokio `AsyncWrite`.
+///
+/// Unlike [`Encoder`], containers aren't emitted through callbacks but are opened with
+/// [`AsyncEncoder::begin_list`] or [`AsyncEncoder::begin_dict`] and closed with
+/// [`AsyncEncoder::end`]. Output is collected in a small buffer which is written to the
+/// underlying writer whenever it grows past the flush threshold, so arbitrarily large
+/// structures can be streamed without holding them in memory.
+///
+/// The same validation as for [`Encoder`] applies; once an error occurred, all further
+/// calls fail with the same error.
+///
+/// ```
+/// # use bendy::encoding::{AsyncEncoder, Error};
+/// # use tokio_ as tokio;
+/// #
+/// # async fn run() -> Result<(), Error> {
+/// let mut encoder = AsyncEncoder::new(Vec::new());
+/// encoder.begin_dict().await?;
+/// encoder.emit_str("peers").await?;
+/// encoder.begin_list().await?;
+/// for port in 6881..6884 {
+///     encoder.emit_int(port).await?;
+/// }
+/// encoder.end().await?;
+/// encoder.end().await?;
+///
+/// let output = encoder.finish().await?;
+/// assert_eq!(output, b"d5:peersli6881ei6882ei6883eee");
+/// # Ok(())
+/// # }
+/// #
+/// # tokio::runtime::Builder::new_current_thread()
+/// #     .build()
+/// #     .unwrap()
+/// #     .block_on(run())
+/// #     .unwrap();
+/// ```
+#[derive(Debug)]
+pub struct AsyncEncoder<W> {
+    writer: W,
+    state: StateTracker<Vec<u8>, Error>,
+    buffer: Vec<u8>,
+    flush_threshold: usize,
+    /// The number of bytes already written to the writer
+    written: usize,
+}
+
+impl<W: AsyncWrite + Unpin> AsyncEncoder<W> {
+    /// Create a new encoder writing to the given writer
+    pub fn new(writer: W) -> Self {
+        AsyncEncoder {
+            writer,
+            state: StateTracker::new(),
+            buffer: Vec::new(),
+            flush_threshold: DEFAULT_FLUSH_THRESHOLD,
+            written: 0,
+        }
+    }
+
+    /// Set the max depth of the encoded object
+    #[must_use]
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.state.set_max_depth(max_depth);
+        self
+    }
+
+    /// Set the number of buffered bytes after which the output is written to the
+    /// underlying writer. A threshold of zero writes every token immediately.
+    #[must_use]
+    pub fn with_flush_threshold(mut self, flush_threshold: usize) -> Self {
+        self.flush_threshold = flush_threshold;
+        self
+    }
+
+    /// Emit an arbitrary encodable object. The object is encoded into memory in one
+    /// go, so use the streaming methods for large containers.
+    pub async fn emit<E: ToBencode>(&mut self, value: E) -> Result<(), Error> {
+        self.state.check_error()?;
+
+        let mut encoder = Encoder::new().with_max_depth(self.state.remaining_depth());
+        let encoded = self
+            .state
+            .latch_err(encoder.emit(value).and_then(|()| encoder.get_output()))?;
+
+        // Strings need to be observed as such, as they may be dict keys. Anything else
+        // was validated by the inner encoder and only has to count as a single value.
+        match encoded.iter().position(|&b| b == b':') {
+            Some(colon) if encoded[0].is_ascii_digit() => {
+                let token = Token::String(&encoded[colon + 1..]);
+                self.state.observe_token(&token, self.position())?;
+            },
+            _ => self.state.observe_token(&Token::Num(""), self.position())?,
+        }
+
+        self.buffer.extend_from_slice(&encoded);
+        self.write_if_full().await
+    }
+
+    /// Emit an integer
+    pub async fn emit_int<T: PrintableInteger>(&mut self, value: T) -> Result<(), Error> {
+        self.state.check_error()?;
+        self.state.observe_token(&Token::Num(""), self.position())?;
+        self.buffer.push(b'i');
+        self.buffer.extend_from_slice(value.to_string().as_bytes());
+        self.buffer.push(b'e');
+        self.write_if_full().await
+    }
+
+    /// Emit a string
+    pub async fn emit_str(&mut self, value: &str) -> Result<(), Error> {
+        self.emit_bytes(value.as_bytes()).await
+    }
+
+    /// Emit a byte array
+    pub async fn emit_bytes(&mut self, value: &[u8]) -> Result<(), Error> {
+        self.emit_token(Token::String(value)).await
+    }
+
+    /// Begin a list. Every value emitted until the matching [`AsyncEncoder::end`] is
+    /// an item of the list.
+    pub async fn begin_list(&mut self) -> Result<(), Error> {
+        self.emit_token(Token::List).await
+    }
+
+    /// Begin a dictionary. Until the matching [`AsyncEncoder::end`], keys and values
+    /// need to be emitted alternately, with the keys in sorted order.
+    pub async fn begin_dict(&mut self) -> Result<(), Error> {
+        self.emit_token(Token::Dict).await
+    }
+
+    /// End the innermost open list or dictionary
+    pub async fn end(&mut self) -> Result<(), Error> {
+        self.emit_token(Token::End).await
+    }
+
+    /// Write all buffered output to the underlying writer and flush it
+    pub async fn flush(&mut self) -> Result<(), Error> {
+        self.state.check_error()?;
+        self.write_buffer().await?;
+        let result = self.writer.flush().await.map_err(Error::from);
+        self.state.latch_err(result)
+    }
+
+    /// Check that a complete object was written, flush all output and return the
+    /// underlying writer
+    pub async fn finish(mut self) -> Result<W, Error> {
+        self.state.observe_eof()?;
+        self.flush().await?;
+        Ok(self.writer)
+    }
+
+    async fn emit_token(&mut self, token: Token<'_>) -> Result<(), Error> {
+        self.state.check_error()?;
+        self.state.observe_token(&token, self.position())?;
+        match token {
+            Token::List => self.buffer.push(b'l'),
+            Token::Dict => self.buffer.push(b'd'),
+            Token::String(s) => {
+                self.buffer
+                    .extend_from_slice(s.len().to_string().as_bytes());
+                self.buffer.push(b':');
+                self.buffer.extend_from_slice(s);
+            },
+            Token::Num(num) => {
+                self.buffer.push(b'i');
+                self.buffer.extend_from_slice(num.as_bytes());
+                self.buffer.push(b'e');
+            },
+            Token::End => self.buffer.push(b'e'),
+        }
+        self.write_if_full().await
+    }
+
+    async fn write_if_full(&mut self) -> Result<(), Error> {
+        if self.buffer.len() >= self.flush_threshold {
+            self.write_buffer().await?;
+        }
+        Ok(())
+    }
+
+    async fn write_buffer(&mut self) -> Result<(), Error> {
+        let result = self
+            .writer
+            .write_all(&self.buffer)
+            .await
+            .map_err(Error::from);
+        self.state.latch_err(result)?;
+        self.written += self.buffer.len();
+        self.buffer.clear();
+        Ok(())
+    }
+
+    /// The number of bytes emitted so far, whether written or still buffered
+    fn position(&self) -> usize {
+        self.written + self.buffer.len()
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<W: tokio_::io::AsyncWrite + Unpin> AsyncEncoder<Compat<W>> {
+    /// Create a new encoder writing to the given tokio writer. [`AsyncEncoder::finish`]
+    /// returns it wrapped in a `Compat`, whose `into_inner` unwraps it.
+    ///
+    /// ```
+    /// # use bendy::encoding::AsyncEncoder;
+    /// # use tokio_ as tokio;
+    /// use tokio::io::AsyncReadExt;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let (socket, mut peer) = tokio::io::duplex(64);
+    /// let mut encoder = AsyncEncoder::from_tokio(socket);
+    /// encoder.emit("ping").await.unwrap();
+    /// drop(encoder.finish().await.unwrap());
+    ///
+    /// let mut received = Vec::new();
+    /// peer.read_to_end(&mut received).await.unwrap();
+    /// assert_eq!(received, b"4:ping");
+    /// # });
+    /// ```
+    pub fn from_tokio(writer: W) -> Self {
+        Self::new(writer.compat_write())
+    }
+}
+
+impl<W> AsyncEncoder<W> {
+    /// Get a reference to the underlying writer. Output may still be buffered in the
+    /// encoder until the next flush.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::{
+        future::Future,
+        io,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        tokio_::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    /// A writer recording the size of every write
+    #[derive(Default)]
+    struct ChunkRecorder {
+        chunks: Vec<Vec<u8>>,
+    }
+
+    impl AsyncWrite for ChunkRecorder {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            self.chunks.push(buf.to_vec());
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn simple_encoding_works() {
+        let output = block_on(async {
+            let mut encoder = AsyncEncoder::new(Vec::new());
+            encoder.begin_dict().await?;
+            encoder.emit_str("bar").await?;
+            encoder.emit_int(25).await?;
+            encoder.emit_str("foo").await?;
+            encoder.emit(vec!["baz", "qux"]).await?;
+            encoder.end().await?;
+            encoder.finish().await
+        })
+        .unwrap();
+
+        assert_eq!(output, b"d3:bari25e3:fool3:baz3:quxee");
+    }
+
+    #[test]
+    fn emitted_strings_can_be_keys() {
+        let output = block_on(async {
+            let mut encoder = AsyncEncoder::new(Vec::new());
+            encoder.begin_dict().await?;
+            encoder.emit("a").await?;
+            encoder.emit(1).await?;
+            encoder.end().await?;
+            encoder.finish().await
+        })
+        .unwrap();
+
+        assert_eq!(output, b"d1:ai1ee");
+    }
+
+    #[test]
+    fn unsorted_keys_are_rejected() {
+        let result = block_on(async {
+            let mut encoder = AsyncEncoder::new(Vec::new());
+            encoder.begin_dict().await?;
+            encoder.emit_str("foo").await?;
+            encoder.emit_int(1).await?;
+            encoder.emit_str("bar").await
+        });
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn incomplete_output_is_rejected() {
+        let result = block_on(async {
+            let mut encoder = AsyncEncoder::new(Vec::new());
+            encoder.begin_list().await?;
+            encoder.finish().await
+        });
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn output_should_be_written_incrementally() {
+        let recorder = block_on(async {
+            let mut encoder = AsyncEncoder::new(ChunkRecorder::default()).with_flush_threshold(4);
+            encoder.begin_list().await?;
+            for _ in 0..4 {
+                encoder.emit_int(100).await?;
+                assert!(!encoder.get_ref().chunks.is_empty());
+            }
+            encoder.end().await?;
+            encoder.finish().await
+        })
+        .unwrap();
+
+        assert!(recorder.chunks.len() > 1);
+        assert_eq!(recorder.chunks.concat(), b"li100ei100ei100ei100ee");
+    }
+}
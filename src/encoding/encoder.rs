@@ -2,7 +2,6 @@
 use alloc::{
     borrow::ToOwned,
     collections::BTreeMap,
-    format,
     string::{String, ToString},
     vec::Vec,
 };
@@ -10,16 +9,24 @@ use alloc::{
 use std::{collections::BTreeMap, vec::Vec};
 
 use crate::{
-    encoding::{Error, PrintableInteger, ToBencode},
+    encoding::{output::private::AsDynOutput, Error, Output, PrintableInteger, ToBencode},
     state_tracker::{StateTracker, StructureError, Token},
+    value::Value,
 };
 
 /// The actual encoder. Unlike the decoder, this is not zero-copy, as that would
 /// result in a horrible interface
+///
+/// By default, the output is collected in a `Vec<u8>`; use [`Encoder::with_output`] to
+/// write it into any other [`Output`] instead.
 #[derive(Default, Debug)]
-pub struct Encoder {
+pub struct Encoder<O: ?Sized = Vec<u8>> {
     state: StateTracker<Vec<u8>, Error>,
-    output: Vec<u8>,
+    /// The number of bytes written so far, reported as the position of errors
+    written: usize,
+    // This needs to be the last field so that encoders can be coerced to use a type
+    // erased output
+    output: O,
 }
 
 impl Encoder {
@@ -27,6 +34,17 @@ impl Encoder {
     pub fn new() -> Self {
         <Self as Default>::default()
     }
+}
+
+impl<O: Output> Encoder<O> {
+    /// Create a new encoder writing into the given output
+    pub fn with_output(output: O) -> Self {
+        Encoder {
+            state: StateTracker::new(),
+            written: 0,
+            output,
+        }
+    }
 
     /// Set the max depth of the encoded object
     #[must_use]
@@ -35,29 +53,107 @@ impl Encoder {
         self
     }
 
+    /// Allow the keys of dictionaries emitted with [`Encoder::emit_dict`] to be in any
+    /// order, and write them in the order they were given instead of failing with
+    /// [`UnsortedKeys`]. Duplicate keys are still rejected.
+    ///
+    /// The output of such an encoder is not canonical bencode, so only use this to talk
+    /// to peers which insist on a specific key order. Dictionaries emitted with
+    /// [`Encoder::emit_and_sort_dict`] are sorted as usual.
+    ///
+    /// ```
+    /// # use bendy::encoding::{Encoder, Error};
+    /// #
+    /// # fn main() -> Result<(), Error> {
+    /// let mut encoder = Encoder::new().with_preserved_key_order();
+    /// encoder.emit_dict(|mut e| {
+    ///     e.emit_pair(b"b", 2)?;
+    ///     e.emit_pair(b"a", "foo")
+    /// })?;
+    ///
+    /// assert_eq!(encoder.get_output()?, b"d1:bi2e1:a3:fooe");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`UnsortedKeys`]: crate::state_tracker::StructureError::UnsortedKeys
+    #[must_use]
+    pub fn with_preserved_key_order(mut self) -> Self {
+        self.state.set_allow_unsorted_keys(true);
+        self
+    }
+
+    /// Return the output, if all objects written are complete
+    pub fn get_output(mut self) -> Result<O, Error> {
+        self.state.observe_eof()?;
+        Ok(self.output)
+    }
+
+    /// The number of lists and dictionaries which are still open
+    #[cfg(feature = "std")]
+    pub(crate) fn depth(&self) -> usize {
+        self.state.depth()
+    }
+
+    /// The output written to
+    #[cfg(feature = "std")]
+    pub(crate) fn output(&self) -> &O {
+        &self.output
+    }
+
+    /// The output written to, for changing its configuration
+    #[cfg(feature = "std")]
+    pub(crate) fn output_mut(&mut self) -> &mut O {
+        &mut self.output
+    }
+
+    /// The number of bytes written so far
+    #[cfg(all(test, feature = "std"))]
+    pub(crate) fn written(&self) -> usize {
+        self.written
+    }
+
+    /// Run the given operation on the output, latching any error it reports
+    #[cfg(feature = "std")]
+    pub(crate) fn with_output_mut<F>(&mut self, operation: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut O) -> Result<(), Error>,
+    {
+        self.state.check_error()?;
+        let result = operation(&mut self.output);
+        self.state.latch_err(result)
+    }
+}
+
+impl<O: Output + AsDynOutput + ?Sized> Encoder<O> {
     /// Emit a single token to the encoder
     pub(crate) fn emit_token(&mut self, token: Token) -> Result<(), Error> {
         self.state.check_error()?;
-        self.state.observe_token(&token)?;
+        self.state.observe_token(&token, self.written)?;
         match token {
-            Token::List => self.output.push(b'l'),
-            Token::Dict => self.output.push(b'd'),
+            Token::List => self.write_output(b"l"),
+            Token::Dict => self.write_output(b"d"),
             Token::String(s) => {
-                // Writing to a vec can't fail
                 let length = s.len().to_string();
-                self.output.extend_from_slice(length.as_bytes());
-                self.output.push(b':');
-                self.output.extend_from_slice(s);
+                self.write_output(length.as_bytes())?;
+                self.write_output(b":")?;
+                self.write_output(s)
             },
             Token::Num(num) => {
                 // Alas, this doesn't verify that the given number is valid
-                self.output.push(b'i');
-                self.output.extend_from_slice(num.as_bytes());
-                self.output.push(b'e');
+                self.write_output(b"i")?;
+                self.write_output(num.as_bytes())?;
+                self.write_output(b"e")
             },
-            Token::End => self.output.push(b'e'),
+            Token::End => self.write_output(b"e"),
         }
+    }
 
+    /// Write raw bytes to the output, latching any error it reports
+    fn write_output(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        let result = self.output.write_bytes(bytes);
+        self.state.latch_err(result)?;
+        self.written += bytes.len();
         Ok(())
     }
 
@@ -73,7 +169,7 @@ impl Encoder {
     {
         let mut value_written = false;
         let ret = value_cb(SingleItemEncoder {
-            encoder: self,
+            encoder: O::as_dyn_encoder(self),
             value_written: &mut value_written,
         });
 
@@ -82,9 +178,7 @@ impl Encoder {
         if !value_written {
             return self
                 .state
-                .latch_err(Err(Error::from(StructureError::invalid_state(
-                    "No value was emitted",
-                ))));
+                .latch_err(Err(Error::from(StructureError::NoValueEmitted)));
         }
 
         Ok(())
@@ -99,11 +193,10 @@ impl Encoder {
         // We observe an int here, as we need something that isn't a string (and therefore
         // possibly valid as a key) but we also want to require as few state transitions as
         // possible (for performance)
-        self.state.observe_token(&Token::Num(""))?;
-        self.output.push(b'i');
-        self.output.extend_from_slice(value.to_string().as_bytes());
-        self.output.push(b'e');
-        Ok(())
+        self.state.observe_token(&Token::Num(""), self.written)?;
+        self.write_output(b"i")?;
+        self.write_output(value.to_string().as_bytes())?;
+        self.write_output(b"e")
     }
 
     /// Emit a string
@@ -141,7 +234,9 @@ impl Encoder {
         F: FnOnce(SortedDictEncoder) -> Result<(), Error>,
     {
         self.emit_token(Token::Dict)?;
-        content_cb(SortedDictEncoder { encoder: self })?;
+        content_cb(SortedDictEncoder {
+            encoder: O::as_dyn_encoder(self),
+        })?;
         self.emit_token(Token::End)
     }
 
@@ -163,7 +258,7 @@ impl Encoder {
     /// ```
     pub fn emit_list<F>(&mut self, list_cb: F) -> Result<(), Error>
     where
-        F: FnOnce(&mut Encoder) -> Result<(), Error>,
+        F: FnOnce(&mut Self) -> Result<(), Error>,
     {
         self.emit_token(Token::List)?;
         list_cb(self)?;
@@ -199,17 +294,15 @@ impl Encoder {
         self.end_unsorted_dict(encoder)
     }
 
-    /// Return the encoded string, if all objects written are complete
-    pub fn get_output(mut self) -> Result<Vec<u8>, Error> {
-        self.state.observe_eof()?;
-        Ok(self.output)
-    }
-
     pub(crate) fn begin_unsorted_dict(&mut self) -> Result<UnsortedDictEncoder, Error> {
         // emit the dict token so that a pre-existing state error is reported early
         self.emit_token(Token::Dict)?;
 
-        Ok(UnsortedDictEncoder::new(self.state.remaining_depth()))
+        Ok(UnsortedDictEncoder::new(
+            self.state.remaining_depth(),
+            self.state.allows_unsorted_keys(),
+            self.written,
+        ))
     }
 
     pub(crate) fn end_unsorted_dict(&mut self, encoder: UnsortedDictEncoder) -> Result<(), Error> {
@@ -218,8 +311,8 @@ impl Encoder {
         for (k, v) in content {
             self.emit_bytes(&k)?;
             // We know that the output is a single object by construction
-            self.state.observe_token(&Token::Num(""))?;
-            self.output.extend_from_slice(&v);
+            self.state.observe_token(&Token::Num(""), self.written)?;
+            self.write_output(&v)?;
         }
 
         self.emit_token(Token::End)?;
@@ -232,7 +325,7 @@ impl Encoder {
 /// for usage examples; the only difference between these classes is
 /// that `SingleItemEncoder` can only be used once.
 pub struct SingleItemEncoder<'a> {
-    encoder: &'a mut Encoder,
+    encoder: &'a mut Encoder<dyn Output + 'a>,
     /// Whether we attempted to write a value to the encoder. The value
     /// of the referent of this field is meaningless if the encode method
     /// failed.
@@ -274,7 +367,7 @@ impl<'a> SingleItemEncoder<'a> {
     /// Emit an arbitrary list
     pub fn emit_list<F>(self, list_cb: F) -> Result<(), Error>
     where
-        F: FnOnce(&mut Encoder) -> Result<(), Error>,
+        F: FnOnce(&mut Encoder<dyn Output + 'a>) -> Result<(), Error>,
     {
         *self.value_written = true;
         self.encoder.emit_list(list_cb)
@@ -319,7 +412,7 @@ impl<'a> SingleItemEncoder<'a> {
 
 /// Encodes a map with pre-sorted keys
 pub struct SortedDictEncoder<'a> {
-    encoder: &'a mut Encoder,
+    encoder: &'a mut Encoder<dyn Output + 'a>,
 }
 
 impl<'a> SortedDictEncoder<'a> {
@@ -350,14 +443,19 @@ pub struct UnsortedDictEncoder {
     content: BTreeMap<Vec<u8>, Vec<u8>>,
     error: Result<(), Error>,
     remaining_depth: usize,
+    preserve_key_order: bool,
+    /// Where the entries will be written in the output of the parent encoder
+    position: usize,
 }
 
 impl UnsortedDictEncoder {
-    pub(crate) fn new(remaining_depth: usize) -> Self {
+    pub(crate) fn new(remaining_depth: usize, preserve_key_order: bool, position: usize) -> Self {
         Self {
             content: BTreeMap::new(),
             error: Ok(()),
             remaining_depth,
+            preserve_key_order,
+            position,
         }
     }
 
@@ -377,6 +475,9 @@ impl UnsortedDictEncoder {
         let mut value_written = false;
 
         let mut encoder = Encoder::new().with_max_depth(self.remaining_depth);
+        if self.preserve_key_order {
+            encoder = encoder.with_preserved_key_order();
+        }
 
         let ret = value_cb(SingleItemEncoder {
             encoder: &mut encoder,
@@ -389,11 +490,9 @@ impl UnsortedDictEncoder {
         }
 
         if !value_written {
-            self.error = Err(Error::from(StructureError::InvalidState(
-                "No value was emitted".to_owned(),
-            )));
+            self.error = Err(Error::from(StructureError::NoValueEmitted));
         } else {
-            self.error = encoder.state.observe_eof().map_err(Error::from);
+            self.error = encoder.state.observe_eof();
         }
 
         if self.error.is_err() {
@@ -407,6 +506,41 @@ impl UnsortedDictEncoder {
         self.save_pair(key, encoded_object)
     }
 
+    /// Emit the entries of a value which encodes to a dictionary as entries of this
+    /// dictionary. This is how `#[bendy(flatten)]` fields of derived implementations are
+    /// encoded.
+    pub fn emit_flattened<E>(&mut self, value: E) -> Result<(), Error>
+    where
+        E: ToBencode,
+    {
+        if self.error.is_err() {
+            return self.error.clone();
+        }
+
+        // The entries of the flattened dictionary end up on the level of this one
+        let mut encoder = Encoder::new().with_max_depth(self.remaining_depth + 1);
+        let encoded = match encoder.emit(value).and_then(|()| encoder.get_output()) {
+            Ok(encoded) => encoded,
+            Err(error) => {
+                self.error = Err(error.clone());
+                return Err(error);
+            },
+        };
+
+        match Value::decode(&encoded) {
+            Ok(Value::Dict(entries)) => {
+                for (key, value) in entries {
+                    self.emit_pair(&key, value)?;
+                }
+                Ok(())
+            },
+            _ => {
+                self.error = Err(Error::from(StructureError::FlattenedNotDict));
+                self.error.clone()
+            },
+        }
+    }
+
     #[cfg(feature = "serde")]
     pub(crate) fn remaining_depth(&self) -> usize {
         self.remaining_depth
@@ -429,10 +563,10 @@ impl UnsortedDictEncoder {
         let vacancy = match self.content.entry(unencoded_key.to_owned()) {
             Entry::Vacant(vacancy) => vacancy,
             Entry::Occupied(occupation) => {
-                self.error = Err(Error::from(StructureError::InvalidState(format!(
-                    "Duplicate key {}",
-                    String::from_utf8_lossy(occupation.key())
-                ))));
+                self.error = Err(Error::from(StructureError::DuplicateKey {
+                    key: String::from_utf8_lossy(occupation.key()).into_owned(),
+                    position: self.position,
+                }));
                 return self.error.clone();
             },
         };
@@ -474,9 +608,110 @@ mod test {
         );
     }
 
+    #[test]
+    fn unsorted_keys_are_rejected_by_default() {
+        let mut encoder = Encoder::new();
+        let result = encoder.emit_dict(|mut e| {
+            e.emit_pair(b"foo", 1)?;
+            e.emit_pair(b"bar", 2)
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn preserved_key_order_keeps_caller_order() {
+        let mut encoder = Encoder::new().with_preserved_key_order();
+        encoder
+            .emit_dict(|mut e| {
+                e.emit_pair(b"foo", 1)?;
+                e.emit_pair_with(b"bar", |e| {
+                    e.emit_dict(|mut e| {
+                        e.emit_pair(b"z", 1)?;
+                        e.emit_pair(b"a", 2)
+                    })
+                })
+            })
+            .expect("Unsorted keys should be accepted");
+        assert_eq!(
+            &encoder.get_output().unwrap(),
+            &b"d3:fooi1e3:bard1:zi1e1:ai2eee"
+        );
+    }
+
+    #[test]
+    fn preserved_key_order_applies_within_sorted_dicts() {
+        let mut encoder = Encoder::new().with_preserved_key_order();
+        encoder
+            .emit_and_sort_dict(|e| {
+                e.emit_pair(b"b", 1)?;
+                e.emit_pair_with(b"a", |e| {
+                    e.emit_dict(|mut e| {
+                        e.emit_pair(b"z", 1)?;
+                        e.emit_pair(b"y", 2)
+                    })
+                })
+            })
+            .expect("Unsorted nested keys should be accepted");
+        assert_eq!(
+            &encoder.get_output().unwrap(),
+            &b"d1:ad1:zi1e1:yi2ee1:bi1ee"
+        );
+    }
+
+    #[test]
+    fn preserved_key_order_rejects_duplicate_keys() {
+        let mut encoder = Encoder::new().with_preserved_key_order();
+        let result = encoder.emit_dict(|mut e| {
+            e.emit_pair(b"foo", 1)?;
+            e.emit_pair(b"bar", 2)?;
+            e.emit_pair(b"foo", 3)
+        });
+        assert!(result.is_err());
+
+        let mut encoder = Encoder::new().with_preserved_key_order();
+        encoder
+            .emit_list(|e| {
+                e.emit_dict(|mut e| e.emit_pair(b"foo", 1))?;
+                e.emit_dict(|mut e| e.emit_pair(b"foo", 1))
+            })
+            .expect("Keys of separate dicts don't collide");
+    }
+
     #[test]
     fn emit_cb_must_emit() {
         let mut encoder = Encoder::new();
         assert!(encoder.emit_with(|_| Ok(())).is_err());
     }
+
+    #[test]
+    fn flattened_entries_are_merged_and_sorted() {
+        let mut inner = BTreeMap::new();
+        inner.insert("a", 1);
+        inner.insert("c", 3);
+
+        let mut encoder = Encoder::new();
+        encoder
+            .emit_and_sort_dict(|e| {
+                e.emit_pair(b"b", 2)?;
+                e.emit_flattened(&inner)
+            })
+            .unwrap();
+        assert_eq!(encoder.get_output().unwrap(), b"d1:ai1e1:bi2e1:ci3ee");
+    }
+
+    #[test]
+    fn flattened_values_must_be_unique_dicts() {
+        let mut encoder = Encoder::new();
+        let result = encoder.emit_and_sort_dict(|e| e.emit_flattened(1));
+        assert!(result.is_err());
+
+        let mut inner = BTreeMap::new();
+        inner.insert("a", 1);
+        let mut encoder = Encoder::new();
+        let result = encoder.emit_and_sort_dict(|e| {
+            e.emit_pair(b"a", 2)?;
+            e.emit_flattened(&inner)
+        });
+        assert!(result.is_err());
+    }
 }
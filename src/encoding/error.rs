@@ -1,5 +1,5 @@
 #[cfg(feature = "std")]
-use std::sync::Arc;
+use std::{io, sync::Arc};
 
 use failure::Fail;
 
@@ -11,6 +11,7 @@ pub struct Error(#[fail(cause)] pub ErrorKind);
 
 /// An enumeration of potential errors that appear during bencode encoding.
 #[derive(Debug, Clone, Fail)]
+#[non_exhaustive]
 pub enum ErrorKind {
     /// Error that occurs if the serialized structure contains invalid semantics.
     #[cfg(feature = "std")]
@@ -23,6 +24,10 @@ pub enum ErrorKind {
     /// Error in the bencode structure (e.g. a missing field end separator).
     #[fail(display = "bencode encoding corrupted")]
     StructureError(#[fail(cause)] StructureError),
+    /// Error while writing the encoded output.
+    #[cfg(feature = "std")]
+    #[fail(display = "writing output failed: {}", _0)]
+    Io(Arc<io::Error>),
 }
 
 impl Error {
@@ -49,6 +54,13 @@ impl From<StructureError> for Error {
     }
 }
 
+#[cfg(feature = "std")]
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Self(ErrorKind::Io(Arc::new(error)))
+    }
+}
+
 impl From<ErrorKind> for Error {
     fn from(kind: ErrorKind) -> Self {
         Self(kind)
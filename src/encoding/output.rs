@@ -0,0 +1,191 @@
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+use crate::encoding::{Encoder, Error};
+
+/// A sink the [`Encoder`] writes the encoded bytes into.
+///
+/// The encoder only ever appends to its output, so implementations don't need to
+/// support anything but writing. `Vec<u8>` is the default output; implement this for
+/// your own types to stream the encoded bytes somewhere else instead of collecting them
+/// in memory first.
+pub trait Output {
+    /// Append the given bytes to the output
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Error>;
+}
+
+impl Output for Vec<u8> {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        // Writing to a vec can't fail
+        self.extend_from_slice(bytes);
+        Ok(())
+    }
+}
+
+impl<O: Output + ?Sized> Output for &mut O {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        (**self).write_bytes(bytes)
+    }
+}
+
+/// Hashes the encoded bytes as they are produced instead of storing them.
+///
+/// This is mostly useful to compute info-hashes, as the hash of the encoded form can be
+/// computed without holding that encoded form in memory. Any [RustCrypto] hash can be
+/// used; see [`Sha1Sink`] for an example.
+///
+/// [RustCrypto]: https://github.com/RustCrypto/hashes
+#[cfg(feature = "digest")]
+#[derive(Clone, Debug, Default)]
+pub struct DigestSink<D> {
+    digest: D,
+}
+
+#[cfg(feature = "digest")]
+impl<D: digest::Digest> DigestSink<D> {
+    /// Create a sink feeding a fresh instance of the digest
+    pub fn new() -> Self {
+        Self::from_digest(D::new())
+    }
+
+    /// Create a sink feeding the given digest, e.g. one which already hashed a prefix
+    pub fn from_digest(digest: D) -> Self {
+        DigestSink { digest }
+    }
+
+    /// Return the hash of all bytes written so far
+    pub fn finalize(self) -> digest::Output<D> {
+        self.digest.finalize()
+    }
+
+    /// Return the underlying digest
+    pub fn into_inner(self) -> D {
+        self.digest
+    }
+}
+
+#[cfg(feature = "digest")]
+impl<D: digest::Digest> Output for DigestSink<D> {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.digest.update(bytes);
+        Ok(())
+    }
+}
+
+/// A [`DigestSink`] computing the SHA-1 hash used for BitTorrent v1 info-hashes.
+///
+/// ```
+/// # use bendy::encoding::{Encoder, Error, Sha1Sink};
+/// # use sha1_::{Digest, Sha1};
+/// #
+/// # fn main() -> Result<(), Error> {
+/// let mut encoder = Encoder::with_output(Sha1Sink::new());
+/// encoder.emit_dict(|mut e| {
+///     e.emit_pair(b"length", 42)?;
+///     e.emit_pair(b"name", "example.txt")
+/// })?;
+/// let info_hash = encoder.get_output()?.finalize();
+///
+/// assert_eq!(info_hash, Sha1::digest(b"d6:lengthi42e4:name11:example.txte"));
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "sha-1")]
+pub type Sha1Sink = DigestSink<sha1_::Sha1>;
+
+/// A [`DigestSink`] computing the SHA-256 hash used for BitTorrent v2 info-hashes.
+#[cfg(feature = "sha2")]
+pub type Sha256Sink = DigestSink<sha2_::Sha256>;
+
+pub(crate) mod private {
+    use super::*;
+
+    /// Allows encoders with any output to hand out the type erased encoder used by
+    /// [`SingleItemEncoder`](crate::encoding::SingleItemEncoder), which keeps
+    /// [`ToBencode`](crate::encoding::ToBencode) independent of the output type.
+    pub trait AsDynOutput {
+        fn as_dyn_encoder(encoder: &mut Encoder<Self>) -> &mut Encoder<dyn Output + '_>;
+    }
+
+    impl<O: Output> AsDynOutput for O {
+        fn as_dyn_encoder(encoder: &mut Encoder<Self>) -> &mut Encoder<dyn Output + '_> {
+            encoder
+        }
+    }
+
+    impl<'a> AsDynOutput for dyn Output + 'a {
+        fn as_dyn_encoder(encoder: &mut Encoder<Self>) -> &mut Encoder<dyn Output + '_> {
+            encoder
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::vec;
+
+    use super::*;
+
+    use core::fmt;
+
+    /// An output which refuses to take more than a fixed number of bytes
+    struct LimitedOutput {
+        remaining: usize,
+    }
+
+    impl Output for LimitedOutput {
+        fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Error> {
+            if bytes.len() > self.remaining {
+                return Err(Error::malformed_content(fmt::Error));
+            }
+            self.remaining -= bytes.len();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn borrowed_output_receives_bytes() {
+        let mut output = Vec::new();
+        let mut encoder = Encoder::with_output(&mut output);
+        encoder
+            .emit_list(|e| {
+                e.emit_str("foo")?;
+                e.emit(vec![1, 2])
+            })
+            .unwrap();
+        encoder.get_output().unwrap();
+
+        assert_eq!(output, b"l3:fooli1ei2eee");
+    }
+
+    #[test]
+    fn output_errors_are_latched() {
+        let mut encoder = Encoder::with_output(LimitedOutput { remaining: 4 });
+        let result = encoder.emit_list(|e| {
+            e.emit_int(1)?;
+            e.emit_int(2)
+        });
+
+        assert!(result.is_err());
+        assert!(encoder.emit_int(3).is_err());
+    }
+
+    #[cfg(feature = "sha-1")]
+    #[test]
+    fn digest_sink_matches_hash_of_buffered_output() {
+        use crate::encoding::ToBencode;
+        use sha1_::{Digest, Sha1};
+
+        let value = vec!["announce", "info"];
+        let mut encoder = Encoder::with_output(Sha1Sink::new());
+        encoder.emit(&value).unwrap();
+
+        assert_eq!(
+            encoder.get_output().unwrap().finalize(),
+            Sha1::digest(value.to_bencode().unwrap())
+        );
+    }
+}
@@ -11,6 +11,7 @@ use alloc::{
 use std::{
     collections::{BTreeMap, HashMap, LinkedList, VecDeque},
     hash::{BuildHasher, Hash},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
     rc::Rc,
     sync::Arc,
 };
@@ -29,7 +30,7 @@ pub trait ToBencode {
     /// Encode this object to a byte string
     fn to_bencode(&self) -> Result<Vec<u8>, Error> {
         let mut encoder = Encoder::new().with_max_depth(Self::MAX_DEPTH);
-        encoder.emit_with(|e| self.encode(e).map_err(Error::into))?;
+        encoder.emit_with(|e| self.encode(e))?;
 
         let bytes = encoder.get_output()?;
         Ok(bytes)
@@ -40,6 +41,25 @@ pub trait ToBencode {
 #[derive(Clone, Copy, Debug, Default, Hash, Eq, PartialEq, PartialOrd, Ord)]
 pub struct AsString<I>(pub I);
 
+/// Wrapper to encode network addresses in the compact binary format of BitTorrent
+/// instead of as text: the 4 or 16 bytes of an IP address in network byte order,
+/// followed by 2 bytes of the port for socket addresses.
+///
+/// Addresses without the wrapper are encoded as text, like `127.0.0.1` or
+/// `[::1]:6881`. Both formats appear in tracker and DHT messages.
+///
+/// ```
+/// use bendy::encoding::{AsCompact, ToBencode};
+/// use std::net::SocketAddr;
+///
+/// let peer: SocketAddr = "127.0.0.1:6881".parse().unwrap();
+/// assert_eq!(peer.to_bencode().unwrap(), b"14:127.0.0.1:6881");
+/// assert_eq!(AsCompact(peer).to_bencode().unwrap(), b"6:\x7f\0\0\x01\x1a\xe1");
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Default, Hash, Eq, PartialEq, PartialOrd, Ord)]
+pub struct AsCompact<A>(pub A);
+
 // Forwarding impls
 impl<'a, E: 'a + ToBencode + Sized> ToBencode for &'a E {
     const MAX_DEPTH: usize = E::MAX_DEPTH;
@@ -54,7 +74,7 @@ impl<E: ToBencode> ToBencode for Box<E> {
     const MAX_DEPTH: usize = E::MAX_DEPTH;
 
     fn encode(&self, encoder: SingleItemEncoder) -> Result<(), Error> {
-        E::encode(&*self, encoder)
+        E::encode(self, encoder)
     }
 }
 
@@ -62,7 +82,7 @@ impl<E: ToBencode> ToBencode for Rc<E> {
     const MAX_DEPTH: usize = E::MAX_DEPTH;
 
     fn encode(&self, encoder: SingleItemEncoder) -> Result<(), Error> {
-        E::encode(&*self, encoder)
+        E::encode(self, encoder)
     }
 }
 
@@ -70,16 +90,16 @@ impl<E: ToBencode> ToBencode for Arc<E> {
     const MAX_DEPTH: usize = E::MAX_DEPTH;
 
     fn encode(&self, encoder: SingleItemEncoder) -> Result<(), Error> {
-        E::encode(&*self, encoder)
+        E::encode(self, encoder)
     }
 }
 
 // Base type impls
-impl<'a> ToBencode for &'a str {
+impl ToBencode for &str {
     const MAX_DEPTH: usize = 0;
 
     fn encode(&self, encoder: SingleItemEncoder) -> Result<(), Error> {
-        encoder.emit_str(self).map_err(Error::from)
+        encoder.emit_str(self)
     }
 }
 
@@ -87,7 +107,7 @@ impl ToBencode for String {
     const MAX_DEPTH: usize = 0;
 
     fn encode(&self, encoder: SingleItemEncoder) -> Result<(), Error> {
-        encoder.emit_str(self).map_err(Error::from)
+        encoder.emit_str(self)
     }
 }
 
@@ -97,7 +117,7 @@ macro_rules! impl_encodable_integer {
             const MAX_DEPTH: usize = 1;
 
             fn encode(&self, encoder: SingleItemEncoder) -> Result<(), Error> {
-                encoder.emit_int(*self).map_err(Error::from)
+                encoder.emit_int(*self)
             }
         }
     )*}
@@ -129,7 +149,7 @@ macro_rules! impl_encodable_iterable {
 
 impl_encodable_iterable!(Vec VecDeque LinkedList);
 
-impl<'a, ContentT> ToBencode for &'a [ContentT]
+impl<ContentT> ToBencode for &[ContentT]
 where
     ContentT: ToBencode,
 {
@@ -188,6 +208,66 @@ where
     }
 }
 
+#[cfg(feature = "std")]
+macro_rules! impl_encodable_address {
+    ($($type:ty)*) => {$(
+        impl ToBencode for $type {
+            const MAX_DEPTH: usize = 0;
+
+            fn encode(&self, encoder: SingleItemEncoder) -> Result<(), Error> {
+                encoder.emit_str(&self.to_string())
+            }
+        }
+    )*}
+}
+
+#[cfg(feature = "std")]
+impl_encodable_address!(IpAddr Ipv4Addr Ipv6Addr SocketAddr);
+
+#[cfg(feature = "std")]
+impl ToBencode for AsCompact<Ipv4Addr> {
+    const MAX_DEPTH: usize = 0;
+
+    fn encode(&self, encoder: SingleItemEncoder) -> Result<(), Error> {
+        encoder.emit_bytes(&self.0.octets())
+    }
+}
+
+#[cfg(feature = "std")]
+impl ToBencode for AsCompact<Ipv6Addr> {
+    const MAX_DEPTH: usize = 0;
+
+    fn encode(&self, encoder: SingleItemEncoder) -> Result<(), Error> {
+        encoder.emit_bytes(&self.0.octets())
+    }
+}
+
+#[cfg(feature = "std")]
+impl ToBencode for AsCompact<IpAddr> {
+    const MAX_DEPTH: usize = 0;
+
+    fn encode(&self, encoder: SingleItemEncoder) -> Result<(), Error> {
+        match self.0 {
+            IpAddr::V4(ip) => AsCompact(ip).encode(encoder),
+            IpAddr::V6(ip) => AsCompact(ip).encode(encoder),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl ToBencode for AsCompact<SocketAddr> {
+    const MAX_DEPTH: usize = 0;
+
+    fn encode(&self, encoder: SingleItemEncoder) -> Result<(), Error> {
+        let mut bytes = match self.0.ip() {
+            IpAddr::V4(ip) => ip.octets().to_vec(),
+            IpAddr::V6(ip) => ip.octets().to_vec(),
+        };
+        bytes.extend_from_slice(&self.0.port().to_be_bytes());
+        encoder.emit_bytes(&bytes)
+    }
+}
+
 impl<I> ToBencode for AsString<I>
 where
     I: AsRef<[u8]>,
@@ -237,7 +317,7 @@ mod test {
 
         fn encode(&self, encoder: SingleItemEncoder) -> Result<(), Error> {
             encoder.emit_dict(|mut e| {
-                e.emit_pair(b"bar", &self.bar)?;
+                e.emit_pair(b"bar", self.bar)?;
                 e.emit_pair(b"baz", &self.baz)?;
                 e.emit_pair(b"qux", AsString(&self.qux))?;
                 Ok(())
@@ -0,0 +1,225 @@
+use std::io::Write;
+
+use crate::{
+    encoding::{Encoder, Error, Output, ToBencode},
+    state_tracker::Token,
+};
+
+/// The default number of buffered bytes after which the output is written out.
+const DEFAULT_FLUSH_THRESHOLD: usize = 8 * 1024;
+
+/// An encoder writing to a [`Write`] as values are pushed, for collections which are too
+/// large to be held in memory, e.g. items read from a database cursor
+///
+/// Containers are opened with [`WriterEncoder::begin_list`] or
+/// [`WriterEncoder::begin_dict`], filled one item or pair at a time and closed with
+/// [`WriterEncoder::end`]; [`WriterEncoder::finish`] closes any containers which are
+/// still open. Output is collected in a small buffer which is written out whenever it
+/// grows past the flush threshold. Writing blocks while the writer can't take more
+/// output, so a slow consumer holds up the producer instead of letting the buffer grow.
+///
+/// The same validation as for [`Encoder`] applies; once an error occurred, all further
+/// calls fail with the same error.
+///
+/// ```
+/// # use bendy::{encoding::{Error, WriterEncoder}, value::Value};
+/// #
+/// # fn main() -> Result<(), Error> {
+/// let cursor = vec![(b"aaaaaaaaaaaaaaaaaaaa", 5), (b"bbbbbbbbbbbbbbbbbbbb", 2)];
+///
+/// let mut encoder = WriterEncoder::new(Vec::new());
+/// encoder.begin_dict()?;
+/// encoder.push_item("files")?;
+/// encoder.begin_dict()?;
+/// for (info_hash, complete) in cursor {
+///     encoder.push_pair(info_hash, Value::dict().with_entry("complete", complete))?;
+/// }
+///
+/// let output = encoder.finish()?;
+/// assert_eq!(
+///     output,
+///     &b"d5:filesd20:aaaaaaaaaaaaaaaaaaaad8:completei5ee\
+///        20:bbbbbbbbbbbbbbbbbbbbd8:completei2eeee"[..]
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct WriterEncoder<W: Write> {
+    encoder: Encoder<BufferedWriter<W>>,
+}
+
+/// An output collecting bytes until the flush threshold is reached
+#[derive(Debug)]
+struct BufferedWriter<W> {
+    writer: W,
+    buffer: Vec<u8>,
+    flush_threshold: usize,
+}
+
+impl<W: Write> BufferedWriter<W> {
+    fn write_buffer(&mut self) -> Result<(), Error> {
+        self.writer.write_all(&self.buffer)?;
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write> Output for BufferedWriter<W> {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.buffer.extend_from_slice(bytes);
+        if self.buffer.len() >= self.flush_threshold {
+            self.write_buffer()?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> WriterEncoder<W> {
+    /// Create a new encoder writing to the given writer
+    pub fn new(writer: W) -> Self {
+        WriterEncoder {
+            encoder: Encoder::with_output(BufferedWriter {
+                writer,
+                buffer: Vec::new(),
+                flush_threshold: DEFAULT_FLUSH_THRESHOLD,
+            }),
+        }
+    }
+
+    /// Set the max depth of the encoded object
+    #[must_use]
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.encoder = self.encoder.with_max_depth(max_depth);
+        self
+    }
+
+    /// Set the number of buffered bytes after which the output is written to the
+    /// underlying writer. A threshold of zero writes every token immediately.
+    #[must_use]
+    pub fn with_flush_threshold(mut self, flush_threshold: usize) -> Self {
+        self.encoder.output_mut().flush_threshold = flush_threshold;
+        self
+    }
+
+    /// Push an item, which is encoded into memory in one go. In a dictionary, keys and
+    /// values are pushed alternately; see [`WriterEncoder::push_pair`].
+    pub fn push_item<E: ToBencode>(&mut self, value: E) -> Result<(), Error> {
+        self.encoder.emit(value)
+    }
+
+    /// Push a key and its value into the innermost dictionary. Keys need to be pushed
+    /// in sorted order.
+    pub fn push_pair<E: ToBencode>(&mut self, key: &[u8], value: E) -> Result<(), Error> {
+        self.encoder.emit_bytes(key)?;
+        self.encoder.emit(value)
+    }
+
+    /// Begin a list. Every item pushed until the matching [`WriterEncoder::end`] is an
+    /// item of the list.
+    pub fn begin_list(&mut self) -> Result<(), Error> {
+        self.encoder.emit_token(Token::List)
+    }
+
+    /// Begin a dictionary. Until the matching [`WriterEncoder::end`], keys and values
+    /// need to be pushed alternately, with the keys in sorted order.
+    pub fn begin_dict(&mut self) -> Result<(), Error> {
+        self.encoder.emit_token(Token::Dict)
+    }
+
+    /// End the innermost open list or dictionary
+    pub fn end(&mut self) -> Result<(), Error> {
+        self.encoder.emit_token(Token::End)
+    }
+
+    /// Write all buffered output to the underlying writer and flush it
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.encoder.with_output_mut(|output| {
+            output.write_buffer()?;
+            output.writer.flush()?;
+            Ok(())
+        })
+    }
+
+    /// Close all open lists and dictionaries, flush all output and return the underlying
+    /// writer. Fails if a dictionary is left with a key but no value.
+    pub fn finish(mut self) -> Result<W, Error> {
+        while self.encoder.depth() > 0 {
+            self.end()?;
+        }
+        self.flush()?;
+        Ok(self.encoder.get_output()?.writer)
+    }
+
+    /// Get a reference to the underlying writer. Output may still be buffered in the
+    /// encoder until the next flush.
+    pub fn get_ref(&self) -> &W {
+        &self.encoder.output().writer
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::io;
+
+    /// A writer recording the size of every write
+    #[derive(Default)]
+    struct ChunkRecorder {
+        chunks: Vec<Vec<u8>>,
+    }
+
+    impl Write for ChunkRecorder {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.chunks.push(buf.to_vec());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn output_should_be_written_incrementally() {
+        let mut encoder = WriterEncoder::new(ChunkRecorder::default()).with_flush_threshold(8);
+        encoder.begin_list().unwrap();
+        for i in 0..100 {
+            encoder.push_item(i).unwrap();
+            // Nothing but the last few items is buffered
+            let written: usize = encoder.get_ref().chunks.iter().map(Vec::len).sum();
+            assert!(written + 8 >= encoder.encoder.written());
+        }
+
+        let recorder = encoder.finish().unwrap();
+        let expected = (0..100).collect::<Vec<u32>>().to_bencode().unwrap();
+        assert!(recorder.chunks.len() > 10);
+        assert_eq!(recorder.chunks.concat(), expected);
+    }
+
+    #[test]
+    fn invalid_structures_should_be_rejected() {
+        let mut encoder = WriterEncoder::new(Vec::new());
+        encoder.begin_dict().unwrap();
+        encoder.push_pair(b"b", 1).unwrap();
+        assert!(encoder.push_pair(b"a", 2).is_err());
+        assert!(encoder.finish().is_err());
+
+        let mut encoder = WriterEncoder::new(Vec::new());
+        encoder.begin_dict().unwrap();
+        encoder.push_item("key").unwrap();
+        assert!(encoder.finish().is_err());
+
+        let mut encoder = WriterEncoder::new(Vec::new());
+        assert!(encoder.end().is_err());
+    }
+
+    #[test]
+    fn writer_errors_should_be_latched() {
+        let mut output = [0; 4];
+        let mut encoder = WriterEncoder::new(&mut output[..]).with_flush_threshold(0);
+        encoder.push_item(vec![1, 2]).unwrap_err();
+        assert!(encoder.push_item(3).is_err());
+    }
+}
@@ -0,0 +1,666 @@
+//! Messages of the KRPC protocol of the BitTorrent DHT, as described by [BEP 5]
+//!
+//! Every [`Message`] carries a transaction id, chosen by the querying node and echoed in
+//! the reply, and a [`Body`] which is a [`Query`], a [`Response`] or an [`Error`]:
+//!
+//! ```
+//! use bendy::{
+//!     decoding::FromBencode,
+//!     encoding::ToBencode,
+//!     krpc::{Message, Query, TransactionIds},
+//!     torrent::InfoHash,
+//! };
+//!
+//! let mut transaction_ids = TransactionIds::new();
+//! let query = Message::query(
+//!     transaction_ids.next_id(),
+//!     Query::GetPeers {
+//!         id: InfoHash([1; 20]),
+//!         info_hash: InfoHash([2; 20]),
+//!     },
+//! );
+//!
+//! let encoded = query.to_bencode().unwrap();
+//! assert!(encoded.starts_with(b"d1:ad2:id20:"));
+//! assert_eq!(Message::from_bencode(&encoded).unwrap(), query);
+//! ```
+//!
+//! The contents of a response depend on the query it answers, so [`Response`] has all
+//! fields any of the queries above can be answered with. Use the transaction id to
+//! find the query a response belongs to.
+//!
+//! [BEP 5]: http://bittorrent.org/beps/bep_0005.html
+
+use std::net::SocketAddr;
+
+use crate::{
+    decoding::{self, FromBencode, Object, ResultExt},
+    encoding::{self, AsString, SingleItemEncoder, ToBencode},
+    torrent::{
+        decode_compact_nodes, decode_compact_nodes6, decode_compact_peer, encode_compact_nodes,
+        encode_compact_peer, InfoHash,
+    },
+};
+
+pub use crate::torrent::NodeId;
+
+/// A KRPC message
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Message {
+    /// The id chosen by the querying node, echoed in the reply
+    pub transaction_id: Vec<u8>,
+    /// The client version of the sender, see [BEP 20](http://bittorrent.org/beps/bep_0020.html)
+    pub version: Option<Vec<u8>>,
+    /// The contents of the message
+    pub body: Body,
+}
+
+/// Whether a message is a query or a reply
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Body {
+    Query(Query),
+    Response(Response),
+    Error(Error),
+}
+
+/// A query to another node
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Query {
+    /// Check whether the node is reachable
+    Ping { id: NodeId },
+    /// Ask for the contacts of the nodes closest to the target
+    FindNode { id: NodeId, target: NodeId },
+    /// Ask for peers of a torrent, or the nodes closest to its info-hash
+    GetPeers { id: NodeId, info_hash: InfoHash },
+    /// Announce that the querying node is a peer of a torrent
+    AnnouncePeer {
+        id: NodeId,
+        info_hash: InfoHash,
+        /// The port the peer listens on
+        port: u16,
+        /// The token of an earlier `get_peers` response of the queried node
+        token: Vec<u8>,
+        /// Use the source port of the query instead of `port`, for peers behind NAT
+        implied_port: bool,
+    },
+}
+
+/// A response to a query
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Response {
+    /// The id of the responding node
+    pub id: NodeId,
+    /// The contacts of nodes, from `nodes` and the IPv6 `nodes6` of
+    /// [BEP 32](http://bittorrent.org/beps/bep_0032.html)
+    pub nodes: Vec<Node>,
+    /// The addresses of peers of the torrent
+    pub values: Vec<SocketAddr>,
+    /// The token to announce with, in responses to `get_peers`
+    pub token: Option<Vec<u8>>,
+}
+
+/// The contact of a node
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Node {
+    pub id: NodeId,
+    pub address: SocketAddr,
+}
+
+/// An error reply to a query
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Error {
+    /// The error code, like [`Error::METHOD_UNKNOWN`]
+    pub code: i64,
+    /// A human-readable message
+    pub message: String,
+}
+
+impl From<(NodeId, SocketAddr)> for Node {
+    fn from((id, address): (NodeId, SocketAddr)) -> Self {
+        Node { id, address }
+    }
+}
+
+impl Error {
+    pub const GENERIC: i64 = 201;
+    pub const SERVER: i64 = 202;
+    /// A malformed packet, invalid arguments or a bad token
+    pub const PROTOCOL: i64 = 203;
+    pub const METHOD_UNKNOWN: i64 = 204;
+}
+
+/// Generates transaction ids for outgoing queries
+///
+/// Ids are two bytes long, which is enough to tell apart the queries in flight, and
+/// wrap around after 65536 queries.
+#[derive(Clone, Debug, Default)]
+pub struct TransactionIds {
+    next: u16,
+}
+
+impl TransactionIds {
+    /// Create a generator starting at zero
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a generator starting at the given value, e.g. a random one so that
+    /// stale replies to an earlier session aren't mistaken for replies to new queries
+    pub fn starting_at(next: u16) -> Self {
+        TransactionIds { next }
+    }
+
+    /// Take the next transaction id
+    pub fn next_id(&mut self) -> Vec<u8> {
+        let id = self.next.to_be_bytes().to_vec();
+        self.next = self.next.wrapping_add(1);
+        id
+    }
+}
+
+impl Message {
+    /// Create a query with the given transaction id
+    pub fn query(transaction_id: Vec<u8>, query: Query) -> Self {
+        Self::new(transaction_id, Body::Query(query))
+    }
+
+    /// Create a response to the query with the given transaction id
+    pub fn response(transaction_id: Vec<u8>, response: Response) -> Self {
+        Self::new(transaction_id, Body::Response(response))
+    }
+
+    /// Create an error reply to the query with the given transaction id
+    pub fn error(transaction_id: Vec<u8>, code: i64, message: impl Into<String>) -> Self {
+        let error = Error {
+            code,
+            message: message.into(),
+        };
+        Self::new(transaction_id, Body::Error(error))
+    }
+
+    fn new(transaction_id: Vec<u8>, body: Body) -> Self {
+        Message {
+            transaction_id,
+            version: None,
+            body,
+        }
+    }
+
+    /// Whether this message is a reply to a query with the given transaction id
+    pub fn is_reply_to(&self, transaction_id: &[u8]) -> bool {
+        !matches!(self.body, Body::Query(_)) && self.transaction_id == transaction_id
+    }
+}
+
+impl Query {
+    /// The name of the query method
+    pub fn method(&self) -> &'static str {
+        match self {
+            Query::Ping { .. } => "ping",
+            Query::FindNode { .. } => "find_node",
+            Query::GetPeers { .. } => "get_peers",
+            Query::AnnouncePeer { .. } => "announce_peer",
+        }
+    }
+
+    /// The id of the querying node
+    pub fn id(&self) -> &NodeId {
+        match self {
+            Query::Ping { id }
+            | Query::FindNode { id, .. }
+            | Query::GetPeers { id, .. }
+            | Query::AnnouncePeer { id, .. } => id,
+        }
+    }
+}
+
+/// The keys of the query arguments and the response dictionary. As the message type is
+/// only known at the end of the message, both are decoded the same way.
+#[derive(Default)]
+struct Fields {
+    id: Option<NodeId>,
+    implied_port: bool,
+    info_hash: Option<InfoHash>,
+    nodes: Vec<Node>,
+    port: Option<u16>,
+    target: Option<NodeId>,
+    token: Option<Vec<u8>>,
+    values: Vec<SocketAddr>,
+}
+
+impl FromBencode for Fields {
+    const EXPECTED_RECURSION_DEPTH: usize = 2;
+
+    fn decode_bencode_object(object: Object) -> Result<Self, decoding::Error>
+    where
+        Self: Sized,
+    {
+        let mut fields = Fields::default();
+
+        let mut dict = object.try_into_dictionary()?;
+        while let Some(pair) = dict.next_pair()? {
+            match pair {
+                (b"id", value) => {
+                    fields.id = NodeId::decode_bencode_object(value)
+                        .context_field("id")
+                        .map(Some)?;
+                },
+                (b"implied_port", value) => {
+                    fields.implied_port =
+                        u8::decode_bencode_object(value).context_field("implied_port")? == 1;
+                },
+                (b"info_hash", value) => {
+                    fields.info_hash = InfoHash::decode_bencode_object(value)
+                        .context_field("info_hash")
+                        .map(Some)?;
+                },
+                (b"nodes", value) => {
+                    let nodes = value
+                        .try_into_bytes()
+                        .and_then(decode_compact_nodes)
+                        .context_field("nodes")?;
+                    fields.nodes.extend(nodes.into_iter().map(Node::from));
+                },
+                (b"nodes6", value) => {
+                    let nodes = value
+                        .try_into_bytes()
+                        .and_then(decode_compact_nodes6)
+                        .context_field("nodes6")?;
+                    fields.nodes.extend(nodes.into_iter().map(Node::from));
+                },
+                (b"port", value) => {
+                    fields.port = u16::decode_bencode_object(value)
+                        .context_field("port")
+                        .map(Some)?;
+                },
+                (b"target", value) => {
+                    fields.target = NodeId::decode_bencode_object(value)
+                        .context_field("target")
+                        .map(Some)?;
+                },
+                (b"token", value) => {
+                    fields.token = AsString::decode_bencode_object(value)
+                        .context_field("token")
+                        .map(|AsString(token)| Some(token))?;
+                },
+                (b"values", value) => {
+                    decode_values(value, &mut fields.values).context_field("values")?;
+                },
+                _ => (),
+            }
+        }
+
+        Ok(fields)
+    }
+}
+
+fn decode_values(object: Object, values: &mut Vec<SocketAddr>) -> Result<(), decoding::Error> {
+    let mut list = object.try_into_list()?;
+    let mut index = 0;
+    while let Some(value) = list.next_object()? {
+        let peer = value.try_into_bytes().context_index(index)?;
+        values.push(decode_compact_peer(peer).context_index(index)?);
+        index += 1;
+    }
+    Ok(())
+}
+
+impl FromBencode for Error {
+    const EXPECTED_RECURSION_DEPTH: usize = 1;
+
+    fn decode_bencode_object(object: Object) -> Result<Self, decoding::Error>
+    where
+        Self: Sized,
+    {
+        let mut list = object.try_into_list()?;
+        let code = match list.next_object()? {
+            Some(code) => i64::decode_bencode_object(code).context_index(0)?,
+            None => return Err(decoding::Error::missing_field("code")),
+        };
+        let message = match list.next_object()? {
+            Some(message) => String::decode_bencode_object(message).context_index(1)?,
+            None => return Err(decoding::Error::missing_field("message")),
+        };
+        if list.next_object()?.is_some() {
+            return Err(decoding::Error::unexpected_token(
+                "the end of the error",
+                "a third item",
+            ));
+        }
+
+        Ok(Error { code, message })
+    }
+}
+
+impl FromBencode for Message {
+    const EXPECTED_RECURSION_DEPTH: usize = 3;
+
+    fn decode_bencode_object(object: Object) -> Result<Self, decoding::Error>
+    where
+        Self: Sized,
+    {
+        let mut arguments = None;
+        let mut error = None;
+        let mut method = None;
+        let mut response = None;
+        let mut transaction_id = None;
+        let mut version = None;
+        let mut kind = None;
+
+        let mut dict = object.try_into_dictionary()?;
+        while let Some(pair) = dict.next_pair()? {
+            match pair {
+                (b"a", value) => {
+                    arguments = Fields::decode_bencode_object(value)
+                        .context_field("a")
+                        .map(Some)?;
+                },
+                (b"e", value) => {
+                    error = Error::decode_bencode_object(value)
+                        .context_field("e")
+                        .map(Some)?;
+                },
+                (b"q", value) => {
+                    method = AsString::decode_bencode_object(value)
+                        .context_field("q")
+                        .map(|AsString(method)| Some(method))?;
+                },
+                (b"r", value) => {
+                    response = Fields::decode_bencode_object(value)
+                        .context_field("r")
+                        .map(Some)?;
+                },
+                (b"t", value) => {
+                    transaction_id = AsString::decode_bencode_object(value)
+                        .context_field("t")
+                        .map(|AsString(id)| Some(id))?;
+                },
+                (b"v", value) => {
+                    version = AsString::decode_bencode_object(value)
+                        .context_field("v")
+                        .map(|AsString(version)| Some(version))?;
+                },
+                (b"y", value) => {
+                    kind = AsString::decode_bencode_object(value)
+                        .context_field("y")
+                        .map(|AsString(kind)| Some(kind))?;
+                },
+                _ => (),
+            }
+        }
+
+        let body = match kind.as_deref() {
+            Some(b"q") => {
+                let method = method.ok_or_else(|| decoding::Error::missing_field("q"))?;
+                let arguments = arguments.ok_or_else(|| decoding::Error::missing_field("a"))?;
+                Body::Query(decode_query(&method, arguments).context_field("a")?)
+            },
+            Some(b"r") => {
+                let response = response.ok_or_else(|| decoding::Error::missing_field("r"))?;
+                Body::Response(Response {
+                    id: required(response.id, "id").context_field("r")?,
+                    nodes: response.nodes,
+                    values: response.values,
+                    token: response.token,
+                })
+            },
+            Some(b"e") => Body::Error(error.ok_or_else(|| decoding::Error::missing_field("e"))?),
+            Some(other) => {
+                let error = decoding::Error::unknown_variant(other);
+                return Err(error.context_field("y"));
+            },
+            None => return Err(decoding::Error::missing_field("y")),
+        };
+
+        Ok(Message {
+            transaction_id: required(transaction_id, "t")?,
+            version,
+            body,
+        })
+    }
+}
+
+fn required<T>(field: Option<T>, name: &str) -> Result<T, decoding::Error> {
+    field.ok_or_else(|| decoding::Error::missing_field(name))
+}
+
+fn decode_query(method: &[u8], arguments: Fields) -> Result<Query, decoding::Error> {
+    let id = required(arguments.id, "id")?;
+    let query = match method {
+        b"ping" => Query::Ping { id },
+        b"find_node" => Query::FindNode {
+            id,
+            target: required(arguments.target, "target")?,
+        },
+        b"get_peers" => Query::GetPeers {
+            id,
+            info_hash: required(arguments.info_hash, "info_hash")?,
+        },
+        b"announce_peer" => Query::AnnouncePeer {
+            id,
+            info_hash: required(arguments.info_hash, "info_hash")?,
+            port: required(arguments.port, "port")?,
+            token: required(arguments.token, "token")?,
+            implied_port: arguments.implied_port,
+        },
+        other => return Err(decoding::Error::unknown_variant(other)),
+    };
+    Ok(query)
+}
+
+impl ToBencode for Message {
+    const MAX_DEPTH: usize = 3;
+
+    fn encode(&self, encoder: SingleItemEncoder) -> Result<(), encoding::Error> {
+        encoder.emit_dict(|mut e| {
+            match &self.body {
+                Body::Query(query) => {
+                    e.emit_pair(b"a", query)?;
+                    e.emit_pair(b"q", query.method())?;
+                },
+                Body::Response(response) => e.emit_pair(b"r", response)?,
+                Body::Error(error) => e.emit_pair(b"e", error)?,
+            }
+            e.emit_pair(b"t", AsString(&self.transaction_id))?;
+            if let Some(version) = &self.version {
+                e.emit_pair(b"v", AsString(version))?;
+            }
+            let kind = match self.body {
+                Body::Query(_) => "q",
+                Body::Response(_) => "r",
+                Body::Error(_) => "e",
+            };
+            e.emit_pair(b"y", kind)
+        })
+    }
+}
+
+/// Encodes the arguments of the query
+impl ToBencode for Query {
+    const MAX_DEPTH: usize = 1;
+
+    fn encode(&self, encoder: SingleItemEncoder) -> Result<(), encoding::Error> {
+        encoder.emit_dict(|mut e| {
+            e.emit_pair(b"id", self.id())?;
+            match self {
+                Query::Ping { .. } => (),
+                Query::FindNode { target, .. } => e.emit_pair(b"target", target)?,
+                Query::GetPeers { info_hash, .. } => e.emit_pair(b"info_hash", info_hash)?,
+                Query::AnnouncePeer {
+                    info_hash,
+                    port,
+                    token,
+                    implied_port,
+                    ..
+                } => {
+                    if *implied_port {
+                        e.emit_pair(b"implied_port", 1)?;
+                    }
+                    e.emit_pair(b"info_hash", info_hash)?;
+                    e.emit_pair(b"port", port)?;
+                    e.emit_pair(b"token", AsString(token))?;
+                },
+            }
+            Ok(())
+        })
+    }
+}
+
+impl ToBencode for Response {
+    const MAX_DEPTH: usize = 2;
+
+    fn encode(&self, encoder: SingleItemEncoder) -> Result<(), encoding::Error> {
+        let (nodes, nodes6) =
+            encode_compact_nodes(self.nodes.iter().map(|node| (node.id, node.address)));
+
+        encoder.emit_dict(|mut e| {
+            e.emit_pair(b"id", self.id)?;
+            if !nodes.is_empty() {
+                e.emit_pair(b"nodes", AsString(&nodes))?;
+            }
+            if !nodes6.is_empty() {
+                e.emit_pair(b"nodes6", AsString(&nodes6))?;
+            }
+            if let Some(token) = &self.token {
+                e.emit_pair(b"token", AsString(token))?;
+            }
+            if !self.values.is_empty() {
+                e.emit_pair_with(b"values", |e| {
+                    e.emit_list(|e| {
+                        for value in &self.values {
+                            let mut peer = Vec::new();
+                            encode_compact_peer(value, &mut peer);
+                            e.emit(AsString(peer))?;
+                        }
+                        Ok(())
+                    })
+                })?;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl ToBencode for Error {
+    const MAX_DEPTH: usize = 1;
+
+    fn encode(&self, encoder: SingleItemEncoder) -> Result<(), encoding::Error> {
+        encoder.emit_list(|e| {
+            e.emit(self.code)?;
+            e.emit(&self.message)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn round_trip(message: &Message, encoded: &[u8]) {
+        assert_eq!(message.to_bencode().unwrap(), encoded);
+        assert_eq!(&Message::from_bencode(encoded).unwrap(), message);
+    }
+
+    #[test]
+    fn bep_5_examples_should_round_trip() {
+        let id = InfoHash(*b"abcdefghij0123456789");
+        let other = InfoHash(*b"mnopqrstuvwxyz123456");
+
+        round_trip(
+            &Message::query(b"aa".to_vec(), Query::Ping { id }),
+            b"d1:ad2:id20:abcdefghij0123456789e1:q4:ping1:t2:aa1:y1:qe",
+        );
+        round_trip(
+            &Message::response(
+                b"aa".to_vec(),
+                Response {
+                    id: other,
+                    ..Response::default()
+                },
+            ),
+            b"d1:rd2:id20:mnopqrstuvwxyz123456e1:t2:aa1:y1:re",
+        );
+        round_trip(
+            &Message::query(b"aa".to_vec(), Query::FindNode { id, target: other }),
+            b"d1:ad2:id20:abcdefghij01234567896:target20:mnopqrstuvwxyz123456e\
+              1:q9:find_node1:t2:aa1:y1:qe",
+        );
+        round_trip(
+            &Message::query(
+                b"aa".to_vec(),
+                Query::AnnouncePeer {
+                    id,
+                    info_hash: other,
+                    port: 6881,
+                    token: b"aoeusnth".to_vec(),
+                    implied_port: true,
+                },
+            ),
+            b"d1:ad2:id20:abcdefghij012345678912:implied_porti1e\
+              9:info_hash20:mnopqrstuvwxyz1234564:porti6881e5:token8:aoeusnthe\
+              1:q13:announce_peer1:t2:aa1:y1:qe",
+        );
+        round_trip(
+            &Message::error(b"aa".to_vec(), Error::GENERIC, "A Generic Error Ocurred"),
+            b"d1:eli201e23:A Generic Error Ocurrede1:t2:aa1:y1:ee",
+        );
+    }
+
+    #[test]
+    fn get_peers_responses_should_round_trip() {
+        let mut message = Message::response(
+            b"\x00\x01".to_vec(),
+            Response {
+                id: InfoHash([1; 20]),
+                nodes: vec![
+                    Node {
+                        id: InfoHash([2; 20]),
+                        address: "1.2.3.4:6881".parse().unwrap(),
+                    },
+                    Node {
+                        id: InfoHash([3; 20]),
+                        address: "[::1]:6882".parse().unwrap(),
+                    },
+                ],
+                values: vec!["5.6.7.8:80".parse().unwrap(), "[::2]:81".parse().unwrap()],
+                token: Some(b"token".to_vec()),
+            },
+        );
+        message.version = Some(b"UT\x01\x02".to_vec());
+
+        let encoded = message.to_bencode().unwrap();
+        assert_eq!(Message::from_bencode(&encoded).unwrap(), message);
+        assert!(message.is_reply_to(b"\x00\x01"));
+    }
+
+    #[test]
+    fn transaction_ids_should_wrap() {
+        let mut ids = TransactionIds::starting_at(u16::MAX);
+        assert_eq!(ids.next_id(), b"\xff\xff");
+        assert_eq!(ids.next_id(), b"\x00\x00");
+    }
+
+    #[test]
+    fn invalid_messages_should_fail() {
+        let cases: &[&[u8]] = &[
+            b"d1:t2:aa1:y1:qe",
+            b"d1:ad2:id20:abcdefghij0123456789e1:q4:pong1:t2:aa1:y1:qe",
+            b"d1:ad2:id20:abcdefghij0123456789e1:q9:find_node1:t2:aa1:y1:qe",
+            b"d1:ad2:id3:abce1:q4:ping1:t2:aa1:y1:qe",
+            b"d1:rd2:id20:abcdefghij01234567895:nodes3:abce1:t2:aa1:y1:re",
+            b"d1:rd2:id20:abcdefghij0123456789e1:y1:re",
+            b"d1:eli201ee1:t2:aa1:y1:ee",
+            b"d1:t2:aa1:y1:xe",
+        ];
+        for &case in cases {
+            assert!(Message::from_bencode(case).is_err());
+        }
+    }
+
+    #[test]
+    fn errors_should_name_the_field() {
+        let input = b"d1:ad2:id20:abcdefghij0123456789e1:q9:get_peers1:t2:aa1:y1:qe";
+        let error = Message::from_bencode(input).unwrap_err();
+        assert_eq!(error.to_string(), "Error: missing field: info_hash in a");
+    }
+}
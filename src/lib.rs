@@ -6,6 +6,8 @@
 //! The encoder is likewise designed to ensure that it only produces valid structures.
 
 #![cfg_attr(not(feature = "std"), no_std)]
+// `failure_derive` expands its impls inside anonymous constants.
+#![allow(non_local_definitions)]
 
 extern crate alloc;
 
@@ -13,11 +15,20 @@ extern crate alloc;
 #[macro_use]
 mod assert_matches;
 
+#[cfg(feature = "codec")]
+pub mod codec;
+#[cfg(any(feature = "chrono", feature = "time"))]
+mod datetime;
 pub mod decoding;
 pub mod encoding;
+#[cfg(feature = "krpc")]
+pub mod krpc;
 pub mod state_tracker;
 
 #[cfg(feature = "serde")]
 pub mod serde;
 
+#[cfg(feature = "torrent")]
+pub mod torrent;
+
 pub mod value;
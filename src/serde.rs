@@ -12,8 +12,8 @@
 //! assert_eq!(to_bytes(&10).unwrap(), b"i10e");
 //! assert_eq!(from_bytes::<u64>(b"i10e").unwrap(), 10);
 //!
-//! #[serde(crate = "serde_")]
 //! #[derive(Serialize, Deserialize, Debug, PartialEq)]
+//! #[serde(crate = "serde_")]
 //! struct Foo {
 //!     bar: bool,
 //! }
@@ -30,6 +30,13 @@
 //! - `true`: The integer value `1`.
 //! - `false`: The integer value `0`.
 //! - `char`: A string containing the UTF-8 encoding of the value.
+//! - `str` and `String`: A string containing the UTF-8 encoding of the value.
+//! - byte buffers: A byte string. Serde only treats `&[u8]` and `Vec<u8>` as byte
+//!   buffers when they are wrapped with [`serde_bytes`], i.e. as `serde_bytes::Bytes`,
+//!   `serde_bytes::ByteBuf`, or a field annotated with `#[serde(with = "serde_bytes")]`.
+//!   Unwrapped, they are sequences and serialize as lists of integers. When
+//!   deserializing, sequences (including arrays like `[u8; 20]`) may also be read
+//!   from a byte string, with each byte becoming one element.
 //! - `f32`: Represented as a length-four bencode byte string containing the big-
 //!   endian order bytes of the IEEE-754 representation of the value.
 //! - `f64`: Represented as a length-eight bencode byte string containing the big-
@@ -64,6 +71,37 @@
 //! If you require bencoded values to have canonical representations, then it is best
 //! to avoid floating point values.
 //!
+//! [`serde_bytes`]: https://docs.rs/serde_bytes
+//!
+//! Self-Describing Deserialization
+//! -------------------------------
+//!
+//! Bencode doesn't distinguish between text and binary strings, so when a type
+//! asks the deserializer to describe the input (via `deserialize_any`), byte strings
+//! that are valid UTF-8 are presented as strings and all others as bytes. Integers
+//! are presented as the smallest of `i64`, `u64`, `i128` and `u128` they fit into.
+//!
+//! This also makes `Deserializer` and `Serializer` suitable for use with
+//! [`serde_transcode`], which streams a document from one format into another
+//! without building an intermediate tree:
+//!
+//! ```
+//! use bendy::serde::{Deserializer, Serializer};
+//!
+//! let mut deserializer = Deserializer::from_bytes(b"d3:agei42e4:name3:bobe");
+//! let mut json = Vec::new();
+//! serde_transcode::transcode(&mut deserializer, &mut serde_json::Serializer::new(&mut json))
+//!     .unwrap();
+//! assert_eq!(json, br#"{"age":42,"name":"bob"}"#);
+//!
+//! let mut serializer = Serializer::new();
+//! let mut deserializer = serde_json::Deserializer::from_slice(&json);
+//! serde_transcode::transcode(&mut deserializer, &mut serializer).unwrap();
+//! assert_eq!(serializer.into_bytes().unwrap(), b"d3:agei42e4:name3:bobe");
+//! ```
+//!
+//! [`serde_transcode`]: https://docs.rs/serde_transcode
+//!
 //! Example Representations
 //! -----------------------
 //!
@@ -97,23 +135,23 @@
 //! map.insert("bar", 2);
 //! repr(map, "d3:bari2e3:fooi1ee");
 //!
-//! #[serde(crate = "serde_")]
 //! #[derive(Serialize)]
+//! #[serde(crate = "serde_")]
 //! struct Unit;
 //! repr(Unit, "le");
 //!
-//! #[serde(crate = "serde_")]
 //! #[derive(Serialize)]
+//! #[serde(crate = "serde_")]
 //! struct Newtype(String);
 //! repr(Newtype("foo".into()), "3:foo");
 //!
-//! #[serde(crate = "serde_")]
 //! #[derive(Serialize)]
+//! #[serde(crate = "serde_")]
 //! struct Tuple(bool, i32);
 //! repr(Tuple(false, 100), "li0ei100ee");
 //!
-//! #[serde(crate = "serde_")]
 //! #[derive(Serialize)]
+//! #[serde(crate = "serde_")]
 //! struct Record {
 //!     a: String,
 //!     b: bool,
@@ -127,8 +165,8 @@
 //!     "d1:a5:hello1:bi0ee",
 //! );
 //!
-//! #[serde(crate = "serde_")]
 //! #[derive(Serialize)]
+//! #[serde(crate = "serde_")]
 //! enum Enum {
 //!     Unit,
 //!     Newtype(i32),
@@ -141,9 +179,9 @@
 //! repr(Enum::Tuple(true, 10), "d5:Tupleli1ei10eee");
 //! repr(Enum::Struct { a: 'x', b: true }, "d6:Structd1:a1:x1:bi1eee");
 //!
+//! #[derive(Serialize)]
 //! #[serde(untagged)]
 //! #[serde(crate = "serde_")]
-//! #[derive(Serialize)]
 //! enum Untagged {
 //!     Foo { x: i32 },
 //!     Bar { y: char },
@@ -164,6 +202,7 @@ pub use error::{Error, Result};
 pub use ser::{to_bytes, Serializer};
 
 #[cfg(test)]
+#[allow(clippy::needless_borrow)]
 mod tests {
     use super::common::*;
 
@@ -171,7 +210,7 @@ mod tests {
 
     use super::{
         de::{from_bytes, Deserializer},
-        ser::to_bytes,
+        ser::{to_bytes, Serializer},
     };
 
     use serde::{de::DeserializeOwned, ser::Serialize};
@@ -359,6 +398,48 @@ mod tests {
         case_borrowed(Borrowed { bytes: &[1, 2, 3] }, b"3:\x01\x02\x03");
     }
 
+    #[test]
+    fn bytes_field_with_serde_bytes() {
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        #[serde(crate = "serde_")]
+        struct Info {
+            name: String,
+            #[serde(rename = "piece length")]
+            piece_length: u64,
+            #[serde(with = "serde_bytes")]
+            pieces: Vec<u8>,
+        }
+
+        case(
+            Info {
+                name: "a".into(),
+                piece_length: 2,
+                pieces: vec![0, 0xff, b'e'],
+            },
+            b"d4:name1:a12:piece lengthi2e6:pieces3:\x00\xffee",
+        );
+    }
+
+    #[test]
+    fn bytes_with_byte_buf() {
+        use serde_bytes::ByteBuf;
+
+        case(ByteBuf::from(vec![1, 2, 3]), b"3:\x01\x02\x03");
+        case(Some(ByteBuf::from(vec![1])), b"l1:\x01e");
+
+        let mut map = HashMap::new();
+        map.insert(ByteBuf::from(vec![0xff]), ByteBuf::from(vec![0]));
+        case(map, b"d1:\xff1:\x00e");
+    }
+
+    #[test]
+    fn bytes_as_seq() {
+        assert_eq!(from_bytes::<Vec<u8>>(b"3:abc").unwrap(), b"abc".to_vec());
+        assert_eq!(from_bytes::<[u8; 3]>(b"3:abc").unwrap(), *b"abc");
+        assert_eq!(from_bytes::<[u8; 3]>(b"li1ei2ei3ee").unwrap(), [1, 2, 3]);
+        assert_matches!(from_bytes::<[u8; 4]>(b"3:abc"), Err(Error::CustomDecode(_)));
+    }
+
     #[test]
     fn map() {
         let mut map = HashMap::new();
@@ -458,8 +539,8 @@ mod tests {
 
     #[test]
     fn untagged_enum() {
-        #[serde(untagged)]
         #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        #[serde(untagged)]
         #[serde(crate = "serde_")]
         enum Untagged {
             Foo { x: i32 },
@@ -488,6 +569,44 @@ mod tests {
         case(Foo { bar: Bar { x: 1 } }, "d1:xi1ee");
     }
 
+    #[test]
+    fn transcode_to_json() {
+        fn transcode(bencode: &[u8]) -> String {
+            let mut deserializer = Deserializer::from_bytes(bencode);
+            let mut json = Vec::new();
+            serde_transcode::transcode(
+                &mut deserializer,
+                &mut serde_json::Serializer::new(&mut json),
+            )
+            .unwrap();
+            String::from_utf8(json).unwrap()
+        }
+
+        assert_eq!(transcode(b"i-3e"), "-3");
+        assert_eq!(transcode(b"i18446744073709551615e"), "18446744073709551615");
+        assert_eq!(transcode(b"3:foo"), r#""foo""#);
+        assert_eq!(transcode(b"2:\xff\x00"), "[255,0]");
+        assert_eq!(
+            transcode(b"d1:ali1e1:be1:bd1:c0:ee"),
+            r#"{"a":[1,"b"],"b":{"c":""}}"#
+        );
+    }
+
+    #[test]
+    fn transcode_from_json() {
+        fn transcode(json: &str) -> Vec<u8> {
+            let mut deserializer = serde_json::Deserializer::from_str(json);
+            let mut serializer = Serializer::new();
+            serde_transcode::transcode(&mut deserializer, &mut serializer).unwrap();
+            serializer.into_bytes().unwrap()
+        }
+
+        assert_eq!(transcode("-3"), b"i-3e");
+        assert_eq!(transcode(r#""foo""#), b"3:foo");
+        assert_eq!(transcode("[1, true, null]"), b"li1ei1elee");
+        assert_eq!(transcode(r#"{"b": 1, "a": {}}"#), b"d1:ade1:bi1ee");
+    }
+
     #[test]
     fn invalid_bool() {
         assert_matches!(
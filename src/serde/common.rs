@@ -12,7 +12,8 @@ pub(crate) use serde_ as serde;
 /// Dependencies
 pub(crate) use serde::{
     de::{
-        DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, VariantAccess, Visitor,
+        value::SeqDeserializer, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess,
+        SeqAccess, VariantAccess, Visitor,
     },
     ser::{
         Serialize, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant,
@@ -38,10 +38,8 @@ impl<'de> Deserializer<'de> {
     {
         let t = T::deserialize(&mut self)?;
 
-        if self.forbid_trailing_bytes {
-            if let Some(_) = self.tokens.next() {
-                return Err(Error::TrailingBytes);
-            }
+        if self.forbid_trailing_bytes && self.tokens.next().is_some() {
+            return Err(Error::TrailingBytes);
         }
 
         Ok(t)
@@ -103,6 +101,33 @@ impl<'de> Deserializer<'de> {
         Ok(())
     }
 
+    fn visit_integer<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let text = self.next_integer()?;
+        if let Ok(value) = text.parse::<i64>() {
+            visitor.visit_i64(value)
+        } else if let Ok(value) = text.parse::<u64>() {
+            visitor.visit_u64(value)
+        } else if let Ok(value) = text.parse::<i128>() {
+            visitor.visit_i128(value)
+        } else {
+            visitor.visit_u128(text.parse()?)
+        }
+    }
+
+    fn visit_string_or_bytes<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let bytes = self.next_bytes()?;
+        match str::from_utf8(bytes) {
+            Ok(string) => visitor.visit_borrowed_str(string),
+            Err(_) => visitor.visit_borrowed_bytes(bytes),
+        }
+    }
+
     fn peek_end(&mut self) -> bool {
         self.peek() == Some(Token::End)
     }
@@ -116,7 +141,7 @@ impl<'de> Deserializer<'de> {
     }
 }
 
-impl<'de, 'a> serde::de::Deserializer<'de> for &'a mut Deserializer<'de> {
+impl<'de> serde::de::Deserializer<'de> for &mut Deserializer<'de> {
     type Error = Error;
 
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
@@ -125,10 +150,10 @@ impl<'de, 'a> serde::de::Deserializer<'de> for &'a mut Deserializer<'de> {
     {
         match self.peek() {
             Some(Token::Dict) => self.deserialize_map(visitor),
-            Some(Token::String(_)) => self.deserialize_bytes(visitor),
+            Some(Token::String(_)) => self.visit_string_or_bytes(visitor),
             Some(Token::List) => self.deserialize_seq(visitor),
-            Some(Token::Num(_)) => self.deserialize_i64(visitor),
-            Some(Token::End) => Err(Error::Decode(StructureError::invalid_state("End").into())),
+            Some(Token::Num(_)) => self.visit_integer(visitor),
+            Some(Token::End) => Err(decoding::Error::unexpected_token("a value", "End").into()),
             None => Err(Error::Decode(StructureError::UnexpectedEof.into())),
         }
     }
@@ -247,11 +272,11 @@ impl<'de, 'a> serde::de::Deserializer<'de> for &'a mut Deserializer<'de> {
         V: Visitor<'de>,
     {
         let s: &str = self.next_string()?;
-        let count = s.chars().count();
-        if count != 1 {
-            return Err(Error::InvalidChar(count));
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => visitor.visit_char(c),
+            _ => Err(Error::InvalidChar(s.chars().count())),
         }
-        visitor.visit_char(s.chars().next().unwrap())
     }
 
     fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
@@ -322,6 +347,16 @@ impl<'de, 'a> serde::de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
+        // Byte strings are accepted as sequences of `u8` so that types like
+        // `Vec<u8>` and `[u8; 20]` can be read from their natural bencode
+        // representation, even without `serde_bytes`
+        if let Some(Token::String(_)) = self.peek() {
+            let mut bytes = SeqDeserializer::<_, Error>::new(self.next_bytes()?.iter().copied());
+            let value = visitor.visit_seq(&mut bytes)?;
+            bytes.end()?;
+            return Ok(value);
+        }
+
         self.expect_list_begin()?;
         let value = visitor.visit_seq(&mut *self)?;
         self.expect_end()?;
@@ -1,4 +1,4 @@
-///! Serde error and result types
+//! Serde error and result types
 use crate::serde::common::*;
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -23,6 +23,12 @@ pub struct Serializer {
     encoder: Encoder,
 }
 
+impl Default for Serializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Serializer {
     /// Create a new `Serializer`
     pub fn new() -> Self {
@@ -48,12 +54,12 @@ impl Serializer {
         Ok(())
     }
 
-    fn begin_struct(&mut self) -> Result<StructSerializer> {
+    fn begin_struct(&mut self) -> Result<StructSerializer<'_>> {
         let encoder = self.encoder.begin_unsorted_dict()?;
         Ok(StructSerializer::new(&mut self.encoder, encoder))
     }
 
-    fn begin_map(&mut self) -> Result<MapSerializer> {
+    fn begin_map(&mut self) -> Result<MapSerializer<'_>> {
         let encoder = self.encoder.begin_unsorted_dict()?;
         Ok(MapSerializer::new(&mut self.encoder, encoder))
     }
@@ -257,7 +263,7 @@ impl<'a> serde::ser::Serializer for &'a mut Serializer {
     }
 }
 
-impl<'a> SerializeSeq for &'a mut Serializer {
+impl SerializeSeq for &mut Serializer {
     type Error = Error;
     type Ok = ();
 
@@ -274,7 +280,7 @@ impl<'a> SerializeSeq for &'a mut Serializer {
     }
 }
 
-impl<'a> SerializeTuple for &'a mut Serializer {
+impl SerializeTuple for &mut Serializer {
     type Error = Error;
     type Ok = ();
 
@@ -291,7 +297,7 @@ impl<'a> SerializeTuple for &'a mut Serializer {
     }
 }
 
-impl<'a> SerializeTupleStruct for &'a mut Serializer {
+impl SerializeTupleStruct for &mut Serializer {
     type Error = Error;
     type Ok = ();
 
@@ -308,7 +314,7 @@ impl<'a> SerializeTupleStruct for &'a mut Serializer {
     }
 }
 
-impl<'a> SerializeMap for &'a mut Serializer {
+impl SerializeMap for &mut Serializer {
     type Error = Error;
     type Ok = ();
 
@@ -331,7 +337,7 @@ impl<'a> SerializeMap for &'a mut Serializer {
     }
 }
 
-impl<'a> SerializeTupleVariant for &'a mut Serializer {
+impl SerializeTupleVariant for &mut Serializer {
     type Error = Error;
     type Ok = ();
 
@@ -349,7 +355,7 @@ impl<'a> SerializeTupleVariant for &'a mut Serializer {
     }
 }
 
-impl<'a> SerializeStructVariant for &'a mut Serializer {
+impl SerializeStructVariant for &mut Serializer {
     type Error = Error;
     type Ok = ();
 
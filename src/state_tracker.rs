@@ -3,5 +3,6 @@ mod state;
 mod structure_error;
 mod token;
 
-pub use self::token::Token;
-pub(crate) use self::{stack::Stack, state::StateTracker, structure_error::StructureError};
+pub use self::structure_error::StructureError;
+pub use self::token::{Number, Token, TypedToken};
+pub(crate) use self::{stack::Stack, state::StateTracker};
@@ -1,36 +1,178 @@
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
+use core::{alloc::Layout, fmt, mem::ManuallyDrop};
 
 pub trait Stack<T> {
-    fn peek_mut(&mut self) -> Option<&mut T>;
-
     fn peek(&self) -> Option<&T>;
-
-    fn replace_top(&mut self, new_value: T);
 }
 
 impl<T> Stack<T> for Vec<T> {
-    fn peek_mut(&mut self) -> Option<&mut T> {
-        let len = self.len();
-        if len == 0 {
-            None
+    fn peek(&self) -> Option<&T> {
+        self.last()
+    }
+}
+
+/// Remove all items of a vector and change their type, keeping the memory allocated for
+/// them if both types have the same layout, e.g. if they only differ in lifetimes
+pub fn recycle<A, B>(mut vec: Vec<A>) -> Vec<B> {
+    vec.clear();
+    if Layout::new::<A>() != Layout::new::<B>() || Layout::new::<A>().size() == 0 {
+        return Vec::new();
+    }
+
+    let mut vec = ManuallyDrop::new(vec);
+    // SAFETY: The vector is empty and was allocated for `capacity` items with the same
+    // size and alignment as `B`, and the allocation is no longer owned by `vec`.
+    unsafe { Vec::from_raw_parts(vec.as_mut_ptr().cast::<B>(), 0, vec.capacity()) }
+}
+
+/// A stack which keeps its first `N` items inline and only allocates for the items
+/// beyond them, so shallow documents are tracked without touching the heap
+#[derive(Clone)]
+pub struct InlineStack<T, const N: usize> {
+    /// The bottom of the stack; the first `inline_len` slots are occupied
+    inline: [Option<T>; N],
+    inline_len: usize,
+    /// The items above the inline ones, once those are full
+    spilled: Vec<T>,
+}
+
+impl<T, const N: usize> InlineStack<T, N> {
+    pub fn new() -> Self {
+        InlineStack {
+            inline: core::array::from_fn(|_| None),
+            inline_len: 0,
+            spilled: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.inline_len + self.spilled.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn push(&mut self, item: T) {
+        if self.inline_len < N {
+            self.inline[self.inline_len] = Some(item);
+            self.inline_len += 1;
         } else {
-            Some(&mut self[len - 1])
+            self.spilled.push(item);
         }
     }
 
+    pub fn pop(&mut self) -> Option<T> {
+        if let Some(item) = self.spilled.pop() {
+            return Some(item);
+        }
+        self.inline_len = self.inline_len.checked_sub(1)?;
+        self.inline[self.inline_len].take()
+    }
+
+    /// Remove the items above the first `len` ones
+    pub fn truncate(&mut self, len: usize) {
+        while self.len() > len {
+            self.pop();
+        }
+    }
+
+    /// Iterate over the items from the bottom of the stack to its top
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.inline.iter().flatten().chain(&self.spilled)
+    }
+
+    /// Remove all items and change their type, keeping the memory allocated for spilled
+    /// items
+    pub fn recycle<U>(self) -> InlineStack<U, N> {
+        InlineStack {
+            inline: core::array::from_fn(|_| None),
+            inline_len: 0,
+            spilled: recycle(self.spilled),
+        }
+    }
+}
+
+impl<T, const N: usize> Default for InlineStack<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Stack<T> for InlineStack<T, N> {
     fn peek(&self) -> Option<&T> {
-        let len = self.len();
-        if len == 0 {
-            None
-        } else {
-            Some(&self[len - 1])
+        match self.spilled.last() {
+            Some(item) => Some(item),
+            None => self.inline[..self.inline_len].last()?.as_ref(),
+        }
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for InlineStack<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::vec;
+
+    use super::*;
+
+    #[test]
+    fn items_should_spill_beyond_the_inline_capacity() {
+        let mut stack = InlineStack::<u32, 2>::new();
+        assert_eq!(stack.pop(), None);
+
+        for item in 0..5 {
+            stack.push(item);
+            assert_eq!(stack.peek(), Some(&item));
         }
+        assert_eq!(stack.len(), 5);
+        assert_eq!(stack.iter().copied().collect::<Vec<_>>(), [0, 1, 2, 3, 4]);
+
+        stack.truncate(1);
+        assert_eq!(stack.iter().copied().collect::<Vec<_>>(), [0]);
+        stack.push(5);
+        assert_eq!(stack.pop(), Some(5));
+        assert_eq!(stack.pop(), Some(0));
+        assert!(stack.is_empty());
+        assert_eq!(stack.peek(), None);
     }
 
-    fn replace_top(&mut self, new_value: T) {
-        self.peek_mut()
-            .map(|top| *top = new_value)
-            .expect("Shouldn't replace_top with nothing on the stack");
+    #[test]
+    fn recycled_stacks_should_be_empty() {
+        let mut stack = InlineStack::<u32, 1>::new();
+        stack.push(1);
+        stack.push(2);
+
+        let stack = stack.recycle::<u64>();
+        assert!(stack.is_empty());
+        assert_eq!(stack.peek(), None);
+    }
+
+    #[test]
+    fn recycled_vectors_should_keep_their_memory() {
+        let vec = vec![1u32, 2, 3];
+        let capacity = vec.capacity();
+
+        let recycled = recycle::<u32, i32>(vec);
+        assert!(recycled.is_empty());
+        assert_eq!(recycled.capacity(), capacity);
+        assert_eq!(recycle::<u32, u64>(vec![1]).capacity(), 0);
+    }
+
+    #[test]
+    fn stacks_without_inline_items_should_know_when_they_are_empty() {
+        let mut stack = InlineStack::<u32, 0>::new();
+        assert!(stack.is_empty());
+
+        stack.push(1);
+        assert!(!stack.is_empty());
+        assert_eq!(stack.pop(), Some(1));
+        assert!(stack.is_empty());
     }
 }
@@ -1,7 +1,15 @@
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
+use alloc::{collections::BTreeSet, string::String};
 
-use crate::state_tracker::{Stack, StructureError, Token};
+use crate::state_tracker::{
+    stack::{self, InlineStack},
+    Stack, StructureError, Token,
+};
+
+/// The nesting depth up to which the states of the open lists and maps are kept inline.
+/// Deeper documents are rare, and only they need memory from the heap.
+const INLINE_DEPTH: usize = 8;
 
 /// The state of current level of the decoder
 #[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Debug)]
@@ -17,24 +25,34 @@ enum State<S: AsRef<[u8]>, E> {
 }
 
 /// Used to validate that a structure is valid
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct StateTracker<S: AsRef<[u8]>, E = StructureError> {
-    state: Vec<State<S, E>>,
+    state: InlineStack<State<S, E>, INLINE_DEPTH>,
     max_depth: usize,
+    /// The keys seen in each currently open map, innermost last. Only maintained if
+    /// unsorted keys are allowed, as duplicates can't be detected by ordering then.
+    seen_keys: Option<Vec<BTreeSet<S>>>,
+    /// Accept duplicate map keys instead of failing
+    allow_duplicate_keys: bool,
+    /// Whether the last key observed was a duplicate of an earlier key in its map
+    last_key_duplicate: bool,
 }
 
 impl<S: AsRef<[u8]>, E> Default for StateTracker<S, E> {
     fn default() -> Self {
         StateTracker {
-            state: Vec::new(),
+            state: InlineStack::new(),
             max_depth: 2048,
+            seen_keys: None,
+            allow_duplicate_keys: false,
+            last_key_duplicate: false,
         }
     }
 }
 
 impl<S: AsRef<[u8]>, E> StateTracker<S, E>
 where
-    S: AsRef<[u8]>,
+    S: AsRef<[u8]> + Ord,
     E: From<StructureError> + Clone,
 {
     pub fn new() -> Self {
@@ -45,8 +63,41 @@ where
         self.max_depth = new_max_depth
     }
 
+    /// Accept map keys in any order. Duplicate keys are still rejected.
+    pub fn set_allow_unsorted_keys(&mut self, allow: bool) {
+        self.seen_keys = if allow { Some(Vec::new()) } else { None };
+    }
+
+    pub fn allows_unsorted_keys(&self) -> bool {
+        self.seen_keys.is_some()
+    }
+
+    /// Accept duplicate map keys. Use [`StateTracker::last_key_was_duplicate`] to find out
+    /// about them.
+    pub fn set_allow_duplicate_keys(&mut self, allow: bool) {
+        self.allow_duplicate_keys = allow;
+    }
+
+    /// Whether the last map key observed was already seen in the same map
+    pub fn last_key_was_duplicate(&self) -> bool {
+        self.last_key_duplicate
+    }
+
+    /// Whether the next token has to be a map key
+    pub fn expects_key(&self) -> bool {
+        matches!(self.state.peek(), Some(State::MapKey(_)))
+    }
+
+    /// The number of currently open lists and maps
+    pub fn depth(&self) -> usize {
+        match self.state.peek() {
+            Some(State::Failed(_)) => self.state.len() - 1,
+            _ => self.state.len(),
+        }
+    }
+
     pub fn remaining_depth(&self) -> usize {
-        self.max_depth - self.state.len()
+        self.max_depth.saturating_sub(self.state.len())
     }
 
     /// Observe that an EOF was seen. This function is idempotent.
@@ -60,8 +111,9 @@ where
         }
     }
 
+    /// Observe a token starting at the given position, which is reported in any error
     #[allow(clippy::match_same_arms)]
-    pub fn observe_token<'a>(&mut self, token: &Token<'a>) -> Result<(), E>
+    pub fn observe_token<'a>(&mut self, token: &Token<'a>, position: usize) -> Result<(), E>
     where
         S: From<&'a [u8]>,
     {
@@ -69,46 +121,57 @@ where
 
         match (self.state.pop(), *token) {
             (None, End) => {
-                return self.latch_err(Err(E::from(StructureError::invalid_state(
-                    "End not allowed at top level",
-                ))));
+                return self.latch_err(Err(E::from(StructureError::UnexpectedEnd { position })));
             },
             (Some(Seq), End) => {},
-            (Some(MapKey(_)), End) => {},
+            (Some(MapKey(_)), End) => {
+                if let Some(seen_keys) = self.seen_keys.as_mut() {
+                    seen_keys.pop();
+                }
+            },
             (Some(MapKey(None)), String(label)) => {
+                self.observe_key(label, position)?;
                 self.state.push(MapValue(S::from(label)));
             },
             (Some(MapKey(Some(oldlabel))), String(label)) => {
-                if oldlabel.as_ref() >= label {
-                    return self.latch_err(Err(E::from(StructureError::UnsortedKeys)));
+                if self.seen_keys.is_none() {
+                    let duplicate = oldlabel.as_ref() == label;
+                    if oldlabel.as_ref() > label || (duplicate && !self.allow_duplicate_keys) {
+                        return self
+                            .latch_err(Err(E::from(StructureError::UnsortedKeys { position })));
+                    }
+                    self.last_key_duplicate = duplicate;
+                } else {
+                    self.observe_key(label, position)?;
                 }
                 self.state.push(MapValue(S::from(label)));
             },
-            (Some(oldstate @ MapKey(_)), _tok) => {
+            (Some(oldstate @ MapKey(_)), tok) => {
                 self.state.push(oldstate);
-                return self.latch_err(Err(E::from(StructureError::invalid_state(
-                    "Map keys must be strings",
-                ))));
+                return self.latch_err(Err(E::from(StructureError::NonStringKey {
+                    found: tok.name(),
+                    position,
+                })));
             },
             (Some(MapValue(label)), List) => {
                 self.state.push(MapKey(Some(label)));
                 if self.state.len() >= self.max_depth {
-                    return self.latch_err(Err(E::from(StructureError::NestingTooDeep)));
+                    return self
+                        .latch_err(Err(E::from(StructureError::NestingTooDeep { position })));
                 }
                 self.state.push(Seq);
             },
             (Some(MapValue(label)), Dict) => {
                 self.state.push(MapKey(Some(label)));
                 if self.state.len() >= self.max_depth {
-                    return self.latch_err(Err(E::from(StructureError::NestingTooDeep)));
+                    return self
+                        .latch_err(Err(E::from(StructureError::NestingTooDeep { position })));
                 }
-                self.state.push(MapKey(None));
+                self.open_map();
             },
             (Some(oldstate @ MapValue(_)), End) => {
                 self.state.push(oldstate);
-                return self.latch_err(Err(E::from(StructureError::invalid_state(
-                    "Missing map value",
-                ))));
+                return self.latch_err(Err(E::from(StructureError::MissingValue { position })));
             },
             (Some(MapValue(label)), _) => {
                 self.state.push(MapKey(Some(label)));
@@ -118,7 +181,8 @@ where
                     self.state.push(oldstate);
                 }
                 if self.state.len() >= self.max_depth {
-                    return self.latch_err(Err(E::from(StructureError::NestingTooDeep)));
+                    return self
+                        .latch_err(Err(E::from(StructureError::NestingTooDeep { position })));
                 }
                 self.state.push(Seq);
             },
@@ -128,9 +192,10 @@ where
                 }
 
                 if self.state.len() >= self.max_depth {
-                    return self.latch_err(Err(E::from(StructureError::NestingTooDeep)));
+                    return self
+                        .latch_err(Err(E::from(StructureError::NestingTooDeep { position })));
                 }
-                self.state.push(MapKey(None));
+                self.open_map();
             },
             (oldstate, _) => {
                 if let Some(oldstate) = oldstate {
@@ -141,6 +206,53 @@ where
         Ok(())
     }
 
+    fn open_map(&mut self) {
+        self.state.push(State::MapKey(None));
+        if let Some(seen_keys) = self.seen_keys.as_mut() {
+            seen_keys.push(BTreeSet::new());
+        }
+    }
+
+    /// Record a key of the innermost map, rejecting duplicates. Only does anything
+    /// if unsorted keys are allowed; otherwise the sort order check covers duplicates.
+    fn observe_key<'a>(&mut self, label: &'a [u8], position: usize) -> Result<(), E>
+    where
+        S: From<&'a [u8]>,
+    {
+        let is_new = match self.seen_keys.as_mut().and_then(|seen| seen.last_mut()) {
+            Some(keys) => keys.insert(S::from(label)),
+            None => true,
+        };
+        self.last_key_duplicate = !is_new;
+
+        if is_new || self.allow_duplicate_keys {
+            Ok(())
+        } else {
+            self.latch_err(Err(E::from(StructureError::DuplicateKey {
+                key: String::from_utf8_lossy(label).into_owned(),
+                position,
+            })))
+        }
+    }
+
+    /// Forget a latched error and close all but the outermost `depth` lists and maps, so
+    /// that decoding can continue after the input was resynchronized
+    pub fn recover(&mut self, depth: usize) {
+        if let Some(State::Failed(_)) = self.state.peek() {
+            self.state.pop();
+        }
+        self.state.truncate(depth);
+
+        if let Some(seen_keys) = self.seen_keys.as_mut() {
+            let maps = self
+                .state
+                .iter()
+                .filter(|state| matches!(state, State::MapKey(_) | State::MapValue(_)))
+                .count();
+            seen_keys.truncate(maps);
+        }
+    }
+
     pub fn latch_err<T>(&mut self, result: Result<T, E>) -> Result<T, E> {
         self.check_error()?;
         if let Err(ref err) = result {
@@ -149,8 +261,29 @@ where
         result
     }
 
+    /// Whether an error was latched
+    #[cfg(feature = "tracing")]
+    pub fn is_failed(&self) -> bool {
+        matches!(self.state.peek(), Some(State::Failed(_)))
+    }
+
+    /// Forget all state and change the type of the keys the tracker holds, keeping the
+    /// memory allocated for its stacks where possible
+    pub fn recycle<T>(self) -> StateTracker<T, E>
+    where
+        T: AsRef<[u8]>,
+    {
+        StateTracker {
+            state: self.state.recycle(),
+            max_depth: self.max_depth,
+            seen_keys: self.seen_keys.map(stack::recycle),
+            allow_duplicate_keys: self.allow_duplicate_keys,
+            last_key_duplicate: false,
+        }
+    }
+
     pub fn check_error(&self) -> Result<(), E> {
-        if let Some(&State::Failed(ref error)) = self.state.peek() {
+        if let Some(State::Failed(error)) = self.state.peek() {
             Err(error.clone())
         } else {
             Ok(())
@@ -1,8 +1,5 @@
 #[cfg(not(feature = "std"))]
-use alloc::{
-    format,
-    string::{String, ToString},
-};
+use alloc::string::{String, ToString};
 #[cfg(not(feature = "std"))]
 use core::fmt::Display;
 #[cfg(feature = "std")]
@@ -11,34 +8,96 @@ use std::fmt::Display;
 use failure::Fail;
 
 /// An encoding or decoding error
+///
+/// Errors found in the input carry the `position` they were detected at, as a byte offset
+/// into the input (including any base set with
+/// [`Decoder::with_offset_base`](crate::decoding::Decoder::with_offset_base)). Errors
+/// raised by an encoder report the number of bytes the encoder had written instead.
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Fail)]
+#[non_exhaustive]
 pub enum StructureError {
-    #[fail(display = "Saw the wrong type of token: {}", _0)]
-    /// Wrong type of token detected.
-    InvalidState(String),
-    #[fail(display = "Keys were not sorted")]
+    #[fail(display = "End not allowed at top level at offset {}", position)]
+    /// The end of a list or dictionary outside of any of them.
+    UnexpectedEnd { position: usize },
+    #[fail(
+        display = "Map keys must be strings, got {} at offset {}",
+        found, position
+    )]
+    /// A dictionary key which isn't a byte string.
+    NonStringKey {
+        found: &'static str,
+        position: usize,
+    },
+    #[fail(display = "Missing map value at offset {}", position)]
+    /// A dictionary ended after a key, without its value.
+    MissingValue { position: usize },
+    #[fail(display = "Duplicate key {} at offset {}", key, position)]
+    /// A key occurred more than once in the same dictionary.
+    DuplicateKey { key: String, position: usize },
+    #[fail(display = "Keys were not sorted at offset {}", position)]
     /// Keys were not sorted.
-    UnsortedKeys,
+    UnsortedKeys { position: usize },
     #[fail(display = "Reached EOF in the middle of a message")]
     /// EOF reached to early.
     UnexpectedEof,
-    #[fail(display = "Malformed number of unexpected character: {}", _0)]
-    /// Unexpected characters detected.
-    SyntaxError(String),
-    #[fail(display = "Maximum nesting depth exceeded")]
+    #[fail(
+        display = "Expected {}, got {:?} at offset {}",
+        expected, found, position
+    )]
+    /// A character which isn't allowed at this point of an integer or string length.
+    UnexpectedCharacter {
+        expected: String,
+        found: char,
+        position: usize,
+    },
+    #[fail(
+        display = "Invalid token starting with {:?} at offset {}",
+        found, position
+    )]
+    /// A character which doesn't start any token.
+    InvalidToken { found: char, position: usize },
+    #[fail(display = "Invalid string length at offset {}", position)]
+    /// A string length which doesn't fit into a `usize`.
+    InvalidLength { position: usize },
+    #[fail(display = "Trailing data at offset {}", position)]
+    /// Data after the end of the first value, with strict EOF handling enabled.
+    TrailingData { position: usize },
+    #[fail(display = "Maximum nesting depth exceeded at offset {}", position)]
     /// Exceeded the recursion limit.
-    NestingTooDeep,
+    NestingTooDeep { position: usize },
+    #[fail(display = "No value was emitted")]
+    /// An encoder callback returned without emitting a value.
+    NoValueEmitted,
+    #[fail(display = "Flattened values must encode to a dictionary")]
+    /// A flattened value didn't encode to a dictionary.
+    FlattenedNotDict,
 }
 
 impl StructureError {
-    pub fn unexpected(expected: impl Display, got: char, offset: usize) -> Self {
-        StructureError::SyntaxError(format!(
-            "Expected {}, got {:?} at offset {}",
-            expected, got, offset
-        ))
+    pub fn unexpected(expected: impl Display, found: char, position: usize) -> Self {
+        StructureError::UnexpectedCharacter {
+            expected: expected.to_string(),
+            found,
+            position,
+        }
     }
 
-    pub fn invalid_state(expected: impl Display) -> Self {
-        StructureError::InvalidState(expected.to_string())
+    /// The byte offset the error was detected at, if it carries one
+    pub fn position(&self) -> Option<usize> {
+        match *self {
+            StructureError::UnexpectedEnd { position }
+            | StructureError::NonStringKey { position, .. }
+            | StructureError::MissingValue { position }
+            | StructureError::DuplicateKey { position, .. }
+            | StructureError::UnexpectedCharacter { position, .. }
+            | StructureError::InvalidToken { position, .. }
+            | StructureError::InvalidLength { position }
+            | StructureError::TrailingData { position }
+            | StructureError::UnsortedKeys { position }
+            | StructureError::NestingTooDeep { position } => Some(position),
+            StructureError::UnexpectedEof
+            | StructureError::NoValueEmitted
+            | StructureError::FlattenedNotDict => None,
+        }
     }
 }
@@ -24,3 +24,90 @@ impl<'a> Token<'a> {
         }
     }
 }
+
+/// A raw bencode token with integers classified by the smallest type they fit into
+///
+/// Produced by [`Decoder::typed_tokens`](crate::decoding::Decoder::typed_tokens).
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum TypedToken<'a> {
+    /// The beginning of a list
+    List,
+    /// The beginning of a dictionary
+    Dict,
+    /// A byte string; may not be UTF-8
+    String(&'a [u8]),
+    /// A classified number
+    Num(Number<'a>),
+    /// The end of a list or dictionary
+    End,
+}
+
+/// An integer classified by the smallest type it fits into
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Number<'a> {
+    /// An integer in the range of `i64`
+    I64(i64),
+    /// A positive integer too large for `i64` but in the range of `u64`
+    U64(u64),
+    /// An integer outside the range of both `i64` and `u64`; left unparsed
+    Big(&'a str),
+}
+
+impl<'a> Number<'a> {
+    /// Classify the (already validated) digits of a bencode integer
+    pub fn classify(digits: &'a str) -> Self {
+        if let Ok(value) = digits.parse() {
+            Number::I64(value)
+        } else if let Ok(value) = digits.parse() {
+            Number::U64(value)
+        } else {
+            Number::Big(digits)
+        }
+    }
+}
+
+impl<'a> From<Token<'a>> for TypedToken<'a> {
+    fn from(token: Token<'a>) -> Self {
+        match token {
+            Token::List => TypedToken::List,
+            Token::Dict => TypedToken::Dict,
+            Token::String(bytes) => TypedToken::String(bytes),
+            Token::Num(digits) => TypedToken::Num(Number::classify(digits)),
+            Token::End => TypedToken::End,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn numbers_are_classified_by_range() {
+        assert_eq!(Number::classify("0"), Number::I64(0));
+        assert_eq!(
+            Number::classify("-9223372036854775808"),
+            Number::I64(i64::MIN)
+        );
+        assert_eq!(
+            Number::classify("9223372036854775807"),
+            Number::I64(i64::MAX)
+        );
+        assert_eq!(
+            Number::classify("9223372036854775808"),
+            Number::U64(1 << 63)
+        );
+        assert_eq!(
+            Number::classify("18446744073709551615"),
+            Number::U64(u64::MAX)
+        );
+        assert_eq!(
+            Number::classify("18446744073709551616"),
+            Number::Big("18446744073709551616")
+        );
+        assert_eq!(
+            Number::classify("-9223372036854775809"),
+            Number::Big("-9223372036854775809")
+        );
+    }
+}
@@ -0,0 +1,106 @@
+//! Typed torrent metainfo, as described by [BEP 3]
+//!
+//! [`MetaInfo`] decodes a `.torrent` file and encodes it back with [`FromBencode`] and
+//! [`ToBencode`]:
+//!
+//! ```
+//! use bendy::{decoding::FromBencode, torrent::{Layout, MetaInfo}};
+//!
+//! let torrent = MetaInfo::from_bencode(
+//!     b"d8:announce15:http://tracker/4:infod6:lengthi20e4:name5:a.txt\
+//!       12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee",
+//! )
+//! .unwrap();
+//!
+//! assert_eq!(torrent.trackers().collect::<Vec<_>>(), vec!["http://tracker/"]);
+//! assert_eq!(torrent.info.name, "a.txt");
+//! assert_eq!(torrent.info.layout, Some(Layout::SingleFile { length: 20 }));
+//! assert_eq!(torrent.info.piece_hashes().count(), 1);
+//! ```
+//!
+//! [`hash_chunks`] splits concatenated piece hashes into fixed-size arrays, checking
+//! that none is truncated.
+//!
+//! [`AnnounceList`] holds the tiers of trackers of [BEP 12] and the bookkeeping clients
+//! do on them, like promoting trackers which responded.
+//!
+//! Keys which aren't covered by these types are skipped, so encoding a decoded torrent
+//! doesn't necessarily reproduce its input. Compute info-hashes over the original bytes
+//! of the info dictionary instead, as [`info_hash`] does with the `sha-1` feature.
+//!
+//! With the `std` feature, [`Info::files`] lists the files of a torrent with sanitized
+//! relative paths, rejecting paths which would escape the download directory.
+//!
+//! With the `std` feature, [`AnnounceResponse`] and [`ScrapeResponse`] decode the
+//! responses of HTTP trackers, expanding compact peer lists into socket addresses and
+//! keying scrape statistics by binary [`InfoHash`]es. The compact peer and node formats
+//! they use are available on their own, e.g. as [`decode_compact_peers`].
+//! [`ResumeData`] reads and writes the fast-resume files of libtorrent.
+//!
+//! [`ExtensionHandshake`] is the bencoded handshake of the extension protocol peers
+//! exchange after connecting. [`MetadataMessage`] covers its metadata exchange, which
+//! fetches the info dictionary of a torrent from peers.
+//!
+//! [`MagnetLink`] parses and writes magnet links, and with the `sha-1` feature creates
+//! them from torrent files.
+//!
+//! With the `create-torrent` feature, [`TorrentBuilder`] creates the metainfo of a file or
+//! directory on disk, hashing its pieces on as many threads as requested.
+//!
+//! BitTorrent v2 torrents, described by [BEP 52], list their files in a [`FileTree`]
+//! instead of a [`Layout`], and hybrid torrents carry both. [`Info::version`] tells
+//! them apart, and [`info_hash_v2`] computes their SHA-256 info-hash with the `sha2`
+//! feature.
+//!
+//! [BEP 3]: http://bittorrent.org/beps/bep_0003.html
+//! [BEP 12]: http://bittorrent.org/beps/bep_0012.html
+//! [BEP 52]: http://bittorrent.org/beps/bep_0052.html
+//! [`FromBencode`]: crate::decoding::FromBencode
+//! [`ToBencode`]: crate::encoding::ToBencode
+
+mod announce;
+#[cfg(feature = "create-torrent")]
+mod builder;
+#[cfg(feature = "std")]
+mod compact;
+#[cfg(feature = "std")]
+mod extension;
+#[cfg(feature = "std")]
+mod files;
+mod hashes;
+mod info_hash;
+mod magnet;
+mod metadata;
+mod metainfo;
+#[cfg(feature = "std")]
+mod resume;
+#[cfg(feature = "std")]
+mod tracker;
+mod v2;
+
+#[cfg(feature = "create-torrent")]
+pub use self::builder::TorrentBuilder;
+#[cfg(feature = "std")]
+pub use self::compact::{
+    decode_compact_nodes, decode_compact_nodes6, decode_compact_peer, decode_compact_peers,
+    decode_compact_peers6, encode_compact_nodes, encode_compact_peer, encode_compact_peers, NodeId,
+};
+#[cfg(feature = "std")]
+pub use self::extension::ExtensionHandshake;
+#[cfg(feature = "sha-1")]
+pub use self::info_hash::info_hash;
+#[cfg(feature = "sha2")]
+pub use self::info_hash::info_hash_v2;
+#[cfg(feature = "std")]
+pub use self::resume::ResumeData;
+#[cfg(feature = "std")]
+pub use self::tracker::{Announce, AnnounceResponse, Scrape, ScrapeResponse, ScrapeStats};
+pub use self::{
+    announce::AnnounceList,
+    hashes::{hash_chunks, SHA1_HASH_LENGTH, SHA256_HASH_LENGTH},
+    info_hash::{info_bytes, InfoHash},
+    magnet::MagnetLink,
+    metadata::{MetadataMessage, METADATA_PIECE_LENGTH},
+    metainfo::{FileEntry, Info, Layout, MetaInfo, Version},
+    v2::{FileNode, FileTree, PieceLayers},
+};
@@ -0,0 +1,205 @@
+#[cfg(not(feature = "std"))]
+use alloc::{collections::BTreeSet, string::String, vec, vec::Vec};
+#[cfg(feature = "std")]
+use std::collections::BTreeSet;
+
+use crate::{
+    decoding::{self, FromBencode, Object},
+    encoding::{self, SingleItemEncoder, ToBencode},
+};
+
+/// Tiers of tracker URLs, see [BEP 12](http://bittorrent.org/beps/bep_0012.html)
+///
+/// Clients try the tiers in order, and the trackers of a tier in order until one of
+/// them responds. A tracker which responds is [promoted](AnnounceList::promote) to the
+/// front of its tier, so it's tried first next time. BEP 12 also asks clients to shuffle
+/// each tier once when loading a torrent, which is left to the caller.
+///
+/// ```
+/// use bendy::torrent::AnnounceList;
+///
+/// let mut list = AnnounceList::new()
+///     .with_tier(vec!["http://a/".into(), "http://b/".into()])
+///     .with_tier(vec!["http://c/".into(), "http://a/".into()]);
+///
+/// list.dedup();
+/// assert!(list.promote("http://b/"));
+/// assert_eq!(
+///     list.trackers().collect::<Vec<_>>(),
+///     ["http://b/", "http://a/", "http://c/"]
+/// );
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AnnounceList(pub Vec<Vec<String>>);
+
+impl AnnounceList {
+    /// Create an empty announce list
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Combine the `announce` URL and the `announce-list` of a torrent into the tiers
+    /// clients use: the announce list if it has any trackers, as BEP 12 says to ignore
+    /// the announce URL then, and otherwise the announce URL in a tier of its own
+    pub fn from_announce(announce: Option<&str>, announce_list: Option<&AnnounceList>) -> Self {
+        match announce_list {
+            Some(list) if !list.is_empty() => list.clone(),
+            _ => AnnounceList(announce.map(|url| vec![url.into()]).into_iter().collect()),
+        }
+    }
+
+    /// Append a tier of trackers. Empty tiers are skipped.
+    #[must_use]
+    pub fn with_tier(mut self, tier: Vec<String>) -> Self {
+        if !tier.is_empty() {
+            self.0.push(tier);
+        }
+        self
+    }
+
+    /// The tiers of tracker URLs, in order
+    pub fn tiers(&self) -> &[Vec<String>] {
+        &self.0
+    }
+
+    /// The URLs of all trackers, in the order they are tried
+    pub fn trackers(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().flatten().map(String::as_str)
+    }
+
+    /// The first tracker, which torrent creators also write as the `announce` URL for
+    /// clients without support for announce lists
+    pub fn primary(&self) -> Option<&str> {
+        self.trackers().next()
+    }
+
+    /// Whether there are no trackers in any tier
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(Vec::is_empty)
+    }
+
+    /// Remove repeated tracker URLs, keeping the first occurrence, and the tiers which
+    /// are left empty
+    pub fn dedup(&mut self) {
+        let mut seen = BTreeSet::new();
+        for tier in &mut self.0 {
+            tier.retain(|url| seen.insert(url.clone()));
+        }
+        self.0.retain(|tier| !tier.is_empty());
+    }
+
+    /// Move a tracker which responded to the front of its tier. Returns whether the
+    /// tracker was found.
+    pub fn promote(&mut self, url: &str) -> bool {
+        for tier in &mut self.0 {
+            if let Some(index) = tier.iter().position(|tracker| tracker == url) {
+                tier[..=index].rotate_right(1);
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Append the tiers of another announce list, without the trackers which are
+    /// already part of this one
+    pub fn merge(&mut self, other: &AnnounceList) {
+        let mut seen = self.trackers().map(String::from).collect::<BTreeSet<_>>();
+        for tier in &other.0 {
+            let tier = tier
+                .iter()
+                .filter(|url| seen.insert((*url).clone()))
+                .cloned()
+                .collect::<Vec<_>>();
+            if !tier.is_empty() {
+                self.0.push(tier);
+            }
+        }
+    }
+}
+
+impl From<Vec<Vec<String>>> for AnnounceList {
+    fn from(tiers: Vec<Vec<String>>) -> Self {
+        AnnounceList(tiers)
+    }
+}
+
+impl FromBencode for AnnounceList {
+    const EXPECTED_RECURSION_DEPTH: usize = 2;
+
+    fn decode_bencode_object(object: Object) -> Result<Self, decoding::Error>
+    where
+        Self: Sized,
+    {
+        Vec::decode_bencode_object(object).map(AnnounceList)
+    }
+}
+
+impl ToBencode for AnnounceList {
+    const MAX_DEPTH: usize = 2;
+
+    fn encode(&self, encoder: SingleItemEncoder) -> Result<(), encoding::Error> {
+        encoder.emit(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::borrow::ToOwned;
+
+    use super::*;
+
+    fn tiers(tiers: &[&[&str]]) -> AnnounceList {
+        AnnounceList(
+            tiers
+                .iter()
+                .map(|tier| tier.iter().map(|&url| url.to_owned()).collect())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn announce_should_only_be_used_without_a_list() {
+        let list = tiers(&[&["http://a/"], &["http://b/"]]);
+        assert_eq!(
+            AnnounceList::from_announce(Some("http://x/"), Some(&list)),
+            list
+        );
+        assert_eq!(
+            AnnounceList::from_announce(Some("http://x/"), Some(&tiers(&[&[]]))),
+            tiers(&[&["http://x/"]])
+        );
+        assert_eq!(
+            AnnounceList::from_announce(Some("http://x/"), None),
+            tiers(&[&["http://x/"]])
+        );
+        assert!(AnnounceList::from_announce(None, None).is_empty());
+    }
+
+    #[test]
+    fn promoted_trackers_should_keep_the_order_of_the_others() {
+        let mut list = tiers(&[&["http://a/"], &["http://b/", "http://c/", "http://d/"]]);
+        assert!(list.promote("http://d/"));
+        assert_eq!(
+            list,
+            tiers(&[&["http://a/"], &["http://d/", "http://b/", "http://c/"]])
+        );
+        assert!(!list.promote("http://e/"));
+    }
+
+    #[test]
+    fn merging_should_skip_known_trackers() {
+        let mut list = tiers(&[&["http://a/", "http://b/"]]);
+        list.merge(&tiers(&[&["http://b/"], &["http://c/", "http://a/"]]));
+        assert_eq!(list, tiers(&[&["http://a/", "http://b/"], &["http://c/"]]));
+    }
+
+    #[test]
+    fn lists_should_round_trip() {
+        let list = tiers(&[&["http://a/", "udp://b:80"], &["http://c/"]]);
+        let encoded = list.to_bencode().unwrap();
+        assert_eq!(encoded, &b"ll9:http://a/10:udp://b:80el9:http://c/ee"[..]);
+        assert_eq!(AnnounceList::from_bencode(&encoded).unwrap(), list);
+        assert_eq!(list.primary(), Some("http://a/"));
+    }
+}
@@ -0,0 +1,474 @@
+use std::{
+    cmp, fs,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    ops::Range,
+    path::{Path, PathBuf},
+    thread,
+};
+
+use sha1_::{Digest, Sha1};
+
+use crate::{
+    encoding::Error,
+    torrent::{AnnounceList, FileEntry, Info, Layout, MetaInfo},
+};
+
+/// The default number of bytes in each piece
+const DEFAULT_PIECE_LENGTH: u64 = 256 * 1024;
+
+/// Create the metainfo of a torrent sharing a file or a directory
+///
+/// The data is split into pieces of the configured length, which are hashed with SHA-1,
+/// optionally on several threads. The files of a directory are listed in the order of
+/// their paths, so building the same directory again yields the same torrent.
+///
+/// ```
+/// use bendy::{encoding::ToBencode, torrent::TorrentBuilder};
+/// # use std::fs;
+/// #
+/// # let path = std::env::temp_dir().join("bendy-builder-doctest.txt");
+/// # fs::write(&path, b"hello world").unwrap();
+///
+/// let torrent = TorrentBuilder::new(&path)
+///     .with_piece_length(16 * 1024)
+///     .with_tracker("http://tracker.example/announce")
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(torrent.info.total_length(), 11);
+/// assert_eq!(torrent.info.piece_hashes().count(), 1);
+/// let _file = torrent.to_bencode().unwrap();
+/// # fs::remove_file(&path).unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct TorrentBuilder {
+    path: PathBuf,
+    name: Option<String>,
+    piece_length: u64,
+    trackers: AnnounceList,
+    comment: Option<String>,
+    created_by: Option<String>,
+    creation_date: Option<i64>,
+    private: bool,
+    threads: usize,
+}
+
+impl TorrentBuilder {
+    /// Create a builder for a torrent sharing the file or directory at the given path
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        TorrentBuilder {
+            path: path.into(),
+            name: None,
+            piece_length: DEFAULT_PIECE_LENGTH,
+            trackers: AnnounceList::new(),
+            comment: None,
+            created_by: None,
+            creation_date: None,
+            private: false,
+            threads: 1,
+        }
+    }
+
+    /// Set the name of the torrent. Defaults to the name of the file or directory.
+    #[must_use]
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Set the number of bytes in each piece, which has to be a power of two of at
+    /// least 16 KiB. Defaults to 256 KiB.
+    #[must_use]
+    pub fn with_piece_length(mut self, piece_length: u64) -> Self {
+        self.piece_length = piece_length;
+        self
+    }
+
+    /// Add a tracker in a tier of its own. The first tracker is also the announce URL;
+    /// an announce list is only written if there are several trackers.
+    #[must_use]
+    pub fn with_tracker(self, url: impl Into<String>) -> Self {
+        self.with_tracker_tier(vec![url.into()])
+    }
+
+    /// Add a tier of trackers which are tried in any order, see
+    /// [BEP 12](http://bittorrent.org/beps/bep_0012.html)
+    #[must_use]
+    pub fn with_tracker_tier(mut self, urls: Vec<String>) -> Self {
+        self.trackers = self.trackers.with_tier(urls);
+        self
+    }
+
+    /// Set a free-form comment
+    #[must_use]
+    pub fn with_comment(mut self, comment: impl Into<String>) -> Self {
+        self.comment = Some(comment.into());
+        self
+    }
+
+    /// Set the program creating the torrent
+    #[must_use]
+    pub fn with_created_by(mut self, created_by: impl Into<String>) -> Self {
+        self.created_by = Some(created_by.into());
+        self
+    }
+
+    /// Set the creation time, in seconds since the Unix epoch. No time is written by
+    /// default, so building a torrent is reproducible.
+    #[must_use]
+    pub fn with_creation_date(mut self, creation_date: i64) -> Self {
+        self.creation_date = Some(creation_date);
+        self
+    }
+
+    /// Only allow peers from the trackers, see
+    /// [BEP 27](http://bittorrent.org/beps/bep_0027.html)
+    #[must_use]
+    pub fn with_private(mut self, private: bool) -> Self {
+        self.private = private;
+        self
+    }
+
+    /// Hash the pieces on the given number of threads. Defaults to a single thread.
+    #[must_use]
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = cmp::max(threads, 1);
+        self
+    }
+
+    /// Read and hash the data and create the metainfo. Encode it with
+    /// [`ToBencode::to_bencode`](crate::encoding::ToBencode::to_bencode) to get the
+    /// contents of the `.torrent` file.
+    pub fn build(&self) -> Result<MetaInfo, Error> {
+        if !self.piece_length.is_power_of_two() || self.piece_length < 16 * 1024 {
+            return Err(Error::malformed_content(failure::format_err!(
+                "piece length {} is not a power of two of at least 16 KiB",
+                self.piece_length
+            )));
+        }
+
+        let name = match &self.name {
+            Some(name) => name.clone(),
+            None => match self.path.file_name().map(|name| name.to_str()) {
+                Some(Some(name)) => name.to_owned(),
+                _ => return Err(non_utf8_path(&self.path)),
+            },
+        };
+
+        let (layout, files) = if fs::metadata(&self.path)?.is_dir() {
+            let mut files = Vec::new();
+            collect_files(&self.path, &mut Vec::new(), &mut files)?;
+            if files.is_empty() {
+                return Err(Error::malformed_content(failure::format_err!(
+                    "directory {} contains no files",
+                    self.path.display()
+                )));
+            }
+
+            let entries = files.iter().map(|(entry, _)| entry.clone()).collect();
+            let files = files.into_iter().map(|(entry, path)| (path, entry.length));
+            (Layout::MultiFile { files: entries }, files.collect())
+        } else {
+            let length = fs::metadata(&self.path)?.len();
+            (
+                Layout::SingleFile { length },
+                vec![(self.path.clone(), length)],
+            )
+        };
+
+        let info = Info {
+            name,
+            piece_length: self.piece_length,
+            pieces: Some(self.hash_pieces(&files)?),
+            private: self.private,
+            layout: Some(layout),
+            meta_version: None,
+            file_tree: None,
+        };
+
+        Ok(MetaInfo {
+            announce: self.trackers.primary().map(String::from),
+            announce_list: Some(self.trackers.clone()).filter(|list| list.trackers().count() > 1),
+            comment: self.comment.clone(),
+            created_by: self.created_by.clone(),
+            creation_date: self.creation_date,
+            info,
+            piece_layers: None,
+        })
+    }
+
+    /// Hash all pieces of the concatenated files, splitting them evenly between the
+    /// threads
+    fn hash_pieces(&self, files: &[(PathBuf, u64)]) -> Result<Vec<u8>, Error> {
+        let total_length: u64 = files.iter().map(|(_, length)| length).sum();
+        let pieces = total_length.div_ceil(self.piece_length);
+        if self.threads == 1 || pieces < 2 {
+            return Ok(hash_range(files, self.piece_length, 0..pieces)?);
+        }
+
+        let per_thread = pieces.div_ceil(self.threads as u64);
+        let hashes = thread::scope(|scope| {
+            let threads: Vec<_> = (0..pieces)
+                .step_by(per_thread as usize)
+                .map(|start| {
+                    let range = start..cmp::min(start + per_thread, pieces);
+                    scope.spawn(move || hash_range(files, self.piece_length, range))
+                })
+                .collect();
+
+            threads
+                .into_iter()
+                .map(|thread| thread.join().expect("hashing thread panicked"))
+                .collect::<io::Result<Vec<_>>>()
+        })?;
+        Ok(hashes.concat())
+    }
+}
+
+/// Hash the given pieces of the concatenated files
+fn hash_range(
+    files: &[(PathBuf, u64)],
+    piece_length: u64,
+    pieces: Range<u64>,
+) -> io::Result<Vec<u8>> {
+    let total_length: u64 = files.iter().map(|(_, length)| length).sum();
+    let mut reader = ConcatReader::new(files, pieces.start * piece_length)?;
+    let mut piece = vec![0; piece_length as usize];
+    let mut hashes = Vec::with_capacity((pieces.end - pieces.start) as usize * 20);
+
+    for index in pieces {
+        let length = cmp::min(piece_length, total_length - index * piece_length) as usize;
+        reader.read_exact(&mut piece[..length])?;
+        hashes.extend_from_slice(&Sha1::digest(&piece[..length]));
+    }
+    Ok(hashes)
+}
+
+/// Collect the files below a directory, sorted by their paths, as entries of the
+/// torrent along with their paths on disk
+fn collect_files(
+    directory: &Path,
+    prefix: &mut Vec<String>,
+    files: &mut Vec<(FileEntry, PathBuf)>,
+) -> Result<(), Error> {
+    let mut children = fs::read_dir(directory)?
+        .map(|child| child.map(|child| child.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    children.sort();
+
+    for child in children {
+        let name = match child.file_name().and_then(|name| name.to_str()) {
+            Some(name) => name.to_owned(),
+            None => return Err(non_utf8_path(&child)),
+        };
+        prefix.push(name);
+
+        let metadata = fs::metadata(&child)?;
+        if metadata.is_dir() {
+            collect_files(&child, prefix, files)?;
+        } else {
+            let entry = FileEntry {
+                length: metadata.len(),
+                path: prefix.clone(),
+                attr: None,
+            };
+            files.push((entry, child));
+        }
+
+        prefix.pop();
+    }
+    Ok(())
+}
+
+fn non_utf8_path(path: &Path) -> Error {
+    Error::malformed_content(failure::format_err!(
+        "path {} is not valid UTF-8",
+        path.display()
+    ))
+}
+
+/// Reads the concatenated contents of files, failing if a file is shorter than
+/// expected
+struct ConcatReader<'a> {
+    files: &'a [(PathBuf, u64)],
+    current: Option<io::Take<File>>,
+}
+
+impl<'a> ConcatReader<'a> {
+    /// Open the files for reading from the given offset into their concatenation
+    fn new(mut files: &'a [(PathBuf, u64)], mut offset: u64) -> io::Result<Self> {
+        while let Some(((_, length), rest)) = files.split_first() {
+            if offset < *length {
+                break;
+            }
+            offset -= length;
+            files = rest;
+        }
+
+        let mut reader = ConcatReader {
+            files,
+            current: None,
+        };
+        if let Some(file) = reader.open_next()? {
+            file.get_mut().seek(SeekFrom::Start(offset))?;
+            file.set_limit(file.limit() - offset);
+        }
+        Ok(reader)
+    }
+
+    fn open_next(&mut self) -> io::Result<Option<&mut io::Take<File>>> {
+        self.current = match self.files.split_first() {
+            Some(((path, length), rest)) => {
+                self.files = rest;
+                Some(File::open(path)?.take(*length))
+            },
+            None => None,
+        };
+        Ok(self.current.as_mut())
+    }
+}
+
+impl Read for ConcatReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while let Some(file) = &mut self.current {
+            if file.limit() == 0 {
+                self.open_next()?;
+                continue;
+            }
+
+            let read = file.read(buf)?;
+            if read == 0 && !buf.is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "file changed while hashing",
+                ));
+            }
+            return Ok(read);
+        }
+        Ok(0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{decoding::FromBencode, encoding::ToBencode};
+
+    /// A directory below the temporary directory, removed when dropped
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("bendy-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+
+        fn write(&self, path: &str, length: usize) -> Vec<u8> {
+            let data: Vec<u8> = (0..length).map(|i| (i % 251) as u8).collect();
+            let path = self.0.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, &data).unwrap();
+            data
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn expected_hashes(data: &[u8], piece_length: usize) -> Vec<u8> {
+        data.chunks(piece_length)
+            .flat_map(|piece| Sha1::digest(piece).to_vec())
+            .collect()
+    }
+
+    #[test]
+    fn directories_should_be_hashed_across_files() {
+        let dir = TempDir::new("builder-directory");
+        let mut data = dir.write("b/c.bin", 40_000);
+        data.splice(0..0, dir.write("a.bin", 20_000));
+        data.extend(dir.write("d.bin", 1));
+
+        let torrent = TorrentBuilder::new(&dir.0)
+            .with_name("data")
+            .with_piece_length(16 * 1024)
+            .with_tracker("http://a/")
+            .with_tracker("http://b/")
+            .build()
+            .unwrap();
+
+        assert_eq!(torrent.announce.as_deref(), Some("http://a/"));
+        assert_eq!(torrent.trackers().count(), 2);
+        assert_eq!(torrent.info.name, "data");
+        assert_eq!(torrent.info.total_length(), 60_001);
+        assert_eq!(torrent.info.pieces, Some(expected_hashes(&data, 16 * 1024)));
+
+        let paths: Vec<_> = torrent.info.files().map(Result::unwrap).collect();
+        assert_eq!(
+            paths,
+            vec![
+                (PathBuf::from("data/a.bin"), 20_000, 0),
+                (PathBuf::from("data/b/c.bin"), 40_000, 20_000),
+                (PathBuf::from("data/d.bin"), 1, 60_000),
+            ]
+        );
+
+        let encoded = torrent.to_bencode().unwrap();
+        assert_eq!(MetaInfo::from_bencode(&encoded).unwrap(), torrent);
+    }
+
+    #[test]
+    fn parallel_hashing_should_match() {
+        let dir = TempDir::new("builder-parallel");
+        dir.write("a.bin", 100_000);
+        dir.write("b.bin", 70_000);
+
+        let builder = TorrentBuilder::new(&dir.0).with_piece_length(16 * 1024);
+        let serial = builder.build().unwrap();
+        for threads in 2..5 {
+            assert_eq!(
+                builder.clone().with_threads(threads).build().unwrap(),
+                serial
+            );
+        }
+    }
+
+    #[test]
+    fn single_files_should_be_named_after_the_file() {
+        let dir = TempDir::new("builder-single");
+        let data = dir.write("file.iso", 50_000);
+
+        let torrent = TorrentBuilder::new(dir.0.join("file.iso"))
+            .with_piece_length(32 * 1024)
+            .build()
+            .unwrap();
+
+        assert_eq!(torrent.announce, None);
+        assert_eq!(torrent.announce_list, None);
+        assert_eq!(torrent.info.name, "file.iso");
+        assert_eq!(
+            torrent.info.layout,
+            Some(Layout::SingleFile { length: 50_000 })
+        );
+        assert_eq!(torrent.info.pieces, Some(expected_hashes(&data, 32 * 1024)));
+    }
+
+    #[test]
+    fn invalid_input_should_fail() {
+        let dir = TempDir::new("builder-invalid");
+        assert!(TorrentBuilder::new(&dir.0).build().is_err());
+
+        dir.write("a.bin", 1);
+        assert!(TorrentBuilder::new(&dir.0).build().is_ok());
+        assert!(TorrentBuilder::new(&dir.0)
+            .with_piece_length(20_000)
+            .build()
+            .is_err());
+        assert!(TorrentBuilder::new(dir.0.join("missing")).build().is_err());
+    }
+}
@@ -0,0 +1,173 @@
+use std::net::SocketAddr;
+
+use crate::{
+    decoding::{Error, FromBencode, Object},
+    encoding::AsCompact,
+    torrent::InfoHash,
+};
+
+/// The id of a DHT node, which shares the 160-bit space of info-hashes
+pub type NodeId = InfoHash;
+
+/// The length of an IPv4 address and port in the compact format
+const PEER_LENGTH: usize = 6;
+/// The length of an IPv6 address and port in the compact format
+const PEER6_LENGTH: usize = 18;
+/// The length of a node id
+const NODE_ID_LENGTH: usize = 20;
+
+/// Decode a single peer in the compact format of
+/// [BEP 23](http://bittorrent.org/beps/bep_0023.html): a 4 byte IPv4 or 16 byte IPv6
+/// address followed by the port, both in network byte order.
+///
+/// ```
+/// use bendy::torrent::decode_compact_peer;
+///
+/// let peer = decode_compact_peer(b"\x7f\x00\x00\x01\x1a\xe1").unwrap();
+/// assert_eq!(peer, "127.0.0.1:6881".parse().unwrap());
+/// ```
+pub fn decode_compact_peer(entry: &[u8]) -> Result<SocketAddr, Error> {
+    AsCompact::decode_bencode_object(Object::Bytes(entry)).map(|AsCompact(peer)| peer)
+}
+
+/// Append a peer in the compact format, 6 bytes for IPv4 and 18 bytes for IPv6
+pub fn encode_compact_peer(peer: &SocketAddr, output: &mut Vec<u8>) {
+    match peer {
+        SocketAddr::V4(peer) => output.extend_from_slice(&peer.ip().octets()),
+        SocketAddr::V6(peer) => output.extend_from_slice(&peer.ip().octets()),
+    }
+    output.extend_from_slice(&peer.port().to_be_bytes());
+}
+
+/// Decode the concatenated IPv4 peers of a compact peer list, as in the `peers` of a
+/// tracker response
+pub fn decode_compact_peers(bytes: &[u8]) -> Result<Vec<SocketAddr>, Error> {
+    decode_entries(bytes, PEER_LENGTH, decode_compact_peer)
+}
+
+/// Decode the concatenated IPv6 peers of a compact peer list, as in the `peers6` of a
+/// tracker response
+pub fn decode_compact_peers6(bytes: &[u8]) -> Result<Vec<SocketAddr>, Error> {
+    decode_entries(bytes, PEER6_LENGTH, decode_compact_peer)
+}
+
+/// Encode peers as compact peer lists, returning the IPv4 and the IPv6 peers
+/// separately, as they are sent in different keys
+///
+/// ```
+/// use bendy::torrent::{decode_compact_peers6, encode_compact_peers};
+///
+/// let peers = ["1.2.3.4:80".parse().unwrap(), "[::1]:6881".parse().unwrap()];
+/// let (peers4, peers6) = encode_compact_peers(peers.iter().copied());
+///
+/// assert_eq!(peers4, b"\x01\x02\x03\x04\x00\x50");
+/// assert_eq!(decode_compact_peers6(&peers6).unwrap(), &peers[1..]);
+/// ```
+pub fn encode_compact_peers(peers: impl IntoIterator<Item = SocketAddr>) -> (Vec<u8>, Vec<u8>) {
+    let mut peers4 = Vec::new();
+    let mut peers6 = Vec::new();
+    for peer in peers {
+        match peer {
+            SocketAddr::V4(_) => encode_compact_peer(&peer, &mut peers4),
+            SocketAddr::V6(_) => encode_compact_peer(&peer, &mut peers6),
+        }
+    }
+    (peers4, peers6)
+}
+
+/// Decode the concatenated 26 byte entries of IPv4 nodes, as in the `nodes` of a DHT
+/// response: the node id followed by the compact peer format of its address
+pub fn decode_compact_nodes(bytes: &[u8]) -> Result<Vec<(NodeId, SocketAddr)>, Error> {
+    decode_entries(bytes, NODE_ID_LENGTH + PEER_LENGTH, decode_node)
+}
+
+/// Decode the concatenated 38 byte entries of IPv6 nodes, as in the `nodes6` of a DHT
+/// response, see [BEP 32](http://bittorrent.org/beps/bep_0032.html)
+pub fn decode_compact_nodes6(bytes: &[u8]) -> Result<Vec<(NodeId, SocketAddr)>, Error> {
+    decode_entries(bytes, NODE_ID_LENGTH + PEER6_LENGTH, decode_node)
+}
+
+/// Encode nodes in the compact format, returning the IPv4 and the IPv6 nodes separately,
+/// as they are sent in different keys
+pub fn encode_compact_nodes(
+    nodes: impl IntoIterator<Item = (NodeId, SocketAddr)>,
+) -> (Vec<u8>, Vec<u8>) {
+    let mut nodes4 = Vec::new();
+    let mut nodes6 = Vec::new();
+    for (id, address) in nodes {
+        let output = match address {
+            SocketAddr::V4(_) => &mut nodes4,
+            SocketAddr::V6(_) => &mut nodes6,
+        };
+        output.extend_from_slice(&id.0);
+        encode_compact_peer(&address, output);
+    }
+    (nodes4, nodes6)
+}
+
+fn decode_node(entry: &[u8]) -> Result<(NodeId, SocketAddr), Error> {
+    let (id, address) = entry.split_at(NODE_ID_LENGTH);
+    let mut node_id = [0; NODE_ID_LENGTH];
+    node_id.copy_from_slice(id);
+    Ok((InfoHash(node_id), decode_compact_peer(address)?))
+}
+
+/// Split concatenated entries of the given length and decode each one
+fn decode_entries<T>(
+    bytes: &[u8],
+    length: usize,
+    decode: impl Fn(&[u8]) -> Result<T, Error>,
+) -> Result<Vec<T>, Error> {
+    if bytes.len() % length != 0 {
+        return Err(Error::unexpected_token(
+            format!("a multiple of {} bytes", length),
+            format!("{} bytes", bytes.len()),
+        ));
+    }
+    bytes.chunks(length).map(decode).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn peers_should_use_network_byte_order() {
+        let peer: SocketAddr = "[2001:db8::1]:258".parse().unwrap();
+        let mut encoded = Vec::new();
+        encode_compact_peer(&peer, &mut encoded);
+
+        assert_eq!(
+            encoded,
+            b"\x20\x01\x0d\xb8\0\0\0\0\0\0\0\0\0\0\0\x01\x01\x02"
+        );
+        assert_eq!(decode_compact_peer(&encoded).unwrap(), peer);
+    }
+
+    #[test]
+    fn nodes_should_round_trip() {
+        let nodes = vec![
+            (InfoHash([1; 20]), "1.2.3.4:6881".parse().unwrap()),
+            (InfoHash([2; 20]), "5.6.7.8:6882".parse().unwrap()),
+            (InfoHash([3; 20]), "[::1]:6883".parse().unwrap()),
+        ];
+
+        let (nodes4, nodes6) = encode_compact_nodes(nodes.iter().copied());
+        assert_eq!(nodes4.len(), 52);
+        assert_eq!(nodes6.len(), 38);
+
+        let mut decoded = decode_compact_nodes(&nodes4).unwrap();
+        decoded.extend(decode_compact_nodes6(&nodes6).unwrap());
+        assert_eq!(decoded, nodes);
+    }
+
+    #[test]
+    fn truncated_entries_should_fail() {
+        assert!(decode_compact_peer(b"\x01\x02\x03").is_err());
+        assert!(decode_compact_peers(&[0; 7]).is_err());
+        assert!(decode_compact_peers6(&[0; 6]).is_err());
+        assert!(decode_compact_nodes(&[0; 38]).is_err());
+        assert!(decode_compact_nodes6(&[0; 26]).is_err());
+        assert_eq!(decode_compact_peers(b"").unwrap(), vec![]);
+    }
+}
@@ -0,0 +1,190 @@
+use std::{collections::BTreeMap, net::IpAddr};
+
+use crate::{
+    decoding::{self, FromBencode, Object, ResultExt},
+    encoding::{self, AsCompact, SingleItemEncoder, ToBencode},
+};
+
+/// The handshake of the extension protocol, see
+/// [BEP 10](http://bittorrent.org/beps/bep_0010.html)
+///
+/// The handshake is the bencoded payload of the extended message with id 0, sent after
+/// the BitTorrent handshake to peers which support the protocol. Keys which aren't
+/// covered are ignored.
+///
+/// ```
+/// use bendy::{decoding::FromBencode, torrent::ExtensionHandshake};
+///
+/// let handshake = ExtensionHandshake::from_bencode(
+///     b"d1:md11:ut_metadatai3e6:ut_pexi0ee13:metadata_sizei31235e1:pi6881e1:v4:abc e",
+/// )
+/// .unwrap();
+///
+/// assert_eq!(handshake.extension_id("ut_metadata"), Some(3));
+/// assert_eq!(handshake.extension_id("ut_pex"), None);
+/// assert_eq!(handshake.metadata_size, Some(31235));
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ExtensionHandshake {
+    /// The message ids the sender wants to receive each extension with (`m`). An id of
+    /// zero disables an extension enabled in an earlier handshake.
+    pub extensions: BTreeMap<String, u8>,
+    /// The size of the info dictionary in bytes (`metadata_size`), see
+    /// [BEP 9](http://bittorrent.org/beps/bep_0009.html)
+    pub metadata_size: Option<u64>,
+    /// The TCP port the sender listens on (`p`)
+    pub port: Option<u16>,
+    /// The number of outstanding requests the sender accepts (`reqq`)
+    pub request_queue: Option<u64>,
+    /// The name and version of the sender's client (`v`)
+    pub version: Option<String>,
+    /// The address the sender sees the receiver at (`yourip`)
+    pub your_ip: Option<IpAddr>,
+}
+
+impl ExtensionHandshake {
+    /// Create an empty handshake
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enable an extension, to be sent to the sender of this handshake with the given
+    /// message id
+    #[must_use]
+    pub fn with_extension(mut self, name: impl Into<String>, id: u8) -> Self {
+        self.extensions.insert(name.into(), id);
+        self
+    }
+
+    /// The message id to send an extension with, if the extension is enabled
+    pub fn extension_id(&self, name: &str) -> Option<u8> {
+        self.extensions.get(name).copied().filter(|&id| id != 0)
+    }
+}
+
+impl FromBencode for ExtensionHandshake {
+    const EXPECTED_RECURSION_DEPTH: usize = 2;
+
+    fn decode_bencode_object(object: Object) -> Result<Self, decoding::Error>
+    where
+        Self: Sized,
+    {
+        let mut handshake = ExtensionHandshake::default();
+
+        let mut dict = object.try_into_dictionary()?;
+        while let Some(pair) = dict.next_pair()? {
+            match pair {
+                (b"m", value) => {
+                    handshake.extensions =
+                        BTreeMap::decode_bencode_object(value).context_field("m")?;
+                },
+                (b"metadata_size", value) => {
+                    handshake.metadata_size = u64::decode_bencode_object(value)
+                        .context_field("metadata_size")
+                        .map(Some)?;
+                },
+                (b"p", value) => {
+                    handshake.port = u16::decode_bencode_object(value)
+                        .context_field("p")
+                        .map(Some)?;
+                },
+                (b"reqq", value) => {
+                    handshake.request_queue = u64::decode_bencode_object(value)
+                        .context_field("reqq")
+                        .map(Some)?;
+                },
+                (b"v", value) => {
+                    handshake.version = String::decode_bencode_object(value)
+                        .context_field("v")
+                        .map(Some)?;
+                },
+                (b"yourip", value) => {
+                    handshake.your_ip = AsCompact::decode_bencode_object(value)
+                        .context_field("yourip")
+                        .map(|AsCompact(ip)| Some(ip))?;
+                },
+                _ => (),
+            }
+        }
+
+        Ok(handshake)
+    }
+}
+
+impl ToBencode for ExtensionHandshake {
+    const MAX_DEPTH: usize = 2;
+
+    fn encode(&self, encoder: SingleItemEncoder) -> Result<(), encoding::Error> {
+        encoder.emit_dict(|mut e| {
+            e.emit_pair(b"m", &self.extensions)?;
+            if let Some(metadata_size) = self.metadata_size {
+                e.emit_pair(b"metadata_size", metadata_size)?;
+            }
+            if let Some(port) = self.port {
+                e.emit_pair(b"p", port)?;
+            }
+            if let Some(request_queue) = self.request_queue {
+                e.emit_pair(b"reqq", request_queue)?;
+            }
+            if let Some(version) = &self.version {
+                e.emit_pair(b"v", version)?;
+            }
+            if let Some(your_ip) = self.your_ip {
+                e.emit_pair(b"yourip", AsCompact(your_ip))?;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn handshakes_should_round_trip() {
+        let handshake = ExtensionHandshake {
+            metadata_size: Some(1024),
+            port: Some(6881),
+            request_queue: Some(250),
+            version: Some("bendy 0.4".to_owned()),
+            your_ip: Some("2001:db8::1".parse().unwrap()),
+            ..ExtensionHandshake::new()
+                .with_extension("ut_metadata", 2)
+                .with_extension("ut_pex", 1)
+        };
+
+        let encoded = handshake.to_bencode().unwrap();
+        assert!(encoded.starts_with(b"d1:md11:ut_metadatai2e6:ut_pexi1ee"));
+        assert_eq!(
+            ExtensionHandshake::from_bencode(&encoded).unwrap(),
+            handshake
+        );
+    }
+
+    #[test]
+    fn empty_handshakes_should_round_trip() {
+        let handshake = ExtensionHandshake::from_bencode(b"de").unwrap();
+        assert_eq!(handshake, ExtensionHandshake::default());
+        assert_eq!(handshake.to_bencode().unwrap(), b"d1:mdee");
+    }
+
+    #[test]
+    fn compact_addresses_should_decode() {
+        let handshake = ExtensionHandshake::from_bencode(b"d6:yourip4:\x7f\0\0\x01e").unwrap();
+        assert_eq!(handshake.your_ip, Some("127.0.0.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn invalid_handshakes_should_fail() {
+        let cases: &[&[u8]] = &[
+            b"d1:md6:ut_pexi256eee",
+            b"d1:mli1eee",
+            b"d1:pi-1ee",
+            b"d6:yourip3:abce",
+        ];
+        for &case in cases {
+            assert!(ExtensionHandshake::from_bencode(case).is_err());
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use std::path::PathBuf;
+
+use crate::{
+    decoding::Error,
+    torrent::{FileEntry, Info, Layout},
+};
+
+impl Info {
+    /// The files of the torrent as `(path, length, offset)` triples, in the order their
+    /// data is concatenated. Paths are relative to the download directory and start with
+    /// the name of the torrent; offsets are the positions of the files in the
+    /// concatenated data. Padding files aren't returned, but are accounted for in the
+    /// offsets.
+    ///
+    /// Every path is checked with [`FileEntry::relative_path`], so joining it to the
+    /// download directory can't escape it. v2 torrents without a v1 layout have no
+    /// concatenated data and yield no files.
+    ///
+    /// ```
+    /// use bendy::{decoding::FromBencode, torrent::MetaInfo};
+    /// use std::path::Path;
+    ///
+    /// let torrent = MetaInfo::from_bencode(
+    ///     b"d4:infod5:filesld6:lengthi3e4:pathl5:a.txteed6:lengthi5e4:pathl3:sub5:b.txteee\
+    ///       4:name3:dir12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee",
+    /// )
+    /// .unwrap();
+    ///
+    /// let files = torrent.info.files().collect::<Result<Vec<_>, _>>().unwrap();
+    /// assert_eq!(files[1], (Path::new("dir/sub/b.txt").to_owned(), 5, 3));
+    /// ```
+    pub fn files(&self) -> impl Iterator<Item = Result<(PathBuf, u64, u64), Error>> + '_ {
+        let single = match &self.layout {
+            Some(Layout::SingleFile { length }) => Some(*length),
+            _ => None,
+        };
+        let files = match &self.layout {
+            Some(Layout::MultiFile { files }) => files.as_slice(),
+            _ => &[],
+        };
+
+        let single = single.map(move |length| self.root().map(|root| (root, length, 0)));
+        let mut offset = Some(0u64);
+        let multi = files.iter().enumerate().filter_map(move |(index, file)| {
+            let start = offset;
+            offset = offset.and_then(|offset| offset.checked_add(file.length));
+            if file.is_padding() {
+                return None;
+            }
+
+            let file = match (start, offset) {
+                (Some(start), Some(_)) => self
+                    .root()
+                    .and_then(|root| Ok((root.join(file.relative_path()?), file.length, start))),
+                _ => Err(Error::unexpected_token(
+                    "file lengths summing to less than 2^64 bytes",
+                    "a larger sum",
+                )
+                .context_field("length")),
+            };
+            Some(file.map_err(|error| error.context_index(index).context_field("files")))
+        });
+
+        single.into_iter().chain(multi)
+    }
+
+    /// The name of the torrent as the root of all file paths
+    fn root(&self) -> Result<PathBuf, Error> {
+        check_component(&self.name).map_err(|error| error.context_field("name"))?;
+        Ok(PathBuf::from(&self.name))
+    }
+}
+
+impl FileEntry {
+    /// Join the components of the path of the file, relative to the torrent's
+    /// directory.
+    ///
+    /// Torrents come from untrusted sources, so every component has to be a plain file
+    /// name: empty components, `.` and `..`, and components containing separators,
+    /// colons or NUL are rejected. Backslashes and colons, which start Windows drive
+    /// prefixes, are rejected on all platforms, so a torrent is accepted or rejected the
+    /// same way everywhere.
+    ///
+    /// ```
+    /// use bendy::torrent::FileEntry;
+    /// use std::path::Path;
+    ///
+    /// let file = FileEntry {
+    ///     length: 3,
+    ///     path: vec!["sub".to_owned(), "a.txt".to_owned()],
+    ///     attr: None,
+    /// };
+    /// assert_eq!(file.relative_path().unwrap(), Path::new("sub/a.txt"));
+    ///
+    /// let file = FileEntry {
+    ///     length: 3,
+    ///     path: vec!["..".to_owned(), "a.txt".to_owned()],
+    ///     attr: None,
+    /// };
+    /// assert!(file.relative_path().is_err());
+    /// ```
+    pub fn relative_path(&self) -> Result<PathBuf, Error> {
+        if self.path.is_empty() {
+            let error = Error::unexpected_token("a file name", "an empty path");
+            return Err(error.context_field("path"));
+        }
+
+        let mut path = PathBuf::new();
+        for (index, component) in self.path.iter().enumerate() {
+            check_component(component)
+                .map_err(|error| error.context_index(index).context_field("path"))?;
+            path.push(component);
+        }
+        Ok(path)
+    }
+}
+
+/// Check that a path component is a plain file name which can't escape its directory.
+/// The component is checked as a string rather than parsed as a path, which would
+/// depend on the platform: e.g. `C:` is a drive prefix only on Windows.
+fn check_component(component: &str) -> Result<(), Error> {
+    let is_file_name =
+        !matches!(component, "" | "." | "..") && !component.contains(&['/', '\\', ':', '\0'][..]);
+
+    if is_file_name {
+        Ok(())
+    } else {
+        Err(Error::unexpected_token(
+            "a file name",
+            format!("`{}`", component.escape_debug()),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn entry(length: u64, path: &[&str]) -> FileEntry {
+        FileEntry {
+            length,
+            path: path.iter().map(|&component| component.to_owned()).collect(),
+            attr: None,
+        }
+    }
+
+    fn info(files: Vec<FileEntry>) -> Info {
+        Info {
+            name: "dir".to_owned(),
+            piece_length: 16,
+            pieces: Some(Vec::new()),
+            private: false,
+            layout: Some(Layout::MultiFile { files }),
+            meta_version: None,
+            file_tree: None,
+        }
+    }
+
+    #[test]
+    fn files_should_have_offsets_into_the_data() {
+        let padding = FileEntry {
+            attr: Some("p".to_owned()),
+            ..entry(13, &[".pad", "13"])
+        };
+        let info = info(vec![entry(3, &["a"]), padding, entry(5, &["sub", "b"])]);
+
+        let files = info.files().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(
+            files,
+            vec![
+                (PathBuf::from("dir/a"), 3, 0),
+                (PathBuf::from("dir/sub/b"), 5, 16),
+            ]
+        );
+    }
+
+    #[test]
+    fn single_files_should_be_named_after_the_torrent() {
+        let info = Info {
+            layout: Some(Layout::SingleFile { length: 7 }),
+            ..info(Vec::new())
+        };
+
+        let files = info.files().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(files, vec![(PathBuf::from("dir"), 7, 0)]);
+    }
+
+    #[test]
+    fn traversing_paths_should_be_rejected() {
+        let cases: &[&[&str]] = &[
+            &[],
+            &[""],
+            &["."],
+            &[".."],
+            &["a", "..", "b"],
+            &["/etc"],
+            &["a/b"],
+            &["a\\b"],
+            &["C:"],
+            &["C:a"],
+            &["a:b"],
+            &["a\0"],
+        ];
+        for &case in cases {
+            assert!(entry(1, case).relative_path().is_err(), "{:?}", case);
+        }
+
+        let info = Info {
+            name: "..".to_owned(),
+            ..info(vec![entry(1, &["a"])])
+        };
+        assert!(info.files().all(|file| file.is_err()));
+    }
+
+    #[test]
+    fn overflowing_offsets_should_be_rejected() {
+        let info = info(vec![entry(u64::MAX, &["a"]), entry(1, &["b"])]);
+
+        let files: Vec<_> = info.files().collect();
+        assert!(files[0].is_ok());
+        assert!(files[1].is_err());
+    }
+
+    #[test]
+    fn errors_should_name_the_component() {
+        let info = info(vec![entry(1, &["a"]), entry(1, &["b", ".."])]);
+
+        let error = info.files().nth(1).unwrap().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Error: discovered `..` but expected a file name in files[1].path[1]"
+        );
+    }
+}
@@ -0,0 +1,54 @@
+#[cfg(not(feature = "std"))]
+use alloc::format;
+
+use crate::decoding::Error;
+
+/// The length of a SHA-1 piece hash of BitTorrent v1
+pub const SHA1_HASH_LENGTH: usize = 20;
+
+/// The length of a SHA-256 piece hash of BitTorrent v2
+pub const SHA256_HASH_LENGTH: usize = 32;
+
+/// Split concatenated hashes of `N` bytes, like the `pieces` of a v1 info dictionary or
+/// a layer of the [`PieceLayers`](crate::torrent::PieceLayers) of a v2 torrent, into
+/// the individual hashes. Fails if the length isn't a multiple of `N`.
+///
+/// ```
+/// use bendy::torrent::{hash_chunks, SHA1_HASH_LENGTH};
+///
+/// let pieces = [[1; 20], [2; 20]].concat();
+/// let hashes = hash_chunks::<SHA1_HASH_LENGTH>(&pieces).unwrap();
+///
+/// assert_eq!(hashes.len(), 2);
+/// assert_eq!(hashes[1], [2; 20]);
+/// assert!(hash_chunks::<SHA1_HASH_LENGTH>(&pieces[1..]).is_err());
+/// ```
+pub fn hash_chunks<const N: usize>(hashes: &[u8]) -> Result<&[[u8; N]], Error> {
+    if hashes.len() % N != 0 {
+        return Err(Error::unexpected_token(
+            format!("a multiple of {} bytes", N),
+            format!("{} bytes", hashes.len()),
+        ));
+    }
+
+    // `slice::as_chunks` does this, but is newer than the MSRV. Arrays of bytes have no
+    // padding and are aligned to one byte, and the length is a multiple of `N`.
+    let chunks = hashes.as_ptr().cast::<[u8; N]>();
+    Ok(unsafe { core::slice::from_raw_parts(chunks, hashes.len() / N) })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn hashes_should_split_at_their_length() {
+        let layer = [[7; 32], [8; 32], [9; 32]].concat();
+        let hashes = hash_chunks::<SHA256_HASH_LENGTH>(&layer).unwrap();
+        assert_eq!(hashes, [[7; 32], [8; 32], [9; 32]]);
+
+        // 96 bytes are a multiple of 32 but not of 20
+        assert!(hash_chunks::<SHA1_HASH_LENGTH>(&layer).is_err());
+        assert!(hash_chunks::<SHA1_HASH_LENGTH>(&[]).unwrap().is_empty());
+    }
+}
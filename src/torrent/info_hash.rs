@@ -0,0 +1,194 @@
+#[cfg(not(feature = "std"))]
+use alloc::format;
+use core::{
+    convert::TryInto,
+    fmt::{self, Display, Formatter},
+};
+
+#[cfg(any(feature = "sha-1", feature = "sha2"))]
+use digest::Digest;
+#[cfg(feature = "sha-1")]
+use sha1_::Sha1;
+#[cfg(feature = "sha2")]
+use sha2_::Sha256;
+
+use crate::{
+    decoding::{validate, Decoder, Error, FromBencode, Object},
+    encoding::{self, SingleItemEncoder, ToBencode},
+};
+
+/// A v1 info-hash, as found in binary form in tracker and DHT messages
+///
+/// Info-hashes aren't valid UTF-8 in general, so unlike strings they can't be decoded as
+/// `String` map keys. `InfoHash` decodes from a byte string of exactly 20 bytes, can be
+/// used as the key of a `BTreeMap` or `HashMap` in both directions, and displays as hex.
+///
+/// ```
+/// use bendy::{decoding::FromBencode, torrent::InfoHash};
+/// use std::collections::BTreeMap;
+///
+/// let map = BTreeMap::<InfoHash, u64>::from_bencode(b"d20:\xff\xfe012345678901234567i3ee")
+///     .unwrap();
+/// let hash = map.keys().next().unwrap();
+/// assert_eq!(hash.to_string(), "fffe303132333435363738393031323334353637");
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct InfoHash(pub [u8; 20]);
+
+impl From<[u8; 20]> for InfoHash {
+    fn from(hash: [u8; 20]) -> Self {
+        InfoHash(hash)
+    }
+}
+
+impl AsRef<[u8]> for InfoHash {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Display for InfoHash {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+    }
+}
+
+impl FromBencode for InfoHash {
+    const EXPECTED_RECURSION_DEPTH: usize = 0;
+
+    fn decode_bencode_object(object: Object) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        let bytes = object.try_into_bytes()?;
+        bytes.try_into().map(InfoHash).map_err(|_| {
+            Error::unexpected_token("a 20 byte info-hash", format!("{} bytes", bytes.len()))
+        })
+    }
+}
+
+impl ToBencode for InfoHash {
+    const MAX_DEPTH: usize = 0;
+
+    fn encode(&self, encoder: SingleItemEncoder) -> Result<(), encoding::Error> {
+        encoder.emit_bytes(&self.0)
+    }
+}
+
+/// Find the info dictionary of a torrent file and return its encoded bytes, exactly as
+/// they appear in the file. The torrent file has to hold a single valid value, without
+/// anything after it.
+///
+/// ```
+/// use bendy::torrent::info_bytes;
+///
+/// let torrent = b"d8:announce3:foo4:infod6:lengthi3e4:name1:aee";
+/// assert_eq!(info_bytes(torrent).unwrap(), b"d6:lengthi3e4:name1:ae");
+/// ```
+pub fn info_bytes(torrent: &[u8]) -> Result<&[u8], Error> {
+    validate(torrent)?;
+
+    let mut decoder = Decoder::new(torrent);
+    let info = match decoder.seek_path(&[b"info"])? {
+        Some(info) => info.try_into_dictionary()?.into_raw(),
+        None => Err(Error::missing_field("info")),
+    };
+    info
+}
+
+/// Compute the info-hash of a torrent file, the SHA-1 hash identifying it in BitTorrent
+/// v1.
+///
+/// The hash is computed over the original bytes of the info dictionary. Decoding the
+/// torrent and encoding the info dictionary again would drop keys which aren't known to
+/// the decoding type, yielding a different hash.
+///
+/// ```
+/// use bendy::torrent::info_hash;
+///
+/// let torrent = b"d4:infod6:lengthi3e4:name1:a1:xi1eee";
+/// let hash = info_hash(torrent).unwrap();
+/// assert_eq!(hash[..4], [0x4b, 0x39, 0xc2, 0xcd]);
+/// ```
+#[cfg(feature = "sha-1")]
+pub fn info_hash(torrent: &[u8]) -> Result<[u8; 20], Error> {
+    let info = info_bytes(torrent)?;
+    Ok(Sha1::digest(info).into())
+}
+
+/// Compute the v2 info-hash of a torrent file, the SHA-256 hash identifying it in
+/// BitTorrent v2, see [BEP 52](http://bittorrent.org/beps/bep_0052.html). Like
+/// [`info_hash`], it covers the original bytes of the info dictionary.
+///
+/// Hybrid torrents have both info-hashes; the v1 one is used to join v1 swarms.
+#[cfg(feature = "sha2")]
+pub fn info_hash_v2(torrent: &[u8]) -> Result<[u8; 32], Error> {
+    let info = info_bytes(torrent)?;
+    Ok(Sha256::digest(info).into())
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::collections::BTreeMap;
+    #[cfg(all(not(feature = "std"), feature = "sha2"))]
+    use alloc::string::String;
+    #[cfg(all(not(feature = "std"), feature = "sha-1"))]
+    use alloc::string::ToString;
+    #[cfg(feature = "std")]
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[test]
+    fn binary_info_hashes_should_be_map_keys() {
+        let input = b"d20:\x00\x01bcdefghijklmnopqrsi1e20:\xff\xfebcdefghijklmnopqrsi2ee";
+        let map = BTreeMap::<InfoHash, u8>::from_bencode(input).unwrap();
+
+        assert_eq!(map.values().copied().sum::<u8>(), 3);
+        assert_eq!(map.to_bencode().unwrap(), &input[..]);
+        assert!(BTreeMap::<InfoHash, u8>::from_bencode(b"d3:abci1ee").is_err());
+    }
+
+    #[test]
+    fn info_dictionaries_should_be_found() {
+        let torrent = b"d1:ali1ee4:infod1:xle1:yi1ee1:zi2ee";
+        assert_eq!(info_bytes(torrent).unwrap(), b"d1:xle1:yi1ee");
+
+        assert!(info_bytes(b"d1:ai1ee").is_err());
+        assert!(info_bytes(b"d4:infoi1ee").is_err());
+        assert!(info_bytes(b"d4:infod1:xi1e").is_err());
+        assert!(info_bytes(b"d4:infod1:ai1eeXXXX").is_err());
+        assert!(info_bytes(b"d4:infod1:ai1eeee").is_err());
+    }
+
+    #[cfg(feature = "sha-1")]
+    #[test]
+    fn info_hashes_should_cover_the_original_bytes() {
+        static DEBIAN_TORRENT: &[u8] =
+            include_bytes!("../../examples/torrent_files/debian-9.4.0-amd64-netinst.iso.torrent");
+
+        let hash = info_hash(DEBIAN_TORRENT).unwrap();
+        assert_eq!(
+            InfoHash::from(hash).to_string(),
+            "7431a969b347e14bba641b3517c024f7b40dfb7f"
+        );
+    }
+
+    #[cfg(feature = "sha2")]
+    #[test]
+    fn v2_info_hashes_should_cover_the_original_bytes() {
+        let torrent = b"d4:infod9:file treed1:ad0:d6:lengthi3eeee12:meta versioni2e\
+                        4:name1:a12:piece lengthi16384eee";
+
+        let hash = info_hash_v2(torrent).unwrap();
+        let hex = hash
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>();
+        assert_eq!(
+            hex,
+            "c1f16f117a10e8ff0a36e68d1188ba2834bf6041d1a9897e355c708f3b2783ff"
+        );
+    }
+}
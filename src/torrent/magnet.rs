@@ -0,0 +1,377 @@
+#[cfg(not(feature = "std"))]
+use alloc::{format, string::String, vec::Vec};
+use core::{
+    fmt::{self, Display, Formatter, Write},
+    str::FromStr,
+};
+
+#[cfg(feature = "sha-1")]
+use crate::decoding::FromBencode;
+use crate::decoding::{Error, ResultExt};
+#[cfg(feature = "sha-1")]
+use crate::torrent::{info_hash, MetaInfo};
+
+/// The characters which are written to magnet links as they are, see RFC 3986
+fn is_unreserved(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~')
+}
+
+/// A magnet link, which identifies a torrent by its info-hash so its metainfo can be
+/// fetched from peers, see [BEP 9](http://bittorrent.org/beps/bep_0009.html)
+///
+/// Links are parsed with [`str::parse`] and written with [`Display`]. Of the optional
+/// parameters, the display name (`dn`), trackers (`tr`) and peers (`x.pe`) are kept;
+/// others are ignored.
+///
+/// ```
+/// use bendy::torrent::MagnetLink;
+///
+/// let link: MagnetLink = "magnet:?xt=urn:btih:c12fe1c06bba254a9dc9f519b335aa7c1367a88a\
+///                         &dn=debian%209.4&tr=http%3A%2F%2Ftracker%2Fannounce"
+///     .parse()
+///     .unwrap();
+///
+/// assert_eq!(link.info_hash.unwrap()[0], 0xc1);
+/// assert_eq!(link.display_name.as_deref(), Some("debian 9.4"));
+/// assert_eq!(link.trackers, vec!["http://tracker/announce"]);
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MagnetLink {
+    /// The v1 info-hash, from an `xt=urn:btih:` parameter
+    pub info_hash: Option<[u8; 20]>,
+    /// The v2 info-hash, from an `xt=urn:btmh:` parameter, see
+    /// [BEP 52](http://bittorrent.org/beps/bep_0052.html)
+    pub info_hash_v2: Option<[u8; 32]>,
+    /// The name to display while the metainfo is fetched
+    pub display_name: Option<String>,
+    /// The URLs of trackers
+    pub trackers: Vec<String>,
+    /// Addresses of peers as `host:port`
+    pub peers: Vec<String>,
+}
+
+impl MagnetLink {
+    /// Create a magnet link for the given v1 info-hash
+    pub fn new(info_hash: [u8; 20]) -> Self {
+        MagnetLink {
+            info_hash: Some(info_hash),
+            ..Self::default()
+        }
+    }
+
+    /// Create a magnet link for a torrent file. It carries the info-hash, the name and
+    /// all trackers of the torrent, and the v2 info-hash of v2 and hybrid torrents if
+    /// the `sha2` feature is enabled.
+    ///
+    /// The v1 info-hash is left out for v2 torrents, as they have no v1 swarm.
+    #[cfg(feature = "sha-1")]
+    pub fn from_torrent(torrent: &[u8]) -> Result<Self, Error> {
+        let metainfo = MetaInfo::from_bencode(torrent)?;
+
+        #[cfg(feature = "sha2")]
+        let info_hash_v2 = match metainfo.info.file_tree {
+            Some(_) => Some(super::info_hash_v2(torrent)?),
+            None => None,
+        };
+        #[cfg(not(feature = "sha2"))]
+        let info_hash_v2 = None;
+
+        Ok(MagnetLink {
+            info_hash: match metainfo.info.layout {
+                Some(_) => Some(info_hash(torrent)?),
+                None => None,
+            },
+            info_hash_v2,
+            display_name: Some(metainfo.info.name.clone()),
+            trackers: metainfo.trackers().map(String::from).collect(),
+            peers: Vec::new(),
+        })
+    }
+
+    /// Set the name to display
+    #[must_use]
+    pub fn with_display_name(mut self, display_name: impl Into<String>) -> Self {
+        self.display_name = Some(display_name.into());
+        self
+    }
+
+    /// Add a tracker
+    #[must_use]
+    pub fn with_tracker(mut self, url: impl Into<String>) -> Self {
+        self.trackers.push(url.into());
+        self
+    }
+
+    /// Add a peer, given as `host:port`
+    #[must_use]
+    pub fn with_peer(mut self, address: impl Into<String>) -> Self {
+        self.peers.push(address.into());
+        self
+    }
+}
+
+impl FromStr for MagnetLink {
+    type Err = Error;
+
+    fn from_str(link: &str) -> Result<Self, Error> {
+        let query = match link.strip_prefix("magnet:?") {
+            Some(query) => query,
+            None => return Err(Error::unexpected_token("a magnet link", "another URI")),
+        };
+
+        let mut magnet = MagnetLink::default();
+        for parameter in query.split('&').filter(|parameter| !parameter.is_empty()) {
+            let (key, value) = parameter.split_at(parameter.find('=').unwrap_or(parameter.len()));
+            let value = percent_decode(value.get(1..).unwrap_or("")).context_field(key)?;
+
+            match key {
+                "xt" => parse_exact_topic(&mut magnet, &value).context_field(key)?,
+                "dn" => magnet.display_name = Some(value),
+                "tr" => magnet.trackers.push(value),
+                "x.pe" => magnet.peers.push(value),
+                _ => (),
+            }
+        }
+
+        if magnet.info_hash.is_none() && magnet.info_hash_v2.is_none() {
+            return Err(Error::missing_field("xt"));
+        }
+        Ok(magnet)
+    }
+}
+
+impl Display for MagnetLink {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str("magnet:?")?;
+
+        let mut separator = "";
+        if let Some(info_hash) = &self.info_hash {
+            f.write_str("xt=urn:btih:")?;
+            write_hex(f, info_hash)?;
+            separator = "&";
+        }
+        if let Some(info_hash) = &self.info_hash_v2 {
+            // A multihash of a SHA-256 hash: the code of SHA-256 and the hash length
+            write!(f, "{}xt=urn:btmh:1220", separator)?;
+            write_hex(f, info_hash)?;
+            separator = "&";
+        }
+
+        let parameters = self
+            .display_name
+            .iter()
+            .map(|name| ("dn", name))
+            .chain(self.trackers.iter().map(|url| ("tr", url)))
+            .chain(self.peers.iter().map(|peer| ("x.pe", peer)));
+        for (key, value) in parameters {
+            write!(f, "{}{}=", separator, key)?;
+            percent_encode(f, value)?;
+            separator = "&";
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse an exact topic, which is either a v1 info-hash in hex or base32, or a v2
+/// info-hash as a multihash in hex
+fn parse_exact_topic(magnet: &mut MagnetLink, topic: &str) -> Result<(), Error> {
+    if let Some(hash) = topic.strip_prefix("urn:btih:") {
+        let mut info_hash = [0; 20];
+        let valid = match hash.len() {
+            40 => decode_hex(hash, &mut info_hash),
+            32 => decode_base32(hash, &mut info_hash),
+            _ => false,
+        };
+        if !valid {
+            return Err(Error::unexpected_token(
+                "a v1 info-hash of 40 hex or 32 base32 digits",
+                format!("`{}`", hash),
+            ));
+        }
+        magnet.info_hash = Some(info_hash);
+    } else if let Some(hash) = topic.strip_prefix("urn:btmh:") {
+        let mut info_hash = [0; 32];
+        if !matches!(hash.strip_prefix("1220"), Some(hash) if decode_hex(hash, &mut info_hash)) {
+            return Err(Error::unexpected_token(
+                "a v2 info-hash as a SHA-256 multihash",
+                format!("`{}`", hash),
+            ));
+        }
+        magnet.info_hash_v2 = Some(info_hash);
+    }
+
+    // Exact topics of other networks are ignored
+    Ok(())
+}
+
+fn decode_hex(hex: &str, output: &mut [u8]) -> bool {
+    // `from_str_radix` would accept a sign as well
+    if hex.len() != output.len() * 2 || !hex.bytes().all(|digit| digit.is_ascii_hexdigit()) {
+        return false;
+    }
+
+    for (byte, digits) in output.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        match u8::from_str_radix(core::str::from_utf8(digits).unwrap_or(""), 16) {
+            Ok(value) => *byte = value,
+            Err(_) => return false,
+        }
+    }
+    true
+}
+
+/// Decode base32 of RFC 4648 without padding, in either case
+fn decode_base32(base32: &str, output: &mut [u8]) -> bool {
+    let mut buffer = 0u64;
+    let mut bits = 0;
+    let mut length = 0;
+
+    for digit in base32.bytes() {
+        let value = match digit.to_ascii_uppercase() {
+            letter @ b'A'..=b'Z' => letter - b'A',
+            digit @ b'2'..=b'7' => digit - b'2' + 26,
+            _ => return false,
+        };
+        buffer = buffer << 5 | u64::from(value);
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            if length == output.len() {
+                return false;
+            }
+            output[length] = (buffer >> bits) as u8;
+            length += 1;
+        }
+    }
+    length == output.len()
+}
+
+fn write_hex(f: &mut Formatter, bytes: &[u8]) -> fmt::Result {
+    bytes.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+}
+
+fn percent_encode(f: &mut Formatter, value: &str) -> fmt::Result {
+    for byte in value.bytes() {
+        if is_unreserved(byte) {
+            f.write_char(char::from(byte))?;
+        } else {
+            write!(f, "%{:02X}", byte)?;
+        }
+    }
+    Ok(())
+}
+
+/// Decode percent-encoded UTF-8, reading `+` as a space as in HTML forms
+fn percent_decode(value: &str) -> Result<String, Error> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut input = value.bytes();
+
+    while let Some(byte) = input.next() {
+        match byte {
+            b'%' => {
+                let digits = [input.next().unwrap_or(0), input.next().unwrap_or(0)];
+                let mut decoded = [0];
+                let digits = core::str::from_utf8(&digits).unwrap_or("");
+                if !decode_hex(digits, &mut decoded) {
+                    return Err(Error::unexpected_token(
+                        "two hex digits after `%`",
+                        format!("`{}`", value),
+                    ));
+                }
+                bytes.push(decoded[0]);
+            },
+            b'+' => bytes.push(b' '),
+            byte => bytes.push(byte),
+        }
+    }
+
+    String::from_utf8(bytes).map_err(|_| Error::unexpected_token("UTF-8", "invalid UTF-8"))
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::string::ToString;
+
+    use super::*;
+
+    #[test]
+    fn magnet_links_should_round_trip() {
+        let link = MagnetLink::new([0xab; 20])
+            .with_display_name("a b&c")
+            .with_tracker("udp://tracker:1337/announce?x=1")
+            .with_tracker("http://backup/")
+            .with_peer("[::1]:6881");
+        let link = MagnetLink {
+            info_hash_v2: Some([0x12; 32]),
+            ..link
+        };
+
+        let encoded = link.to_string();
+        assert!(encoded.starts_with(
+            "magnet:?xt=urn:btih:abababababababababababababababababababab\
+             &xt=urn:btmh:12201212"
+        ));
+        assert!(encoded.contains("&dn=a%20b%26c&tr=udp%3A%2F%2Ftracker%3A1337"));
+        assert_eq!(encoded.parse::<MagnetLink>().unwrap(), link);
+    }
+
+    #[test]
+    fn base32_info_hashes_should_parse() {
+        let link: MagnetLink = "magnet:?xt=urn:btih:YEX6DQDLXISUVHOJ6UM3GNNKPQJWPKEK&dn=a+b"
+            .parse()
+            .unwrap();
+        let hex: MagnetLink = "magnet:?xt=urn:btih:C12FE1C06BBA254A9DC9F519B335AA7C1367A88A"
+            .parse()
+            .unwrap();
+
+        assert_eq!(link.info_hash, hex.info_hash);
+        assert_eq!(link.display_name.as_deref(), Some("a b"));
+    }
+
+    #[test]
+    fn invalid_magnet_links_should_fail() {
+        let cases = [
+            "http://example/",
+            "magnet:?dn=foo",
+            "magnet:?xt=urn:btih:abc",
+            "magnet:?xt=urn:btih:zz2fe1c06bba254a9dc9f519b335aa7c1367a88a",
+            "magnet:?xt=urn:btmh:1120abababababababababababababababababababababababababababababababab",
+            "magnet:?xt=urn:btih:c12fe1c06bba254a9dc9f519b335aa7c1367a88a&dn=%4",
+            "magnet:?xt=urn:btih:c12fe1c06bba254a9dc9f519b335aa7c1367a88a&dn=%+4",
+            "magnet:?xt=urn:btih:c12fe1c06bba254a9dc9f519b335aa7c1367a88a&dn=%ff",
+        ];
+        for &case in &cases {
+            assert!(case.parse::<MagnetLink>().is_err(), "{}", case);
+        }
+    }
+
+    #[test]
+    fn unknown_parameters_should_be_ignored() {
+        let link: MagnetLink = "magnet:?xl=10&xt=urn:sha1:abc&xt=urn:btih:\
+                                c12fe1c06bba254a9dc9f519b335aa7c1367a88a&ws=http%3A%2F%2Fa"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            link.to_string(),
+            "magnet:?xt=urn:btih:c12fe1c06bba254a9dc9f519b335aa7c1367a88a"
+        );
+        assert!(link.trackers.is_empty());
+    }
+
+    #[cfg(feature = "sha-1")]
+    #[test]
+    fn torrents_should_convert_to_magnet_links() {
+        static DEBIAN_TORRENT: &[u8] =
+            include_bytes!("../../examples/torrent_files/debian-9.4.0-amd64-netinst.iso.torrent");
+
+        let link = MagnetLink::from_torrent(DEBIAN_TORRENT).unwrap();
+        assert_eq!(link.info_hash, Some(info_hash(DEBIAN_TORRENT).unwrap()));
+        assert_eq!(link.info_hash_v2, None);
+        assert_eq!(
+            link.display_name.as_deref(),
+            Some("debian-9.4.0-amd64-netinst.iso")
+        );
+        assert_eq!(link.trackers, ["http://bttracker.debian.org:6969/announce"]);
+    }
+}
@@ -0,0 +1,200 @@
+#[cfg(not(feature = "std"))]
+use alloc::{string::ToString, vec::Vec};
+
+use crate::{
+    decoding::{self, Decoder, FromBencode, Object, ResultExt},
+    encoding::{self, SingleItemEncoder, ToBencode},
+    state_tracker::StructureError,
+};
+
+/// The length of the pieces the info dictionary is split into for the metadata
+/// exchange. Only the last piece may be shorter.
+pub const METADATA_PIECE_LENGTH: usize = 16 * 1024;
+
+/// A message of the metadata exchange extension (`ut_metadata`), which fetches the info
+/// dictionary of a torrent from peers, see
+/// [BEP 9](http://bittorrent.org/beps/bep_0009.html)
+///
+/// A [`Data`](MetadataMessage::Data) message is followed by the bytes of the piece,
+/// which aren't bencoded. [`MetadataMessage::decode_prefix`] reports where the bencoded
+/// dictionary ends so they can be sliced off the payload:
+///
+/// ```
+/// use bendy::torrent::MetadataMessage;
+///
+/// let payload = b"d8:msg_typei1e5:piecei0e10:total_sizei6eeabcdef";
+/// let (message, length) = MetadataMessage::decode_prefix(payload).unwrap();
+///
+/// assert_eq!(message, MetadataMessage::Data { piece: 0, total_size: 6 });
+/// assert_eq!(&payload[length..], b"abcdef");
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MetadataMessage {
+    /// Ask for a piece of the info dictionary (`msg_type` 0)
+    Request { piece: u32 },
+    /// A piece of the info dictionary, followed by its bytes (`msg_type` 1)
+    Data {
+        piece: u32,
+        /// The size of the whole info dictionary in bytes
+        total_size: u64,
+    },
+    /// Refuse a request for a piece (`msg_type` 2)
+    Reject { piece: u32 },
+}
+
+impl MetadataMessage {
+    /// The index of the piece the message refers to
+    pub fn piece(&self) -> u32 {
+        match *self {
+            MetadataMessage::Request { piece }
+            | MetadataMessage::Data { piece, .. }
+            | MetadataMessage::Reject { piece } => piece,
+        }
+    }
+
+    /// Decode the bencoded dictionary at the start of a message payload, returning the
+    /// message and the length of the dictionary. For a
+    /// [`Data`](MetadataMessage::Data) message, the piece data starts at that offset.
+    pub fn decode_prefix(payload: &[u8]) -> Result<(Self, usize), decoding::Error> {
+        let mut decoder = Decoder::new(payload).with_max_depth(Self::EXPECTED_RECURSION_DEPTH);
+        let message = match decoder.next_object()? {
+            Some(object) => Self::decode_bencode_object(object)?,
+            None => return Err(StructureError::UnexpectedEof.into()),
+        };
+        Ok((message, decoder.position()))
+    }
+
+    /// Encode the message followed by the bytes of a piece, as the payload of a
+    /// [`Data`](MetadataMessage::Data) message
+    pub fn to_bencode_with_data(&self, data: &[u8]) -> Result<Vec<u8>, encoding::Error> {
+        let mut payload = self.to_bencode()?;
+        payload.extend_from_slice(data);
+        Ok(payload)
+    }
+}
+
+impl FromBencode for MetadataMessage {
+    const EXPECTED_RECURSION_DEPTH: usize = 1;
+
+    fn decode_bencode_object(object: Object) -> Result<Self, decoding::Error>
+    where
+        Self: Sized,
+    {
+        let mut msg_type = None;
+        let mut piece = None;
+        let mut total_size = None;
+
+        let mut dict = object.try_into_dictionary()?;
+        while let Some(pair) = dict.next_pair()? {
+            match pair {
+                (b"msg_type", value) => {
+                    msg_type = u8::decode_bencode_object(value)
+                        .context_field("msg_type")
+                        .map(Some)?;
+                },
+                (b"piece", value) => {
+                    piece = u32::decode_bencode_object(value)
+                        .context_field("piece")
+                        .map(Some)?;
+                },
+                (b"total_size", value) => {
+                    total_size = u64::decode_bencode_object(value)
+                        .context_field("total_size")
+                        .map(Some)?;
+                },
+                _ => (),
+            }
+        }
+
+        let msg_type = msg_type.ok_or_else(|| decoding::Error::missing_field("msg_type"))?;
+        let piece = piece.ok_or_else(|| decoding::Error::missing_field("piece"))?;
+        match msg_type {
+            0 => Ok(MetadataMessage::Request { piece }),
+            1 => Ok(MetadataMessage::Data {
+                piece,
+                total_size: total_size
+                    .ok_or_else(|| decoding::Error::missing_field("total_size"))?,
+            }),
+            2 => Ok(MetadataMessage::Reject { piece }),
+            other => {
+                let error = decoding::Error::unknown_variant(other.to_string());
+                Err(error.context_field("msg_type"))
+            },
+        }
+    }
+}
+
+impl ToBencode for MetadataMessage {
+    const MAX_DEPTH: usize = 1;
+
+    fn encode(&self, encoder: SingleItemEncoder) -> Result<(), encoding::Error> {
+        let msg_type: u8 = match self {
+            MetadataMessage::Request { .. } => 0,
+            MetadataMessage::Data { .. } => 1,
+            MetadataMessage::Reject { .. } => 2,
+        };
+        encoder.emit_dict(|mut e| {
+            e.emit_pair(b"msg_type", msg_type)?;
+            e.emit_pair(b"piece", self.piece())?;
+            if let MetadataMessage::Data { total_size, .. } = self {
+                e.emit_pair(b"total_size", total_size)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn messages_should_round_trip() {
+        let messages = [
+            MetadataMessage::Request { piece: 1 },
+            MetadataMessage::Data {
+                piece: 2,
+                total_size: 40000,
+            },
+            MetadataMessage::Reject { piece: 3 },
+        ];
+        for message in &messages {
+            let encoded = message.to_bencode().unwrap();
+            assert_eq!(MetadataMessage::from_bencode(&encoded).unwrap(), *message);
+        }
+        assert_eq!(
+            messages[0].to_bencode().unwrap(),
+            b"d8:msg_typei0e5:piecei1ee"
+        );
+    }
+
+    #[test]
+    fn piece_data_should_follow_the_dictionary() {
+        let message = MetadataMessage::Data {
+            piece: 0,
+            total_size: 5,
+        };
+        let payload = message.to_bencode_with_data(b"d1:ae").unwrap();
+
+        // The piece data is bencode itself and mustn't be mistaken for part of the header
+        let (decoded, length) = MetadataMessage::decode_prefix(&payload).unwrap();
+        assert_eq!(decoded, message);
+        assert_eq!(&payload[length..], b"d1:ae");
+    }
+
+    #[test]
+    fn invalid_messages_should_fail() {
+        let cases: &[&[u8]] = &[
+            b"",
+            b"d5:piecei0ee",
+            b"d8:msg_typei0ee",
+            b"d8:msg_typei1e5:piecei0ee",
+            b"d8:msg_typei3e5:piecei0ee",
+            b"d8:msg_typei0e5:piecei-1ee",
+            b"d8:msg_typei0e5:piecei0e",
+        ];
+        for &case in cases {
+            assert!(MetadataMessage::decode_prefix(case).is_err());
+        }
+    }
+}
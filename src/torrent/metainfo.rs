@@ -0,0 +1,638 @@
+#[cfg(not(feature = "std"))]
+use alloc::{format, string::String, vec::Vec};
+
+use crate::{
+    decoding::{self, FromBencode, Object, ResultExt, DEFAULT_MAX_DEPTH},
+    encoding::{self, AsString, SingleItemEncoder, ToBencode},
+    torrent::{
+        hash_chunks,
+        v2::{self, FileNode, FileTree, PieceLayers},
+        AnnounceList, SHA1_HASH_LENGTH,
+    },
+};
+
+/// The contents of a torrent file
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MetaInfo {
+    /// The URL of the tracker
+    pub announce: Option<String>,
+    /// Tiers of tracker URLs, see [BEP 12](http://bittorrent.org/beps/bep_0012.html)
+    pub announce_list: Option<AnnounceList>,
+    /// A free-form comment of the author
+    pub comment: Option<String>,
+    /// The program which created the torrent
+    pub created_by: Option<String>,
+    /// The time the torrent was created, in seconds since the Unix epoch
+    pub creation_date: Option<i64>,
+    /// The description of the shared files
+    pub info: Info,
+    /// The piece hashes of the files of a v2 torrent, see [`PieceLayers`]
+    pub piece_layers: Option<PieceLayers>,
+}
+
+/// The info dictionary, which describes the shared files
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Info {
+    /// The name of the file, or of the directory containing the files
+    pub name: String,
+    /// The number of bytes in each piece, except for the last one
+    pub piece_length: u64,
+    /// The SHA-1 hashes of all pieces, concatenated. Only present in v1 and hybrid
+    /// torrents.
+    pub pieces: Option<Vec<u8>>,
+    /// Whether peers may only be obtained from the trackers, see
+    /// [BEP 27](http://bittorrent.org/beps/bep_0027.html)
+    pub private: bool,
+    /// The file or files of the torrent in v1 format. Only present in v1 and hybrid
+    /// torrents.
+    pub layout: Option<Layout>,
+    /// The version of the metainfo format, 2 for v2 and hybrid torrents
+    pub meta_version: Option<u64>,
+    /// The files of the torrent in v2 format. Only present in v2 and hybrid torrents.
+    pub file_tree: Option<FileTree>,
+}
+
+/// The BitTorrent protocol versions a torrent can be shared with
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Version {
+    V1,
+    /// BitTorrent v2, see [BEP 52](http://bittorrent.org/beps/bep_0052.html)
+    V2,
+    /// Both versions, sharing the same files
+    Hybrid,
+}
+
+/// Whether a torrent contains a single file or a directory of files
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Layout {
+    /// A single file named after the torrent
+    SingleFile { length: u64 },
+    /// The files of a directory named after the torrent, in the order their data is
+    /// concatenated for splitting it into pieces
+    MultiFile { files: Vec<FileEntry> },
+}
+
+/// A file of a multi-file torrent
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FileEntry {
+    /// The length of the file in bytes
+    pub length: u64,
+    /// The components of the path of the file, relative to the torrent's directory
+    pub path: Vec<String>,
+    /// The attributes of the file, e.g. `p` for the padding files of hybrid torrents,
+    /// see [BEP 47](http://bittorrent.org/beps/bep_0047.html)
+    pub attr: Option<String>,
+}
+
+impl MetaInfo {
+    /// The URLs of all trackers. The announce list takes precedence over the single
+    /// announce URL if present, as specified by BEP 12.
+    pub fn trackers(&self) -> impl Iterator<Item = &str> {
+        let tiers = match &self.announce_list {
+            Some(list) => list.tiers(),
+            None => &[],
+        };
+        let announce = self.announce.as_deref().filter(|_| tiers.is_empty());
+
+        tiers.iter().flatten().map(String::as_str).chain(announce)
+    }
+
+    /// The tiers of trackers clients use, combining the announce URL and the announce
+    /// list as described by [`AnnounceList::from_announce`]
+    pub fn announce_tiers(&self) -> AnnounceList {
+        AnnounceList::from_announce(self.announce.as_deref(), self.announce_list.as_ref())
+    }
+}
+
+impl Info {
+    /// The protocol versions the torrent can be shared with
+    pub fn version(&self) -> Version {
+        match (&self.layout, &self.file_tree) {
+            (Some(_), Some(_)) => Version::Hybrid,
+            (None, Some(_)) => Version::V2,
+            _ => Version::V1,
+        }
+    }
+
+    /// The total length of all files in bytes, not counting the padding files of hybrid
+    /// torrents
+    pub fn total_length(&self) -> u64 {
+        match (&self.layout, &self.file_tree) {
+            (_, Some(tree)) => tree.values().map(FileNode::total_length).sum(),
+            (Some(Layout::SingleFile { length }), None) => *length,
+            (Some(Layout::MultiFile { files }), None) => files
+                .iter()
+                .filter(|file| !file.is_padding())
+                .map(|file| file.length)
+                .sum(),
+            (None, None) => 0,
+        }
+    }
+
+    /// The SHA-1 hash of each piece, in order. There are none in v2 torrents.
+    pub fn piece_hashes(&self) -> impl Iterator<Item = &[u8]> {
+        self.pieces
+            .as_deref()
+            .unwrap_or_default()
+            .chunks(SHA1_HASH_LENGTH)
+    }
+}
+
+impl FileEntry {
+    /// Whether this is a padding file, which only aligns the next file to a piece
+    /// boundary
+    pub fn is_padding(&self) -> bool {
+        self.attr.as_ref().is_some_and(|attr| attr.contains('p'))
+    }
+}
+
+impl FromBencode for MetaInfo {
+    fn decode_bencode_object(object: Object) -> Result<Self, decoding::Error>
+    where
+        Self: Sized,
+    {
+        let mut announce = None;
+        let mut announce_list = None;
+        let mut comment = None;
+        let mut created_by = None;
+        let mut creation_date = None;
+        let mut info = None;
+        let mut piece_layers = None;
+
+        let mut dict = object.try_into_dictionary()?;
+        while let Some(pair) = dict.next_pair()? {
+            match pair {
+                (b"announce", value) => {
+                    announce = String::decode_bencode_object(value)
+                        .context_field("announce")
+                        .map(Some)?;
+                },
+                (b"announce-list", value) => {
+                    announce_list = AnnounceList::decode_bencode_object(value)
+                        .context_field("announce-list")
+                        .map(Some)?;
+                },
+                (b"comment", value) => {
+                    comment = String::decode_bencode_object(value)
+                        .context_field("comment")
+                        .map(Some)?;
+                },
+                (b"created by", value) => {
+                    created_by = String::decode_bencode_object(value)
+                        .context_field("created by")
+                        .map(Some)?;
+                },
+                (b"creation date", value) => {
+                    creation_date = i64::decode_bencode_object(value)
+                        .context_field("creation date")
+                        .map(Some)?;
+                },
+                (b"info", value) => {
+                    info = Info::decode_bencode_object(value)
+                        .context_field("info")
+                        .map(Some)?;
+                },
+                (b"piece layers", value) => {
+                    piece_layers = v2::decode_piece_layers(value)
+                        .context_field("piece layers")
+                        .map(Some)?;
+                },
+                _ => (),
+            }
+        }
+
+        Ok(MetaInfo {
+            announce,
+            announce_list,
+            comment,
+            created_by,
+            creation_date,
+            info: info.ok_or_else(|| decoding::Error::missing_field("info"))?,
+            piece_layers,
+        })
+    }
+}
+
+impl FromBencode for Info {
+    fn decode_bencode_object(object: Object) -> Result<Self, decoding::Error>
+    where
+        Self: Sized,
+    {
+        let mut file_tree = None;
+        let mut files = None;
+        let mut length = None;
+        let mut meta_version = None;
+        let mut name = None;
+        let mut piece_length = None;
+        let mut pieces = None;
+        let mut private = false;
+
+        let mut dict = object.try_into_dictionary()?;
+        while let Some(pair) = dict.next_pair()? {
+            match pair {
+                (b"file tree", value) => {
+                    file_tree = FileTree::decode_bencode_object(value)
+                        .context_field("file tree")
+                        .map(Some)?;
+                },
+                (b"files", value) => {
+                    files = Vec::decode_bencode_object(value)
+                        .context_field("files")
+                        .map(Some)?;
+                },
+                (b"length", value) => {
+                    length = u64::decode_bencode_object(value)
+                        .context_field("length")
+                        .map(Some)?;
+                },
+                (b"meta version", value) => {
+                    meta_version = u64::decode_bencode_object(value)
+                        .context_field("meta version")
+                        .map(Some)?;
+                },
+                (b"name", value) => {
+                    name = String::decode_bencode_object(value)
+                        .context_field("name")
+                        .map(Some)?;
+                },
+                (b"piece length", value) => {
+                    piece_length = u64::decode_bencode_object(value)
+                        .context_field("piece length")
+                        .map(Some)?;
+                },
+                (b"pieces", value) => {
+                    let AsString(bytes) =
+                        AsString::decode_bencode_object(value).context_field("pieces")?;
+                    hash_chunks::<SHA1_HASH_LENGTH>(&bytes).context_field("pieces")?;
+                    pieces = Some(bytes);
+                },
+                (b"private", value) => {
+                    private = u8::decode_bencode_object(value).context_field("private")? == 1;
+                },
+                _ => (),
+            }
+        }
+
+        let layout = match (length, files) {
+            (Some(length), None) => Some(Layout::SingleFile { length }),
+            (None, Some(files)) => Some(Layout::MultiFile { files }),
+            (None, None) => None,
+            (Some(_), Some(_)) => return Err(decoding::Error::unexpected_field("files")),
+        };
+
+        // The v1 and v2 descriptions of the files have to be complete on their own
+        match (&layout, &pieces) {
+            (Some(_), None) => return Err(decoding::Error::missing_field("pieces")),
+            (None, Some(_)) => return Err(decoding::Error::missing_field("length")),
+            (None, None) if file_tree.is_none() => {
+                return Err(decoding::Error::missing_field("length"));
+            },
+            _ => (),
+        }
+        match (meta_version, &file_tree) {
+            (None, None) | (Some(2), Some(_)) => (),
+            (Some(2), None) => return Err(decoding::Error::missing_field("file tree")),
+            (None, Some(_)) => return Err(decoding::Error::missing_field("meta version")),
+            (Some(version), _) => {
+                let error = decoding::Error::unexpected_token(
+                    "meta version 2",
+                    format!("meta version {}", version),
+                );
+                return Err(error.context_field("meta version"));
+            },
+        }
+
+        Ok(Info {
+            name: name.ok_or_else(|| decoding::Error::missing_field("name"))?,
+            piece_length: piece_length
+                .ok_or_else(|| decoding::Error::missing_field("piece length"))?,
+            pieces,
+            private,
+            layout,
+            meta_version,
+            file_tree,
+        })
+    }
+}
+
+impl FromBencode for FileEntry {
+    const EXPECTED_RECURSION_DEPTH: usize = 2;
+
+    fn decode_bencode_object(object: Object) -> Result<Self, decoding::Error>
+    where
+        Self: Sized,
+    {
+        let mut attr = None;
+        let mut length = None;
+        let mut path = None;
+
+        let mut dict = object.try_into_dictionary()?;
+        while let Some(pair) = dict.next_pair()? {
+            match pair {
+                (b"attr", value) => {
+                    attr = String::decode_bencode_object(value)
+                        .context_field("attr")
+                        .map(Some)?;
+                },
+                (b"length", value) => {
+                    length = u64::decode_bencode_object(value)
+                        .context_field("length")
+                        .map(Some)?;
+                },
+                (b"path", value) => {
+                    path = Vec::decode_bencode_object(value)
+                        .context_field("path")
+                        .map(Some)?;
+                },
+                _ => (),
+            }
+        }
+
+        Ok(FileEntry {
+            length: length.ok_or_else(|| decoding::Error::missing_field("length"))?,
+            path: path.ok_or_else(|| decoding::Error::missing_field("path"))?,
+            attr,
+        })
+    }
+}
+
+impl ToBencode for MetaInfo {
+    const MAX_DEPTH: usize = Info::MAX_DEPTH + 1;
+
+    fn encode(&self, encoder: SingleItemEncoder) -> Result<(), encoding::Error> {
+        encoder.emit_dict(|mut e| {
+            if let Some(announce) = &self.announce {
+                e.emit_pair(b"announce", announce)?;
+            }
+            if let Some(announce_list) = &self.announce_list {
+                e.emit_pair(b"announce-list", announce_list)?;
+            }
+            if let Some(comment) = &self.comment {
+                e.emit_pair(b"comment", comment)?;
+            }
+            if let Some(created_by) = &self.created_by {
+                e.emit_pair(b"created by", created_by)?;
+            }
+            if let Some(creation_date) = self.creation_date {
+                e.emit_pair(b"creation date", creation_date)?;
+            }
+            e.emit_pair(b"info", &self.info)?;
+            if let Some(piece_layers) = &self.piece_layers {
+                e.emit_pair_with(b"piece layers", |e| {
+                    v2::encode_piece_layers(piece_layers, e)
+                })?;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl ToBencode for Info {
+    const MAX_DEPTH: usize = DEFAULT_MAX_DEPTH;
+
+    fn encode(&self, encoder: SingleItemEncoder) -> Result<(), encoding::Error> {
+        encoder.emit_dict(|mut e| {
+            if let Some(file_tree) = &self.file_tree {
+                e.emit_pair(b"file tree", file_tree)?;
+            }
+            match &self.layout {
+                Some(Layout::SingleFile { length }) => e.emit_pair(b"length", length)?,
+                Some(Layout::MultiFile { files }) => e.emit_pair(b"files", files)?,
+                None => (),
+            }
+            if let Some(meta_version) = self.meta_version {
+                e.emit_pair(b"meta version", meta_version)?;
+            }
+            e.emit_pair(b"name", &self.name)?;
+            e.emit_pair(b"piece length", self.piece_length)?;
+            if let Some(pieces) = &self.pieces {
+                e.emit_pair(b"pieces", AsString(pieces))?;
+            }
+            if self.private {
+                e.emit_pair(b"private", 1)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl ToBencode for FileEntry {
+    const MAX_DEPTH: usize = 2;
+
+    fn encode(&self, encoder: SingleItemEncoder) -> Result<(), encoding::Error> {
+        encoder.emit_dict(|mut e| {
+            if let Some(attr) = &self.attr {
+                e.emit_pair(b"attr", attr)?;
+            }
+            e.emit_pair(b"length", self.length)?;
+            e.emit_pair(b"path", &self.path)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::{borrow::ToOwned, vec};
+
+    use super::*;
+    use crate::{decoding::Decoder, torrent::info_bytes};
+
+    static DEBIAN_TORRENT: &[u8] =
+        include_bytes!("../../examples/torrent_files/debian-9.4.0-amd64-netinst.iso.torrent");
+
+    #[test]
+    fn single_file_torrents_should_decode() {
+        let torrent = MetaInfo::from_bencode(DEBIAN_TORRENT).unwrap();
+
+        assert_eq!(
+            torrent.trackers().collect::<Vec<_>>(),
+            vec!["http://bttracker.debian.org:6969/announce"]
+        );
+        assert_eq!(torrent.creation_date, Some(1_520_682_848));
+        assert_eq!(torrent.info.name, "debian-9.4.0-amd64-netinst.iso");
+        assert_eq!(torrent.info.total_length(), 305_135_616);
+        assert_eq!(torrent.info.piece_length, 262_144);
+        assert_eq!(torrent.info.piece_hashes().count(), 1164);
+
+        // The info dictionary has no keys which aren't covered
+        let mut decoder = Decoder::new(DEBIAN_TORRENT);
+        let info = decoder.seek_path(&[b"info"]).unwrap().unwrap();
+        let info = info.try_into_dictionary().unwrap().into_raw().unwrap();
+        assert_eq!(torrent.info.to_bencode().unwrap(), info);
+    }
+
+    #[test]
+    fn multi_file_torrents_should_round_trip() {
+        let torrent = MetaInfo {
+            announce: Some("http://a/".to_owned()),
+            announce_list: Some(AnnounceList::from(vec![vec![
+                "http://b/".to_owned(),
+                "http://c/".to_owned(),
+            ]])),
+            comment: None,
+            created_by: Some("bendy".to_owned()),
+            creation_date: None,
+            info: Info {
+                name: "dir".to_owned(),
+                piece_length: 16384,
+                pieces: Some(vec![0; 40]),
+                private: true,
+                layout: Some(Layout::MultiFile {
+                    files: vec![
+                        FileEntry {
+                            length: 3,
+                            path: vec!["a.txt".to_owned()],
+                            attr: None,
+                        },
+                        FileEntry {
+                            length: 5,
+                            path: vec!["sub".to_owned(), "b.txt".to_owned()],
+                            attr: None,
+                        },
+                    ],
+                }),
+                meta_version: None,
+                file_tree: None,
+            },
+            piece_layers: None,
+        };
+
+        let encoded = torrent.to_bencode().unwrap();
+        assert_eq!(MetaInfo::from_bencode(&encoded).unwrap(), torrent);
+        assert_eq!(torrent.info.total_length(), 8);
+        assert_eq!(
+            torrent.trackers().collect::<Vec<_>>(),
+            vec!["http://b/", "http://c/"]
+        );
+    }
+
+    #[test]
+    fn hybrid_torrents_should_round_trip() {
+        let mut directory = FileTree::new();
+        directory.insert(
+            "b.txt".to_owned(),
+            FileNode::File {
+                length: 5,
+                pieces_root: None,
+            },
+        );
+        let mut file_tree = FileTree::new();
+        file_tree.insert(
+            "a.txt".to_owned(),
+            FileNode::File {
+                length: 20000,
+                pieces_root: Some([1; 32]),
+            },
+        );
+        file_tree.insert("sub".to_owned(), FileNode::Directory(directory));
+        let mut piece_layers = PieceLayers::new();
+        piece_layers.insert([1; 32], vec![2; 64]);
+
+        let torrent = MetaInfo {
+            announce: Some("http://a/".to_owned()),
+            announce_list: None,
+            comment: None,
+            created_by: None,
+            creation_date: None,
+            info: Info {
+                name: "dir".to_owned(),
+                piece_length: 16384,
+                pieces: Some(vec![0; 60]),
+                private: false,
+                layout: Some(Layout::MultiFile {
+                    files: vec![
+                        FileEntry {
+                            length: 20000,
+                            path: vec!["a.txt".to_owned()],
+                            attr: None,
+                        },
+                        FileEntry {
+                            length: 12768,
+                            path: vec![".pad".to_owned(), "12768".to_owned()],
+                            attr: Some("p".to_owned()),
+                        },
+                        FileEntry {
+                            length: 5,
+                            path: vec!["sub".to_owned(), "b.txt".to_owned()],
+                            attr: None,
+                        },
+                    ],
+                }),
+                meta_version: Some(2),
+                file_tree: Some(file_tree),
+            },
+            piece_layers: Some(piece_layers),
+        };
+
+        let encoded = torrent.to_bencode().unwrap();
+        assert_eq!(MetaInfo::from_bencode(&encoded).unwrap(), torrent);
+        assert_eq!(torrent.info.version(), Version::Hybrid);
+        assert_eq!(torrent.info.total_length(), 20005);
+    }
+
+    #[test]
+    fn versions_should_be_detected() {
+        let v2 = b"d4:infod9:file treed1:ad0:d6:lengthi3eeee12:meta versioni2e\
+                   4:name1:a12:piece lengthi16384eee";
+        let torrent = MetaInfo::from_bencode(v2).unwrap();
+        assert_eq!(torrent.info.version(), Version::V2);
+        assert_eq!(torrent.info.total_length(), 3);
+        assert_eq!(torrent.info.piece_hashes().count(), 0);
+        assert_eq!(torrent.info.to_bencode().unwrap(), info_bytes(v2).unwrap());
+
+        let torrent = MetaInfo::from_bencode(DEBIAN_TORRENT).unwrap();
+        assert_eq!(torrent.info.version(), Version::V1);
+    }
+
+    #[test]
+    fn invalid_v2_info_dictionaries_should_fail() {
+        let cases: &[&[u8]] = &[
+            // Missing the meta version
+            b"d4:infod9:file treed1:ad0:d6:lengthi3eeee4:name1:a12:piece lengthi1eee",
+            // Unknown meta version
+            b"d4:infod9:file treed1:ad0:d6:lengthi3eeee12:meta versioni3e\
+              4:name1:a12:piece lengthi1eee",
+            // Missing the file tree
+            b"d4:infod12:meta versioni2e4:name1:a12:piece lengthi1eee",
+            // Pieces root of the wrong length
+            b"d4:infod9:file treed1:ad0:d6:lengthi3e11:pieces root3:abceeee\
+              12:meta versioni2e4:name1:a12:piece lengthi1eee",
+            // Piece layer which isn't a multiple of 32 bytes
+            b"d4:infod9:file treed1:ad0:d6:lengthi3eeee12:meta versioni2e\
+              4:name1:a12:piece lengthi1ee12:piece layersd32:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\
+              3:abcee",
+            // File properties mixed with children
+            b"d4:infod9:file treed1:ad0:d6:lengthi3ee1:bd0:d6:lengthi1eeeee\
+              12:meta versioni2e4:name1:a12:piece lengthi1eee",
+        ];
+        for &case in cases {
+            assert!(MetaInfo::from_bencode(case).is_err());
+        }
+    }
+
+    #[test]
+    fn invalid_info_dictionaries_should_fail() {
+        let cases: &[&[u8]] = &[
+            b"d4:infod4:name1:a12:piece lengthi1e6:pieces0:ee",
+            b"d4:infod5:filesle6:lengthi1e4:name1:a12:piece lengthi1e6:pieces0:ee",
+            b"d4:infod6:lengthi1e4:name1:a12:piece lengthi1e6:pieces3:abcee",
+            b"d4:infod6:lengthi1e4:name1:a6:pieces0:ee",
+            b"d8:announce3:fooe",
+        ];
+        for &case in cases {
+            assert!(MetaInfo::from_bencode(case).is_err());
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn errors_should_name_the_field() {
+        let input = b"d4:infod6:lengthi1e4:name1:a12:piece lengthi1e6:pieces3:abcee";
+        let error = MetaInfo::from_bencode(input).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Error: discovered 3 bytes but expected a multiple of 20 bytes in info.pieces"
+        );
+    }
+}
@@ -0,0 +1,339 @@
+use std::net::SocketAddr;
+
+use crate::{
+    decoding::{self, FromBencode, Object, ResultExt},
+    encoding::{self, AsString, SingleItemEncoder, ToBencode},
+    torrent::{decode_compact_peers, decode_compact_peers6, encode_compact_peers, InfoHash},
+};
+
+/// The `file-format` libtorrent writes to its resume files
+const FILE_FORMAT: &str = "libtorrent resume file";
+
+/// The flag of a piece in [`ResumeData::pieces`] which is set once it's downloaded
+const PIECE_DOWNLOADED: u8 = 1;
+
+/// The fast-resume data libtorrent keeps next to a torrent, so it can resume the
+/// download without checking the files on disk again
+///
+/// Only the keys needed to carry a download over to another client are covered. Like
+/// [`MetaInfo`](crate::torrent::MetaInfo), other keys are skipped when decoding.
+///
+/// ```
+/// use bendy::{decoding::FromBencode, torrent::ResumeData};
+///
+/// let resume = ResumeData::from_bencode(
+///     b"d11:file-format22:libtorrent resume file12:file-versioni1e\
+///       13:file_priorityli4ei0ee6:pieces3:\x01\x00\x01e",
+/// )
+/// .unwrap();
+///
+/// assert_eq!(resume.file_priorities, vec![4, 0]);
+/// assert!(resume.has_piece(0));
+/// assert!(!resume.has_piece(1));
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ResumeData {
+    /// The info-hash of the torrent (`info-hash`)
+    pub info_hash: Option<InfoHash>,
+    /// The name of the torrent (`name`)
+    pub name: Option<String>,
+    /// The directory the torrent is downloaded to (`save_path`)
+    pub save_path: Option<String>,
+    /// When the torrent was added, as a UNIX timestamp (`added_time`)
+    pub added_time: Option<i64>,
+    /// When the download completed, as a UNIX timestamp (`completed_time`)
+    pub completed_time: Option<i64>,
+    /// The number of bytes downloaded over the lifetime of the torrent
+    /// (`total_downloaded`)
+    pub total_downloaded: Option<u64>,
+    /// The number of bytes uploaded over the lifetime of the torrent (`total_uploaded`)
+    pub total_uploaded: Option<u64>,
+    /// Whether the torrent is paused (`paused`)
+    pub paused: bool,
+    /// The priority of each file from 0 (don't download) to 7 (`file_priority`)
+    pub file_priorities: Vec<u8>,
+    /// The priority of each piece, one byte per piece (`piece_priority`)
+    pub piece_priorities: Vec<u8>,
+    /// The flags of each piece, one byte per piece (`pieces`). The lowest bit is set
+    /// for downloaded pieces, see [`ResumeData::has_piece`].
+    pub pieces: Vec<u8>,
+    /// Peers to connect to when the torrent resumes, from both `peers` and `peers6`
+    pub peers: Vec<SocketAddr>,
+    /// Peers which must not be connected to, from both `banned_peers` and
+    /// `banned_peers6`
+    pub banned_peers: Vec<SocketAddr>,
+    /// The announce URLs of the trackers, grouped in tiers (`trackers`)
+    pub trackers: Vec<Vec<String>>,
+}
+
+impl ResumeData {
+    /// Create empty resume data
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the piece at the given index is downloaded
+    pub fn has_piece(&self, index: usize) -> bool {
+        self.pieces
+            .get(index)
+            .is_some_and(|flags| flags & PIECE_DOWNLOADED != 0)
+    }
+
+    /// Mark the piece at the given index as downloaded or missing, growing
+    /// [`ResumeData::pieces`] as needed
+    pub fn set_piece(&mut self, index: usize, downloaded: bool) {
+        if index >= self.pieces.len() {
+            if !downloaded {
+                return;
+            }
+            self.pieces.resize(index + 1, 0);
+        }
+        if downloaded {
+            self.pieces[index] |= PIECE_DOWNLOADED;
+        } else {
+            self.pieces[index] &= !PIECE_DOWNLOADED;
+        }
+    }
+}
+
+impl FromBencode for ResumeData {
+    const EXPECTED_RECURSION_DEPTH: usize = 3;
+
+    fn decode_bencode_object(object: Object) -> Result<Self, decoding::Error>
+    where
+        Self: Sized,
+    {
+        let mut resume = ResumeData::default();
+
+        let mut dict = object.try_into_dictionary()?;
+        while let Some(pair) = dict.next_pair()? {
+            match pair {
+                (b"added_time", value) => {
+                    resume.added_time = i64::decode_bencode_object(value)
+                        .context_field("added_time")
+                        .map(Some)?;
+                },
+                (b"banned_peers", value) => {
+                    resume.banned_peers.extend(
+                        decode_peers(value, decode_compact_peers).context_field("banned_peers")?,
+                    );
+                },
+                (b"banned_peers6", value) => {
+                    resume.banned_peers.extend(
+                        decode_peers(value, decode_compact_peers6)
+                            .context_field("banned_peers6")?,
+                    );
+                },
+                (b"completed_time", value) => {
+                    resume.completed_time = i64::decode_bencode_object(value)
+                        .context_field("completed_time")
+                        .map(Some)?;
+                },
+                (b"file-format", value) => {
+                    let format =
+                        String::decode_bencode_object(value).context_field("file-format")?;
+                    if format != FILE_FORMAT {
+                        let error = decoding::Error::unexpected_token(FILE_FORMAT, format);
+                        return Err(error.context_field("file-format"));
+                    }
+                },
+                (b"file_priority", value) => {
+                    resume.file_priorities =
+                        Vec::decode_bencode_object(value).context_field("file_priority")?;
+                },
+                (b"info-hash", value) => {
+                    resume.info_hash = InfoHash::decode_bencode_object(value)
+                        .context_field("info-hash")
+                        .map(Some)?;
+                },
+                (b"name", value) => {
+                    resume.name = String::decode_bencode_object(value)
+                        .context_field("name")
+                        .map(Some)?;
+                },
+                (b"paused", value) => {
+                    resume.paused = i64::decode_bencode_object(value).context_field("paused")? != 0;
+                },
+                (b"peers", value) => {
+                    resume
+                        .peers
+                        .extend(decode_peers(value, decode_compact_peers).context_field("peers")?);
+                },
+                (b"peers6", value) => {
+                    resume.peers.extend(
+                        decode_peers(value, decode_compact_peers6).context_field("peers6")?,
+                    );
+                },
+                (b"piece_priority", value) => {
+                    resume.piece_priorities = AsString::decode_bencode_object(value)
+                        .context_field("piece_priority")
+                        .map(|AsString(priorities)| priorities)?;
+                },
+                (b"pieces", value) => {
+                    resume.pieces = AsString::decode_bencode_object(value)
+                        .context_field("pieces")
+                        .map(|AsString(pieces)| pieces)?;
+                },
+                (b"save_path", value) => {
+                    resume.save_path = String::decode_bencode_object(value)
+                        .context_field("save_path")
+                        .map(Some)?;
+                },
+                (b"total_downloaded", value) => {
+                    resume.total_downloaded = u64::decode_bencode_object(value)
+                        .context_field("total_downloaded")
+                        .map(Some)?;
+                },
+                (b"total_uploaded", value) => {
+                    resume.total_uploaded = u64::decode_bencode_object(value)
+                        .context_field("total_uploaded")
+                        .map(Some)?;
+                },
+                (b"trackers", value) => {
+                    resume.trackers =
+                        Vec::decode_bencode_object(value).context_field("trackers")?;
+                },
+                _ => (),
+            }
+        }
+
+        Ok(resume)
+    }
+}
+
+/// Decode peers in the compact format with the given function
+fn decode_peers(
+    object: Object,
+    decode_compact: fn(&[u8]) -> Result<Vec<SocketAddr>, decoding::Error>,
+) -> Result<Vec<SocketAddr>, decoding::Error> {
+    decode_compact(object.try_into_bytes()?)
+}
+
+impl ToBencode for ResumeData {
+    const MAX_DEPTH: usize = 3;
+
+    fn encode(&self, encoder: SingleItemEncoder) -> Result<(), encoding::Error> {
+        let (peers, peers6) = encode_compact_peers(self.peers.iter().copied());
+        let (banned_peers, banned_peers6) = encode_compact_peers(self.banned_peers.iter().copied());
+
+        encoder.emit_dict(|mut e| {
+            if let Some(added_time) = self.added_time {
+                e.emit_pair(b"added_time", added_time)?;
+            }
+            if !banned_peers.is_empty() {
+                e.emit_pair(b"banned_peers", AsString(&banned_peers))?;
+            }
+            if !banned_peers6.is_empty() {
+                e.emit_pair(b"banned_peers6", AsString(&banned_peers6))?;
+            }
+            if let Some(completed_time) = self.completed_time {
+                e.emit_pair(b"completed_time", completed_time)?;
+            }
+            e.emit_pair(b"file-format", FILE_FORMAT)?;
+            e.emit_pair(b"file-version", 1)?;
+            if !self.file_priorities.is_empty() {
+                e.emit_pair(b"file_priority", &self.file_priorities)?;
+            }
+            if let Some(info_hash) = &self.info_hash {
+                e.emit_pair(b"info-hash", info_hash)?;
+            }
+            if let Some(name) = &self.name {
+                e.emit_pair(b"name", name)?;
+            }
+            e.emit_pair(b"paused", u8::from(self.paused))?;
+            if !peers.is_empty() {
+                e.emit_pair(b"peers", AsString(&peers))?;
+            }
+            if !peers6.is_empty() {
+                e.emit_pair(b"peers6", AsString(&peers6))?;
+            }
+            if !self.piece_priorities.is_empty() {
+                e.emit_pair(b"piece_priority", AsString(&self.piece_priorities))?;
+            }
+            if !self.pieces.is_empty() {
+                e.emit_pair(b"pieces", AsString(&self.pieces))?;
+            }
+            if let Some(save_path) = &self.save_path {
+                e.emit_pair(b"save_path", save_path)?;
+            }
+            if let Some(total_downloaded) = self.total_downloaded {
+                e.emit_pair(b"total_downloaded", total_downloaded)?;
+            }
+            if let Some(total_uploaded) = self.total_uploaded {
+                e.emit_pair(b"total_uploaded", total_uploaded)?;
+            }
+            if !self.trackers.is_empty() {
+                e.emit_pair(b"trackers", &self.trackers)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn resume_data_should_round_trip() {
+        let mut resume = ResumeData {
+            info_hash: Some(InfoHash([7; 20])),
+            name: Some("debian.iso".to_owned()),
+            save_path: Some("/srv/downloads".to_owned()),
+            added_time: Some(1_600_000_000),
+            total_uploaded: Some(1 << 40),
+            paused: true,
+            file_priorities: vec![4, 0, 7],
+            piece_priorities: vec![1, 1, 0],
+            peers: vec![
+                "1.2.3.4:6881".parse().unwrap(),
+                "[::1]:6882".parse().unwrap(),
+            ],
+            banned_peers: vec!["5.6.7.8:1".parse().unwrap()],
+            trackers: vec![vec!["http://a/".to_owned()], vec!["udp://b:80".to_owned()]],
+            ..ResumeData::new()
+        };
+        resume.set_piece(2, true);
+
+        let encoded = resume.to_bencode().unwrap();
+        assert_eq!(ResumeData::from_bencode(&encoded).unwrap(), resume);
+    }
+
+    #[test]
+    fn pieces_should_be_flags_per_byte() {
+        let mut resume = ResumeData::new();
+        resume.set_piece(1, false);
+        assert!(resume.pieces.is_empty());
+
+        resume.set_piece(3, true);
+        assert_eq!(resume.pieces, [0, 0, 0, 1]);
+
+        // Other flags, like libtorrent's verified bit, are kept
+        resume.pieces[3] |= 2;
+        resume.set_piece(3, false);
+        assert_eq!(resume.pieces, [0, 0, 0, 2]);
+        assert!(!resume.has_piece(3));
+        assert!(!resume.has_piece(10));
+    }
+
+    #[test]
+    fn empty_resume_data_should_encode_the_header() {
+        assert_eq!(
+            ResumeData::new().to_bencode().unwrap(),
+            &b"d11:file-format22:libtorrent resume file12:file-versioni1e6:pausedi0ee"[..]
+        );
+    }
+
+    #[test]
+    fn invalid_resume_data_should_fail() {
+        let cases: &[&[u8]] = &[
+            b"d11:file-format4:atome",
+            b"d13:file_priorityli256eee",
+            b"d5:peers5:abcdee",
+            b"d8:trackersl3:fooee",
+        ];
+        for &case in cases {
+            assert!(ResumeData::from_bencode(case).is_err());
+        }
+    }
+}
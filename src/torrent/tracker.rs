@@ -0,0 +1,561 @@
+use std::{
+    collections::BTreeMap,
+    net::{IpAddr, SocketAddr},
+};
+
+use crate::{
+    decoding::{self, FromBencode, Object, ResultExt},
+    encoding::{self, AsString, SingleItemEncoder, ToBencode},
+    torrent::{decode_compact_peers, decode_compact_peers6, encode_compact_peers, InfoHash},
+};
+
+/// The response of an HTTP tracker to an announce, see
+/// [BEP 3](http://bittorrent.org/beps/bep_0003.html)
+///
+/// ```
+/// use bendy::{decoding::FromBencode, torrent::AnnounceResponse};
+///
+/// let response = AnnounceResponse::from_bencode(
+///     b"d8:completei5e10:incompletei2e8:intervali1800e5:peers6:\x7f\0\0\x01\x1a\xe1e",
+/// )
+/// .unwrap();
+///
+/// match response {
+///     AnnounceResponse::Success(announce) => {
+///         assert_eq!(announce.interval, 1800);
+///         assert_eq!(announce.peers, vec!["127.0.0.1:6881".parse().unwrap()]);
+///     },
+///     AnnounceResponse::Failure { reason } => panic!("announce failed: {}", reason),
+/// }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AnnounceResponse {
+    /// The tracker accepted the announce
+    Success(Announce),
+    /// The tracker rejected the announce with a human-readable reason
+    Failure { reason: String },
+}
+
+/// The swarm information of a successful announce
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Announce {
+    /// The number of seconds to wait before announcing again
+    pub interval: u64,
+    /// The number of seconds clients must wait before announcing again
+    pub min_interval: Option<u64>,
+    /// An id to send with the next announces
+    pub tracker_id: Option<Vec<u8>>,
+    /// A warning to show to the user, although the announce succeeded
+    pub warning_message: Option<String>,
+    /// The number of seeders
+    pub complete: Option<u64>,
+    /// The number of leechers
+    pub incomplete: Option<u64>,
+    /// The addresses of peers, from both `peers` and `peers6`.
+    ///
+    /// Peers may be sent as a list of dictionaries or in the compact format of
+    /// [BEP 23](http://bittorrent.org/beps/bep_0023.html) and
+    /// [BEP 7](http://bittorrent.org/beps/bep_0007.html). The peer ids of the dictionary
+    /// model are dropped, as are peers given by a host name instead of an IP address.
+    pub peers: Vec<SocketAddr>,
+}
+
+impl FromBencode for AnnounceResponse {
+    const EXPECTED_RECURSION_DEPTH: usize = 3;
+
+    fn decode_bencode_object(object: Object) -> Result<Self, decoding::Error>
+    where
+        Self: Sized,
+    {
+        let mut complete = None;
+        let mut failure_reason = None;
+        let mut incomplete = None;
+        let mut interval = None;
+        let mut min_interval = None;
+        let mut peers = Vec::new();
+        let mut tracker_id = None;
+        let mut warning_message = None;
+
+        let mut dict = object.try_into_dictionary()?;
+        while let Some(pair) = dict.next_pair()? {
+            match pair {
+                (b"complete", value) => {
+                    complete = u64::decode_bencode_object(value)
+                        .context_field("complete")
+                        .map(Some)?;
+                },
+                (b"failure reason", value) => {
+                    failure_reason = String::decode_bencode_object(value)
+                        .context_field("failure reason")
+                        .map(Some)?;
+                },
+                (b"incomplete", value) => {
+                    incomplete = u64::decode_bencode_object(value)
+                        .context_field("incomplete")
+                        .map(Some)?;
+                },
+                (b"interval", value) => {
+                    interval = u64::decode_bencode_object(value)
+                        .context_field("interval")
+                        .map(Some)?;
+                },
+                (b"min interval", value) => {
+                    min_interval = u64::decode_bencode_object(value)
+                        .context_field("min interval")
+                        .map(Some)?;
+                },
+                (b"peers", value) => {
+                    decode_peers(value, decode_compact_peers, &mut peers).context_field("peers")?;
+                },
+                (b"peers6", value) => {
+                    decode_peers(value, decode_compact_peers6, &mut peers)
+                        .context_field("peers6")?;
+                },
+                (b"tracker id", value) => {
+                    tracker_id = AsString::decode_bencode_object(value)
+                        .context_field("tracker id")
+                        .map(|AsString(id)| Some(id))?;
+                },
+                (b"warning message", value) => {
+                    warning_message = String::decode_bencode_object(value)
+                        .context_field("warning message")
+                        .map(Some)?;
+                },
+                _ => (),
+            }
+        }
+
+        if let Some(reason) = failure_reason {
+            return Ok(AnnounceResponse::Failure { reason });
+        }
+
+        Ok(AnnounceResponse::Success(Announce {
+            interval: interval.ok_or_else(|| decoding::Error::missing_field("interval"))?,
+            min_interval,
+            tracker_id,
+            warning_message,
+            complete,
+            incomplete,
+            peers,
+        }))
+    }
+}
+
+/// Decode peers in the compact format with the given function, or in the dictionary
+/// model
+fn decode_peers(
+    object: Object,
+    decode_compact: fn(&[u8]) -> Result<Vec<SocketAddr>, decoding::Error>,
+    peers: &mut Vec<SocketAddr>,
+) -> Result<(), decoding::Error> {
+    match object {
+        Object::Bytes(bytes) => peers.extend(decode_compact(bytes)?),
+        Object::List(mut list) => {
+            let mut index = 0;
+            while let Some(peer) = list.next_object()? {
+                if let Some(peer) = decode_peer(peer).context_index(index)? {
+                    peers.push(peer);
+                }
+                index += 1;
+            }
+        },
+        other => {
+            return Err(decoding::Error::unexpected_token(
+                "String or List",
+                other.into_token().name(),
+            ));
+        },
+    }
+    Ok(())
+}
+
+/// Decode a peer of the dictionary model, skipping peers given by a host name
+fn decode_peer(object: Object) -> Result<Option<SocketAddr>, decoding::Error> {
+    let mut ip = None;
+    let mut port = None;
+
+    let mut dict = object.try_into_dictionary()?;
+    while let Some(pair) = dict.next_pair()? {
+        match pair {
+            (b"ip", value) => {
+                ip = String::decode_bencode_object(value)
+                    .context_field("ip")
+                    .map(Some)?;
+            },
+            (b"port", value) => {
+                port = u16::decode_bencode_object(value)
+                    .context_field("port")
+                    .map(Some)?;
+            },
+            _ => (),
+        }
+    }
+
+    let ip = ip.ok_or_else(|| decoding::Error::missing_field("ip"))?;
+    let port = port.ok_or_else(|| decoding::Error::missing_field("port"))?;
+    Ok(ip
+        .parse::<IpAddr>()
+        .ok()
+        .map(|ip| SocketAddr::new(ip, port)))
+}
+
+impl ToBencode for AnnounceResponse {
+    const MAX_DEPTH: usize = 1;
+
+    /// Encode the response with peers in the compact format
+    fn encode(&self, encoder: SingleItemEncoder) -> Result<(), encoding::Error> {
+        let announce = match self {
+            AnnounceResponse::Success(announce) => announce,
+            AnnounceResponse::Failure { reason } => {
+                return encoder.emit_dict(|mut e| e.emit_pair(b"failure reason", reason));
+            },
+        };
+
+        let (peers, peers6) = encode_compact_peers(announce.peers.iter().copied());
+
+        encoder.emit_dict(|mut e| {
+            if let Some(complete) = announce.complete {
+                e.emit_pair(b"complete", complete)?;
+            }
+            if let Some(incomplete) = announce.incomplete {
+                e.emit_pair(b"incomplete", incomplete)?;
+            }
+            e.emit_pair(b"interval", announce.interval)?;
+            if let Some(min_interval) = announce.min_interval {
+                e.emit_pair(b"min interval", min_interval)?;
+            }
+            e.emit_pair(b"peers", AsString(&peers))?;
+            if !peers6.is_empty() {
+                e.emit_pair(b"peers6", AsString(&peers6))?;
+            }
+            if let Some(tracker_id) = &announce.tracker_id {
+                e.emit_pair(b"tracker id", AsString(tracker_id))?;
+            }
+            if let Some(warning_message) = &announce.warning_message {
+                e.emit_pair(b"warning message", warning_message)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+/// The response of an HTTP tracker to a scrape, see
+/// [BEP 48](http://bittorrent.org/beps/bep_0048.html)
+///
+/// ```
+/// use bendy::{decoding::FromBencode, torrent::ScrapeResponse};
+///
+/// let response = ScrapeResponse::from_bencode(
+///     b"d5:filesd20:\xc1\x2f\xe1\xc0\x6b\xba\x25\x4a\x9d\xc9\xf5\x19\xb3\x35\xaa\x7c\x13\x67\xa8\x8a\
+///       d8:completei5e10:downloadedi50e10:incompletei10eeee",
+/// )
+/// .unwrap();
+///
+/// if let ScrapeResponse::Success(scrape) = response {
+///     let (hash, stats) = scrape.files.iter().next().unwrap();
+///     assert_eq!(hash.to_string(), "c12fe1c06bba254a9dc9f519b335aa7c1367a88a");
+///     assert_eq!(stats.downloaded, 50);
+/// }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ScrapeResponse {
+    /// The tracker answered the scrape
+    Success(Scrape),
+    /// The tracker rejected the scrape with a human-readable reason
+    Failure { reason: String },
+}
+
+/// The statistics of a successful scrape
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Scrape {
+    /// The statistics of each torrent, keyed by its info-hash
+    pub files: BTreeMap<InfoHash, ScrapeStats>,
+    /// The number of seconds to wait before scraping again, from the `flags` dictionary
+    pub min_request_interval: Option<u64>,
+}
+
+/// The statistics of a torrent
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ScrapeStats {
+    /// The number of seeders
+    pub complete: u64,
+    /// The number of completed downloads ever reported to the tracker
+    pub downloaded: u64,
+    /// The number of leechers
+    pub incomplete: u64,
+    /// The name of the torrent, an extension of some trackers
+    pub name: Option<String>,
+}
+
+impl FromBencode for ScrapeResponse {
+    const EXPECTED_RECURSION_DEPTH: usize = 3;
+
+    fn decode_bencode_object(object: Object) -> Result<Self, decoding::Error>
+    where
+        Self: Sized,
+    {
+        let mut failure_reason = None;
+        let mut files = None;
+        let mut min_request_interval = None;
+
+        let mut dict = object.try_into_dictionary()?;
+        while let Some(pair) = dict.next_pair()? {
+            match pair {
+                (b"failure reason", value) => {
+                    failure_reason = String::decode_bencode_object(value)
+                        .context_field("failure reason")
+                        .map(Some)?;
+                },
+                (b"files", value) => {
+                    files = decode_scrape_files(value)
+                        .context_field("files")
+                        .map(Some)?;
+                },
+                (b"flags", value) => {
+                    let mut flags = value.try_into_dictionary().context_field("flags")?;
+                    while let Some(pair) = flags.next_pair().context_field("flags")? {
+                        if let (b"min_request_interval", value) = pair {
+                            min_request_interval = u64::decode_bencode_object(value)
+                                .context_field("min_request_interval")
+                                .context_field("flags")
+                                .map(Some)?;
+                        }
+                    }
+                },
+                _ => (),
+            }
+        }
+
+        if let Some(reason) = failure_reason {
+            return Ok(ScrapeResponse::Failure { reason });
+        }
+
+        Ok(ScrapeResponse::Success(Scrape {
+            files: files.ok_or_else(|| decoding::Error::missing_field("files"))?,
+            min_request_interval,
+        }))
+    }
+}
+
+/// Decode the statistics keyed by binary info-hashes. Unlike the generic map
+/// implementation, errors name the info-hash in hex rather than as lossy UTF-8.
+fn decode_scrape_files(object: Object) -> Result<BTreeMap<InfoHash, ScrapeStats>, decoding::Error> {
+    let mut files = BTreeMap::new();
+
+    let mut dict = object.try_into_dictionary()?;
+    while let Some((hash, value)) = dict.next_pair()? {
+        let hash = InfoHash::decode_bencode_object(Object::Bytes(hash))?;
+        let stats = ScrapeStats::decode_bencode_object(value).context_field(hash)?;
+        files.insert(hash, stats);
+    }
+
+    Ok(files)
+}
+
+impl FromBencode for ScrapeStats {
+    const EXPECTED_RECURSION_DEPTH: usize = 1;
+
+    fn decode_bencode_object(object: Object) -> Result<Self, decoding::Error>
+    where
+        Self: Sized,
+    {
+        let mut complete = None;
+        let mut downloaded = None;
+        let mut incomplete = None;
+        let mut name = None;
+
+        let mut dict = object.try_into_dictionary()?;
+        while let Some(pair) = dict.next_pair()? {
+            match pair {
+                (b"complete", value) => {
+                    complete = u64::decode_bencode_object(value)
+                        .context_field("complete")
+                        .map(Some)?;
+                },
+                (b"downloaded", value) => {
+                    downloaded = u64::decode_bencode_object(value)
+                        .context_field("downloaded")
+                        .map(Some)?;
+                },
+                (b"incomplete", value) => {
+                    incomplete = u64::decode_bencode_object(value)
+                        .context_field("incomplete")
+                        .map(Some)?;
+                },
+                (b"name", value) => {
+                    name = String::decode_bencode_object(value)
+                        .context_field("name")
+                        .map(Some)?;
+                },
+                _ => (),
+            }
+        }
+
+        Ok(ScrapeStats {
+            complete: complete.ok_or_else(|| decoding::Error::missing_field("complete"))?,
+            downloaded: downloaded.ok_or_else(|| decoding::Error::missing_field("downloaded"))?,
+            incomplete: incomplete.ok_or_else(|| decoding::Error::missing_field("incomplete"))?,
+            name,
+        })
+    }
+}
+
+impl ToBencode for ScrapeResponse {
+    const MAX_DEPTH: usize = 3;
+
+    fn encode(&self, encoder: SingleItemEncoder) -> Result<(), encoding::Error> {
+        let scrape = match self {
+            ScrapeResponse::Success(scrape) => scrape,
+            ScrapeResponse::Failure { reason } => {
+                return encoder.emit_dict(|mut e| e.emit_pair(b"failure reason", reason));
+            },
+        };
+
+        encoder.emit_dict(|mut e| {
+            e.emit_pair(b"files", &scrape.files)?;
+            if let Some(min_request_interval) = scrape.min_request_interval {
+                e.emit_pair_with(b"flags", |e| {
+                    e.emit_dict(|mut e| e.emit_pair(b"min_request_interval", min_request_interval))
+                })?;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl ToBencode for ScrapeStats {
+    const MAX_DEPTH: usize = 1;
+
+    fn encode(&self, encoder: SingleItemEncoder) -> Result<(), encoding::Error> {
+        encoder.emit_dict(|mut e| {
+            e.emit_pair(b"complete", self.complete)?;
+            e.emit_pair(b"downloaded", self.downloaded)?;
+            e.emit_pair(b"incomplete", self.incomplete)?;
+            if let Some(name) = &self.name {
+                e.emit_pair(b"name", name)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn success(response: AnnounceResponse) -> Announce {
+        match response {
+            AnnounceResponse::Success(announce) => announce,
+            AnnounceResponse::Failure { reason } => panic!("announce failed: {}", reason),
+        }
+    }
+
+    #[test]
+    fn compact_peers_should_decode() {
+        let input = b"d8:intervali60e5:peers12:\x01\x02\x03\x04\x00\x50\x05\x06\x07\x08\x1a\xe1\
+                      6:peers618:\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\x01\x1a\xe2e";
+        let response = AnnounceResponse::from_bencode(input).unwrap();
+        assert_eq!(response.to_bencode().unwrap(), &input[..]);
+
+        let peers: Vec<SocketAddr> = ["1.2.3.4:80", "5.6.7.8:6881", "[::1]:6882"]
+            .iter()
+            .map(|peer| peer.parse().unwrap())
+            .collect();
+        assert_eq!(success(response).peers, peers);
+    }
+
+    #[test]
+    fn dictionary_peers_should_decode() {
+        let input = b"d8:intervali60e5:peersld2:ip7:1.2.3.47:peer id20:aaaaaaaaaaaaaaaaaaaa\
+                      4:porti80eed2:ip3:::14:porti81eed2:ip9:localhost4:porti82eeee";
+        let announce = success(AnnounceResponse::from_bencode(input).unwrap());
+
+        let peers: Vec<SocketAddr> = ["1.2.3.4:80", "[::1]:81"]
+            .iter()
+            .map(|peer| peer.parse().unwrap())
+            .collect();
+        assert_eq!(announce.peers, peers);
+    }
+
+    #[test]
+    fn failures_should_decode() {
+        let input = b"d14:failure reason9:not founde";
+        let response = AnnounceResponse::from_bencode(input).unwrap();
+
+        assert_eq!(
+            response,
+            AnnounceResponse::Failure {
+                reason: "not found".to_owned()
+            }
+        );
+        assert_eq!(response.to_bencode().unwrap(), &input[..]);
+    }
+
+    #[test]
+    fn invalid_responses_should_fail() {
+        let cases: &[&[u8]] = &[
+            b"d5:peers0:e",
+            b"d8:intervali60e5:peers5:abcdee",
+            b"d8:intervali60e6:peers66:abcdefe",
+            b"d8:intervali60e5:peersi1ee",
+            b"d8:intervali60e5:peersld2:ip7:1.2.3.4eee",
+            b"d8:intervali60e5:peersld2:ip7:1.2.3.44:porti70000eeee",
+        ];
+        for &case in cases {
+            assert!(AnnounceResponse::from_bencode(case).is_err());
+        }
+    }
+
+    #[test]
+    fn errors_should_name_the_field() {
+        let input = b"d8:intervali60e5:peersld2:ip7:1.2.3.4eee";
+        let error = AnnounceResponse::from_bencode(input).unwrap_err();
+        assert_eq!(error.to_string(), "Error: missing field: port in peers[0]");
+    }
+
+    #[test]
+    fn scrapes_should_round_trip() {
+        let mut files = BTreeMap::new();
+        files.insert(
+            InfoHash([0xff; 20]),
+            ScrapeStats {
+                complete: 1,
+                downloaded: 2,
+                incomplete: 3,
+                name: Some("a".to_owned()),
+            },
+        );
+        files.insert(InfoHash([0; 20]), ScrapeStats::default());
+        let response = ScrapeResponse::Success(Scrape {
+            files,
+            min_request_interval: Some(900),
+        });
+
+        let encoded = response.to_bencode().unwrap();
+        assert_eq!(ScrapeResponse::from_bencode(&encoded).unwrap(), response);
+    }
+
+    #[test]
+    fn invalid_scrapes_should_fail() {
+        let cases: &[&[u8]] = &[
+            b"de",
+            b"d5:filesd3:abcd8:completei1e10:downloadedi1e10:incompletei1eeee",
+            b"d5:filesd20:aaaaaaaaaaaaaaaaaaaad8:completei1e10:incompletei1eeee",
+            b"d5:filesde5:flagsi1ee",
+        ];
+        for &case in cases {
+            assert!(ScrapeResponse::from_bencode(case).is_err());
+        }
+    }
+
+    #[test]
+    fn scrape_errors_should_name_the_info_hash() {
+        let input = b"d5:filesd20:\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\
+                      \xff\xff\xff\xff\xff\xff\xff\xff\xff\xffd8:completei1eeee";
+        let error = ScrapeResponse::from_bencode(input).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Error: missing field: downloaded in files.ffffffffffffffffffffffffffffffffffffffff"
+        );
+    }
+}
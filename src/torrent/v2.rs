@@ -0,0 +1,162 @@
+#[cfg(not(feature = "std"))]
+use alloc::{collections::BTreeMap, format, string::String, vec::Vec};
+use core::convert::TryInto;
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+
+use crate::{
+    decoding::{self, FromBencode, Object, ResultExt, DEFAULT_MAX_DEPTH},
+    encoding::{self, AsString, SingleItemEncoder, ToBencode},
+    torrent::{hash_chunks, SHA256_HASH_LENGTH},
+};
+
+/// The length of the SHA-256 merkle roots of files
+pub(crate) const PIECES_ROOT_LENGTH: usize = 32;
+
+/// The files of a BitTorrent v2 torrent by name, see
+/// [BEP 52](http://bittorrent.org/beps/bep_0052.html)
+pub type FileTree = BTreeMap<String, FileNode>;
+
+/// The hashes of the pieces of each file, keyed by the root of the file's merkle tree
+pub type PieceLayers = BTreeMap<[u8; PIECES_ROOT_LENGTH], Vec<u8>>;
+
+/// An entry of a [`FileTree`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FileNode {
+    /// A file. Only files which aren't empty have a pieces root.
+    File {
+        length: u64,
+        pieces_root: Option<[u8; PIECES_ROOT_LENGTH]>,
+    },
+    /// A directory with further entries
+    Directory(FileTree),
+}
+
+impl FileNode {
+    /// The total length of the file or of all files in the directory
+    pub fn total_length(&self) -> u64 {
+        match self {
+            FileNode::File { length, .. } => *length,
+            FileNode::Directory(tree) => tree.values().map(FileNode::total_length).sum(),
+        }
+    }
+}
+
+/// Decode the root of a file's merkle tree
+fn decode_pieces_root(object: Object) -> Result<[u8; PIECES_ROOT_LENGTH], decoding::Error> {
+    let bytes = object.try_into_bytes()?;
+    bytes.try_into().map_err(|_| {
+        decoding::Error::unexpected_token("32 bytes", format!("{} bytes", bytes.len()))
+    })
+}
+
+impl FromBencode for FileNode {
+    fn decode_bencode_object(object: Object) -> Result<Self, decoding::Error>
+    where
+        Self: Sized,
+    {
+        let mut file = None;
+        let mut tree = FileTree::new();
+
+        let mut dict = object.try_into_dictionary()?;
+        while let Some((name, value)) = dict.next_pair()? {
+            if name.is_empty() {
+                file = Some(decode_file(value)?);
+                continue;
+            }
+
+            let name = String::from_utf8(name.to_vec())
+                .map_err(|_| decoding::Error::unexpected_token("a UTF-8 file name", "bytes"))?;
+            let node = FileNode::decode_bencode_object(value).context_field(&name)?;
+            tree.insert(name, node);
+        }
+
+        match file {
+            Some(_) if !tree.is_empty() => Err(decoding::Error::unexpected_field(
+                "file name next to file properties",
+            )),
+            Some(file) => Ok(file),
+            None => Ok(FileNode::Directory(tree)),
+        }
+    }
+}
+
+/// Decode the properties of a file, stored under the empty key
+fn decode_file(object: Object) -> Result<FileNode, decoding::Error> {
+    let mut length = None;
+    let mut pieces_root = None;
+
+    let mut dict = object.try_into_dictionary()?;
+    while let Some(pair) = dict.next_pair()? {
+        match pair {
+            (b"length", value) => {
+                length = u64::decode_bencode_object(value)
+                    .context_field("length")
+                    .map(Some)?;
+            },
+            (b"pieces root", value) => {
+                pieces_root = decode_pieces_root(value)
+                    .context_field("pieces root")
+                    .map(Some)?;
+            },
+            _ => (),
+        }
+    }
+
+    Ok(FileNode::File {
+        length: length.ok_or_else(|| decoding::Error::missing_field("length"))?,
+        pieces_root,
+    })
+}
+
+/// Decode the piece layers of a torrent
+pub(crate) fn decode_piece_layers(object: Object) -> Result<PieceLayers, decoding::Error> {
+    let mut layers = PieceLayers::new();
+
+    let mut dict = object.try_into_dictionary()?;
+    while let Some((root, value)) = dict.next_pair()? {
+        let root = decode_pieces_root(Object::Bytes(root))?;
+        let AsString(hashes) = AsString::decode_bencode_object(value)?;
+        hash_chunks::<SHA256_HASH_LENGTH>(&hashes)?;
+        layers.insert(root, hashes);
+    }
+
+    Ok(layers)
+}
+
+impl ToBencode for FileNode {
+    const MAX_DEPTH: usize = DEFAULT_MAX_DEPTH;
+
+    fn encode(&self, encoder: SingleItemEncoder) -> Result<(), encoding::Error> {
+        match self {
+            FileNode::File {
+                length,
+                pieces_root,
+            } => encoder.emit_dict(|mut e| {
+                e.emit_pair_with(b"", |e| {
+                    e.emit_dict(|mut e| {
+                        e.emit_pair(b"length", length)?;
+                        if let Some(pieces_root) = pieces_root {
+                            e.emit_pair(b"pieces root", AsString(pieces_root))?;
+                        }
+                        Ok(())
+                    })
+                })
+            }),
+            FileNode::Directory(tree) => encoder.emit(tree),
+        }
+    }
+}
+
+/// Encode the piece layers of a torrent
+pub(crate) fn encode_piece_layers(
+    layers: &PieceLayers,
+    encoder: SingleItemEncoder,
+) -> Result<(), encoding::Error> {
+    encoder.emit_dict(|mut e| {
+        for (root, hashes) in layers {
+            e.emit_pair(root, AsString(hashes))?;
+        }
+        Ok(())
+    })
+}
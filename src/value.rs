@@ -1,8 +1,33 @@
-//! `Value`s hold arbitrary borrowed or owneed bencode data. Unlike `Objects`,
+//! `Value`s hold arbitrary borrowed or owned bencode data. Unlike `Objects`,
 //! they can be cloned and traversed multiple times.
 //!
+//! # Zero-copy trees
+//!
+//! [`Value::decode`] and [`Object::into_value`] build a tree whose byte strings and
+//! dictionary keys borrow from the input buffer, so random access to a document
+//! only allocates the containers, never the payload. Integers are parsed while
+//! decoding. Use [`Value::into_owned`] to detach a tree from its input, e.g. to
+//! store it beyond the lifetime of the buffer.
+//!
+//! ```
+//! # use std::borrow::Cow;
+//! # use bendy::value::Value;
+//! #
+//! let buffer = b"d4:infod6:pieces4:\x01\x02\x03\x04ee".to_vec();
+//! let value = Value::decode(&buffer).unwrap();
+//! assert!(matches!(value["info"]["pieces"], Value::Bytes(Cow::Borrowed(_))));
+//!
+//! let owned: Value<'static> = value.into_owned();
+//! drop(buffer);
+//! assert_eq!(owned["info"]["pieces"], Value::from(vec![1, 2, 3, 4]));
+//! ```
+//!
+//! Decoding through [`FromBencode`] always produces owned values, as it
+//! has no access to the input buffer.
+//!
 //! `Value` implements `FromBencode`, `ToBencode`. If the `serde` feature is
-//! enabled, it also implements `Serialize` and `Deserialize`.
+//! enabled, it also implements `Serialize` and `Deserialize`, and with the `arbitrary`
+//! feature, it implements `Arbitrary` for use in fuzz targets.
 
 use alloc::{
     borrow::{Cow, ToOwned},
@@ -26,13 +51,47 @@ use serde::{
     Serialize,
 };
 
+mod access;
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+#[cfg(feature = "bumpalo")]
+mod arena;
+mod convert;
+mod diff;
+mod display;
+mod index;
+#[cfg(feature = "serde_json")]
+mod json;
+#[cfg(feature = "bytes")]
+mod shared;
+mod size;
+
+#[cfg(feature = "bumpalo")]
+pub use self::arena::ArenaValue;
+#[cfg(feature = "serde_json")]
+pub use self::json::{BinaryStrings, JsonError, JsonOptions, OutOfRangeNumbers};
+#[cfg(feature = "bytes")]
+pub use self::shared::SharedValue;
+pub use self::{
+    diff::{Change, PatchError, PathSegment},
+    index::Index,
+};
+
 use crate::{
-    decoding::{FromBencode, Object},
-    encoding::{SingleItemEncoder, ToBencode},
+    decoding::{self, Decoder, FromBencode, Object},
+    encoding::{self, Encoder, Output, SingleItemEncoder, ToBencode},
+    state_tracker::StructureError,
 };
 
 /// An owned or borrowed bencoded value.
-#[derive(PartialEq, Eq, Clone, Debug)]
+///
+/// Values are ordered by kind first, in the order of the leading byte of their encoding
+/// (byte strings, dictionaries, integers, lists). Byte strings compare like dictionary
+/// keys in canonical bencode, integers numerically, and lists and dictionaries
+/// lexicographically by their items and sorted entries. Borrowed and owned values with
+/// the same content are equal and hash the same, so values can be used as map keys or
+/// collected in sets.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Debug)]
 pub enum Value<'a> {
     /// An owned or borrowed byte string
     Bytes(Cow<'a, [u8]>),
@@ -45,6 +104,99 @@ pub enum Value<'a> {
 }
 
 impl<'a> Value<'a> {
+    /// Decode a complete document into a tree of values. Byte strings borrow from the
+    /// input; use [`Value::into_owned`] to detach the result from it. Bytes after the
+    /// value are rejected with [`StructureError::TrailingData`].
+    ///
+    /// ```
+    /// # use bendy::value::Value;
+    /// #
+    /// # fn main() -> Result<(), bendy::decoding::Error> {
+    /// let value = Value::decode(b"d4:name4:spam4:sizei3ee")?;
+    ///
+    /// match value {
+    ///     Value::Dict(dict) => assert_eq!(dict[&b"name"[..]], Value::Bytes(b"spam"[..].into())),
+    ///     _ => unreachable!(),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn decode(bytes: &'a [u8]) -> Result<Self, decoding::Error> {
+        let mut decoder = Decoder::new(bytes)
+            .with_max_depth(<Self as FromBencode>::EXPECTED_RECURSION_DEPTH)
+            .with_strict_eof();
+        let object = decoder.next_object()?;
+
+        object.map_or(
+            Err(decoding::Error::from(StructureError::UnexpectedEof)),
+            Value::from_object,
+        )
+    }
+
+    /// Build a value from an object, borrowing byte strings from the decoder's input
+    pub(crate) fn from_object(object: Object<'_, 'a>) -> Result<Self, decoding::Error> {
+        match object {
+            Object::Bytes(bytes) => Ok(Value::Bytes(Cow::Borrowed(bytes))),
+            Object::Dict(mut decoder) => {
+                let mut dict = BTreeMap::new();
+                while let Some((key, value)) = decoder.next_pair()? {
+                    dict.insert(Cow::Borrowed(key), Value::from_object(value)?);
+                }
+                Ok(Value::Dict(dict))
+            },
+            Object::Integer(text) => Ok(Value::Integer(text.parse()?)),
+            Object::List(mut decoder) => {
+                let mut list = Vec::new();
+                while let Some(object) = decoder.next_object()? {
+                    list.push(Value::from_object(object)?);
+                }
+                Ok(Value::List(list))
+            },
+        }
+    }
+
+    /// Encode the value as canonical bencode. Dictionary keys are kept sorted by the
+    /// underlying map, so this can't fail because of key order.
+    ///
+    /// ```
+    /// # use bendy::value::Value;
+    /// #
+    /// # fn main() -> Result<(), bendy::encoding::Error> {
+    /// let input = b"d8:announce3:foo4:infod6:lengthi3eee";
+    /// let mut torrent = Value::decode(input).unwrap().into_owned();
+    /// if let Value::Dict(dict) = &mut torrent {
+    ///     dict.insert(b"comment".to_vec().into(), Value::Bytes(b"bar".to_vec().into()));
+    /// }
+    ///
+    /// assert_eq!(
+    ///     torrent.to_bytes()?,
+    ///     b"d8:announce3:foo7:comment3:bar4:infod6:lengthi3eee".to_vec()
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_bytes(&self) -> Result<Vec<u8>, encoding::Error> {
+        self.to_bencode()
+    }
+
+    /// Encode the value as canonical bencode, appending it to the given output
+    pub fn encode_to<O: Output>(&self, output: &mut O) -> Result<(), encoding::Error> {
+        let mut encoder =
+            Encoder::with_output(output).with_max_depth(<Self as ToBencode>::MAX_DEPTH);
+        encoder.emit(self)?;
+        encoder.get_output().map(|_| ())
+    }
+
+    /// Name of the kind of value, for use in messages
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            Value::Bytes(_) => "bytes",
+            Value::Dict(_) => "dict",
+            Value::Integer(_) => "integer",
+            Value::List(_) => "list",
+        }
+    }
+
     /// Convert this Value into an owned Value with static lifetime
     pub fn into_owned(self) -> Value<'static> {
         match self {
@@ -63,7 +215,7 @@ impl<'a> Value<'a> {
 impl<'a> ToBencode for Value<'a> {
     // This leaves some room for external containers.
     // TODO(#38): Change this to 0 for v0.4
-    const MAX_DEPTH: usize = usize::max_value() / 4;
+    const MAX_DEPTH: usize = usize::MAX / 4;
 
     fn encode(&self, encoder: SingleItemEncoder) -> Result<(), crate::encoding::Error> {
         match self {
@@ -76,7 +228,8 @@ impl<'a> ToBencode for Value<'a> {
 }
 
 impl<'a> FromBencode for Value<'a> {
-    const EXPECTED_RECURSION_DEPTH: usize = <Self as ToBencode>::MAX_DEPTH;
+    // Decoding is recursive, so the depth must stay bounded to not overflow the stack
+    const EXPECTED_RECURSION_DEPTH: usize = decoding::DEFAULT_MAX_DEPTH;
 
     fn decode_bencode_object(object: Object) -> Result<Self, crate::decoding::Error> {
         match object {
@@ -159,8 +312,14 @@ mod serde_impls {
             Ok(Value::Integer(value))
         }
 
-        fn visit_u64<E>(self, value: u64) -> Result<Value<'a>, E> {
-            Ok(Value::Integer(value.try_into().unwrap()))
+        fn visit_u64<E>(self, value: u64) -> Result<Value<'a>, E>
+        where
+            E: serde::de::Error,
+        {
+            let integer = value
+                .try_into()
+                .map_err(|_| E::invalid_value(serde::de::Unexpected::Unsigned(value), &self))?;
+            Ok(Value::Integer(integer))
         }
 
         fn visit_borrowed_bytes<E>(self, value: &'de [u8]) -> Result<Value<'a>, E>
@@ -177,10 +336,18 @@ mod serde_impls {
             Ok(Value::Bytes(Cow::Borrowed(value.as_bytes())))
         }
 
+        fn visit_str<E>(self, value: &str) -> Result<Value<'a>, E> {
+            Ok(Value::Bytes(Cow::Owned(value.as_bytes().to_vec())))
+        }
+
         fn visit_string<E>(self, value: String) -> Result<Value<'a>, E> {
             Ok(Value::Bytes(Cow::Owned(value.into_bytes())))
         }
 
+        fn visit_bytes<E>(self, value: &[u8]) -> Result<Value<'a>, E> {
+            Ok(Value::Bytes(Cow::Owned(value.to_vec())))
+        }
+
         fn visit_byte_buf<E>(self, value: Vec<u8>) -> Result<Value<'a>, E> {
             Ok(Value::Bytes(Cow::Owned(value)))
         }
@@ -210,6 +377,7 @@ mod serde_impls {
 }
 
 #[cfg(test)]
+#[allow(clippy::needless_borrow)]
 mod tests {
     use super::*;
 
@@ -227,7 +395,7 @@ mod tests {
             panic!(
                 "Expected `{:?}` to encode as `{}`, but got `{}",
                 value,
-                String::from_utf8_lossy(expected),
+                String::from_utf8_lossy(&expected),
                 String::from_utf8_lossy(&encoded)
             )
         }
@@ -276,6 +444,133 @@ mod tests {
         }
     }
 
+    #[test]
+    fn decode_borrows_from_input() {
+        let input = b"d3:bar3:baz3:fooli1eee";
+        let value = Value::decode(input).unwrap();
+
+        let dict = match &value {
+            Value::Dict(dict) => dict,
+            _ => panic!("Expected a dict, got {:?}", value),
+        };
+        assert!(matches!(
+            dict[&b"bar"[..]],
+            Value::Bytes(Cow::Borrowed(b"baz"))
+        ));
+        assert_eq!(dict[&b"foo"[..]], Value::List(vec![Value::Integer(1)]));
+        assert_eq!(value.clone().into_owned(), value);
+    }
+
+    #[test]
+    fn object_into_value_leaves_decoder_after_subtree() {
+        let mut decoder = Decoder::new(b"ld1:ali1ei2eee3:fooe");
+        let mut list = decoder
+            .next_object()
+            .unwrap()
+            .unwrap()
+            .try_into_list()
+            .unwrap();
+
+        let first = list.next_object().unwrap().unwrap().into_value().unwrap();
+        let second = list.next_object().unwrap().unwrap().into_value().unwrap();
+        assert!(list.next_object().unwrap().is_none());
+
+        assert_eq!(first, Value::decode(b"d1:ali1ei2eee").unwrap());
+        assert_eq!(second, Value::Bytes(Cow::Borrowed(b"foo")));
+    }
+
+    #[test]
+    fn decode_borrows_nested_keys_and_strings() {
+        fn assert_borrowed(value: &Value) {
+            match value {
+                Value::Bytes(bytes) => assert!(matches!(bytes, Cow::Borrowed(_))),
+                Value::Dict(dict) => {
+                    for (key, value) in dict {
+                        assert!(matches!(key, Cow::Borrowed(_)));
+                        assert_borrowed(value);
+                    }
+                },
+                Value::Integer(_) => (),
+                Value::List(list) => list.iter().for_each(assert_borrowed),
+            }
+        }
+
+        let input = b"d1:ald1:b1:cel1:dei1ee1:e1:fe";
+        assert_borrowed(&Value::decode(input).unwrap());
+    }
+
+    #[test]
+    fn values_are_ordered_by_kind_then_content() {
+        let mut values = vec![
+            Value::list().with_item(1),
+            Value::Integer(10),
+            Value::dict().with_entry("a", 1),
+            Value::from("b"),
+            Value::Integer(2),
+            Value::list(),
+            Value::from("ab"),
+            Value::dict(),
+        ];
+        values.sort();
+
+        assert_eq!(
+            values,
+            vec![
+                Value::from("ab"),
+                Value::from("b"),
+                Value::dict(),
+                Value::dict().with_entry("a", 1),
+                Value::Integer(2),
+                Value::Integer(10),
+                Value::list(),
+                Value::list().with_item(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn borrowed_and_owned_values_are_interchangeable_keys() {
+        use alloc::collections::BTreeSet;
+
+        let mut set = BTreeSet::new();
+        set.insert(Value::decode(b"l3:fooe").unwrap());
+        assert!(!set.insert(Value::decode(b"l3:fooe").unwrap().into_owned()));
+
+        #[cfg(feature = "std")]
+        {
+            let mut set = std::collections::HashSet::new();
+            set.insert(Value::decode(b"d1:ai1ee").unwrap());
+            assert!(!set.insert(Value::dict().with_entry("a", 1)));
+        }
+    }
+
+    #[test]
+    fn decode_rejects_empty_and_malformed_input() {
+        assert!(Value::decode(b"").is_err());
+        assert!(Value::decode(b"l3:fooi1e").is_err());
+        assert!(Value::decode(b"ifooe").is_err());
+    }
+
+    #[test]
+    fn decode_rejects_trailing_data() {
+        for input in [&b"i1egarbage"[..], b"li1ee3:foo", b"de "] {
+            match Value::decode(input).unwrap_err().kind() {
+                decoding::ErrorKind::StructureError(StructureError::TrailingData { .. }) => (),
+                other => panic!("Unexpected error for {:?}: {}", input, other),
+            }
+        }
+    }
+
+    #[test]
+    fn encode_to_appends_to_output() {
+        let value = Value::decode(b"d3:fooli1ei2eee").unwrap();
+        let mut output = b"prefix".to_vec();
+        value.encode_to(&mut output).unwrap();
+
+        assert_eq!(output, b"prefixd3:fooli1ei2eee");
+        assert_eq!(value.to_bytes().unwrap(), b"d3:fooli1ei2eee");
+    }
+
     #[test]
     fn bytes() {
         case(Value::Bytes(Cow::Borrowed(&[1, 2, 3])), b"3:\x01\x02\x03");
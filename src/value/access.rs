@@ -0,0 +1,181 @@
+#[cfg(not(feature = "std"))]
+use alloc::collections::{btree_map::Entry, BTreeMap};
+use alloc::{borrow::Cow, vec::Vec};
+#[cfg(feature = "std")]
+use std::collections::{btree_map::Entry, BTreeMap};
+
+use crate::value::Value;
+
+impl<'a> Value<'a> {
+    /// Return the entries if this is a dictionary
+    pub fn as_dict(&self) -> Option<&BTreeMap<Cow<'a, [u8]>, Value<'a>>> {
+        match self {
+            Value::Dict(dict) => Some(dict),
+            _ => None,
+        }
+    }
+
+    /// Return the entries for modification if this is a dictionary
+    pub fn as_dict_mut(&mut self) -> Option<&mut BTreeMap<Cow<'a, [u8]>, Value<'a>>> {
+        match self {
+            Value::Dict(dict) => Some(dict),
+            _ => None,
+        }
+    }
+
+    /// Return the items if this is a list
+    pub fn as_list(&self) -> Option<&Vec<Value<'a>>> {
+        match self {
+            Value::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    /// Return the items for modification if this is a list
+    pub fn as_list_mut(&mut self) -> Option<&mut Vec<Value<'a>>> {
+        match self {
+            Value::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    /// Insert an entry into a dictionary, returning the value previously stored under
+    /// the key.
+    ///
+    /// ```
+    /// # use bendy::value::Value;
+    /// #
+    /// let mut torrent = Value::decode(b"d8:announce3:fooe").unwrap();
+    /// torrent.insert(&b"announce"[..], Value::Bytes(b"bar"[..].into()));
+    /// torrent.insert(&b"comment"[..], Value::Bytes(b"baz"[..].into()));
+    ///
+    /// assert_eq!(torrent.to_bytes().unwrap(), b"d8:announce3:bar7:comment3:baze");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is not a dictionary.
+    pub fn insert(&mut self, key: impl Into<Cow<'a, [u8]>>, value: Value<'a>) -> Option<Value<'a>> {
+        self.expect_dict("insert").insert(key.into(), value)
+    }
+
+    /// Remove an entry from a dictionary, returning its value. Returns `None` if there is
+    /// no such entry or the value is not a dictionary.
+    pub fn remove(&mut self, key: impl AsRef<[u8]>) -> Option<Value<'a>> {
+        self.as_dict_mut()?.remove(key.as_ref())
+    }
+
+    /// Get the entry for a key of a dictionary for in-place manipulation.
+    ///
+    /// ```
+    /// # use bendy::value::Value;
+    /// #
+    /// let mut torrent = Value::decode(b"de").unwrap();
+    /// torrent
+    ///     .entry(&b"url-list"[..])
+    ///     .or_insert_with(|| Value::List(Vec::new()))
+    ///     .push(Value::Bytes(b"http://example.com/"[..].into()));
+    ///
+    /// assert_eq!(torrent.to_bytes().unwrap(), b"d8:url-listl19:http://example.com/ee");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is not a dictionary.
+    pub fn entry(&mut self, key: impl Into<Cow<'a, [u8]>>) -> Entry<'_, Cow<'a, [u8]>, Value<'a>> {
+        self.expect_dict("entry").entry(key.into())
+    }
+
+    /// Append an item to a list.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is not a list.
+    pub fn push(&mut self, value: Value<'a>) {
+        match self {
+            Value::List(list) => list.push(value),
+            _ => panic!("cannot push onto {}", self.kind()),
+        }
+    }
+
+    /// Keep only the list items, or the values of dictionary entries, for which the
+    /// predicate returns `true`. Does nothing for byte strings and integers.
+    ///
+    /// ```
+    /// # use bendy::value::Value;
+    /// #
+    /// let mut trackers = Value::decode(b"l3:foo3:bar3:baze").unwrap();
+    /// trackers.retain(|tracker| tracker != &Value::Bytes(b"bar"[..].into()));
+    ///
+    /// assert_eq!(trackers.to_bytes().unwrap(), b"l3:foo3:baze");
+    /// ```
+    pub fn retain(&mut self, mut predicate: impl FnMut(&Value<'a>) -> bool) {
+        match self {
+            Value::List(list) => list.retain(|value| predicate(value)),
+            Value::Dict(dict) => dict.retain(|_, value| predicate(value)),
+            Value::Bytes(_) | Value::Integer(_) => (),
+        }
+    }
+
+    fn expect_dict(&mut self, operation: &str) -> &mut BTreeMap<Cow<'a, [u8]>, Value<'a>> {
+        match self {
+            Value::Dict(dict) => dict,
+            _ => panic!("cannot {} into {}", operation, self.kind()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dict_entries_can_be_edited() {
+        let mut value = Value::decode(b"d1:ai1e1:bi2ee").unwrap();
+
+        assert_eq!(
+            value.insert(&b"a"[..], Value::Integer(3)),
+            Some(Value::Integer(1))
+        );
+        assert_eq!(value.insert(b"c".to_vec(), Value::Integer(4)), None);
+        assert_eq!(value.remove("b"), Some(Value::Integer(2)));
+        assert_eq!(value.remove("b"), None);
+        *value.entry(&b"c"[..]).or_insert(Value::Integer(0)) = Value::Integer(5);
+
+        assert_eq!(value.to_bytes().unwrap(), b"d1:ai3e1:ci5ee");
+    }
+
+    #[test]
+    fn list_items_can_be_edited() {
+        let mut value = Value::decode(b"li1ei2ei3ee").unwrap();
+
+        value.push(Value::Integer(4));
+        value.retain(|item| item != &Value::Integer(2));
+        value.as_list_mut().unwrap().insert(0, Value::Integer(0));
+
+        assert_eq!(value.to_bytes().unwrap(), b"li0ei1ei3ei4ee");
+        assert_eq!(value.remove("a"), None);
+    }
+
+    #[test]
+    fn accessors_check_the_variant() {
+        let mut value = Value::decode(b"le").unwrap();
+
+        assert!(value.as_dict().is_none());
+        assert!(value.as_dict_mut().is_none());
+        assert_eq!(value.as_list(), Some(&Vec::new()));
+        assert_eq!(Value::Integer(1).as_list(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot insert into list")]
+    fn insert_panics_for_lists() {
+        Value::List(Vec::new()).insert(&b"a"[..], Value::Integer(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot push onto dict")]
+    fn push_panics_for_dicts() {
+        Value::Dict(BTreeMap::new()).push(Value::Integer(1));
+    }
+}
@@ -0,0 +1,102 @@
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap;
+use alloc::{borrow::Cow, vec::Vec};
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+
+use arbitrary_::{Arbitrary, Result, Unstructured};
+
+use crate::value::Value;
+
+/// Nesting depth up to which arbitrary values contain lists and dictionaries
+const MAX_DEPTH: usize = 8;
+
+/// Maximum number of items in arbitrary lists and dictionaries
+const MAX_ITEMS: usize = 16;
+
+/// Generates values that borrow their byte strings from the fuzzer input. Nesting is
+/// limited to a depth of 8 and containers to 16 items, so values stay small enough to
+/// round-trip quickly.
+impl<'a> Arbitrary<'a> for Value<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        arbitrary_value(u, MAX_DEPTH)
+    }
+}
+
+fn arbitrary_value<'a>(u: &mut Unstructured<'a>, depth: usize) -> Result<Value<'a>> {
+    let kinds = if depth == 0 { 2 } else { 4 };
+
+    Ok(match u.int_in_range(0..=kinds - 1)? {
+        0 => Value::Integer(i64::arbitrary(u)?),
+        1 => Value::Bytes(Cow::Borrowed(<&[u8]>::arbitrary(u)?)),
+        2 => {
+            let mut list = Vec::new();
+            while list.len() < MAX_ITEMS && bool::arbitrary(u)? {
+                list.push(arbitrary_value(u, depth - 1)?);
+            }
+            Value::List(list)
+        },
+        _ => {
+            let mut dict = BTreeMap::new();
+            while dict.len() < MAX_ITEMS && bool::arbitrary(u)? {
+                let key = <&[u8]>::arbitrary(u)?;
+                dict.insert(Cow::Borrowed(key), arbitrary_value(u, depth - 1)?);
+            }
+            Value::Dict(dict)
+        },
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Deterministic pseudo-random input, as the fuzzer would provide it
+    fn input(seed: u64, len: usize) -> Vec<u8> {
+        let mut state = seed.wrapping_mul(6_364_136_223_846_793_005) | 1;
+        (0..len)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6_364_136_223_846_793_005)
+                    .wrapping_add(1_442_695_040_888_963_407);
+                (state >> 56) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn arbitrary_values_round_trip() {
+        for seed in 0..256 {
+            let data = input(seed, 1024);
+            let value = Value::arbitrary(&mut Unstructured::new(&data)).unwrap();
+
+            let encoded = value.to_bytes().unwrap();
+            assert_eq!(Value::decode(&encoded).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn arbitrary_values_respect_limits() {
+        fn check(value: &Value, depth: usize) {
+            match value {
+                Value::List(list) => {
+                    assert!(depth > 0 && list.len() <= MAX_ITEMS);
+                    list.iter().for_each(|item| check(item, depth - 1));
+                },
+                Value::Dict(dict) => {
+                    assert!(depth > 0 && dict.len() <= MAX_ITEMS);
+                    dict.values().for_each(|item| check(item, depth - 1));
+                },
+                Value::Bytes(_) | Value::Integer(_) => (),
+            }
+        }
+
+        for seed in 0..256 {
+            let data = input(seed, 4096);
+            check(
+                &Value::arbitrary(&mut Unstructured::new(&data)).unwrap(),
+                MAX_DEPTH,
+            );
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use alloc::borrow::Cow;
+
+use bumpalo_::{collections::Vec as BumpVec, Bump};
+
+use crate::{
+    decoding::{self, Decoder, FromBencode, Object},
+    encoding::{self, SingleItemEncoder, ToBencode},
+    state_tracker::StructureError,
+    value::Value,
+};
+
+/// A bencoded value whose containers are allocated in a [`Bump`] arena.
+///
+/// Decoding large documents into a [`Value`] allocates every list and dictionary
+/// separately. An `ArenaValue` instead places them in an arena and borrows byte strings
+/// from the input, so the whole tree is released at once when the arena is dropped or
+/// reset. Dictionary entries are stored sorted by key, as they appear in canonical
+/// bencode.
+///
+/// ```
+/// # use bendy::value::ArenaValue;
+/// # use bumpalo_::Bump;
+/// #
+/// let arena = Bump::new();
+/// let torrent = ArenaValue::decode(b"d4:infod5:filesld6:lengthi3eeeee", &arena).unwrap();
+///
+/// let files = torrent.get("info").and_then(|info| info.get("files"));
+/// assert!(matches!(files, Some(ArenaValue::List(files)) if files.len() == 1));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArenaValue<'a> {
+    /// A byte string borrowed from the input
+    Bytes(&'a [u8]),
+    /// Dictionary entries, sorted by key
+    Dict(&'a [(&'a [u8], ArenaValue<'a>)]),
+    /// A signed integer
+    Integer(i64),
+    /// A list of values
+    List(&'a [ArenaValue<'a>]),
+}
+
+impl<'a> ArenaValue<'a> {
+    /// Decode a complete document, allocating its containers in the given arena. Bytes
+    /// after the document are rejected as trailing data.
+    pub fn decode(bytes: &'a [u8], arena: &'a Bump) -> Result<Self, decoding::Error> {
+        let mut decoder = Decoder::new(bytes)
+            .with_max_depth(<Value as FromBencode>::EXPECTED_RECURSION_DEPTH)
+            .with_strict_eof();
+        let object = decoder.next_object()?;
+
+        object.map_or(
+            Err(decoding::Error::from(StructureError::UnexpectedEof)),
+            |object| ArenaValue::from_object(object, arena),
+        )
+    }
+
+    fn from_object(object: Object<'_, 'a>, arena: &'a Bump) -> Result<Self, decoding::Error> {
+        match object {
+            Object::Bytes(bytes) => Ok(ArenaValue::Bytes(bytes)),
+            Object::Dict(mut decoder) => {
+                let mut dict = BumpVec::new_in(arena);
+                while let Some((key, value)) = decoder.next_pair()? {
+                    dict.push((key, ArenaValue::from_object(value, arena)?));
+                }
+                Ok(ArenaValue::Dict(dict.into_bump_slice()))
+            },
+            Object::Integer(text) => Ok(ArenaValue::Integer(text.parse()?)),
+            Object::List(mut decoder) => {
+                let mut list = BumpVec::new_in(arena);
+                while let Some(object) = decoder.next_object()? {
+                    list.push(ArenaValue::from_object(object, arena)?);
+                }
+                Ok(ArenaValue::List(list.into_bump_slice()))
+            },
+        }
+    }
+
+    /// Look up a dictionary value by key. Returns `None` if there is no such entry or
+    /// the value is not a dictionary.
+    pub fn get(&self, key: impl AsRef<[u8]>) -> Option<&ArenaValue<'a>> {
+        match self {
+            ArenaValue::Dict(dict) => dict
+                .binary_search_by(|(k, _)| (*k).cmp(key.as_ref()))
+                .ok()
+                .map(|index| &dict[index].1),
+            _ => None,
+        }
+    }
+
+    /// Copy the tree into a [`Value`], which borrows byte strings from the same input
+    pub fn to_value(&self) -> Value<'a> {
+        match *self {
+            ArenaValue::Bytes(bytes) => Value::Bytes(Cow::Borrowed(bytes)),
+            ArenaValue::Dict(dict) => Value::Dict(
+                dict.iter()
+                    .map(|(key, value)| (Cow::Borrowed(*key), value.to_value()))
+                    .collect(),
+            ),
+            ArenaValue::Integer(integer) => Value::Integer(integer),
+            ArenaValue::List(list) => Value::List(list.iter().map(ArenaValue::to_value).collect()),
+        }
+    }
+}
+
+impl<'a> ToBencode for ArenaValue<'a> {
+    const MAX_DEPTH: usize = <Value as ToBencode>::MAX_DEPTH;
+
+    fn encode(&self, encoder: SingleItemEncoder) -> Result<(), encoding::Error> {
+        match *self {
+            ArenaValue::Bytes(bytes) => encoder.emit_bytes(bytes),
+            ArenaValue::Dict(dict) => encoder.emit_dict(|mut e| {
+                for (key, value) in dict {
+                    e.emit_pair(key, value)?;
+                }
+                Ok(())
+            }),
+            ArenaValue::Integer(integer) => encoder.emit_int(integer),
+            ArenaValue::List(list) => encoder.emit_unchecked_list(list.iter()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    static TORRENT: &[u8] = b"d8:announce3:foo4:infod5:filesld6:lengthi3e4:pathl1:aeed6:lengthi-4e4:pathl1:b1:ceee4:name3:baree";
+
+    #[test]
+    fn decoded_tree_matches_value() {
+        let arena = Bump::new();
+        let value = ArenaValue::decode(TORRENT, &arena).unwrap();
+
+        assert_eq!(value.to_value(), Value::decode(TORRENT).unwrap());
+        assert_eq!(value.to_bencode().unwrap(), TORRENT);
+    }
+
+    #[test]
+    fn get_finds_dict_entries() {
+        let arena = Bump::new();
+        let value = ArenaValue::decode(TORRENT, &arena).unwrap();
+
+        assert_eq!(value.get("announce"), Some(&ArenaValue::Bytes(b"foo")));
+        assert_eq!(
+            value.get("info").and_then(|info| info.get(b"name")),
+            Some(&ArenaValue::Bytes(b"bar"))
+        );
+        assert_eq!(value.get("comment"), None);
+        assert_eq!(ArenaValue::Integer(1).get("announce"), None);
+    }
+
+    #[test]
+    fn malformed_input_is_rejected() {
+        let arena = Bump::new();
+
+        assert!(ArenaValue::decode(b"", &arena).is_err());
+        assert!(ArenaValue::decode(b"d1:bi1e1:ai2ee", &arena).is_err());
+        assert!(ArenaValue::decode(b"li1e", &arena).is_err());
+        assert!(ArenaValue::decode(b"li1eei2e", &arena).is_err());
+    }
+}
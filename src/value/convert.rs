@@ -0,0 +1,206 @@
+use alloc::{borrow::Cow, vec::Vec};
+#[cfg(not(feature = "std"))]
+use alloc::{collections::BTreeMap, string::String};
+use core::{convert::TryFrom, iter::FromIterator, str::FromStr};
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+
+use crate::{decoding, value::Value};
+
+impl<'a> Value<'a> {
+    /// Create an empty dictionary, to be filled using [`Value::with_entry`].
+    ///
+    /// ```
+    /// # use bendy::value::Value;
+    /// #
+    /// let info = Value::dict()
+    ///     .with_entry("name", "example.txt")
+    ///     .with_entry("length", 42)
+    ///     .with_entry("trackers", Value::list().with_item("foo").with_item("bar"));
+    ///
+    /// assert_eq!(
+    ///     info.to_bytes().unwrap(),
+    ///     &b"d6:lengthi42e4:name11:example.txt8:trackersl3:foo3:baree"[..]
+    /// );
+    /// ```
+    pub fn dict() -> Self {
+        Value::Dict(BTreeMap::new())
+    }
+
+    /// Create an empty list, to be filled using [`Value::with_item`]
+    pub fn list() -> Self {
+        Value::List(Vec::new())
+    }
+
+    /// Add an entry to a dictionary, replacing any previous entry with the same key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is not a dictionary.
+    #[must_use]
+    pub fn with_entry(mut self, key: impl AsRef<[u8]>, value: impl Into<Value<'a>>) -> Self {
+        self.insert(key.as_ref().to_vec(), value.into());
+        self
+    }
+
+    /// Append an item to a list.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is not a list.
+    #[must_use]
+    pub fn with_item(mut self, value: impl Into<Value<'a>>) -> Self {
+        self.push(value.into());
+        self
+    }
+}
+
+macro_rules! impl_from_integer {
+    ($($type:ty)*) => {$(
+        impl<'a> From<$type> for Value<'a> {
+            fn from(value: $type) -> Self {
+                Value::Integer(i64::from(value))
+            }
+        }
+    )*}
+}
+
+impl_from_integer!(u8 u16 u32 i8 i16 i32 i64);
+
+impl<'a> From<Vec<u8>> for Value<'a> {
+    fn from(value: Vec<u8>) -> Self {
+        Value::Bytes(Cow::Owned(value))
+    }
+}
+
+impl<'a> From<Cow<'a, [u8]>> for Value<'a> {
+    fn from(value: Cow<'a, [u8]>) -> Self {
+        Value::Bytes(value)
+    }
+}
+
+impl<'a> From<&'a str> for Value<'a> {
+    fn from(value: &'a str) -> Self {
+        Value::Bytes(Cow::Borrowed(value.as_bytes()))
+    }
+}
+
+impl<'a> From<String> for Value<'a> {
+    fn from(value: String) -> Self {
+        Value::Bytes(Cow::Owned(value.into_bytes()))
+    }
+}
+
+impl<'a> From<Vec<Value<'a>>> for Value<'a> {
+    fn from(value: Vec<Value<'a>>) -> Self {
+        Value::List(value)
+    }
+}
+
+impl<'a> From<BTreeMap<Cow<'a, [u8]>, Value<'a>>> for Value<'a> {
+    fn from(value: BTreeMap<Cow<'a, [u8]>, Value<'a>>) -> Self {
+        Value::Dict(value)
+    }
+}
+
+impl<'a> From<BTreeMap<Vec<u8>, Value<'a>>> for Value<'a> {
+    fn from(value: BTreeMap<Vec<u8>, Value<'a>>) -> Self {
+        Value::Dict(
+            value
+                .into_iter()
+                .map(|(key, value)| (Cow::Owned(key), value))
+                .collect(),
+        )
+    }
+}
+
+impl<'a, V: Into<Value<'a>>> FromIterator<V> for Value<'a> {
+    /// Collect the items into a list
+    fn from_iter<I: IntoIterator<Item = V>>(iter: I) -> Self {
+        Value::List(iter.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Decodes the bytes like [`Value::decode`], borrowing byte strings from them. They have
+/// to hold exactly one value.
+impl<'a> TryFrom<&'a [u8]> for Value<'a> {
+    type Error = decoding::Error;
+
+    fn try_from(bytes: &'a [u8]) -> Result<Self, decoding::Error> {
+        Value::decode(bytes)
+    }
+}
+
+/// Decodes bencode given as a string, e.g. in tests or on the command line. Like
+/// [`Value::decode`], trailing data is rejected.
+///
+/// ```
+/// # use bendy::value::Value;
+/// #
+/// let value: Value = "d4:spaml1:a1:bee".parse().unwrap();
+/// assert_eq!(value["spam"][1], Value::from("b"));
+/// ```
+impl FromStr for Value<'static> {
+    type Err = decoding::Error;
+
+    fn from_str(string: &str) -> Result<Self, decoding::Error> {
+        Value::decode(string.as_bytes()).map(Value::into_owned)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::{borrow::ToOwned, vec};
+
+    use super::*;
+
+    #[test]
+    fn conversions_produce_expected_variants() {
+        assert_eq!(Value::from(-3_i8), Value::Integer(-3));
+        assert_eq!(Value::from(u32::MAX), Value::Integer(i64::from(u32::MAX)));
+        assert_eq!(Value::from("foo"), Value::Bytes(Cow::Borrowed(b"foo")));
+        assert_eq!(Value::from("foo".to_owned()), Value::from(b"foo".to_vec()));
+        assert_eq!(Value::from(Cow::Borrowed(&b"foo"[..])), Value::from("foo"));
+        assert_eq!(
+            Value::from(vec![Value::from(1), Value::from("a")]),
+            Value::list().with_item(1).with_item("a")
+        );
+
+        let mut dict = BTreeMap::new();
+        dict.insert(b"a".to_vec(), Value::from(1));
+        assert_eq!(Value::from(dict), Value::dict().with_entry("a", 1));
+    }
+
+    #[test]
+    fn values_can_be_collected_into_lists() {
+        let value: Value = (1..4).collect();
+        assert_eq!(value.to_bytes().unwrap(), b"li1ei2ei3ee");
+    }
+
+    #[test]
+    fn values_can_be_decoded_with_standard_traits() {
+        let bytes = &b"li1e3:fooe"[..];
+        let value = Value::try_from(bytes).unwrap();
+        assert_eq!(value, Value::list().with_item(1).with_item("foo"));
+        assert_eq!("li1e3:fooe".parse::<Value>().unwrap(), value);
+
+        assert!(Value::try_from(&b"li1e"[..]).is_err());
+        assert!("x".parse::<Value>().is_err());
+    }
+
+    #[test]
+    fn conversions_should_reject_trailing_data() {
+        assert!(Value::try_from(&b"i1ei2e"[..]).is_err());
+        assert!("i1ezzz".parse::<Value>().is_err());
+    }
+
+    #[test]
+    fn builders_nest() {
+        let value = Value::dict()
+            .with_entry(b"b", Value::dict().with_entry("c", "d"))
+            .with_entry("a", Value::list().with_item(Value::list()));
+
+        assert_eq!(value.to_bytes().unwrap(), b"d1:allee1:bd1:c1:dee");
+    }
+}
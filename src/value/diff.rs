@@ -0,0 +1,358 @@
+use alloc::borrow::Cow;
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+use core::fmt::{self, Display, Formatter};
+
+use failure::Fail;
+
+use crate::value::Value;
+
+/// A step along the path to a nested value
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PathSegment<'a> {
+    /// The value stored under a dictionary key
+    Key(Cow<'a, [u8]>),
+    /// The list item at an index
+    Index(usize),
+}
+
+/// A single modification of a value, as produced by [`Value::diff`]. Paths are relative
+/// to the value the change is applied to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change<'a> {
+    /// Replace the value at the path, or add it if it doesn't exist. A list item can
+    /// only be added directly after the current end of the list.
+    Set {
+        path: Vec<PathSegment<'a>>,
+        value: Value<'a>,
+    },
+    /// Remove a dictionary entry or list item
+    Remove { path: Vec<PathSegment<'a>> },
+}
+
+/// An error applying a [`Change`] to a value whose structure doesn't match
+#[derive(Clone, Debug, PartialEq, Eq, Fail)]
+#[fail(display = "Cannot apply change at {}: {}", path, reason)]
+pub struct PatchError {
+    /// The path of the failed change, formatted as a JSON Pointer
+    pub path: String,
+    /// Why the change couldn't be applied
+    pub reason: &'static str,
+}
+
+impl<'a> Value<'a> {
+    /// List the changes which turn this value into the other one. Dictionary entries
+    /// are compared by key and list items by position.
+    ///
+    /// ```
+    /// # use bendy::value::{Change, PathSegment, Value};
+    /// #
+    /// let old = Value::decode(b"d8:announce3:foo4:infod6:lengthi3eee").unwrap();
+    /// let new = Value::decode(b"d4:infod6:lengthi4eee").unwrap();
+    ///
+    /// let patch = old.diff(&new);
+    /// assert_eq!(patch, vec![
+    ///     Change::Remove {
+    ///         path: vec![PathSegment::Key(b"announce"[..].into())],
+    ///     },
+    ///     Change::Set {
+    ///         path: vec![
+    ///             PathSegment::Key(b"info"[..].into()),
+    ///             PathSegment::Key(b"length"[..].into()),
+    ///         ],
+    ///         value: Value::Integer(4),
+    ///     },
+    /// ]);
+    ///
+    /// let mut patched = old.clone();
+    /// patched.merge(patch).unwrap();
+    /// assert_eq!(patched, new);
+    /// ```
+    pub fn diff(&self, other: &Value<'a>) -> Vec<Change<'a>> {
+        let mut changes = Vec::new();
+        diff_into(&mut Vec::new(), self, other, &mut changes);
+        changes
+    }
+
+    /// Apply the changes in order. Changes applied before a failing one stay in effect.
+    pub fn merge(&mut self, patch: impl IntoIterator<Item = Change<'a>>) -> Result<(), PatchError> {
+        for change in patch {
+            self.apply(change)?;
+        }
+        Ok(())
+    }
+
+    fn apply(&mut self, change: Change<'a>) -> Result<(), PatchError> {
+        let (path, value) = match change {
+            Change::Set { path, value } => (path, Some(value)),
+            Change::Remove { path } => (path, None),
+        };
+        let fail = |reason| {
+            Err(PatchError {
+                path: format_path(&path),
+                reason,
+            })
+        };
+
+        let (last, parents) = match path.split_last() {
+            Some(split) => split,
+            None => match value {
+                Some(value) => {
+                    *self = value;
+                    return Ok(());
+                },
+                None => return fail("the value itself can't be removed"),
+            },
+        };
+
+        let mut parent = &mut *self;
+        for segment in parents {
+            parent = match (parent, segment) {
+                (Value::Dict(dict), PathSegment::Key(key)) => match dict.get_mut(&key[..]) {
+                    Some(child) => child,
+                    None => return fail("missing parent"),
+                },
+                (Value::List(list), PathSegment::Index(index)) => match list.get_mut(*index) {
+                    Some(child) => child,
+                    None => return fail("missing parent"),
+                },
+                _ => return fail("parent has the wrong type"),
+            };
+        }
+
+        match (parent, last, value) {
+            (Value::Dict(dict), PathSegment::Key(key), Some(value)) => {
+                dict.insert(key.clone(), value);
+            },
+            (Value::Dict(dict), PathSegment::Key(key), None) => {
+                if dict.remove(&key[..]).is_none() {
+                    return fail("missing key");
+                }
+            },
+            (Value::List(list), &PathSegment::Index(index), Some(value)) => {
+                if index < list.len() {
+                    list[index] = value;
+                } else if index == list.len() {
+                    list.push(value);
+                } else {
+                    return fail("index out of bounds");
+                }
+            },
+            (Value::List(list), &PathSegment::Index(index), None) => {
+                if index < list.len() {
+                    list.remove(index);
+                } else {
+                    return fail("index out of bounds");
+                }
+            },
+            _ => return fail("parent has the wrong type"),
+        }
+
+        Ok(())
+    }
+}
+
+fn diff_into<'a>(
+    path: &mut Vec<PathSegment<'a>>,
+    old: &Value<'a>,
+    new: &Value<'a>,
+    changes: &mut Vec<Change<'a>>,
+) {
+    if old == new {
+        return;
+    }
+
+    match (old, new) {
+        (Value::Dict(old), Value::Dict(new)) => {
+            let mut old_entries = old.iter().peekable();
+            let mut new_entries = new.iter().peekable();
+            loop {
+                let (key, old_value, new_value) = match (old_entries.peek(), new_entries.peek()) {
+                    (None, None) => break,
+                    (Some((old_key, _)), Some((new_key, _))) if old_key == new_key => {
+                        let (key, old_value) = old_entries.next().unwrap();
+                        let (_, new_value) = new_entries.next().unwrap();
+                        (key, Some(old_value), Some(new_value))
+                    },
+                    (Some((old_key, _)), Some((new_key, _))) if new_key < old_key => {
+                        let (key, new_value) = new_entries.next().unwrap();
+                        (key, None, Some(new_value))
+                    },
+                    (Some(_), _) => {
+                        let (key, old_value) = old_entries.next().unwrap();
+                        (key, Some(old_value), None)
+                    },
+                    (None, Some(_)) => {
+                        let (key, new_value) = new_entries.next().unwrap();
+                        (key, None, Some(new_value))
+                    },
+                };
+
+                path.push(PathSegment::Key(key.clone()));
+                diff_entry(path, old_value, new_value, changes);
+                path.pop();
+            }
+        },
+        (Value::List(old), Value::List(new)) => {
+            for (index, (old_item, new_item)) in old.iter().zip(new).enumerate() {
+                path.push(PathSegment::Index(index));
+                diff_into(path, old_item, new_item, changes);
+                path.pop();
+            }
+            for (index, new_item) in new.iter().enumerate().skip(old.len()) {
+                path.push(PathSegment::Index(index));
+                diff_entry(path, None, Some(new_item), changes);
+                path.pop();
+            }
+            // Remove surplus items from the back, so the indices stay valid
+            for index in (new.len()..old.len()).rev() {
+                path.push(PathSegment::Index(index));
+                diff_entry(path, Some(&old[index]), None, changes);
+                path.pop();
+            }
+        },
+        _ => changes.push(Change::Set {
+            path: path.clone(),
+            value: new.clone(),
+        }),
+    }
+}
+
+fn diff_entry<'a>(
+    path: &mut Vec<PathSegment<'a>>,
+    old: Option<&Value<'a>>,
+    new: Option<&Value<'a>>,
+    changes: &mut Vec<Change<'a>>,
+) {
+    match (old, new) {
+        (Some(old), Some(new)) => diff_into(path, old, new, changes),
+        (None, Some(new)) => changes.push(Change::Set {
+            path: path.clone(),
+            value: new.clone(),
+        }),
+        (Some(_), None) => changes.push(Change::Remove { path: path.clone() }),
+        (None, None) => (),
+    }
+}
+
+fn format_path(path: &[PathSegment]) -> String {
+    struct Pointer<'p, 'a>(&'p [PathSegment<'a>]);
+
+    impl<'p, 'a> Display for Pointer<'p, 'a> {
+        fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+            for segment in self.0 {
+                match segment {
+                    PathSegment::Key(key) => {
+                        let key = String::from_utf8_lossy(key);
+                        write!(f, "/{}", key.replace('~', "~0").replace('/', "~1"))?
+                    },
+                    PathSegment::Index(index) => write!(f, "/{}", index)?,
+                }
+            }
+            Ok(())
+        }
+    }
+
+    alloc::format!("{}", Pointer(path))
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::vec;
+
+    use super::*;
+
+    fn round_trip(old: &[u8], new: &[u8]) -> Vec<Change<'static>> {
+        let old = Value::decode(old).unwrap().into_owned();
+        let new = Value::decode(new).unwrap().into_owned();
+
+        let patch = old.diff(&new);
+        let mut patched = old.clone();
+        patched.merge(patch.clone()).unwrap();
+        assert_eq!(patched, new);
+
+        patch
+    }
+
+    #[test]
+    fn equal_values_have_no_changes() {
+        assert_eq!(round_trip(b"d1:ali1eee", b"d1:ali1eee"), vec![]);
+    }
+
+    #[test]
+    fn diff_round_trips() {
+        let documents: &[&[u8]] = &[
+            b"i1e",
+            b"3:foo",
+            b"le",
+            b"li1ei2ei3ee",
+            b"li1ed1:ai1eee",
+            b"de",
+            b"d1:ai1e1:bli1ei2eee",
+            b"d1:bli2ee1:cd1:di1eee",
+        ];
+        for old in documents {
+            for new in documents {
+                round_trip(old, new);
+            }
+        }
+    }
+
+    #[test]
+    fn list_changes_are_positional() {
+        assert_eq!(
+            round_trip(b"li1ei2ei3ee", b"li1ee"),
+            vec![
+                Change::Remove {
+                    path: vec![PathSegment::Index(2)]
+                },
+                Change::Remove {
+                    path: vec![PathSegment::Index(1)]
+                },
+            ]
+        );
+        assert_eq!(
+            round_trip(b"li1ee", b"li2ei3ee"),
+            vec![
+                Change::Set {
+                    path: vec![PathSegment::Index(0)],
+                    value: Value::Integer(2),
+                },
+                Change::Set {
+                    path: vec![PathSegment::Index(1)],
+                    value: Value::Integer(3),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_reports_mismatched_structure() {
+        let mut value = Value::decode(b"d1:ali1eee").unwrap();
+        let key = |key: &'static [u8]| PathSegment::Key(Cow::Borrowed(key));
+
+        let error = value
+            .merge(vec![Change::Remove {
+                path: vec![key(b"a"), PathSegment::Index(1)],
+            }])
+            .unwrap_err();
+        assert_eq!(error.path, "/a/1");
+
+        let error = value
+            .merge(vec![Change::Set {
+                path: vec![key(b"b/~"), key(b"c")],
+                value: Value::Integer(1),
+            }])
+            .unwrap_err();
+        assert_eq!(error.path, "/b~1~0/c");
+
+        assert!(value
+            .merge(vec![Change::Set {
+                path: vec![key(b"a"), key(b"c")],
+                value: Value::Integer(1),
+            }])
+            .is_err());
+        assert!(value.merge(vec![Change::Remove { path: vec![] }]).is_err());
+    }
+}
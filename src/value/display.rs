@@ -0,0 +1,180 @@
+#[cfg(not(feature = "std"))]
+use alloc::{format, string::String};
+use core::{
+    fmt::{self, Display, Formatter, Write},
+    str,
+};
+
+use crate::value::Value;
+
+/// Number of characters of a text string shown before it is truncated
+const MAX_TEXT_CHARS: usize = 80;
+
+/// Number of bytes of a binary string shown before it is truncated
+const MAX_BINARY_BYTES: usize = 32;
+
+impl<'a> Value<'a> {
+    /// Render the value for humans, with one list item or dictionary entry per line.
+    /// This is the same as formatting it with `{:#}`.
+    ///
+    /// Byte strings which are valid UTF-8 without control characters are shown as
+    /// quoted text, others as their length and hex dump. Long strings are truncated.
+    ///
+    /// ```
+    /// # use bendy::value::Value;
+    /// #
+    /// let value = Value::decode(b"d4:infod6:lengthi3e6:pieces3:\x00\x01\x02e4:tagslee").unwrap();
+    ///
+    /// assert_eq!(
+    ///     value.to_pretty_string(),
+    ///     r#"{
+    ///   "info": {
+    ///     "length": 3,
+    ///     "pieces": <3 bytes: 000102>
+    ///   },
+    ///   "tags": []
+    /// }"#
+    /// );
+    /// assert_eq!(
+    ///     value.to_string(),
+    ///     r#"{"info": {"length": 3, "pieces": <3 bytes: 000102>}, "tags": []}"#
+    /// );
+    /// ```
+    pub fn to_pretty_string(&self) -> String {
+        format!("{:#}", self)
+    }
+}
+
+/// Renders the value on a single line, or indented across several lines with `{:#}`.
+/// See [`Value::to_pretty_string`] for details.
+impl<'a> Display for Value<'a> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write_value(f, self, 0)
+    }
+}
+
+fn write_value(f: &mut Formatter, value: &Value, indent: usize) -> fmt::Result {
+    match value {
+        Value::Bytes(bytes) => write_bytes(f, bytes),
+        Value::Integer(integer) => write!(f, "{}", integer),
+        Value::List(list) => write_container(f, '[', ']', indent, list.iter(), |f, item| {
+            write_value(f, item, indent + 1)
+        }),
+        Value::Dict(dict) => write_container(f, '{', '}', indent, dict.iter(), |f, (k, v)| {
+            write_bytes(f, k)?;
+            f.write_str(": ")?;
+            write_value(f, v, indent + 1)
+        }),
+    }
+}
+
+fn write_container<T>(
+    f: &mut Formatter,
+    open: char,
+    close: char,
+    indent: usize,
+    items: impl ExactSizeIterator<Item = T>,
+    mut write_item: impl FnMut(&mut Formatter, T) -> fmt::Result,
+) -> fmt::Result {
+    f.write_char(open)?;
+    if items.len() == 0 {
+        return f.write_char(close);
+    }
+
+    let pretty = f.alternate();
+    for (i, item) in items.enumerate() {
+        if i > 0 {
+            f.write_char(',')?;
+            if !pretty {
+                f.write_char(' ')?;
+            }
+        }
+        if pretty {
+            write_newline(f, indent + 1)?;
+        }
+        write_item(f, item)?;
+    }
+    if pretty {
+        write_newline(f, indent)?;
+    }
+
+    f.write_char(close)
+}
+
+fn write_newline(f: &mut Formatter, indent: usize) -> fmt::Result {
+    f.write_char('\n')?;
+    for _ in 0..indent {
+        f.write_str("  ")?;
+    }
+    Ok(())
+}
+
+fn write_bytes(f: &mut Formatter, bytes: &[u8]) -> fmt::Result {
+    match str::from_utf8(bytes) {
+        Ok(text) if !text.chars().any(char::is_control) => {
+            f.write_char('"')?;
+            for c in text.chars().take(MAX_TEXT_CHARS) {
+                write!(f, "{}", c.escape_debug())?;
+            }
+            f.write_char('"')?;
+            if text.chars().nth(MAX_TEXT_CHARS).is_some() {
+                write!(f, "... ({} bytes)", bytes.len())?;
+            }
+            Ok(())
+        },
+        _ => {
+            write!(f, "<{} bytes: ", bytes.len())?;
+            for byte in bytes.iter().take(MAX_BINARY_BYTES) {
+                write!(f, "{:02x}", byte)?;
+            }
+            if bytes.len() > MAX_BINARY_BYTES {
+                f.write_str("...")?;
+            }
+            f.write_char('>')
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::{string::ToString, vec};
+
+    use super::*;
+
+    #[test]
+    fn scalars_are_rendered() {
+        assert_eq!(Value::from(-12).to_string(), "-12");
+        assert_eq!(Value::from("a \"b\"").to_string(), r#""a \"b\"""#);
+        assert_eq!(Value::from("a\nb").to_string(), "<3 bytes: 610a62>");
+        assert_eq!(Value::from("").to_string(), r#""""#);
+    }
+
+    #[test]
+    fn long_strings_are_truncated() {
+        let text = "x".repeat(100);
+        assert_eq!(
+            Value::from(&text[..]).to_string(),
+            format!("\"{}\"... (100 bytes)", &text[..MAX_TEXT_CHARS])
+        );
+
+        let binary = vec![0xff; 40];
+        assert_eq!(
+            Value::from(binary).to_string(),
+            format!("<40 bytes: {}...>", "ff".repeat(MAX_BINARY_BYTES))
+        );
+    }
+
+    #[test]
+    fn nested_containers_are_indented() {
+        let value = Value::list()
+            .with_item(Value::dict().with_entry("a", Value::list()))
+            .with_item(1);
+
+        assert_eq!(
+            value.to_pretty_string(),
+            "[\n  {\n    \"a\": []\n  },\n  1\n]"
+        );
+        assert_eq!(value.to_string(), "[{\"a\": []}, 1]");
+    }
+}
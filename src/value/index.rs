@@ -0,0 +1,296 @@
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+use core::ops;
+
+use crate::value::Value;
+
+/// A type that can be used to look up a member of a [`Value`].
+///
+/// Integers index into lists, while strings and byte strings index into dictionaries.
+/// This trait is sealed and can't be implemented outside of bendy.
+pub trait Index: private::Sealed {
+    #[doc(hidden)]
+    fn index_into<'v, 'a>(&self, value: &'v Value<'a>) -> Option<&'v Value<'a>>;
+
+    #[doc(hidden)]
+    fn index_into_mut<'v, 'a>(&self, value: &'v mut Value<'a>) -> Option<&'v mut Value<'a>>;
+}
+
+impl Index for usize {
+    fn index_into<'v, 'a>(&self, value: &'v Value<'a>) -> Option<&'v Value<'a>> {
+        match value {
+            Value::List(list) => list.get(*self),
+            _ => None,
+        }
+    }
+
+    fn index_into_mut<'v, 'a>(&self, value: &'v mut Value<'a>) -> Option<&'v mut Value<'a>> {
+        match value {
+            Value::List(list) => list.get_mut(*self),
+            _ => None,
+        }
+    }
+}
+
+impl Index for [u8] {
+    fn index_into<'v, 'a>(&self, value: &'v Value<'a>) -> Option<&'v Value<'a>> {
+        match value {
+            Value::Dict(dict) => dict.get(self),
+            _ => None,
+        }
+    }
+
+    fn index_into_mut<'v, 'a>(&self, value: &'v mut Value<'a>) -> Option<&'v mut Value<'a>> {
+        match value {
+            Value::Dict(dict) => dict.get_mut(self),
+            _ => None,
+        }
+    }
+}
+
+impl<const N: usize> Index for [u8; N] {
+    fn index_into<'v, 'a>(&self, value: &'v Value<'a>) -> Option<&'v Value<'a>> {
+        self[..].index_into(value)
+    }
+
+    fn index_into_mut<'v, 'a>(&self, value: &'v mut Value<'a>) -> Option<&'v mut Value<'a>> {
+        self[..].index_into_mut(value)
+    }
+}
+
+impl Index for Vec<u8> {
+    fn index_into<'v, 'a>(&self, value: &'v Value<'a>) -> Option<&'v Value<'a>> {
+        self[..].index_into(value)
+    }
+
+    fn index_into_mut<'v, 'a>(&self, value: &'v mut Value<'a>) -> Option<&'v mut Value<'a>> {
+        self[..].index_into_mut(value)
+    }
+}
+
+impl Index for str {
+    fn index_into<'v, 'a>(&self, value: &'v Value<'a>) -> Option<&'v Value<'a>> {
+        self.as_bytes().index_into(value)
+    }
+
+    fn index_into_mut<'v, 'a>(&self, value: &'v mut Value<'a>) -> Option<&'v mut Value<'a>> {
+        self.as_bytes().index_into_mut(value)
+    }
+}
+
+impl Index for String {
+    fn index_into<'v, 'a>(&self, value: &'v Value<'a>) -> Option<&'v Value<'a>> {
+        self.as_bytes().index_into(value)
+    }
+
+    fn index_into_mut<'v, 'a>(&self, value: &'v mut Value<'a>) -> Option<&'v mut Value<'a>> {
+        self.as_bytes().index_into_mut(value)
+    }
+}
+
+impl<T: Index + ?Sized> Index for &T {
+    fn index_into<'v, 'a>(&self, value: &'v Value<'a>) -> Option<&'v Value<'a>> {
+        (**self).index_into(value)
+    }
+
+    fn index_into_mut<'v, 'a>(&self, value: &'v mut Value<'a>) -> Option<&'v mut Value<'a>> {
+        (**self).index_into_mut(value)
+    }
+}
+
+mod private {
+    #[cfg(not(feature = "std"))]
+    use alloc::{string::String, vec::Vec};
+
+    pub trait Sealed {}
+
+    impl Sealed for usize {}
+    impl Sealed for [u8] {}
+    impl<const N: usize> Sealed for [u8; N] {}
+    impl Sealed for Vec<u8> {}
+    impl Sealed for str {}
+    impl Sealed for String {}
+    impl<T: Sealed + ?Sized> Sealed for &T {}
+}
+
+impl<'a> Value<'a> {
+    /// Look up a list item by position or a dictionary value by key. Returns `None` if
+    /// the value has no such member, including when it is of the wrong kind.
+    ///
+    /// ```
+    /// # use bendy::value::Value;
+    /// #
+    /// let torrent = Value::decode(b"d4:infod5:filesld6:lengthi3eeeee").unwrap();
+    ///
+    /// assert_eq!(
+    ///     torrent.get("info").and_then(|info| info.get("files")).and_then(|files| files.get(0)),
+    ///     Some(&torrent["info"]["files"][0])
+    /// );
+    /// assert_eq!(torrent.get("announce"), None);
+    /// assert_eq!(torrent.get(0), None);
+    /// ```
+    pub fn get<I: Index>(&self, index: I) -> Option<&Value<'a>> {
+        index.index_into(self)
+    }
+
+    /// Mutable version of [`Value::get`]
+    pub fn get_mut<I: Index>(&mut self, index: I) -> Option<&mut Value<'a>> {
+        index.index_into_mut(self)
+    }
+
+    /// Look up a nested value by a [JSON Pointer] like `/info/files/0/length`. Each
+    /// segment is either a dictionary key or a list index, with `~1` and `~0` standing
+    /// for `/` and `~` in keys. The empty pointer refers to the value itself.
+    ///
+    /// For extracting a single value from a large document without decoding all of it,
+    /// see [`Decoder::seek_path`](crate::decoding::Decoder::seek_path).
+    ///
+    /// ```
+    /// # use bendy::value::Value;
+    /// #
+    /// let torrent = Value::decode(b"d4:infod5:filesld6:lengthi3eeeee").unwrap();
+    ///
+    /// assert_eq!(torrent.pointer("/info/files/0/length"), Some(&Value::Integer(3)));
+    /// assert_eq!(torrent.pointer("/info/files/1"), None);
+    /// ```
+    ///
+    /// [JSON Pointer]: https://tools.ietf.org/html/rfc6901
+    pub fn pointer(&self, pointer: &str) -> Option<&Value<'a>> {
+        if pointer.is_empty() {
+            return Some(self);
+        }
+        if !pointer.starts_with('/') {
+            return None;
+        }
+
+        pointer
+            .split('/')
+            .skip(1)
+            .try_fold(self, |value, segment| match value {
+                Value::List(_) => parse_list_index(segment).and_then(|index| value.get(index)),
+                _ => value.get(unescape_segment(segment)),
+            })
+    }
+
+    /// Mutable version of [`Value::pointer`]
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut Value<'a>> {
+        if pointer.is_empty() {
+            return Some(self);
+        }
+        if !pointer.starts_with('/') {
+            return None;
+        }
+
+        pointer
+            .split('/')
+            .skip(1)
+            .try_fold(self, |value, segment| match value {
+                Value::List(_) => {
+                    parse_list_index(segment).and_then(move |index| value.get_mut(index))
+                },
+                _ => value.get_mut(unescape_segment(segment)),
+            })
+    }
+}
+
+fn parse_list_index(segment: &str) -> Option<usize> {
+    // Only the canonical form of an index is allowed, as with JSON Pointer
+    if segment.starts_with('+') || (segment.starts_with('0') && segment.len() > 1) {
+        return None;
+    }
+    segment.parse().ok()
+}
+
+fn unescape_segment(segment: &str) -> String {
+    segment.replace("~1", "/").replace("~0", "~")
+}
+
+/// Look up a list item by position or a dictionary value by key, panicking if the value
+/// has no such member. Use [`Value::get`] for a non-panicking version.
+impl<'a, I: Index + core::fmt::Debug> ops::Index<I> for Value<'a> {
+    type Output = Value<'a>;
+
+    fn index(&self, index: I) -> &Value<'a> {
+        match index.index_into(self) {
+            Some(member) => member,
+            None => panic!("no member {:?} in {}", index, self.kind()),
+        }
+    }
+}
+
+impl<'a, I: Index + core::fmt::Debug> ops::IndexMut<I> for Value<'a> {
+    fn index_mut(&mut self, index: I) -> &mut Value<'a> {
+        let kind = self.kind();
+        match index.index_into_mut(self) {
+            Some(member) => member,
+            None => panic!("no member {:?} in {}", index, kind),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::{borrow::ToOwned, string::ToString};
+
+    use super::*;
+
+    #[test]
+    fn lookups_work_with_all_index_types() {
+        let value = Value::decode(b"d3:fooli1ei2eee").unwrap();
+
+        assert_eq!(value["foo"][1], Value::Integer(2));
+        assert_eq!(value[b"foo"][0], Value::Integer(1));
+        assert_eq!(value[&b"foo"[..]], value["foo".to_string()]);
+        assert_eq!(value[b"foo".to_vec()], value[&"foo".to_owned()]);
+    }
+
+    #[test]
+    fn get_returns_none_for_missing_members() {
+        let value = Value::decode(b"d3:fooli1ei2eee").unwrap();
+
+        assert_eq!(value.get("bar"), None);
+        assert_eq!(value.get(0), None);
+        assert_eq!(value["foo"].get(2), None);
+        assert_eq!(value["foo"].get("foo"), None);
+    }
+
+    #[test]
+    fn index_mut_modifies_member() {
+        let mut value = Value::decode(b"d3:fooli1ei2eee").unwrap();
+        value["foo"][0] = Value::Integer(3);
+
+        assert_eq!(value.to_bytes().unwrap(), b"d3:fooli3ei2eee");
+        assert_eq!(value, Value::decode(b"d3:fooli3ei2eee").unwrap());
+    }
+
+    #[test]
+    fn pointer_follows_keys_and_indices() {
+        let mut value = Value::decode(b"d0:i1e1:/i2e1:ali3ei4ee2:~1i5ee").unwrap();
+
+        assert_eq!(value.pointer(""), Some(&value));
+        assert_eq!(value.pointer("/"), Some(&Value::Integer(1)));
+        assert_eq!(value.pointer("/~1"), Some(&Value::Integer(2)));
+        assert_eq!(value.pointer("/~01"), Some(&Value::Integer(5)));
+        assert_eq!(value.pointer("/a/1"), Some(&Value::Integer(4)));
+
+        *value.pointer_mut("/a/0").unwrap() = Value::Integer(6);
+        assert_eq!(value["a"][0], Value::Integer(6));
+    }
+
+    #[test]
+    fn pointer_rejects_invalid_paths() {
+        let value = Value::decode(b"d1:ali3ei4eee").unwrap();
+
+        for pointer in &["a", "/b", "/a/2", "/a/01", "/a/+1", "/a/-1", "/a/0/0"] {
+            assert_eq!(value.pointer(pointer), None, "{}", pointer);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "no member \"bar\" in dict")]
+    fn index_panics_for_missing_member() {
+        let value = Value::decode(b"d3:fooi1ee").unwrap();
+        let _ = &value["bar"];
+    }
+}
@@ -0,0 +1,349 @@
+use alloc::borrow::Cow;
+#[cfg(not(feature = "std"))]
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+};
+use core::convert::TryFrom;
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+
+use failure::Fail;
+use serde_json::{Number, Value as JsonValue};
+
+use crate::value::Value;
+
+/// How byte strings that aren't valid UTF-8 are represented in JSON
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinaryStrings {
+    /// A string of lowercase hex digits
+    Hex,
+    /// A string in standard, padded base64
+    Base64,
+    /// A string with invalid sequences replaced by U+FFFD
+    LossyUtf8,
+}
+
+/// How JSON numbers that aren't integers in the range of `i64` are represented in
+/// bencode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutOfRangeNumbers {
+    /// Fail the conversion
+    Reject,
+    /// Clamp to the range of `i64` and round fractions towards zero
+    Saturate,
+    /// Store the number's JSON representation as a byte string
+    AsString,
+}
+
+/// Options for the conversion between [`Value`] and `serde_json::Value`.
+///
+/// By default, binary strings are converted to hex and numbers which don't fit into
+/// bencode integers are rejected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct JsonOptions {
+    binary_strings: BinaryStrings,
+    out_of_range_numbers: OutOfRangeNumbers,
+}
+
+impl Default for JsonOptions {
+    fn default() -> Self {
+        JsonOptions {
+            binary_strings: BinaryStrings::Hex,
+            out_of_range_numbers: OutOfRangeNumbers::Reject,
+        }
+    }
+}
+
+impl JsonOptions {
+    /// Create the default options
+    pub fn new() -> Self {
+        <Self as Default>::default()
+    }
+
+    /// Set how byte strings that aren't valid UTF-8 are converted to JSON. Dictionary
+    /// keys are converted in the same way.
+    #[must_use]
+    pub fn with_binary_strings(mut self, binary_strings: BinaryStrings) -> Self {
+        self.binary_strings = binary_strings;
+        self
+    }
+
+    /// Set how JSON numbers which don't fit into bencode integers are converted
+    #[must_use]
+    pub fn with_out_of_range_numbers(mut self, out_of_range_numbers: OutOfRangeNumbers) -> Self {
+        self.out_of_range_numbers = out_of_range_numbers;
+        self
+    }
+}
+
+/// An error converting JSON to bencode
+#[derive(Clone, Debug, PartialEq, Eq, Fail)]
+pub enum JsonError {
+    #[fail(display = "Number {} doesn't fit into a bencode integer", _0)]
+    /// A number wasn't an integer in the range of `i64`.
+    NumberOutOfRange(String),
+    #[fail(display = "Null can't be represented in bencode")]
+    /// The JSON contained `null`.
+    Null,
+    #[fail(display = "Several dictionary keys convert to the JSON key {:?}", _0)]
+    /// Different byte strings of the same dictionary convert to the same JSON key, e.g.
+    /// the binary key `\xff` and the text key `ff` with [`BinaryStrings::Hex`].
+    DuplicateKey(String),
+}
+
+impl<'a> Value<'a> {
+    /// Convert the value to JSON. Byte strings that are valid UTF-8 become JSON strings,
+    /// other byte strings are converted as set in the options. Dictionaries become JSON
+    /// objects. Fails if several keys of a dictionary convert to the same JSON key.
+    ///
+    /// ```
+    /// # use bendy::value::{BinaryStrings, JsonOptions, Value};
+    /// # use serde_json::json;
+    /// #
+    /// let value = Value::decode(b"d4:name3:foo6:pieces2:\xca\xfee").unwrap();
+    ///
+    /// let options = JsonOptions::new().with_binary_strings(BinaryStrings::Base64);
+    /// assert_eq!(
+    ///     value.to_json(&options).unwrap(),
+    ///     json!({"name": "foo", "pieces": "yv4="})
+    /// );
+    /// ```
+    pub fn to_json(&self, options: &JsonOptions) -> Result<JsonValue, JsonError> {
+        Ok(match self {
+            Value::Bytes(bytes) => JsonValue::String(bytes_to_string(bytes, options)),
+            Value::Dict(dict) => {
+                let mut object = serde_json::Map::new();
+                for (key, value) in dict {
+                    let key = bytes_to_string(key, options);
+                    if object.contains_key(&key) {
+                        return Err(JsonError::DuplicateKey(key));
+                    }
+                    object.insert(key, value.to_json(options)?);
+                }
+                JsonValue::Object(object)
+            },
+            Value::Integer(integer) => JsonValue::Number(Number::from(*integer)),
+            Value::List(list) => JsonValue::Array(
+                list.iter()
+                    .map(|item| item.to_json(options))
+                    .collect::<Result<_, _>>()?,
+            ),
+        })
+    }
+
+    /// Convert JSON to bencode. Strings become UTF-8 byte strings, `true` and `false`
+    /// become the integers 1 and 0, and numbers are converted as set in the options.
+    /// Fails for `null`, which has no bencode equivalent.
+    ///
+    /// ```
+    /// # use bendy::value::{JsonOptions, OutOfRangeNumbers, Value};
+    /// # use serde_json::json;
+    /// #
+    /// let json = json!({"name": "foo", "ratio": 1.5});
+    ///
+    /// assert!(Value::from_json(&json, &JsonOptions::new()).is_err());
+    ///
+    /// let options = JsonOptions::new().with_out_of_range_numbers(OutOfRangeNumbers::AsString);
+    /// let value = Value::from_json(&json, &options).unwrap();
+    /// assert_eq!(value.to_bytes().unwrap(), b"d4:name3:foo5:ratio3:1.5e");
+    /// ```
+    pub fn from_json(json: &JsonValue, options: &JsonOptions) -> Result<Value<'static>, JsonError> {
+        Ok(match json {
+            JsonValue::Null => return Err(JsonError::Null),
+            JsonValue::Bool(boolean) => Value::Integer(i64::from(*boolean)),
+            JsonValue::Number(number) => number_to_value(number, options)?,
+            JsonValue::String(string) => Value::Bytes(Cow::Owned(string.as_bytes().to_vec())),
+            JsonValue::Array(array) => Value::List(
+                array
+                    .iter()
+                    .map(|item| Value::from_json(item, options))
+                    .collect::<Result<_, _>>()?,
+            ),
+            JsonValue::Object(object) => Value::Dict(
+                object
+                    .iter()
+                    .map(|(key, value)| {
+                        let key = Cow::Owned(key.as_bytes().to_vec());
+                        Ok((key, Value::from_json(value, options)?))
+                    })
+                    .collect::<Result<BTreeMap<_, _>, _>>()?,
+            ),
+        })
+    }
+}
+
+/// Converts with the default [`JsonOptions`]
+impl<'a, 'v> TryFrom<&'v Value<'a>> for JsonValue {
+    type Error = JsonError;
+
+    fn try_from(value: &'v Value<'a>) -> Result<Self, JsonError> {
+        value.to_json(&JsonOptions::default())
+    }
+}
+
+/// Converts with the default [`JsonOptions`]
+impl<'a> TryFrom<&'a JsonValue> for Value<'static> {
+    type Error = JsonError;
+
+    fn try_from(json: &'a JsonValue) -> Result<Self, JsonError> {
+        Value::from_json(json, &JsonOptions::default())
+    }
+}
+
+fn number_to_value(number: &Number, options: &JsonOptions) -> Result<Value<'static>, JsonError> {
+    if let Some(integer) = number.as_i64() {
+        return Ok(Value::Integer(integer));
+    }
+
+    match options.out_of_range_numbers {
+        OutOfRangeNumbers::Reject => Err(JsonError::NumberOutOfRange(number.to_string())),
+        // Float to integer casts saturate and truncate
+        OutOfRangeNumbers::Saturate => Ok(Value::Integer(match number.as_u64() {
+            Some(_) => i64::MAX,
+            None => number.as_f64().map_or(0, |float| float as i64),
+        })),
+        OutOfRangeNumbers::AsString => {
+            Ok(Value::Bytes(Cow::Owned(number.to_string().into_bytes())))
+        },
+    }
+}
+
+fn bytes_to_string(bytes: &[u8], options: &JsonOptions) -> String {
+    if let Ok(string) = core::str::from_utf8(bytes) {
+        return string.to_string();
+    }
+
+    match options.binary_strings {
+        BinaryStrings::Hex => {
+            const DIGITS: &[u8; 16] = b"0123456789abcdef";
+            let mut hex = String::with_capacity(bytes.len() * 2);
+            for byte in bytes {
+                hex.push(DIGITS[usize::from(byte >> 4)] as char);
+                hex.push(DIGITS[usize::from(byte & 0xf)] as char);
+            }
+            hex
+        },
+        BinaryStrings::Base64 => base64(bytes),
+        BinaryStrings::LossyUtf8 => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0_u32, |group, (i, &byte)| {
+            group | u32::from(byte) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::vec;
+
+    use super::*;
+
+    use serde_json::json;
+
+    #[test]
+    fn utf8_documents_round_trip() {
+        let value = Value::decode(b"d1:ali1ei-2ee1:bd1:c1:dee").unwrap();
+        let json = JsonValue::try_from(&value).unwrap();
+
+        assert_eq!(json, json!({"a": [1, -2], "b": {"c": "d"}}));
+        assert_eq!(Value::try_from(&json).unwrap(), value);
+    }
+
+    #[test]
+    fn binary_strings_follow_options() {
+        let value = Value::from(vec![0xff, 0x00, b'a', 0xfe]);
+        let convert =
+            |binary_strings| value.to_json(&JsonOptions::new().with_binary_strings(binary_strings));
+        let convert = |binary_strings| convert(binary_strings).unwrap();
+
+        assert_eq!(convert(BinaryStrings::Hex), json!("ff0061fe"));
+        assert_eq!(convert(BinaryStrings::Base64), json!("/wBh/g=="));
+        assert_eq!(
+            convert(BinaryStrings::LossyUtf8),
+            json!("\u{fffd}\u{0}a\u{fffd}")
+        );
+
+        let key = Value::dict().with_entry([0xff], 1);
+        assert_eq!(JsonValue::try_from(&key).unwrap(), json!({"ff": 1}));
+    }
+
+    #[test]
+    fn colliding_keys_are_rejected() {
+        let value = Value::dict().with_entry([0xff], 1).with_entry("ff", 2);
+        assert_eq!(
+            JsonValue::try_from(&value),
+            Err(JsonError::DuplicateKey("ff".to_string()))
+        );
+
+        let value = Value::dict().with_entry([0xfe], 1).with_entry([0xff], 2);
+        let options = JsonOptions::new().with_binary_strings(BinaryStrings::LossyUtf8);
+        assert_eq!(
+            value.to_json(&options),
+            Err(JsonError::DuplicateKey("\u{fffd}".to_string()))
+        );
+        let options = JsonOptions::new().with_binary_strings(BinaryStrings::Base64);
+        assert!(value.to_json(&options).is_ok());
+    }
+
+    #[test]
+    fn base64_pads_partial_groups() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn out_of_range_numbers_follow_options() {
+        let json = json!([u64::MAX, -1.5, 2.5]);
+        let convert = |numbers| {
+            Value::from_json(
+                &json,
+                &JsonOptions::new().with_out_of_range_numbers(numbers),
+            )
+        };
+
+        assert_eq!(
+            convert(OutOfRangeNumbers::Reject),
+            Err(JsonError::NumberOutOfRange(u64::MAX.to_string()))
+        );
+        assert_eq!(
+            convert(OutOfRangeNumbers::Saturate).unwrap(),
+            Value::list().with_item(i64::MAX).with_item(-1).with_item(2)
+        );
+        assert_eq!(
+            convert(OutOfRangeNumbers::AsString).unwrap(),
+            Value::list()
+                .with_item(u64::MAX.to_string())
+                .with_item("-1.5")
+                .with_item("2.5")
+        );
+    }
+
+    #[test]
+    fn null_is_rejected_and_booleans_become_integers() {
+        assert_eq!(Value::try_from(&json!([null])), Err(JsonError::Null));
+        assert_eq!(
+            Value::try_from(&json!([true, false])).unwrap(),
+            Value::list().with_item(1).with_item(0)
+        );
+    }
+}
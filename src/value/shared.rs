@@ -0,0 +1,204 @@
+use alloc::borrow::Cow;
+#[cfg(not(feature = "std"))]
+use alloc::{collections::BTreeMap, vec::Vec};
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+
+use bytes::Bytes;
+
+use crate::{
+    decoding::{self, DecodeOptions, Decoder, FromBencode, Object},
+    encoding::{self, SingleItemEncoder, ToBencode},
+    state_tracker::StructureError,
+    value::Value,
+};
+
+/// A bencoded value whose byte strings share ownership of the input [`Bytes`].
+///
+/// Decoding into a [`Value`] either borrows byte strings from the input, tying the tree to
+/// its lifetime, or copies them. A `SharedValue` holds its byte strings and keys as
+/// [`Bytes`] handles into the input instead, so the tree can be passed between tasks
+/// without copying any payload, and the input is released once the last handle is
+/// dropped.
+///
+/// ```
+/// # use bendy::value::SharedValue;
+/// # use bytes::Bytes;
+/// #
+/// let message = Bytes::from_static(b"d1:v5:helloe");
+/// let value = SharedValue::decode(&message).unwrap();
+///
+/// let payload = match value.get("v") {
+///     Some(SharedValue::Bytes(payload)) => payload.clone(),
+///     _ => unreachable!(),
+/// };
+/// assert_eq!(payload, "hello");
+/// assert_eq!(payload.as_ptr(), message[6..].as_ptr());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SharedValue {
+    /// A byte string sharing the input
+    Bytes(Bytes),
+    /// A dictionary, with keys sharing the input
+    Dict(BTreeMap<Bytes, SharedValue>),
+    /// A signed integer
+    Integer(i64),
+    /// A list of values
+    List(Vec<SharedValue>),
+}
+
+impl SharedValue {
+    /// Decode a complete document. Bytes after it are rejected as trailing data.
+    pub fn decode(input: &Bytes) -> Result<Self, decoding::Error> {
+        Self::decode_with_options(input, DecodeOptions::default())
+    }
+
+    /// Decode a complete document, validating it as configured by the given options.
+    /// Bytes after it are rejected as trailing data.
+    pub fn decode_with_options(
+        input: &Bytes,
+        options: DecodeOptions,
+    ) -> Result<Self, decoding::Error> {
+        let max_depth = options
+            .max_depth()
+            .min(<Value as FromBencode>::EXPECTED_RECURSION_DEPTH);
+        let mut decoder = Decoder::new_with_options(input, options)
+            .with_max_depth(max_depth)
+            .with_strict_eof();
+        let object = decoder.next_object()?;
+
+        object.map_or(
+            Err(decoding::Error::from(StructureError::UnexpectedEof)),
+            |object| SharedValue::from_object(object, input),
+        )
+    }
+
+    fn from_object(object: Object, input: &Bytes) -> Result<Self, decoding::Error> {
+        match object {
+            Object::Bytes(bytes) => Ok(SharedValue::Bytes(input.slice_ref(bytes))),
+            Object::Dict(mut decoder) => {
+                let mut dict = BTreeMap::new();
+                while let Some((key, value)) = decoder.next_pair()? {
+                    dict.insert(
+                        input.slice_ref(key),
+                        SharedValue::from_object(value, input)?,
+                    );
+                }
+                Ok(SharedValue::Dict(dict))
+            },
+            Object::Integer(text) => Ok(SharedValue::Integer(text.parse()?)),
+            Object::List(mut decoder) => {
+                let mut list = Vec::new();
+                while let Some(object) = decoder.next_object()? {
+                    list.push(SharedValue::from_object(object, input)?);
+                }
+                Ok(SharedValue::List(list))
+            },
+        }
+    }
+
+    /// Look up a dictionary value by key. Returns `None` if there is no such entry or
+    /// the value is not a dictionary.
+    pub fn get(&self, key: impl AsRef<[u8]>) -> Option<&SharedValue> {
+        match self {
+            SharedValue::Dict(dict) => dict.get(key.as_ref()),
+            _ => None,
+        }
+    }
+
+    /// Copy the tree into a [`Value`], which borrows byte strings from this one
+    pub fn to_value(&self) -> Value<'_> {
+        match self {
+            SharedValue::Bytes(bytes) => Value::Bytes(Cow::Borrowed(bytes)),
+            SharedValue::Dict(dict) => Value::Dict(
+                dict.iter()
+                    .map(|(key, value)| (Cow::Borrowed(&key[..]), value.to_value()))
+                    .collect(),
+            ),
+            SharedValue::Integer(integer) => Value::Integer(*integer),
+            SharedValue::List(list) => {
+                Value::List(list.iter().map(SharedValue::to_value).collect())
+            },
+        }
+    }
+}
+
+impl ToBencode for SharedValue {
+    const MAX_DEPTH: usize = <Value as ToBencode>::MAX_DEPTH;
+
+    fn encode(&self, encoder: SingleItemEncoder) -> Result<(), encoding::Error> {
+        match self {
+            SharedValue::Bytes(bytes) => encoder.emit_bytes(bytes),
+            SharedValue::Dict(dict) => encoder.emit_dict(|mut e| {
+                for (key, value) in dict {
+                    e.emit_pair(key, value)?;
+                }
+                Ok(())
+            }),
+            SharedValue::Integer(integer) => encoder.emit_int(*integer),
+            SharedValue::List(list) => encoder.emit_unchecked_list(list.iter()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::decoding::ErrorKind;
+
+    static TORRENT: &[u8] = b"d8:announce3:foo4:infod5:filesld6:lengthi3e4:pathl1:aeed6:lengthi-4e4:pathl1:b1:ceee4:name3:baree";
+
+    #[test]
+    fn decoded_tree_matches_value() {
+        let input = Bytes::from_static(TORRENT);
+        let value = SharedValue::decode(&input).unwrap();
+
+        assert_eq!(value.to_value(), Value::decode(TORRENT).unwrap());
+        assert_eq!(value.to_bencode().unwrap(), TORRENT);
+    }
+
+    #[test]
+    fn byte_strings_outlive_the_tree() {
+        let input = Bytes::from(TORRENT.to_vec());
+        let name = match SharedValue::decode(&input).unwrap().get("info") {
+            Some(info) => info.get("name").cloned(),
+            None => None,
+        };
+        drop(input);
+
+        assert_eq!(name, Some(SharedValue::Bytes(Bytes::from_static(b"bar"))));
+    }
+
+    #[test]
+    fn malformed_input_is_rejected() {
+        assert!(SharedValue::decode(&Bytes::new()).is_err());
+        assert!(SharedValue::decode(&Bytes::from_static(b"d1:bi1e1:ai2ee")).is_err());
+
+        let options = DecodeOptions::new().with_unsorted_keys();
+        let input = Bytes::from_static(b"d1:bi1e1:ai2ee");
+        assert!(SharedValue::decode_with_options(&input, options).is_ok());
+    }
+
+    #[test]
+    fn options_should_limit_the_depth() {
+        let input = Bytes::from_static(b"llli1eeee");
+        assert!(SharedValue::decode(&input).is_ok());
+
+        let options = DecodeOptions::new().with_max_depth(1);
+        let error = SharedValue::decode_with_options(&input, options).unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ErrorKind::StructureError(StructureError::NestingTooDeep { .. })
+        ));
+    }
+
+    #[test]
+    fn trailing_data_should_be_rejected() {
+        let input = Bytes::from_static(b"i1egarbage");
+        let error = SharedValue::decode(&input).unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ErrorKind::StructureError(StructureError::TrailingData { position: 3 })
+        ));
+    }
+}
@@ -0,0 +1,123 @@
+use alloc::borrow::Cow;
+use core::mem;
+
+use crate::value::Value;
+
+impl<'a> Value<'a> {
+    /// Return the length of the value's bencode encoding, without encoding it
+    ///
+    /// ```
+    /// # use bendy::value::Value;
+    /// #
+    /// let value = Value::dict().with_entry("peers", Value::list().with_item(-12));
+    /// assert_eq!(value.encoded_len(), value.to_bytes().unwrap().len());
+    /// ```
+    pub fn encoded_len(&self) -> usize {
+        match self {
+            Value::Bytes(bytes) => encoded_bytes_len(bytes),
+            Value::Dict(dict) => dict.iter().fold(2, |len, (key, value)| {
+                len + encoded_bytes_len(key) + value.encoded_len()
+            }),
+            Value::Integer(integer) => {
+                2 + usize::from(*integer < 0) + decimal_digits(integer.unsigned_abs())
+            },
+            Value::List(list) => list.iter().fold(2, |len, item| len + item.encoded_len()),
+        }
+    }
+
+    /// Estimate the number of heap bytes owned by the value. Borrowed byte strings
+    /// don't count, and the bookkeeping overhead of dictionaries is not included, so
+    /// this is a lower bound suitable for enforcing quotas.
+    pub fn deep_size(&self) -> usize {
+        match self {
+            Value::Bytes(bytes) => owned_bytes_size(bytes),
+            Value::Dict(dict) => dict.iter().fold(0, |size, (key, value)| {
+                size + mem::size_of::<(Cow<[u8]>, Value)>()
+                    + owned_bytes_size(key)
+                    + value.deep_size()
+            }),
+            Value::Integer(_) => 0,
+            Value::List(list) => list
+                .iter()
+                .fold(list.capacity() * mem::size_of::<Value>(), |size, item| {
+                    size + item.deep_size()
+                }),
+        }
+    }
+}
+
+fn encoded_bytes_len(bytes: &[u8]) -> usize {
+    decimal_digits(bytes.len() as u64) + 1 + bytes.len()
+}
+
+fn decimal_digits(mut number: u64) -> usize {
+    let mut digits = 1;
+    while number >= 10 {
+        number /= 10;
+        digits += 1;
+    }
+    digits
+}
+
+// This needs to tell borrowed and owned bytes apart, so it can't take a slice
+#[allow(clippy::ptr_arg)]
+fn owned_bytes_size(bytes: &Cow<[u8]>) -> usize {
+    match bytes {
+        Cow::Borrowed(_) => 0,
+        Cow::Owned(bytes) => bytes.capacity(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::vec;
+
+    use super::*;
+
+    #[test]
+    fn encoded_len_matches_encoding() {
+        let values = vec![
+            Value::Integer(0),
+            Value::Integer(9),
+            Value::Integer(10),
+            Value::Integer(-1),
+            Value::Integer(i64::MIN),
+            Value::Integer(i64::MAX),
+            Value::from(""),
+            Value::from(vec![0; 10]),
+            Value::list(),
+            Value::list()
+                .with_item("foo")
+                .with_item(Value::list().with_item(1)),
+            Value::dict(),
+            Value::dict()
+                .with_entry("a", Value::dict().with_entry(&[7; 100][..], -100))
+                .with_entry("b", Value::list()),
+        ];
+
+        for value in values {
+            assert_eq!(
+                value.encoded_len(),
+                value.to_bytes().unwrap().len(),
+                "{}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn deep_size_counts_owned_data_only() {
+        let input = b"l3:fooi1ee";
+        let borrowed = Value::decode(input).unwrap();
+        let list_size = borrowed.as_list().unwrap().capacity() * mem::size_of::<Value>();
+        assert_eq!(borrowed.deep_size(), list_size);
+
+        let owned = borrowed.into_owned();
+        let list_size = owned.as_list().unwrap().capacity() * mem::size_of::<Value>();
+        assert_eq!(owned.deep_size(), list_size + 3);
+
+        assert_eq!(Value::Integer(1).deep_size(), 0);
+        assert!(Value::dict().with_entry("a", "b").deep_size() > 2);
+    }
+}
@@ -0,0 +1,57 @@
+//! Decoding shallow documents must not allocate, as they are typically small messages
+//! decoded in bulk, like those of the DHT.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use bendy::{decoding::Decoder, state_tracker::Token};
+
+/// Counts the allocations of the whole test binary, which only runs a single test
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn count_allocations(decode: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    decode();
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+#[test]
+fn shallow_documents_should_decode_without_allocating() {
+    let query = b"d1:ad2:id20:abcdefghij0123456789e1:q4:ping1:t2:aa1:y1:qe";
+    let allocations = count_allocations(|| {
+        let tokens = Decoder::new(query).tokens();
+        assert_eq!(
+            tokens
+                .map(Result::unwrap)
+                .filter(|token| *token == Token::End)
+                .count(),
+            2
+        );
+    });
+    assert_eq!(allocations, 0);
+
+    // Beyond the inline depth, the decoder has to allocate
+    let deep = [&[b'l'; 16][..], &[b'e'; 16][..]].concat();
+    let allocations = count_allocations(|| {
+        assert!(Decoder::new(&deep).tokens().all(|token| token.is_ok()));
+    });
+    assert!(allocations > 0);
+}
@@ -3,6 +3,13 @@
 //! Should only use #![no_std] compatible features but still requires the
 //! `std` feature flag to avoid that we need to define a global allocator.
 
+#![allow(
+    dead_code,
+    clippy::legacy_numeric_constants,
+    clippy::vec_init_then_push,
+    clippy::zero_prefixed_literal
+)]
+
 extern crate alloc;
 use alloc::collections::BTreeMap;
 
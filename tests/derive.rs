@@ -0,0 +1,473 @@
+use bendy::{
+    decoding::{DecodeOptions, FromBencode, PathSegment, Preset},
+    encoding::ToBencode,
+};
+
+#[derive(FromBencode, ToBencode, PartialEq, Eq, Debug)]
+struct File {
+    path: Vec<String>,
+    length: u64,
+}
+
+#[derive(FromBencode, ToBencode, PartialEq, Eq, Debug)]
+struct Info {
+    name: String,
+    files: Vec<File>,
+    r#type: String,
+}
+
+#[derive(FromBencode, ToBencode, PartialEq, Eq, Debug)]
+struct Pair(String, i64);
+
+#[derive(FromBencode, ToBencode, PartialEq, Eq, Debug)]
+struct Port(u16);
+
+#[derive(FromBencode, ToBencode, PartialEq, Eq, Debug)]
+struct Wrapper<T> {
+    inner: T,
+}
+
+#[derive(FromBencode, ToBencode, PartialEq, Eq, Debug)]
+#[bendy(rename_all = "space separated")]
+struct Torrent {
+    #[bendy(rename = "announce-list")]
+    announce_list: Vec<Vec<String>>,
+    created_by: String,
+    piece_length: u64,
+}
+
+#[derive(FromBencode, ToBencode, PartialEq, Eq, Debug)]
+#[bendy(rename_all = "kebab-case")]
+struct Kebab {
+    min_interval: u32,
+}
+
+#[derive(FromBencode, ToBencode, PartialEq, Eq, Debug)]
+struct Response {
+    interval: u32,
+    #[bendy(rename = "failure reason")]
+    failure_reason: Option<String>,
+    #[bendy(default)]
+    peers: Vec<String>,
+}
+
+fn example() -> Info {
+    Info {
+        name: "example".to_string(),
+        files: vec![File {
+            path: vec!["dir".to_string(), "a.txt".to_string()],
+            length: 42,
+        }],
+        r#type: "multi".to_string(),
+    }
+}
+
+const EXAMPLE: &[u8] = b"d5:filesld6:lengthi42e4:pathl3:dir5:a.txteee4:name7:example4:type5:multie";
+
+#[test]
+fn derived_struct_encodes_keys_in_canonical_order() {
+    assert_eq!(example().to_bencode().unwrap(), EXAMPLE);
+}
+
+#[test]
+fn derived_struct_round_trips() {
+    assert_eq!(Info::from_bencode(EXAMPLE).unwrap(), example());
+}
+
+#[test]
+fn unknown_keys_are_ignored() {
+    let file = File::from_bencode(b"d6:lengthi1e3:md54:abcd4:pathl1:aee").unwrap();
+    assert_eq!(file.length, 1);
+}
+
+#[test]
+fn missing_field_is_reported() {
+    let error = File::from_bencode(b"d6:lengthi1ee").unwrap_err();
+    assert_eq!(error.to_string(), "Error: missing field: path");
+}
+
+#[test]
+fn mistyped_field_names_path_to_field() {
+    let error =
+        Info::from_bencode(b"d5:filesld6:length2:424:pathleee4:name0:4:type0:e").unwrap_err();
+    assert_eq!(
+        error.path(),
+        [
+            PathSegment::Field("files".into()),
+            PathSegment::Index(0),
+            PathSegment::Field("length".into()),
+        ]
+    );
+
+    let error = error.to_string();
+    assert!(error.ends_with("in files[0].length"), "{}", error);
+}
+
+#[test]
+fn renamed_fields_use_given_keys() {
+    let torrent = Torrent {
+        announce_list: vec![vec!["udp://tracker".to_string()]],
+        created_by: "bendy".to_string(),
+        piece_length: 16384,
+    };
+    let encoded: &[u8] =
+        b"d13:announce-listll13:udp://trackeree10:created by5:bendy12:piece lengthi16384ee";
+
+    assert_eq!(torrent.to_bencode().unwrap(), encoded);
+    assert_eq!(Torrent::from_bencode(encoded).unwrap(), torrent);
+    assert_eq!(
+        Kebab { min_interval: 60 }.to_bencode().unwrap(),
+        b"d12:min-intervali60ee"
+    );
+}
+
+#[test]
+fn missing_renamed_field_reports_key() {
+    let error = Torrent::from_bencode(b"d13:announce-listle10:created by0:e").unwrap_err();
+    assert_eq!(error.to_string(), "Error: missing field: piece length");
+}
+
+#[test]
+fn optional_fields_may_be_missing() {
+    let response = Response::from_bencode(b"d8:intervali60ee").unwrap();
+    assert_eq!(
+        response,
+        Response {
+            interval: 60,
+            failure_reason: None,
+            peers: Vec::new(),
+        }
+    );
+}
+
+#[test]
+fn none_fields_are_omitted() {
+    let mut response = Response {
+        interval: 60,
+        failure_reason: None,
+        peers: vec!["a".to_string()],
+    };
+    assert_eq!(
+        response.to_bencode().unwrap(),
+        b"d8:intervali60e5:peersl1:aee"
+    );
+
+    response.failure_reason = Some("denied".to_string());
+    let encoded = response.to_bencode().unwrap();
+    assert_eq!(
+        encoded,
+        b"d14:failure reason6:denied8:intervali60e5:peersl1:aee".to_vec()
+    );
+    assert_eq!(Response::from_bencode(&encoded).unwrap(), response);
+}
+
+#[test]
+fn tuple_struct_is_a_list() {
+    let pair = Pair("a".to_string(), 1);
+    assert_eq!(pair.to_bencode().unwrap(), b"l1:ai1ee");
+    assert_eq!(Pair::from_bencode(b"l1:ai1ee").unwrap(), pair);
+    assert!(Pair::from_bencode(b"l1:ae").is_err());
+    assert!(Pair::from_bencode(b"l1:ai1ei2ee").is_err());
+}
+
+#[test]
+fn newtype_struct_is_transparent() {
+    assert_eq!(Port(6881).to_bencode().unwrap(), b"i6881e");
+    assert_eq!(Port::from_bencode(b"i6881e").unwrap(), Port(6881));
+}
+
+#[test]
+fn generic_struct_is_supported() {
+    let wrapper = Wrapper { inner: Port(1) };
+    assert_eq!(wrapper.to_bencode().unwrap(), b"d5:inneri1ee");
+    assert_eq!(Wrapper::from_bencode(b"d5:inneri1ee").unwrap(), wrapper);
+}
+
+#[test]
+fn depth_covers_nested_fields() {
+    assert_eq!(<Info as ToBencode>::MAX_DEPTH, 4);
+    assert_eq!(<Info as FromBencode>::EXPECTED_RECURSION_DEPTH, 4);
+    assert_eq!(
+        <Port as ToBencode>::MAX_DEPTH,
+        <u16 as ToBencode>::MAX_DEPTH
+    );
+}
+
+#[derive(FromBencode, ToBencode, PartialEq, Eq, Debug)]
+#[bendy(rename_all = "lowercase")]
+enum Event {
+    Started,
+    Stopped,
+    Completed,
+}
+
+#[derive(FromBencode, ToBencode, PartialEq, Eq, Debug)]
+#[bendy(integer)]
+enum ErrorCode {
+    Generic = 201,
+    Server = 202,
+    Protocol,
+}
+
+#[derive(FromBencode, ToBencode, PartialEq, Eq, Debug)]
+#[bendy(rename_all = "snake_case")]
+enum Piece {
+    Missing,
+    Hash(String),
+    Range(u64, u64),
+    #[bendy(rename_all = "space separated")]
+    Block {
+        piece_index: u64,
+        data: String,
+    },
+}
+
+#[derive(FromBencode, ToBencode, PartialEq, Eq, Debug)]
+#[bendy(tag = "y")]
+enum Krpc {
+    #[bendy(rename = "q")]
+    Query { t: String, q: String },
+    #[bendy(rename = "r")]
+    Response { t: String, r: Vec<String> },
+    #[bendy(rename = "p")]
+    Ping,
+}
+
+#[test]
+fn unit_variants_are_byte_strings() {
+    assert_eq!(Event::Completed.to_bencode().unwrap(), b"9:completed");
+    assert_eq!(Event::from_bencode(b"7:started").unwrap(), Event::Started);
+
+    let error = Event::from_bencode(b"6:paused").unwrap_err();
+    assert_eq!(error.to_string(), "Error: unknown variant: paused");
+}
+
+#[test]
+fn integer_enum_uses_discriminants() {
+    assert_eq!(ErrorCode::Server.to_bencode().unwrap(), b"i202e");
+    assert_eq!(ErrorCode::Protocol.to_bencode().unwrap(), b"i203e");
+    assert_eq!(
+        ErrorCode::from_bencode(b"i201e").unwrap(),
+        ErrorCode::Generic
+    );
+    assert!(ErrorCode::from_bencode(b"i200e").is_err());
+    assert!(ErrorCode::from_bencode(b"4:i201").is_err());
+}
+
+#[test]
+fn externally_tagged_variants_round_trip() {
+    let cases: Vec<(Piece, &[u8])> = vec![
+        (Piece::Missing, b"7:missing"),
+        (Piece::Hash("abc".to_string()), b"d4:hash3:abce"),
+        (Piece::Range(1, 2), b"d5:rangeli1ei2eee"),
+        (
+            Piece::Block {
+                piece_index: 3,
+                data: "x".to_string(),
+            },
+            b"d5:blockd4:data1:x11:piece indexi3eee",
+        ),
+    ];
+
+    for (piece, encoded) in cases {
+        assert_eq!(piece.to_bencode().unwrap(), encoded);
+        assert_eq!(Piece::from_bencode(encoded).unwrap(), piece);
+    }
+}
+
+#[test]
+fn externally_tagged_dict_needs_single_known_key() {
+    assert!(Piece::from_bencode(b"de").is_err());
+    assert!(Piece::from_bencode(b"d4:hash3:abc5:rangelee").is_err());
+    assert_eq!(
+        Piece::from_bencode(b"d4:skip3:abce")
+            .unwrap_err()
+            .to_string(),
+        "Error: unknown variant: skip"
+    );
+    assert!(Piece::from_bencode(b"i1e").is_err());
+}
+
+#[test]
+fn internally_tagged_variants_round_trip() {
+    let cases: Vec<(Krpc, &[u8])> = vec![
+        (
+            Krpc::Query {
+                t: "aa".to_string(),
+                q: "ping".to_string(),
+            },
+            b"d1:q4:ping1:t2:aa1:y1:qe",
+        ),
+        (
+            Krpc::Response {
+                t: "aa".to_string(),
+                r: vec!["id".to_string()],
+            },
+            b"d1:rl2:ide1:t2:aa1:y1:re",
+        ),
+        (Krpc::Ping, b"d1:y1:pe"),
+    ];
+
+    for (message, encoded) in cases {
+        assert_eq!(message.to_bencode().unwrap(), encoded);
+        assert_eq!(Krpc::from_bencode(encoded).unwrap(), message);
+    }
+}
+
+#[test]
+fn internally_tagged_errors_name_tag_and_fields() {
+    let error = Krpc::from_bencode(b"d1:t2:aae").unwrap_err();
+    assert_eq!(error.to_string(), "Error: missing field: y");
+
+    let error = Krpc::from_bencode(b"d1:t2:aa1:y1:xe").unwrap_err();
+    assert_eq!(error.to_string(), "Error: unknown variant: x");
+
+    let error = Krpc::from_bencode(b"d1:t2:aa1:y1:qe").unwrap_err();
+    assert_eq!(error.to_string(), "Error: missing field: q");
+}
+
+#[test]
+fn enum_depth_covers_variants() {
+    assert_eq!(<Piece as ToBencode>::MAX_DEPTH, 3);
+    assert_eq!(<Krpc as FromBencode>::EXPECTED_RECURSION_DEPTH, 2);
+    assert_eq!(<Event as ToBencode>::MAX_DEPTH, 0);
+}
+
+#[derive(FromBencode, ToBencode, PartialEq, Eq, Debug)]
+struct KrpcMessage {
+    t: String,
+    v: Option<String>,
+    #[bendy(flatten)]
+    body: KrpcBody,
+}
+
+#[derive(FromBencode, ToBencode, PartialEq, Eq, Debug)]
+#[bendy(tag = "y")]
+enum KrpcBody {
+    #[bendy(rename = "q")]
+    Query { q: String, a: Wrapper<String> },
+    #[bendy(rename = "e")]
+    Error { e: Pair },
+}
+
+#[test]
+fn flattened_fields_share_the_dict() {
+    let message = KrpcMessage {
+        t: "aa".to_string(),
+        v: None,
+        body: KrpcBody::Query {
+            q: "ping".to_string(),
+            a: Wrapper {
+                inner: "id".to_string(),
+            },
+        },
+    };
+    let encoded: &[u8] = b"d1:ad5:inner2:ide1:q4:ping1:t2:aa1:y1:qe";
+
+    assert_eq!(message.to_bencode().unwrap(), encoded);
+    assert_eq!(KrpcMessage::from_bencode(encoded).unwrap(), message);
+}
+
+#[test]
+fn flattened_fields_report_their_errors() {
+    let error = KrpcMessage::from_bencode(b"d1:el1:ae1:t2:aa1:y1:ee").unwrap_err();
+    assert_eq!(error.to_string(), "Error: missing field: 1 in e");
+
+    let error = KrpcMessage::from_bencode(b"d1:t2:aae").unwrap_err();
+    assert_eq!(error.to_string(), "Error: missing field: y");
+}
+
+#[test]
+fn flattened_depth_shares_the_level() {
+    assert_eq!(
+        <KrpcMessage as ToBencode>::MAX_DEPTH,
+        <KrpcBody as ToBencode>::MAX_DEPTH
+    );
+}
+
+#[derive(FromBencode, ToBencode, PartialEq, Eq, Debug)]
+#[bendy(deny_unknown_fields)]
+struct Strict {
+    a: i64,
+    b: Option<i64>,
+}
+
+#[derive(FromBencode, ToBencode, PartialEq, Eq, Debug)]
+#[bendy(tag = "y", deny_unknown_fields)]
+enum StrictMessage {
+    #[bendy(rename = "q")]
+    Query { q: String, strict: Strict },
+}
+
+#[test]
+fn unknown_fields_are_rejected_with_offset() {
+    assert_eq!(
+        Strict::from_bencode(b"d1:ai1e1:bi2ee").unwrap(),
+        Strict { a: 1, b: Some(2) }
+    );
+
+    let error = Strict::from_bencode(b"d1:ai1e1:ci2ee").unwrap_err();
+    assert_eq!(error.to_string(), "Error: unknown field c at offset 7");
+}
+
+#[test]
+fn unknown_field_offsets_point_into_whole_input() {
+    let encoded = b"d1:q4:ping6:strictd1:ai1e1:xi0ee1:y1:qe";
+    let error = StrictMessage::from_bencode(encoded).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Error: unknown field x at offset 25 in strict"
+    );
+    assert_eq!(&encoded[25..28], b"1:x");
+
+    let encoded = b"d1:q4:ping6:strictd1:ai1ee1:y1:q1:zi0ee";
+    let error = StrictMessage::from_bencode(encoded).unwrap_err();
+    assert_eq!(error.to_string(), "Error: unknown field z at offset 32");
+    assert_eq!(&encoded[32..35], b"1:z");
+}
+
+#[test]
+fn lenient_options_apply_to_tagged_and_flattened_fields() {
+    let options = DecodeOptions::preset(Preset::ClientQuirks);
+    let query = Krpc::Query {
+        t: "aa".to_string(),
+        q: "ping".to_string(),
+    };
+
+    let encoded = b"d1:t2:aa1:y1:q1:q4:pinge";
+    assert!(Krpc::from_bencode(encoded).is_err());
+    assert_eq!(
+        Krpc::from_bencode_with_options(encoded, options.clone()).unwrap(),
+        query
+    );
+
+    // Of repeated tags, the last one counts like for any other key
+    let encoded = b"d1:y1:p1:q4:ping1:t2:aa1:y1:qe";
+    assert_eq!(
+        Krpc::from_bencode_with_options(encoded, options.clone()).unwrap(),
+        query
+    );
+
+    let encoded = b"d1:t2:aa1:y1:q1:q4:ping1:ad5:inner2:idee";
+    assert_eq!(
+        KrpcMessage::from_bencode_with_options(encoded, options.clone()).unwrap(),
+        KrpcMessage {
+            t: "aa".to_string(),
+            v: None,
+            body: KrpcBody::Query {
+                q: "ping".to_string(),
+                a: Wrapper {
+                    inner: "id".to_string(),
+                },
+            },
+        }
+    );
+
+    let encoded = b"d1:y1:q1:q4:ping6:strictd1:xi0e1:ai1eee";
+    let error = StrictMessage::from_bencode_with_options(encoded, options).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Error: unknown field x at offset 25 in strict"
+    );
+    assert_eq!(&encoded[25..28], b"1:x");
+}
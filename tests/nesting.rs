@@ -0,0 +1,87 @@
+//! Pathologically deep input must fail with an error instead of exhausting the stack.
+
+use std::thread;
+
+use bendy::{
+    decoding::{Decoder, FromBencode, DEFAULT_MAX_DEPTH},
+    value::Value,
+};
+
+fn nested_lists(depth: usize) -> Vec<u8> {
+    let mut message = vec![b'l'; depth];
+    message.resize(2 * depth, b'e');
+    message
+}
+
+/// Run the test on a thread with a small stack, so overflows show up reliably
+fn with_small_stack(test: impl FnOnce() + Send + 'static) {
+    thread::Builder::new()
+        .stack_size(1 << 20)
+        .spawn(test)
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+#[test]
+fn value_accepts_default_max_depth() {
+    with_small_stack(|| {
+        assert!(Value::from_bencode(&nested_lists(DEFAULT_MAX_DEPTH)).is_ok());
+        assert!(Value::decode(&nested_lists(DEFAULT_MAX_DEPTH)).is_ok());
+    });
+}
+
+#[test]
+fn value_rejects_deep_nesting() {
+    with_small_stack(|| {
+        let message = nested_lists(100_000);
+        assert!(Value::from_bencode(&message).is_err());
+        assert!(Value::decode(&message).is_err());
+    });
+}
+
+#[test]
+fn dropping_deeply_nested_decoders_fails_cleanly() {
+    with_small_stack(|| {
+        let message = nested_lists(100_000);
+        let mut decoder = Decoder::new(&message);
+        drop(decoder.next_object());
+        assert!(decoder.next_object().is_err());
+    });
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_rejects_deep_nesting() {
+    with_small_stack(|| {
+        let message = nested_lists(100_000);
+        assert!(bendy::serde::from_bytes::<Value>(&message).is_err());
+    });
+}
+
+#[test]
+fn dropping_unlimited_decoders_does_not_recurse() {
+    with_small_stack(|| {
+        let message = nested_lists(100_000);
+        let mut decoder = Decoder::new(&message).with_max_depth(usize::MAX);
+
+        let mut outer = decoder
+            .next_object()
+            .unwrap()
+            .unwrap()
+            .try_into_list()
+            .unwrap();
+        let mut inner = outer
+            .next_object()
+            .unwrap()
+            .unwrap()
+            .try_into_list()
+            .unwrap();
+        inner.next_object().unwrap();
+        drop(inner);
+        assert!(outer.next_object().unwrap().is_none());
+        drop(outer);
+
+        assert!(decoder.next_object().unwrap().is_none());
+    });
+}
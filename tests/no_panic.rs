@@ -0,0 +1,226 @@
+//! Feeds mutated documents through all decoding entry points, asserting that malformed
+//! input results in errors and never in a panic.
+//!
+//! The mutations are driven by a fixed seed, so failures are reproducible. Set
+//! `BENDY_FUZZ_ITERATIONS` to run more iterations than the default.
+
+use std::{collections::BTreeMap, env};
+
+use bendy::{
+    decoding::{
+        validate, DecodeOptions, Decoder, DuplicateKeys, Event, FromBencode, Object, StreamDecoder,
+        Utf8Policy,
+    },
+    value::Value,
+};
+
+const SEEDS: &[&[u8]] = &[
+    b"d8:announce28:http://tracker.example.com:80804:infod6:lengthi1024e4:name8:file.txt12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee",
+    b"ld3:fooli1ei-2ei0eee3:barde4:spami-9223372036854775808ei18446744073709551616ee",
+    b"d1:ad1:bd1:cleeee1:xi42ee",
+    b"l0:i0eli1eel3:abce",
+    b"i-0e",
+    b"d3:fooi1e3:fooi2ee",
+];
+
+/// A small xorshift generator, good enough to pick mutations
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+}
+
+/// Bytes which are meaningful to the decoder, so mutations hit interesting paths
+const INTERESTING: &[u8] = b"ildue:-0123456789\xff";
+
+fn mutate(rng: &mut Rng, input: &mut Vec<u8>) {
+    for _ in 0..=rng.below(4) {
+        let position = rng.below(input.len() + 1);
+        match rng.below(5) {
+            0 if position < input.len() => input[position] = rng.next() as u8,
+            1 if position < input.len() => {
+                input[position] = INTERESTING[rng.below(INTERESTING.len())]
+            },
+            2 => input.insert(position, INTERESTING[rng.below(INTERESTING.len())]),
+            3 if position < input.len() => {
+                input.remove(position);
+            },
+            _ => input.truncate(position),
+        }
+    }
+}
+
+/// Read an object using as many of the high level helpers as possible
+fn walk(object: Object, rng: &mut Rng) -> Result<(), bendy::decoding::Error> {
+    match object {
+        Object::Integer(_) => {
+            object.as_i64().ok();
+            object.as_u128().ok();
+        },
+        Object::Bytes(_) => {
+            object.try_into_str().ok();
+        },
+        Object::List(mut list) => match rng.below(4) {
+            0 => list.try_for_each(|item| walk(item, rng))?,
+            1 => {
+                list.peek_token()?;
+                list.nth(1)?;
+                list.count_remaining()?;
+            },
+            2 => {
+                list.collect::<Value, Vec<_>>()?;
+            },
+            _ => list.finish()?,
+        },
+        Object::Dict(mut dict) => match rng.below(5) {
+            0 => {
+                while let Some((_, value)) = dict.next_pair_str(Utf8Policy::Lossy)? {
+                    walk(value, rng)?;
+                }
+            },
+            1 => {
+                dict.find(b"info")?;
+                dict.next_pair_raw()?;
+                dict.consume_all()?;
+            },
+            2 => {
+                dict.collect::<Vec<u8>, Value, BTreeMap<_, _>>()?;
+            },
+            3 => {
+                dict.next_pair_str(Utf8Policy::Skip)?;
+                dict.finish()?;
+            },
+            _ => {
+                dict.into_raw()?;
+            },
+        },
+    }
+    Ok(())
+}
+
+fn exercise(input: &[u8], rng: &mut Rng) {
+    Decoder::new(input).tokens().for_each(drop);
+    Decoder::new(input).typed_tokens().for_each(drop);
+    Decoder::new(input).documents().for_each(drop);
+    Value::decode(input).ok();
+    Value::from_bencode(input).ok();
+    Vec::<String>::from_bencode(input).ok();
+    BTreeMap::<String, i64>::from_bencode(input).ok();
+
+    let mut decoder = Decoder::new(input);
+    decoder.peek_token().ok();
+    decoder.seek_path(&[b"info", b"name"]).ok();
+
+    let mut decoder = Decoder::new(input);
+    let checkpoint = decoder.checkpoint();
+    decoder.skip_value().ok();
+    decoder.restore(checkpoint);
+    decoder.next_raw().ok();
+
+    let options = DecodeOptions::new()
+        .with_unsorted_keys()
+        .with_duplicate_keys(DuplicateKeys::FirstWins)
+        .with_leading_zeros()
+        .with_negative_zero()
+        .with_max_depth(16)
+        .with_max_string_length(64)
+        .with_max_tokens(128);
+    for options in [DecodeOptions::new(), options] {
+        let mut decoder = Decoder::new_with_options(input, options);
+        while let Ok(Some(object)) = decoder.next_object() {
+            if walk(object, rng).is_err() {
+                break;
+            }
+        }
+    }
+
+    // The fast validation agrees with reading all tokens of exactly one value
+    let tokens: Vec<_> = Decoder::new(input).with_strict_eof().tokens().collect();
+    let read = !tokens.is_empty() && tokens.iter().all(Result::is_ok);
+    assert_eq!(validate(input).is_ok(), read, "{:?}", input);
+
+    // Decoding in random chunks yields the same tokens and errors
+    let mut decoder = StreamDecoder::new();
+    let mut streamed = Vec::new();
+    let mut rest = Some(input);
+    loop {
+        match decoder.next_event() {
+            Ok(Some(event)) => streamed.push(Ok(event)),
+            Ok(None) => match rest {
+                Some(input) => {
+                    let (chunk, remainder) = input.split_at(rng.below(input.len() + 1));
+                    decoder.feed(chunk);
+                    rest = Some(remainder).filter(|remainder| !remainder.is_empty());
+                    if rest.is_none() {
+                        decoder.finish();
+                    }
+                },
+                None => break,
+            },
+            Err(error) => {
+                streamed.push(Err(error.position()));
+                break;
+            },
+        }
+    }
+    let tokens: Vec<_> = Decoder::new(input)
+        .tokens()
+        .map(|token| token.map(Event::from).map_err(|error| error.position()))
+        .collect();
+    assert_eq!(streamed, tokens, "{:?}", input);
+
+    // Recovered errors don't end decoding, so bound the number of documents
+    let options = DecodeOptions::new()
+        .with_error_recovery()
+        .with_max_depth(16);
+    let mut decoder = Decoder::new_with_options(input, options);
+    for _ in 0..=input.len() {
+        match decoder.next_object() {
+            Ok(Some(object)) => {
+                walk(object, rng).ok();
+            },
+            _ => break,
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    {
+        bendy::serde::from_bytes::<Value>(input).ok();
+        bendy::serde::from_bytes::<BTreeMap<String, Vec<u64>>>(input).ok();
+    }
+}
+
+#[test]
+fn mutated_documents_do_not_panic() {
+    let iterations = env::var("BENDY_FUZZ_ITERATIONS")
+        .ok()
+        .and_then(|iterations| iterations.parse().ok())
+        .unwrap_or(20_000);
+
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for _ in 0..iterations {
+        let mut input = SEEDS[rng.below(SEEDS.len())].to_vec();
+        mutate(&mut rng, &mut input);
+        exercise(&input, &mut rng);
+    }
+}
+
+#[test]
+fn seeds_do_not_panic() {
+    let mut rng = Rng(1);
+    for seed in SEEDS {
+        exercise(seed, &mut rng);
+        for end in 0..seed.len() {
+            exercise(&seed[..end], &mut rng);
+        }
+    }
+}
@@ -3,6 +3,8 @@
 // If `cfg(doctest)` gets stablized or `cfg(test)` gets fixed, we can use
 // doc-comment for running tests in `README.md`.
 
+#![allow(clippy::needless_borrows_for_generic_args)]
+
 mod encoding_1 {
     use bendy::encoding::{Error, ToBencode};
 
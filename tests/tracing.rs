@@ -0,0 +1,98 @@
+//! The decoder reports containers, decoded values and errors as `tracing` events.
+
+use std::{
+    fmt::{self, Write},
+    sync::{Arc, Mutex},
+};
+
+use bendy::{decoding::FromBencode, value::Value};
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Metadata, Subscriber,
+};
+
+/// Records every event as a line of its fields, with the span it was emitted in
+#[derive(Clone, Default)]
+struct Recorder {
+    lines: Arc<Mutex<Vec<String>>>,
+    spans: Arc<Mutex<Vec<String>>>,
+}
+
+struct Line(String);
+
+impl Visit for Line {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if !self.0.is_empty() {
+            self.0.push(' ');
+        }
+        write!(self.0, "{}={:?}", field.name(), value).unwrap();
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &span::Attributes) -> span::Id {
+        let mut line = Line(String::new());
+        span.record(&mut line);
+        let mut spans = self.spans.lock().unwrap();
+        spans.push(format!("{} {}", span.metadata().name(), line.0));
+        span::Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, _: &span::Id, _: &span::Record) {}
+
+    fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+    fn event(&self, event: &Event) {
+        let mut line = Line(String::new());
+        event.record(&mut line);
+        self.lines.lock().unwrap().push(line.0);
+    }
+
+    fn enter(&self, _: &span::Id) {}
+
+    fn exit(&self, _: &span::Id) {}
+}
+
+fn record(decode: impl FnOnce()) -> Recorder {
+    let recorder = Recorder::default();
+    tracing::subscriber::with_default(recorder.clone(), decode);
+    recorder
+}
+
+#[test]
+fn containers_and_values_are_traced() {
+    let recorder = record(|| {
+        Value::from_bencode(b"d1:ali1eee").unwrap();
+    });
+
+    assert_eq!(*recorder.spans.lock().unwrap(), vec!["from_bencode len=10"]);
+    assert_eq!(
+        *recorder.lines.lock().unwrap(),
+        vec![
+            "message=entered dictionary offset=0 depth=1",
+            "message=entered list offset=4 depth=2",
+            "message=left container offset=9 depth=1",
+            "message=left container offset=10 depth=0",
+            "message=decoded value offset=10 tokens=6",
+        ]
+    );
+}
+
+#[test]
+fn errors_are_traced_once() {
+    let recorder = record(|| {
+        Value::from_bencode(b"li1ei2x").unwrap_err();
+    });
+
+    let lines = recorder.lines.lock().unwrap();
+    let errors: Vec<_> = lines
+        .iter()
+        .filter(|line| line.starts_with("message=decoding failed"))
+        .collect();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("offset=Some(6) tokens=3 error="));
+}